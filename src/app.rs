@@ -0,0 +1,2899 @@
+use winit::{
+    application::ApplicationHandler,
+    dpi::{LogicalSize, PhysicalPosition, PhysicalSize},
+    event::{DeviceEvent, TouchPhase, WindowEvent},
+    event_loop::{ActiveEventLoop, ControlFlow, EventLoop, EventLoopProxy},
+    window::{CursorGrabMode, Fullscreen, UserAttentionType, Window, WindowLevel}
+};
+use std::cell::RefCell;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+
+use egui::emath::GuiRounding;
+
+use crate::i18n::tr;
+use crate::init_error::InitError;
+use crate::raw_input::{RawInput, RawInputHandler};
+use crate::settings::PixelSnap;
+use crate::timestep::FixedUpdate;
+use crate::ui::ToolWindowOpen;
+#[cfg(feature = "gamepad")]
+use crate::gamepad;
+#[cfg(feature = "dev")]
+use crate::shaders;
+#[cfg(all(target_os = "windows", feature = "tray"))]
+use crate::tray;
+use crate::{
+    autosave, bench, capabilities, capture, clipboard, config, crash, decorations, dialogs, fonts, frame_clock,
+    i18n, init_error, input_tuning, layouts, loading, logging, memory_trim, motion, native_textures, offscreen,
+    overdraw, on_screen_keyboard, pacing, placement, postprocess, profiler, recording, replay, save_file, scene,
+    settings, shutdown, stats, storage, taskbar, tasks, tessellation_cache, timestep, toasts, ui, viewports,
+    watchdog, window_commands,
+};
+
+/// Smaller windows can't show the menu anymore, in logical pixels.
+const MIN_WINDOW_SIZE: [f64; 2] = [320.0, 240.0];
+
+/// Waits shorter than this are spun instead of handed to the OS timer, see `App::about_to_wait`.
+const FRAME_SPIN_THRESHOLD: Duration = Duration::from_millis(1);
+/// Frames taking longer are reported by the [`watchdog::Watchdog`], unless `--frame-budget` says otherwise.
+const DEFAULT_FRAME_BUDGET: Duration = Duration::from_millis(500);
+/// How long exiting waits for background tasks and the recording writer before it leaves them behind.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
+/// How often the first frame is tried before the window is shown without it, see `AppResources::show_first_frame`.
+const FIRST_FRAME_ATTEMPTS: u32 = 3;
+
+/// Events sent into the winit event loop, possibly from other threads.
+#[derive(Debug)]
+pub enum AppEvent {
+    /// Forwarded from egui's repaint callback, see [`AppResources::new`].
+    RequestRepaint {
+        viewport_id: egui::ViewportId,
+        delay: Duration,
+        cumulative_pass_nr: u64,
+    },
+    /// New records arrived in the log buffer.
+    LogsUpdated,
+    /// Something was pushed into an empty [`window_commands::WindowCommands`], they're applied in `about_to_wait`.
+    WindowCommands,
+    #[cfg(feature = "gamepad")]
+    Gamepad(gamepad::GamepadEvent),
+    /// Shader files that were saved, from the [`shaders::ShaderWatcher`].
+    #[cfg(feature = "dev")]
+    ShadersChanged(Vec<&'static str>),
+    /// A file dialog was closed, see [`dialogs::Dialogs`].
+    Dialog(dialogs::DialogEvent),
+    #[cfg(all(target_os = "windows", feature = "tray"))]
+    Tray(tray::TrayEvent),
+}
+
+/// Results of the file tasks started from the File menu.
+enum FileEvent {
+    Saved { path: PathBuf, contents: String },
+    Opened { path: PathBuf, file: save_file::SaveFile },
+}
+
+/// Startup options, taken from the command line.
+#[derive(Debug, Clone, Default)]
+struct AppOptions {
+    /// `--overlay`: transparent, undecorated, always-on-top window that lets clicks outside the UI pass through.
+    overlay: bool,
+    /// `--custom-decorations`: title bar and window buttons drawn by egui.
+    custom_decorations: bool,
+    /// `--frame-latency=N`: initial `desired_maximum_frame_latency`.
+    frame_latency: Option<u32>,
+    /// `--config <path>`: read the config from there instead of `config.toml` in the data directory.
+    config_path: Option<PathBuf>,
+    /// `--font <path>`, can be given more than once: font files to fall back to for characters the default fonts lack.
+    fonts: Vec<PathBuf>,
+    /// `--stress N`: open the stress test with N widgets, together with `--bench` that's a benchmark of a heavy UI.
+    stress: Option<usize>,
+    /// `--frame-budget MS`: frames taking longer than this are reported, 500 ms by default.
+    frame_budget: Option<Duration>,
+    /// `--frame-backtrace MS`: frames taking longer than this also log a backtrace.
+    frame_backtrace: Option<Duration>,
+    /// `--bench N`: render N frames without vsync or throttling, print their timings and exit.
+    /// Needs a window for now, there is no headless rendering yet.
+    bench: Option<u32>,
+}
+
+impl AppOptions {
+    fn from_args() -> Self {
+        let mut options = Self::default();
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--overlay" => options.overlay = true,
+                "--custom-decorations" => options.custom_decorations = true,
+                "--config" => match args.next() {
+                    Some(path) => options.config_path = Some(path.into()),
+                    None => log::warn!("--config needs the path of the config file"),
+                },
+                "--font" => match args.next() {
+                    Some(path) => options.fonts.push(path.into()),
+                    None => log::warn!("--font needs the path of a font file"),
+                },
+                "--stress" => match args.next().map(|widgets| widgets.parse()) {
+                    Some(Ok(widgets)) => options.stress = Some(widgets),
+                    _ => log::warn!("--stress needs the number of widgets to show"),
+                },
+                "--frame-budget" => match args.next().map(|ms| ms.parse()) {
+                    Some(Ok(ms)) => options.frame_budget = Some(Duration::from_millis(ms)),
+                    _ => log::warn!("--frame-budget needs a time in milliseconds"),
+                },
+                "--frame-backtrace" => match args.next().map(|ms| ms.parse()) {
+                    Some(Ok(ms)) => options.frame_backtrace = Some(Duration::from_millis(ms)),
+                    _ => log::warn!("--frame-backtrace needs a time in milliseconds"),
+                },
+                "--bench" => match args.next().map(|frames| frames.parse()) {
+                    Some(Ok(frames)) => options.bench = Some(frames),
+                    _ => log::warn!("--bench needs the number of frames to render"),
+                },
+                arg => match arg.strip_prefix("--frame-latency=").map(str::parse) {
+                    Some(Ok(frame_latency)) => options.frame_latency = Some(frame_latency),
+                    _ => log::warn!("Ignoring unknown argument {arg:?}"),
+                },
+            }
+        }
+        options
+    }
+}
+
+struct App {
+    app_resources: Option<AppResources>,
+    event_loop_proxy: EventLoopProxy<AppEvent>,
+    logs: Arc<logging::LogBuffer>,
+    options: AppOptions,
+    /// Set when `AppResources::new` failed or the UI kept panicking until the app gave up, the process then exits with
+    /// an error code.
+    failed: bool,
+}
+
+impl App {
+    fn new(event_loop_proxy: EventLoopProxy<AppEvent>, logs: Arc<logging::LogBuffer>, options: AppOptions) -> Self {
+        let proxy = event_loop_proxy.clone();
+        logs.set_wake_callback(move || {
+            let _ = proxy.send_event(AppEvent::LogsUpdated);
+        });
+
+        Self {
+            app_resources: None,
+            event_loop_proxy,
+            logs,
+            options,
+            failed: false,
+        }
+    }
+
+    fn get_app_resources(&mut self) -> &mut AppResources {
+        self.app_resources.as_mut().unwrap()
+    }
+
+    fn render(&mut self, event_loop: &ActiveEventLoop) {
+        let app_resources = self.get_app_resources();
+        let result = app_resources.do_render();
+        app_resources.watchdog.frame_finished();
+        if let Err(error) = result {
+            app_resources.on_surface_error(error, event_loop);
+        }
+        // Right after the frame, `about_to_wait` doesn't come while Windows runs its modal loop for a resize.
+        app_resources.apply_window_commands(event_loop);
+    }
+}
+
+impl shutdown::Shutdown for AppResources {
+    fn tasks(&mut self) -> &mut tasks::TaskRunner {
+        &mut self.tasks
+    }
+
+    fn take_recording(&mut self) -> Option<recording::Recording> {
+        self.recording.take()
+    }
+
+    fn autosave(&mut self) -> &mut autosave::Autosave {
+        &mut self.autosave
+    }
+
+    fn persisted_files(&self) -> Vec<autosave::PersistedFile> {
+        AppResources::persisted_files(self)
+    }
+}
+
+/// A UI frame ready to be painted, tessellated and with its textures and buffers uploaded, see `AppResources::prepare_ui`.
+struct PreparedFrame {
+    clipped_primitives: Rc<[egui::ClippedPrimitive]>,
+    screen_descriptor: egui_wgpu::ScreenDescriptor,
+    textures_to_free: Vec<egui::TextureId>,
+}
+
+struct AppResources {
+    window: Arc<Window>,
+    gpu_resources: GpuResources,
+    ui_painter: egui_wgpu::Renderer,
+    /// Last frame's primitives, which may still be in the vertex and index buffers of `ui_painter`.
+    tessellation_cache: tessellation_cache::TessellationCache,
+    ui_state: egui_winit::State,
+    ui_gallery: ui::WidgetGallery,
+    ui_counter: ui::BackgroundCounter,
+    ui_tasks: ui::TasksWindow,
+    ui_toasts_demo: ui::ToastsDemo,
+    ui_log_console: ui::LogConsoleWindow,
+    ui_settings: ui::SettingsWindow,
+    settings: settings::Settings,
+    pacing: pacing::FramePacing,
+    stats: stats::FrameStats,
+    ui_stats: stats::UiStats,
+    ui_gpu_memory: ui::GpuMemoryWindow,
+    ui_debug: ui::DebugTools,
+    ui_stress_test: ui::StressTest,
+    /// Times the UI render pass for the stress test while it's open, the benchmark has a timer of its own.
+    ui_gpu_timer: Option<bench::GpuTimer>,
+    ui_gradients: ui::GradientWindow,
+    ui_pixel_test: ui::PixelTestPattern,
+    ui_color_test: ui::ColorTestWindow,
+    /// Set from the Debug menu, the next frame fills the font atlas, see [`Self::inflate_font_atlas`].
+    inflating_font_atlas: bool,
+    /// Set by the color test, the next frame is read back from the surface for it.
+    color_readback: bool,
+    ui_image_demo: ui::ImageDemo,
+    ui_painting: ui::PaintingDemo,
+    ui_reorder: ui::ReorderDemo,
+    layouts: layouts::LayoutProfiles,
+    ui_modal_demo: ui::ModalDemo,
+    ui_camera: ui::CameraDemo,
+    ui_zoom_canvas: ui::ZoomCanvas,
+    ui_bouncing_ball: ui::BouncingBall,
+    timestep: timestep::FixedTimestep,
+    frame_clock: frame_clock::FrameClock,
+    watchdog: watchdog::Watchdog,
+    /// Drawn behind the main UI and fed the input egui doesn't use.
+    scene: scene::Scene,
+    /// Windows of the egui viewports other than this one, shared with the callback egui runs immediate viewports with.
+    viewports: Rc<RefCell<viewports::Viewports>>,
+    /// A failed frame was shown as a toast, until a frame is presented again. Only the first of a run of them is.
+    frame_error_shown: bool,
+    /// Asks before quitting with unsaved changes.
+    ui_exit_confirm: ui::Modal,
+    native_textures: native_textures::NativeTextures,
+    /// Counts the times the demo pixel art was regenerated, to pick a different color each time.
+    pixel_art_variant: u32,
+    ui_diagnostics: ui::DiagnosticsWindow,
+    /// What the last [`Self::trim_memory`] freed, shown in the GPU Memory window.
+    trim_report: Option<memory_trim::TrimReport>,
+    idle_trim: memory_trim::IdleTrim,
+    ui_surface_info: ui::SurfaceInfoWindow,
+    ui_adapters: ui::AdaptersWindow,
+    /// Picked in the Adapters window, switched to once the current frame is presented.
+    adapter_switch: Option<wgpu::AdapterInfo>,
+    recording: Option<recording::Recording>,
+    /// Used when a recording is started with the hotkey.
+    recording_format: recording::RecordingFormat,
+    input_tuning: input_tuning::InputTuning,
+    on_screen_keyboard: on_screen_keyboard::OnScreenKeyboard,
+    input_replay: replay::InputReplay,
+    #[cfg(feature = "gamepad")]
+    gamepad: gamepad::GamepadNav,
+    /// Only kept so it keeps watching.
+    #[cfg(feature = "dev")]
+    _shader_watcher: shaders::ShaderWatcher,
+    #[cfg(feature = "profiling")]
+    ui_profiler: ui::ProfilerWindow,
+    /// Picked in the Surface Info window, applied once the current frame is presented.
+    surface_change: Option<ui::SurfaceChange>,
+    logs: Arc<logging::LogBuffer>,
+    tasks: tasks::TaskRunner,
+    /// Until it's done the loading screen is shown instead of the UI.
+    loader: loading::Loader<loading::DemoApp>,
+    toasts: toasts::Toasts,
+    /// egui's defaults until the loader has the fonts.
+    fonts: egui::FontDefinitions,
+    /// When the next frame is due, `None` means nothing asked for one and the event loop can sleep.
+    repaint_at: Option<Instant>,
+    overlay: bool,
+    /// Whether the window currently receives mouse input, only ever false in overlay mode.
+    cursor_hittest: bool,
+    /// Whether the cursor is hidden and held in place, mouse motion then turns the camera demo.
+    cursor_grabbed: bool,
+    /// Last known cursor position in the window, the cursor is put back there when the grab is released.
+    cursor_position: Option<PhysicalPosition<f64>>,
+    /// Where the cursor was when it was grabbed.
+    grab_position: Option<PhysicalPosition<f64>>,
+    /// The touch egui_winit turned into the pointer, tracked the same way it does. Mouse input is held back meanwhile.
+    touch_pointer: Option<u64>,
+    /// The level the window was last set to.
+    window_level: WindowLevel,
+    /// The window options of the settings as they were last applied, the level is `None` to have it set again.
+    applied_window_level: Option<settings::WindowLevel>,
+    applied_skip_taskbar: bool,
+    applied_resizable: bool,
+    custom_decorations: bool,
+    /// Applied after the current frame, see [`window_commands::WindowCommands`].
+    window_commands: window_commands::WindowCommands,
+    taskbar: taskbar::TaskbarProgress,
+    /// `None` if it couldn't be added, closing and starting to the tray then do nothing.
+    #[cfg(all(target_os = "windows", feature = "tray"))]
+    tray: Option<tray::Tray>,
+    /// Whether the window is hidden to the tray, nothing is rendered then.
+    #[cfg(all(target_os = "windows", feature = "tray"))]
+    hidden_to_tray: bool,
+    /// Set while the window asks for attention because a task finished in the background, until it's focused again.
+    attention_requested: bool,
+    /// The title the window has right now, including the suffix.
+    window_title: String,
+    /// Whether the demo state differs from `saved_state`, marked in the window title.
+    unsaved_changes: bool,
+    dialogs: dialogs::Dialogs<AppResources>,
+    /// The file last saved to or opened, shown in the title.
+    file_path: Option<PathBuf>,
+    /// What the demo state looked like when it was last saved or opened, as it's written to the file.
+    saved_state: String,
+    file_events_tx: mpsc::Sender<FileEvent>,
+    file_events_rx: mpsc::Receiver<FileEvent>,
+    clipboard_images: clipboard::ClipboardImages,
+    /// Ctrl+V was pressed since the last frame. egui-winit only turns it into an event when there's text to paste.
+    paste_shortcut: bool,
+    /// Running `--bench`, frames are rendered back to back until it's done.
+    bench: Option<bench::Benchmark>,
+    /// Where `state` is saved, `None` if there's no place for it on this platform.
+    state_path: Option<PathBuf>,
+    /// Everything that persists between runs.
+    state: storage::Document,
+    /// Another instance holds the lock of the data folder and saves into it, this one only reads from it.
+    read_only: bool,
+    /// Held until the app exits, `None` as well if the folder couldn't be locked and it's saved to anyway.
+    _instance_lock: Option<storage::InstanceLock>,
+    autosave: autosave::Autosave,
+    config_file: config::ConfigFile,
+    /// What the app was started with, the settings in it are replaced by the current ones when it's saved.
+    config: config::Config,
+    /// The zoom factor of the settings when they were last applied, see [`Self::apply_settings`].
+    applied_zoom_factor: f32,
+    /// What egui's zoom factor was set to last, `applied_zoom_factor` with the pixel snapping on top.
+    snapped_zoom_factor: f32,
+    system_motion: motion::SystemMotion,
+    placement: Option<placement::WindowPlacement>,
+    /// Set once the UI panicked, the crash screen is shown instead of it then.
+    crash: crash::CrashState,
+    #[cfg(target_os = "android")]
+    android_app: winit::platform::android::activity::AndroidApp,
+    #[cfg(target_os = "android")]
+    soft_input_visible: bool,
+}
+
+impl AppResources {
+    /// Blocks on [`Self::new`], which is fine everywhere but on the web.
+    fn new_blocking(event_loop: &ActiveEventLoop, event_loop_proxy: EventLoopProxy<AppEvent>, logs: Arc<logging::LogBuffer>, options: &AppOptions) -> Result<Self, InitError> {
+        pollster::block_on(Self::new(event_loop, event_loop_proxy, logs, options))
+    }
+
+    /// Creates the window and everything needed to draw into it. Only requesting the GPU device is actually async.
+    async fn new(event_loop: &ActiveEventLoop, event_loop_proxy: EventLoopProxy<AppEvent>, logs: Arc<logging::LogBuffer>, options: &AppOptions) -> Result<Self, InitError> {
+        let state_path = storage::data_dir().map(|dir| dir.join("state.toml"));
+        let config_path = options.config_path.clone().or_else(config::ConfigFile::default_path);
+        // A second instance leaves the files alone, the first one would overwrite what it saved anyway.
+        let (instance_lock, read_only) = match storage::data_dir().map(|dir| storage::InstanceLock::acquire(&dir)).transpose() {
+            Ok(Some(None)) => (None, true),
+            Ok(lock) => (lock.flatten(), false),
+            Err(error) => {
+                log::warn!("Failed to lock the data folder, another instance may overwrite what this one saves: {error}");
+                (None, false)
+            },
+        };
+        let recovered: Vec<PathBuf> = [&state_path, &config_path].into_iter()
+            .flatten()
+            .filter(|path| !read_only && storage::recover_temp(path, |text| storage::Document::parse(text).is_ok()).unwrap_or_else(|error| {
+                log::warn!("Failed to recover the interrupted save of {}: {error}", path.display());
+                false
+            }))
+            .cloned()
+            .collect();
+        let state = state_path.as_deref()
+            .map(storage::Document::load)
+            .transpose()
+            .unwrap_or_else(|error| {
+                log::warn!("Failed to load the saved state, starting fresh: {error}");
+                None
+            })
+            .unwrap_or_default();
+
+        let monitors: Vec<_> = event_loop.available_monitors().collect();
+        let placement = placement::WindowPlacement::load(&state)
+            .map(|placement| placement.clamp_to_monitors(&monitors, event_loop.primary_monitor().as_ref()));
+
+        // Flags win over the config, it can't turn on what they leave off either way.
+        let (config_file, config, config_warnings) = config::ConfigFile::load(config_path);
+        let mut toasts = toasts::Toasts::default();
+        for warning in config_warnings {
+            log::warn!("{warning}");
+            toasts.warning(warning);
+        }
+        let mut settings = config.settings.clone();
+        if let Some(frame_latency) = options.frame_latency {
+            settings.frame_latency = clamp_frame_latency(frame_latency);
+        }
+        i18n::set_language(settings.language);
+        for path in recovered {
+            log::info!("Recovered {} from a save that was interrupted", path.display());
+            toasts.info(i18n::tr_format("autosave.recovered", &[("file", &path.display())]));
+        }
+        if read_only {
+            log::warn!("Another instance is running, nothing is saved in this one");
+            toasts.warning(tr("autosave.read_only"));
+        }
+        let overlay = options.overlay || config.overlay;
+        let custom_decorations = options.custom_decorations || config.custom_decorations;
+
+        // The overlay stays on top until the level is changed.
+        let window_level = if overlay { WindowLevel::AlwaysOnTop } else { settings.window_level.winit() };
+        // Shown once the first frame is in it, see `show_first_frame`, instead of as an empty rectangle before that.
+        let mut attributes = Window::default_attributes()
+            .with_visible(false)
+            .with_title(&settings.title)
+            .with_window_level(window_level)
+            .with_resizable(settings.resizable);
+        #[cfg(target_os = "windows")]
+        {
+            attributes = winit::platform::windows::WindowAttributesExtWindows::with_skip_taskbar(attributes, settings.skip_taskbar);
+        }
+        #[cfg(not(target_os = "windows"))]
+        if settings.skip_taskbar {
+            log::info!("Hiding the window from the taskbar isn't supported on this platform");
+        }
+        if let Some(placement) = placement {
+            attributes = placement.apply(attributes);
+        }
+        if overlay {
+            attributes = attributes
+                .with_transparent(true)
+                .with_decorations(false);
+        } else if custom_decorations {
+            attributes = decorations::window_attributes(attributes);
+        }
+
+        let window = Arc::new(event_loop.create_window(attributes)?);
+        let (applied_window_level, applied_skip_taskbar, applied_resizable) = (settings.window_level, settings.skip_taskbar, settings.resizable);
+        let window_title = settings.title.clone();
+        let ui_gallery = ui::WidgetGallery::default();
+        let saved_state = save_file::SaveFile::new(&ui_gallery, &settings).to_string();
+        let (file_events_tx, file_events_rx) = mpsc::channel();
+        let gpu_config = GpuConfig {
+            transparent: overlay,
+            frame_latency: settings.frame_latency,
+            dithering: settings.dithering,
+            srgb_view: settings.srgb_view,
+            msaa_samples: settings.msaa_samples,
+            preferences: config.gpu,
+        };
+        let mut gpu_resources = GpuResources::new(&window, &gpu_config, None).await?;
+        let bench = options.bench.map(|frames| {
+            gpu_resources.disable_vsync();
+            bench::Benchmark::new(&gpu_resources.device, &gpu_resources.queue, frames)
+        });
+
+        let ui_painter = gpu_resources.create_ui_renderer();
+        let ui_context = egui::Context::default();
+        #[cfg(feature = "gamepad")]
+        {
+            let proxy = event_loop_proxy.clone();
+            gamepad::spawn(move |event| {
+                let _ = proxy.send_event(AppEvent::Gamepad(event));
+            });
+        }
+        #[cfg(all(target_os = "windows", feature = "tray"))]
+        let tray = {
+            let proxy = event_loop_proxy.clone();
+            tray::Tray::new(&settings.title, move |event| {
+                let _ = proxy.send_event(AppEvent::Tray(event));
+            })
+        };
+        #[cfg(feature = "dev")]
+        let shader_watcher = {
+            let proxy = event_loop_proxy.clone();
+            let watcher = shaders::ShaderWatcher::new(move |paths| {
+                let _ = proxy.send_event(AppEvent::ShadersChanged(paths));
+            });
+            for file in postprocess::PostProcessor::SHADERS {
+                watcher.watch(file);
+            }
+            watcher
+        };
+        let window_commands = window_commands::WindowCommands::new(event_loop_proxy.clone());
+        let proxy = event_loop_proxy.clone();
+        let dialogs = dialogs::Dialogs::new(move |event| {
+            let _ = proxy.send_event(AppEvent::Dialog(event));
+        });
+        window_commands.push(window_commands::WindowCommand::SetMinInnerSize(Some(MIN_WINDOW_SIZE)));
+        // Repaints can be requested from any thread, so they go through the event loop which might be asleep.
+        ui_context.set_request_repaint_callback(move |info| {
+            let _ = event_loop_proxy.send_event(AppEvent::RequestRepaint {
+                viewport_id: info.viewport_id,
+                delay: info.delay,
+                cumulative_pass_nr: info.current_cumulative_pass_nr,
+            });
+        });
+        let ui_counter = ui::BackgroundCounter::new(&ui_context);
+        let mut tasks = tasks::TaskRunner::new(&ui_context, Some(window_commands.clone()));
+        // Everything that takes long is left to the loader, the window shows the loading screen meanwhile.
+        let loader = loading::Loader::start(loading::DemoApp { font_paths: options.fonts.clone() }, &mut tasks);
+        let system_motion = motion::SystemMotion::query(&mut tasks);
+        let taskbar = taskbar::TaskbarProgress::new(&window);
+        let clipboard_images = clipboard::ClipboardImages::new(&ui_context);
+        let viewport_id = ui_context.viewport_id();
+        // Touch events are translated by egui_winit as well, the first finger also drives the egui pointer.
+        let viewports = viewports::Viewports::install(&ui_context);
+        // The font atlas grows up to this in both directions, egui's default of 2048 fills up quickly with CJK text.
+        let ui_state = egui_winit::State::new(ui_context, viewport_id, &window, None, None, Some(gpu_resources.max_texture_side));
+        let mut ui_stress_test = ui::StressTest::default();
+        if let Some(widgets) = options.stress {
+            ui_stress_test.set_count(widgets);
+            ui_stress_test.open();
+        }
+
+        let mut frame_clock = frame_clock::FrameClock::default();
+        frame_clock.set_refresh_rate(window.current_monitor().and_then(|monitor| monitor.refresh_rate_millihertz()));
+
+        let mut app_resources = Self {
+            window,
+            gpu_resources,
+            ui_painter,
+            tessellation_cache: tessellation_cache::TessellationCache::default(),
+            ui_state,
+            ui_gallery,
+            ui_counter,
+            ui_tasks: ui::TasksWindow::default(),
+            ui_toasts_demo: ui::ToastsDemo::default(),
+            ui_log_console: ui::LogConsoleWindow::default(),
+            ui_settings: ui::SettingsWindow::default(),
+            settings,
+            pacing: pacing::FramePacing::default(),
+            stats: stats::FrameStats::default(),
+            ui_stats: stats::UiStats::default(),
+            ui_gpu_memory: ui::GpuMemoryWindow::default(),
+            ui_stress_test,
+            ui_gpu_timer: None,
+            ui_gradients: ui::GradientWindow::default(),
+            ui_pixel_test: ui::PixelTestPattern::default(),
+            ui_color_test: ui::ColorTestWindow::default(),
+            inflating_font_atlas: false,
+            color_readback: false,
+            ui_image_demo: ui::ImageDemo::default(),
+            ui_painting: ui::PaintingDemo::default(),
+            ui_reorder: ui::ReorderDemo::load(&state),
+            layouts: layouts::LayoutProfiles::load(&state),
+            ui_debug: ui::DebugTools::load(&state),
+            ui_modal_demo: ui::ModalDemo::default(),
+            ui_camera: ui::CameraDemo::default(),
+            ui_zoom_canvas: ui::ZoomCanvas::default(),
+            ui_bouncing_ball: ui::BouncingBall::default(),
+            timestep: timestep::FixedTimestep::default(),
+            frame_clock,
+            watchdog: watchdog::Watchdog::new(options.frame_budget.unwrap_or(DEFAULT_FRAME_BUDGET), options.frame_backtrace),
+            scene: scene::Scene::default(),
+            viewports,
+            frame_error_shown: false,
+            ui_exit_confirm: exit_confirm_modal(),
+            native_textures: native_textures::NativeTextures::default(),
+            pixel_art_variant: 0,
+            ui_diagnostics: ui::DiagnosticsWindow::default(),
+            trim_report: None,
+            idle_trim: memory_trim::IdleTrim::new(Instant::now()),
+            ui_surface_info: ui::SurfaceInfoWindow::default(),
+            surface_change: None,
+            ui_adapters: ui::AdaptersWindow::default(),
+            adapter_switch: None,
+            recording: None,
+            recording_format: recording::RecordingFormat::default(),
+            input_tuning: input_tuning::InputTuning::default(),
+            on_screen_keyboard: on_screen_keyboard::OnScreenKeyboard::default(),
+            input_replay: replay::InputReplay::default(),
+            #[cfg(feature = "gamepad")]
+            gamepad: gamepad::GamepadNav::default(),
+            #[cfg(feature = "dev")]
+            _shader_watcher: shader_watcher,
+            #[cfg(feature = "profiling")]
+            ui_profiler: ui::ProfilerWindow::default(),
+            logs,
+            tasks,
+            loader,
+            toasts,
+            fonts: egui::FontDefinitions::default(),
+            repaint_at: None,
+            overlay,
+            cursor_hittest: true,
+            cursor_grabbed: false,
+            cursor_position: None,
+            grab_position: None,
+            touch_pointer: None,
+            window_level,
+            applied_window_level: Some(applied_window_level),
+            applied_skip_taskbar,
+            applied_resizable,
+            custom_decorations: custom_decorations && !overlay,
+            window_commands,
+            taskbar,
+            #[cfg(all(target_os = "windows", feature = "tray"))]
+            tray,
+            #[cfg(all(target_os = "windows", feature = "tray"))]
+            hidden_to_tray: false,
+            attention_requested: false,
+            window_title,
+            unsaved_changes: false,
+            dialogs,
+            file_path: None,
+            saved_state,
+            file_events_tx,
+            file_events_rx,
+            clipboard_images,
+            paste_shortcut: false,
+            bench,
+            state_path,
+            state,
+            read_only,
+            _instance_lock: instance_lock,
+            autosave: autosave::Autosave::new(Instant::now()),
+            config_file,
+            config,
+            applied_zoom_factor: 1.0,
+            snapped_zoom_factor: 1.0,
+            system_motion,
+            placement,
+            crash: crash::CrashState::default(),
+            #[cfg(target_os = "android")]
+            android_app: {
+                use winit::platform::android::ActiveEventLoopExtAndroid;
+                event_loop.android_app().clone()
+            },
+            #[cfg(target_os = "android")]
+            soft_input_visible: false,
+        };
+        let files = app_resources.persisted_files();
+        app_resources.autosave.remember(files);
+        Ok(app_resources)
+    }
+
+    /// Takes the input, builds the UI and prepares it for painting into a render pass of `ce`.
+    /// Nothing here needs the surface texture, so this can happen before or after acquiring it.
+    fn ui_frame(&mut self, ce: &mut wgpu::CommandEncoder) -> PreparedFrame {
+        let cpu_start = Instant::now();
+        let raw_input = self.take_ui_input();
+        let output = self.build_ui(raw_input);
+        if let Some(bench) = &mut self.bench {
+            bench.add(bench::Phase::Cpu, cpu_start.elapsed());
+        }
+        self.prepare_ui(output, ce)
+    }
+
+    /// What egui-winit collected since the last frame, with the input settings applied and the input of a replay and
+    /// of gamepads added.
+    fn take_ui_input(&mut self) -> egui::RawInput {
+        #[allow(unused_mut)]
+        let mut raw_input = {
+            profiler::profile_scope!("take_egui_input");
+            self.watchdog.set_phase(watchdog::Phase::Input);
+            self.ui_state.take_egui_input(&self.window)
+        };
+        #[cfg(target_os = "android")]
+        self.apply_content_rect(&mut raw_input);
+        // Before the replay, so a recording has the input as egui got it.
+        let line_scroll_speed = self.ui_state.egui_ctx().options(|options| options.line_scroll_speed);
+        if let Some(delay) = self.input_tuning.process(&mut raw_input, &self.settings, line_scroll_speed, Instant::now()) {
+            self.schedule_repaint(delay);
+        }
+        self.on_screen_keyboard.process(&mut raw_input, self.settings.on_screen_keyboard);
+        if let Some(delay) = self.input_replay.process(&mut raw_input) {
+            self.schedule_repaint(delay);
+        }
+        #[cfg(feature = "gamepad")]
+        {
+            let nothing_focused = self.ui_state.egui_ctx().memory(|memory| memory.focused().is_none());
+            if let Some(delay) = self.gamepad.process(&mut raw_input, nothing_focused, Instant::now()) {
+                self.schedule_repaint(delay);
+            }
+        }
+        raw_input
+    }
+
+    /// Runs the UI for `raw_input` and applies what it asked for. Everything in the output except for what to draw is
+    /// handled here too, [`Self::prepare_ui`] only looks at the shapes and textures.
+    fn build_ui(&mut self, raw_input: egui::RawInput) -> egui::FullOutput {
+        if self.crash.is_crashed() {
+            return self.build_crash_screen(raw_input);
+        }
+        if !self.loader.is_done() {
+            return self.build_loading_screen(raw_input);
+        }
+        let frame_start = Instant::now();
+        for task in self.tasks.poll() {
+            let name = task.name;
+            match (task.status, task.error) {
+                (tasks::TaskStatus::Failed, Some(error)) => self.toasts.error(i18n::tr_format("tasks.failed_with", &[("name", &name), ("error", &error)])),
+                (tasks::TaskStatus::Failed, None) => self.toasts.error(i18n::tr_format("tasks.failed", &[("name", &name)])),
+                (tasks::TaskStatus::Cancelled, _) => self.toasts.info(i18n::tr_format("tasks.cancelled", &[("name", &name)])),
+                _ => self.toasts.success(i18n::tr_format("tasks.completed", &[("name", &name)])),
+            }
+        }
+        while let Ok(event) = self.file_events_rx.try_recv() {
+            self.on_file_event(event);
+        }
+        for result in self.clipboard_images.poll() {
+            self.on_clipboard_result(result);
+        }
+        // The modal of a file dialog takes the input of the widgets, the shortcuts wait as well.
+        let dialog_open = self.dialogs.is_pending();
+        let paste = std::mem::take(&mut self.paste_shortcut) && !dialog_open;
+
+        let mut start_demo_task = None;
+        let mut menu_action = None;
+        let mut image_demo_action = None;
+        let mut painting_action = None;
+        let mut close_requested = false;
+        let mut read_back_colors = false;
+        // Before the pass, whose output is then the first one with the new atlas and without the freed images.
+        if self.ui_debug.take_trim_memory() {
+            self.trim_memory(memory_trim::TrimLevel::Full);
+        }
+        if self.ui_image_demo.needs_textures() {
+            self.create_demo_textures();
+        }
+        // Simulations step at a fixed rate however often frames come, the UI shows them blended between two steps.
+        let update_steps = self.update_simulations(Instant::now());
+        self.frame_clock.tick(self.ui_state.egui_ctx(), frame_start);
+        let update_alpha = self.timestep.alpha();
+        let ui_ctx = self.ui_state.egui_ctx().clone();
+        self.watchdog.set_phase(watchdog::Phase::Run);
+        let menu_state = ui::MenuState {
+            has_file: self.file_path.is_some(),
+            has_config_folder: self.config_file.path().is_some(),
+            recording: self.recording.is_some().then_some(self.recording_format),
+            replay_idle: self.input_replay.is_idle(),
+            replay_recording: self.input_replay.is_recording(),
+            always_on_top: self.window_level == WindowLevel::AlwaysOnTop,
+            diagnostics_detached: self.ui_diagnostics.is_detached(),
+            layouts: self.layouts.names(),
+            current_layout: self.layouts.current().map(str::to_owned),
+        };
+        // Nothing the closure borrows is used again after a panic but the gallery, which is replaced.
+        let ran = panic::catch_unwind(AssertUnwindSafe(|| ui_ctx.run(raw_input, |ctx| {
+            profiler::profile_scope!("ui_ctx.run");
+            if self.custom_decorations && decorations::title_bar(ctx, &self.window, &self.watchdog) == decorations::TitleBarAction::Close {
+                close_requested = true;
+            }
+
+            // F9 toggles the recording from anywhere, with the format used last.
+            let shortcut = |key| !dialog_open && ctx.input(|i| i.key_pressed(key));
+            if shortcut(egui::Key::F9) {
+                menu_action = Some(ui::MenuAction::ToggleRecording(self.recording_format));
+            }
+            if shortcut(egui::Key::F8) {
+                menu_action = Some(ui::MenuAction::NextLayout);
+            }
+            if shortcut(egui::Key::F11) {
+                self.window_commands.push(window_commands::WindowCommand::SetFullscreen(self.window.fullscreen().is_none()));
+            }
+
+            // Before the central panel, which gets the room left above it.
+            self.on_screen_keyboard.show(ctx, self.settings.on_screen_keyboard);
+
+            let main_ui = |ui: &mut egui::Ui| {
+                self.scene.paint(ui);
+                if let Some(action) = ui::main_menu(ui, &menu_state, &mut self.ui_debug) {
+                    menu_action = Some(action);
+                }
+            };
+
+            // A central panel would cover the whole window and swallow every click, so the overlay uses a window instead.
+            if self.overlay {
+                egui::Window::new("Overlay").show(ctx, main_ui);
+            } else {
+                egui::CentralPanel::default().show(ctx, main_ui);
+            }
+
+            self.ui_gallery.show(ctx);
+            self.ui_counter.show(ctx);
+            start_demo_task = self.ui_tasks.show(ctx, &mut self.tasks);
+            self.ui_toasts_demo.show(ctx, &mut self.toasts);
+            self.ui_log_console.show(ctx, &self.logs);
+            self.ui_settings.show(ctx, &mut self.settings);
+            let r = &self.gpu_resources;
+            self.ui_diagnostics.show(ctx, &self.stats, &self.ui_stats, &self.settings, r.render_size(), &self.watchdog);
+            self.ui_gpu_memory.show(ctx, &r.device, &self.ui_stats, self.trim_report.as_ref());
+            let gpu_timing = r.device.features().contains(wgpu::Features::TIMESTAMP_QUERY);
+            self.ui_stress_test.show(ctx, &self.ui_stats, gpu_timing, &mut self.ui_debug.overdraw);
+            self.ui_debug.show(ctx);
+            if std::mem::take(&mut self.inflating_font_atlas) {
+                fonts::inflate_atlas(ctx);
+            }
+            self.ui_gradients.show(ctx);
+            self.ui_pixel_test.show(ctx, self.window.scale_factor() as f32);
+            read_back_colors = self.ui_color_test.show(ctx, r.surface_config.format, r.surface_format);
+            // A focused text field gets the text on the clipboard instead.
+            let paste = paste && !ctx.wants_keyboard_input();
+            image_demo_action = self.ui_image_demo.show(ctx, paste);
+            (self.ui_stats.painting_points, self.ui_stats.painting_shapes, painting_action) = self.ui_painting.show(ctx, paste);
+            self.dialogs.show(ctx);
+            self.ui_reorder.show(ctx);
+            self.ui_modal_demo.show(ctx);
+            self.ui_zoom_canvas.show(ctx);
+            self.ui_bouncing_ball.show(ctx, update_alpha, update_steps);
+            if self.ui_camera.show(ctx, self.cursor_grabbed) {
+                self.window_commands.push(window_commands::WindowCommand::SetCursorGrab(true));
+            }
+            let unsaved_changes = self.unsaved_changes;
+            let exit_choice = self.ui_exit_confirm.show(ctx, |ui| {
+                ui.label(if unsaved_changes { tr("exit.unsaved") } else { tr("exit.saved") });
+            });
+            if exit_choice == Some(ui::ModalChoice::Ok) {
+                self.window_commands.push(window_commands::WindowCommand::Exit);
+            }
+            if let Some(change) = self.ui_surface_info.show(ctx, &r.adapter, &r.capabilities, r.surface.as_ref(), &r.surface_config) {
+                self.surface_change = Some(change);
+            }
+            if let Some(adapter) = self.ui_adapters.show(ctx, &r.instance, &r.adapter.get_info()) {
+                self.adapter_switch = Some(adapter);
+            }
+            #[cfg(feature = "profiling")]
+            self.ui_profiler.show(ctx);
+            self.toasts.show(ctx);
+            #[cfg(feature = "gamepad")]
+            ui::gamepad_indicator(ctx, self.gamepad.controllers());
+            if let Some(recording) = &self.recording {
+                ui::recording_indicator(ctx, recording.elapsed(), recording.dropped());
+            }
+
+            if self.custom_decorations {
+                decorations::resize_handles(ctx, &self.window, &self.watchdog);
+            }
+        })));
+        let mut ui_out = match ran {
+            Ok(ui_out) => ui_out,
+            Err(payload) => return self.on_ui_panic(payload),
+        };
+        self.crash.frame_succeeded();
+
+        self.paint_immediate_viewports(&ui_ctx);
+
+        if let Some(action) = menu_action {
+            self.on_menu_action(action);
+        }
+        if let Some(action) = image_demo_action {
+            self.on_image_demo_action(action);
+        }
+        if let Some(action) = painting_action {
+            self.on_painting_action(action);
+        }
+        if let Some((callback, path)) = self.dialogs.take_finished() {
+            callback(self, path);
+        }
+        if read_back_colors {
+            self.request_color_readback();
+        }
+        if self.ui_debug.take_inflate_atlas() {
+            self.inflate_font_atlas();
+        }
+        if close_requested {
+            self.window_commands.push(window_commands::WindowCommand::RequestExit);
+        }
+        self.update_title();
+
+        match start_demo_task {
+            Some(ui::DemoTask::Steps) => {
+                self.spawn_task("Demo task", |progress| {
+                    for i in 0..=100 {
+                        if progress.is_cancelled() {
+                            return;
+                        }
+                        if i % 10 == 0 {
+                            progress.log(format!("Step {i}"));
+                        }
+                        progress.set_progress(i as f32 / 100.0);
+                        std::thread::sleep(Duration::from_millis(50));
+                    }
+                });
+            },
+            // Long enough to switch to another app and see the taskbar button asking for attention.
+            Some(ui::DemoTask::Sleep) => {
+                self.spawn_task("Sleep 10 seconds", |progress| {
+                    for i in 0..100 {
+                        if progress.is_cancelled() {
+                            return;
+                        }
+                        progress.set_progress(i as f32 / 100.0);
+                        std::thread::sleep(Duration::from_millis(100));
+                    }
+                });
+            },
+            None => {},
+        }
+
+
+        self.handle_ui_output(&mut ui_out);
+
+        // Timestamps are only written for the stress test, so the timer is only around while it's open.
+        let r = &self.gpu_resources;
+        let timed = self.ui_stress_test.is_open() && r.device.features().contains(wgpu::Features::TIMESTAMP_QUERY);
+        if timed != self.ui_gpu_timer.is_some() {
+            self.ui_gpu_timer = timed.then(|| bench::GpuTimer::new(&r.device, &r.queue));
+            self.ui_stats.gpu_time = None;
+        }
+        ui_out
+    }
+
+    /// Hands the platform and viewport output of a pass to winit and the viewports.
+    fn handle_ui_output(&mut self, ui_out: &mut egui::FullOutput) {
+        let ui_ctx = self.ui_state.egui_ctx().clone();
+        // Besides the cursor and clipboard this turns IME on while a text field has focus and moves the candidate window
+        // to the text cursor. That uses egui's points per pixel of the window, which a render scale doesn't change.
+        self.ui_state.handle_platform_output(&self.window, std::mem::take(&mut ui_out.platform_output));
+        if let Some(viewport_output) = ui_out.viewport_output.get(&self.ui_state.egui_input().viewport_id) {
+            self.schedule_repaint(self.frame_clock.repaint_delay(viewport_output.repaint_delay, Instant::now()));
+        }
+        self.viewports.borrow_mut().add_output(std::mem::take(&mut ui_out.viewport_output));
+
+        if self.overlay {
+            self.set_cursor_hittest(ui_ctx.is_pointer_over_area() || ui_ctx.is_using_pointer());
+        }
+
+        #[cfg(target_os = "android")]
+        self.update_soft_input(ui_ctx.wants_keyboard_input());
+    }
+
+    /// The UI panicked in the middle of its pass. Ending the pass keeps egui consistent, and the texture changes in it
+    /// still have to reach the GPU, egui runs only one pass per frame here so they're all there are. What it drew is
+    /// dropped, the crash screen takes over from the next frame.
+    fn on_ui_panic(&mut self, payload: Box<dyn std::any::Any + Send>) -> egui::FullOutput {
+        let mut ui_out = self.ui_state.egui_ctx().end_pass();
+        ui_out.shapes.clear();
+        if !self.crash.on_panic(payload) {
+            log::error!("The UI keeps panicking, exiting");
+            self.window_commands.push(window_commands::WindowCommand::Exit);
+        }
+        self.handle_ui_output(&mut ui_out);
+        self.schedule_repaint(Duration::ZERO);
+        ui_out
+    }
+
+    /// Shown instead of the UI after it panicked, until the user goes on or quits. It can panic as well.
+    fn build_crash_screen(&mut self, raw_input: egui::RawInput) -> egui::FullOutput {
+        let ui_ctx = self.ui_state.egui_ctx().clone();
+        self.watchdog.set_phase(watchdog::Phase::Run);
+        let mut action = None;
+        let ran = panic::catch_unwind(AssertUnwindSafe(|| ui_ctx.run(raw_input, |ctx| action = self.crash.show(ctx))));
+        let mut ui_out = match ran {
+            Ok(ui_out) => ui_out,
+            Err(payload) => return self.on_ui_panic(payload),
+        };
+        match action {
+            Some(crash::CrashAction::Continue) => {
+                self.ui_gallery = ui::WidgetGallery::default();
+                self.schedule_repaint(Duration::ZERO);
+            },
+            Some(crash::CrashAction::Quit) => self.window_commands.push(window_commands::WindowCommand::Exit),
+            None => {},
+        }
+        self.handle_ui_output(&mut ui_out);
+        ui_out
+    }
+
+    /// The loading screen, with the window's own title bar if it has one so it can be moved and closed meanwhile.
+    fn build_loading_screen(&mut self, raw_input: egui::RawInput) -> egui::FullOutput {
+        // Only the loading task runs yet, how it ended is shown here rather than as a toast.
+        self.tasks.poll();
+        if let Some(loaded) = self.loader.poll(&self.tasks) {
+            self.fonts = loaded.fonts;
+            self.ui_state.egui_ctx().set_fonts(self.fonts.clone());
+            self.schedule_repaint(Duration::ZERO);
+        }
+        let ui_ctx = self.ui_state.egui_ctx().clone();
+        self.watchdog.set_phase(watchdog::Phase::Run);
+        let mut action = None;
+        let mut close_requested = false;
+        let ran = panic::catch_unwind(AssertUnwindSafe(|| ui_ctx.run(raw_input, |ctx| {
+            if self.custom_decorations && decorations::title_bar(ctx, &self.window, &self.watchdog) == decorations::TitleBarAction::Close {
+                close_requested = true;
+            }
+            action = self.loader.show(ctx, &mut self.tasks);
+            if self.custom_decorations {
+                decorations::resize_handles(ctx, &self.window, &self.watchdog);
+            }
+        })));
+        let mut ui_out = match ran {
+            Ok(ui_out) => ui_out,
+            Err(payload) => return self.on_ui_panic(payload),
+        };
+        if close_requested || action == Some(loading::LoadAction::Quit) {
+            self.window_commands.push(window_commands::WindowCommand::RequestExit);
+        }
+        self.handle_ui_output(&mut ui_out);
+        ui_out
+    }
+
+    /// Tessellates the UI and uploads its textures, and its vertices and indices with `ce`.
+    fn prepare_ui(&mut self, ui_out: egui::FullOutput, ce: &mut wgpu::CommandEncoder) -> PreparedFrame {
+        let ui_ctx = self.ui_state.egui_ctx().clone();
+
+        // With a render scale the whole UI is laid out as usual and just drawn with fewer or more pixels per point.
+        let r = &self.gpu_resources;
+        let pixels_per_point = ui_out.pixels_per_point * r.render_scale;
+        let mut shapes = ui_out.shapes;
+        if self.settings.pixel_snap != PixelSnap::Off {
+            // The tessellator culls against the clip rects as they are, while egui-wgpu rounds them to whole pixels for
+            // the scissor rects. Rounding them first makes both agree on which pixels a clip rect covers.
+            for shape in &mut shapes {
+                shape.clip_rect = shape.clip_rect.round_to_pixels(pixels_per_point);
+            }
+        }
+        let shape_count = shapes.len();
+        let tessellate_start = Instant::now();
+        self.watchdog.set_phase(watchdog::Phase::Tessellate);
+        let (clipped_primitives, cached) = {
+            profiler::profile_scope!("tessellate");
+            self.tessellation_cache.tessellate(&ui_ctx, shapes, pixels_per_point)
+        };
+        self.ui_stats.frame_tessellated(shape_count, &clipped_primitives, tessellate_start.elapsed(), cached, ui_ctx.screen_rect());
+        let upload_start = Instant::now();
+        self.watchdog.set_phase(watchdog::Phase::Upload);
+        if let Some(bench) = &mut self.bench {
+            bench.add(bench::Phase::Cpu, upload_start - tessellate_start);
+        }
+
+        let screen_descriptor = egui_wgpu::ScreenDescriptor {
+            size_in_pixels: r.render_size(),
+            pixels_per_point,
+        };
+
+        let max_side = r.texture_limit();
+        let mut atlas_overflowed = false;
+        for (id, delta) in &ui_out.textures_delta.set {
+            // Either would make egui-wgpu panic, the texture is left as it was instead.
+            if !fonts::delta_fits(delta, max_side) {
+                log::warn!("Not uploading {id:?}, {:?} pixels don't fit into the GPU's textures of {max_side}", delta.image.size());
+                atlas_overflowed |= *id == egui::TextureId::default();
+                continue;
+            }
+            if delta.pos.is_some() && self.ui_painter.texture(id).is_none() {
+                log::warn!("Not updating {id:?}, it was never uploaded");
+                continue;
+            }
+            self.ui_painter.update_texture(&r.device, &r.queue, *id, delta);
+            self.ui_stats.texture_uploaded(*id);
+        }
+        {
+            profiler::profile_scope!("update_buffers");
+            if self.tessellation_cache.needs_upload(&screen_descriptor) {
+                self.ui_painter.update_buffers(&r.device, &r.queue, ce, &clipped_primitives, &screen_descriptor);
+            }
+        }
+        if let Some(bench) = &mut self.bench {
+            bench.add(bench::Phase::Upload, upload_start.elapsed());
+        }
+        if atlas_overflowed {
+            self.on_font_atlas_overflow();
+        }
+
+        PreparedFrame {
+            clipped_primitives,
+            screen_descriptor,
+            textures_to_free: ui_out.textures_delta.free,
+        }
+    }
+
+    /// Reconfigures the surface with a setting picked in the Surface Info window.
+    fn apply_surface_change(&mut self, change: ui::SurfaceChange) {
+        log::info!("Reconfiguring the surface: {change:?}");
+        let config = &mut self.gpu_resources.surface_config;
+        match change {
+            ui::SurfaceChange::Format(format) => {
+                config.format = format;
+                self.gpu_resources.update_view_format();
+                self.rebuild_ui_renderer();
+            },
+            ui::SurfaceChange::PresentMode(mode) => config.present_mode = mode,
+            ui::SurfaceChange::AlphaMode(mode) => config.alpha_mode = mode,
+        }
+        self.gpu_resources.resize(self.window.inner_size());
+        self.schedule_repaint(Duration::ZERO);
+    }
+
+    fn on_menu_action(&mut self, action: ui::MenuAction) {
+        match action {
+            ui::MenuAction::Open => self.pick_file(dialogs::DialogKind::Open, tr("menu.open"), Self::open_file),
+            ui::MenuAction::Save => {
+                if let Some(path) = self.file_path.clone() {
+                    self.save_file(path);
+                }
+            },
+            ui::MenuAction::SaveAs => self.pick_file(dialogs::DialogKind::Save, tr("menu.save_as"), Self::save_file),
+            ui::MenuAction::OpenConfigFolder => self.open_config_folder(),
+            ui::MenuAction::ClickMe => self.toasts.info(tr("menu.button_clicked")),
+            ui::MenuAction::Show(window) => match window {
+                ui::ToolWindow::Gallery => self.ui_gallery.set_open(true),
+                ui::ToolWindow::Toasts => self.ui_toasts_demo.open(),
+                ui::ToolWindow::LogConsole => self.ui_log_console.open(),
+                ui::ToolWindow::Settings => self.ui_settings.open(),
+                ui::ToolWindow::Diagnostics => self.ui_diagnostics.open(),
+                ui::ToolWindow::GpuMemory => self.ui_gpu_memory.open(),
+                ui::ToolWindow::StressTest => self.ui_stress_test.open(),
+                ui::ToolWindow::SurfaceInfo => self.ui_surface_info.open(),
+                ui::ToolWindow::Adapters => self.ui_adapters.open(),
+                #[cfg(feature = "profiling")]
+                ui::ToolWindow::Profiler => self.ui_profiler.open(),
+                ui::ToolWindow::Images => self.ui_image_demo.open(),
+                ui::ToolWindow::Painting => self.ui_painting.open(),
+                ui::ToolWindow::Reorder => self.ui_reorder.open(),
+                ui::ToolWindow::Modal => self.ui_modal_demo.open(),
+                ui::ToolWindow::Camera => self.ui_camera.open(),
+                ui::ToolWindow::ZoomCanvas => self.ui_zoom_canvas.open(),
+                ui::ToolWindow::BouncingBall => self.ui_bouncing_ball.open(),
+                ui::ToolWindow::Gradients => self.ui_gradients.open(),
+                ui::ToolWindow::PixelTest => self.ui_pixel_test.open(),
+                ui::ToolWindow::ColorTest => self.ui_color_test.open(),
+            },
+            ui::MenuAction::ToggleRecording(format) => self.toggle_recording(format),
+            ui::MenuAction::Replay(action) => self.on_replay_action(action),
+            ui::MenuAction::NextMonitor => placement::move_to_next_monitor(&self.window),
+            ui::MenuAction::SetAlwaysOnTop(always_on_top) => {
+                self.settings.window_level = if always_on_top { settings::WindowLevel::AlwaysOnTop } else { settings::WindowLevel::Normal };
+                // The overlay is on top while the setting may still say normal.
+                self.applied_window_level = None;
+            },
+            ui::MenuAction::DetachDiagnostics(detached) => self.ui_diagnostics.set_detached(detached),
+            ui::MenuAction::SaveLayout(name) => {
+                let open: Vec<_> = ui::ToolWindow::WITH_LAYOUT.iter()
+                    .filter_map(|&window| Some((window, self.tool_window(window)?.is_open())))
+                    .collect();
+                self.layouts.save_profile(layouts::LayoutProfile::capture(self.ui_state.egui_ctx(), name, open));
+            },
+            ui::MenuAction::SwitchLayout(name) => {
+                if let Some(profile) = self.layouts.switch_to(&name).cloned() {
+                    self.apply_layout(&profile);
+                }
+            },
+            ui::MenuAction::NextLayout => {
+                if let Some(profile) = self.layouts.switch_to_next().cloned() {
+                    self.apply_layout(&profile);
+                }
+            },
+            ui::MenuAction::DeleteLayout(name) => self.layouts.delete(&name),
+        }
+        // The pass is over, a window opened here only shows up in the next one.
+        self.schedule_repaint(Duration::ZERO);
+    }
+
+    /// The open flag of `window`, `None` for the modal demo, which has none.
+    fn tool_window(&mut self, window: ui::ToolWindow) -> Option<&mut dyn ToolWindowOpen> {
+        Some(match window {
+            ui::ToolWindow::Gallery => &mut self.ui_gallery,
+            ui::ToolWindow::Toasts => &mut self.ui_toasts_demo,
+            ui::ToolWindow::LogConsole => &mut self.ui_log_console,
+            ui::ToolWindow::Settings => &mut self.ui_settings,
+            ui::ToolWindow::Diagnostics => &mut self.ui_diagnostics,
+            ui::ToolWindow::GpuMemory => &mut self.ui_gpu_memory,
+            ui::ToolWindow::StressTest => &mut self.ui_stress_test,
+            ui::ToolWindow::SurfaceInfo => &mut self.ui_surface_info,
+            ui::ToolWindow::Adapters => &mut self.ui_adapters,
+            #[cfg(feature = "profiling")]
+            ui::ToolWindow::Profiler => &mut self.ui_profiler,
+            ui::ToolWindow::Images => &mut self.ui_image_demo,
+            ui::ToolWindow::Painting => &mut self.ui_painting,
+            ui::ToolWindow::Reorder => &mut self.ui_reorder,
+            ui::ToolWindow::Modal => return None,
+            ui::ToolWindow::Camera => &mut self.ui_camera,
+            ui::ToolWindow::ZoomCanvas => &mut self.ui_zoom_canvas,
+            ui::ToolWindow::BouncingBall => &mut self.ui_bouncing_ball,
+            ui::ToolWindow::Gradients => &mut self.ui_gradients,
+            ui::ToolWindow::PixelTest => &mut self.ui_pixel_test,
+            ui::ToolWindow::ColorTest => &mut self.ui_color_test,
+        })
+    }
+
+    /// Opens and closes the tool windows the way `profile` has them, they're moved there in the next frame. Windows
+    /// that didn't exist yet when it was saved stay as they are.
+    fn apply_layout(&mut self, profile: &layouts::LayoutProfile) {
+        profile.apply(self.ui_state.egui_ctx());
+        for &window in ui::ToolWindow::WITH_LAYOUT {
+            if let (Some(layout), Some(flag)) = (profile.window(window), self.tool_window(window)) {
+                flag.set_open(layout.open);
+            }
+        }
+    }
+
+    fn on_replay_action(&mut self, action: ui::ReplayAction) {
+        let path = storage::data_dir().unwrap_or_default().join("input.rec");
+        let result = match action {
+            ui::ReplayAction::Record => {
+                self.input_replay.start_recording();
+                Ok(())
+            },
+            ui::ReplayAction::Play => self.input_replay.start_playback(&path),
+            ui::ReplayAction::Stop => self.input_replay.stop(&path),
+        };
+        if let Err(error) = result {
+            log::error!("Input replay failed with {}: {error}", path.display());
+            self.toasts.error(i18n::tr_format("notices.replay_failed", &[("error", &error)]));
+        }
+        self.schedule_repaint(Duration::ZERO);
+    }
+
+    /// Has the frame that's being built read back for the color test once it's rendered.
+    fn request_color_readback(&mut self) {
+        let config = &self.gpu_resources.surface_config;
+        if !config.usage.contains(wgpu::TextureUsages::COPY_SRC) || !capture::is_supported_format(config.format) {
+            self.toasts.error(i18n::tr_format("notices.no_readback", &[("format", &format!("{:?}", config.format))]));
+            return;
+        }
+        self.color_readback = true;
+    }
+
+    /// Stops the running recording, or starts a new one in `format`.
+    fn toggle_recording(&mut self, format: recording::RecordingFormat) {
+        if let Some(recording) = self.recording.take() {
+            // The writer thread finishes the file on its own once the recording is dropped.
+            self.toasts.success(i18n::tr_format("notices.recording_to", &[("path", &recording.path().display())]));
+            return;
+        }
+
+        let config = &self.gpu_resources.surface_config;
+        if !config.usage.contains(wgpu::TextureUsages::COPY_SRC) || !capture::is_supported_format(config.format) {
+            self.toasts.error(i18n::tr_format("notices.no_recording", &[("format", &format!("{:?}", config.format))]));
+            return;
+        }
+
+        let dir = storage::data_dir().unwrap_or_default().join("recordings");
+        match recording::Recording::start(format, &dir) {
+            Ok(recording) => {
+                self.recording = Some(recording);
+                self.recording_format = format;
+            },
+            Err(error) => {
+                log::error!("Failed to start recording: {error}");
+                self.toasts.error(i18n::tr_format("notices.recording_failed", &[("error", &error)]));
+            },
+        }
+    }
+
+    /// Makes `view` usable in egui, sampled with the given options instead of egui's defaults.
+    fn register_native_texture_with_options(&mut self, view: &wgpu::TextureView, sampler: wgpu::SamplerDescriptor<'static>) -> egui::TextureId {
+        self.native_textures.register(&mut self.ui_painter, &self.gpu_resources.device, view, sampler)
+    }
+
+    /// Puts a different texture behind `id`, e.g. after the render target it showed was recreated for a new size.
+    fn update_native_texture(&mut self, id: egui::TextureId, view: &wgpu::TextureView) {
+        self.native_textures.update(&mut self.ui_painter, &self.gpu_resources.device, id, view);
+    }
+
+    fn create_pixel_art_texture(&mut self) -> wgpu::TextureView {
+        let r = &self.gpu_resources;
+        let texture = native_textures::create_texture(&r.device, [16, 16], 1);
+        native_textures::upload(&r.queue, &texture, &native_textures::demo_pixel_art(self.pixel_art_variant), false);
+        texture.create_view(&wgpu::TextureViewDescriptor::default())
+    }
+
+    fn create_demo_textures(&mut self) {
+        let sampler = native_textures::sampler(self.ui_image_demo.nearest, self.ui_image_demo.mipmaps);
+        let pixel_art = self.create_pixel_art_texture();
+        let pixel_art = self.register_native_texture_with_options(&pixel_art, sampler.clone());
+
+        let r = &self.gpu_resources;
+        let size = 512;
+        let checkerboard = native_textures::create_texture(&r.device, [size, size], size.ilog2() + 1);
+        native_textures::upload(&r.queue, &checkerboard, &native_textures::demo_checkerboard(size), true);
+        let checkerboard = self.register_native_texture_with_options(&checkerboard.create_view(&wgpu::TextureViewDescriptor::default()), sampler);
+
+        self.ui_image_demo.set_textures(pixel_art, checkerboard);
+    }
+
+    fn on_image_demo_action(&mut self, action: ui::ImageDemoAction) {
+        let (Some(pixel_art), Some(checkerboard)) = (self.ui_image_demo.pixel_art(), self.ui_image_demo.checkerboard()) else {
+            return;
+        };
+        match action {
+            ui::ImageDemoAction::Paste => self.paste_image(clipboard::PasteTarget::ImageDemo),
+            ui::ImageDemoAction::SamplerChanged => {
+                for id in [pixel_art, checkerboard] {
+                    let sampler = native_textures::sampler(self.ui_image_demo.nearest, self.ui_image_demo.mipmaps);
+                    self.native_textures.set_sampler(&mut self.ui_painter, &self.gpu_resources.device, id, sampler);
+                }
+            },
+            ui::ImageDemoAction::Regenerate => {
+                self.pixel_art_variant += 1;
+                let view = self.create_pixel_art_texture();
+                self.update_native_texture(pixel_art, &view);
+            },
+        }
+    }
+
+    fn on_painting_action(&mut self, action: ui::PaintingAction) {
+        match action {
+            ui::PaintingAction::Paste => self.paste_image(clipboard::PasteTarget::Painting),
+            ui::PaintingAction::CopyImage { shapes, size } => match self.render_shapes(shapes, size) {
+                Ok(image) => self.clipboard_images.copy(image),
+                Err(error) => self.toasts.error(error),
+            },
+        }
+    }
+
+    fn paste_image(&mut self, target: clipboard::PasteTarget) {
+        let max_side = self.gpu_resources.device.limits().max_texture_dimension_2d as usize;
+        self.clipboard_images.paste(target, max_side);
+    }
+
+    fn on_clipboard_result(&mut self, result: clipboard::ClipboardResult) {
+        match result {
+            clipboard::ClipboardResult::Pasted(target, image) => {
+                let texture = self.ui_state.egui_ctx().load_texture("pasted", image, egui::TextureOptions::default());
+                match target {
+                    clipboard::PasteTarget::ImageDemo => self.ui_image_demo.add_pasted(texture),
+                    clipboard::PasteTarget::Painting => self.ui_painting.add_image(texture),
+                }
+            },
+            clipboard::ClipboardResult::Copied => self.toasts.success(tr("images.copied")),
+            clipboard::ClipboardResult::Failed(error) => self.toasts.error(error),
+        }
+    }
+
+    /// Draws `shapes` on their own into a texture of `size` points and reads it back, the way the UI would show them.
+    /// Waits for the GPU, this is for one-off copies.
+    fn render_shapes(&mut self, shapes: Vec<egui::epaint::ClippedShape>, size: egui::Vec2) -> Result<egui::ColorImage, String> {
+        let r = &self.gpu_resources;
+        // The renderer's pipelines only work with the format and sample count it was built for.
+        let format = r.surface_format;
+        if !capture::is_supported_format(format) {
+            return Err(format!("Can't read back a {format:?} image"));
+        }
+        let pixels_per_point = self.ui_state.egui_ctx().pixels_per_point();
+        let size_in_pixels = [(size.x * pixels_per_point).round() as u32, (size.y * pixels_per_point).round() as u32];
+        let max_side = r.device.limits().max_texture_dimension_2d;
+        if size_in_pixels.contains(&0) || size_in_pixels.iter().any(|&side| side > max_side) {
+            return Err(format!("Can't render an image of {} × {} pixels", size_in_pixels[0], size_in_pixels[1]));
+        }
+
+        let texture = r.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Shape Image"),
+            size: wgpu::Extent3d { width: size_in_pixels[0], height: size_in_pixels[1], depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut msaa = None;
+        offscreen::MsaaTarget::prepare(&mut msaa, &r.device, format, size_in_pixels, r.msaa_samples);
+
+        let primitives = self.ui_state.egui_ctx().tessellate(shapes, pixels_per_point);
+        let screen_descriptor = egui_wgpu::ScreenDescriptor { size_in_pixels, pixels_per_point };
+        let mut ce = r.device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        self.ui_painter.update_buffers(&r.device, &r.queue, &mut ce, &primitives, &screen_descriptor);
+        // The next frame has to upload its own primitives again.
+        self.tessellation_cache.invalidate_upload();
+        {
+            let render_pass = ce.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Shape Image"),
+                color_attachments: &[Some(offscreen::MsaaTarget::color_attachment(msaa.as_ref(), &view, wgpu::Color::TRANSPARENT))],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            self.ui_painter.render(&mut render_pass.forget_lifetime(), &primitives, &screen_descriptor);
+        }
+        let mut readback = capture::FrameReadback::new(1);
+        readback.copy(&r.device, &mut ce, &texture, Instant::now());
+        r.queue.submit(std::iter::once(ce.finish()));
+        readback.submitted();
+        let _ = r.device.poll(wgpu::Maintain::Wait);
+        let frame = readback.poll(&r.device).pop().ok_or("Failed to read back the image")?;
+        Ok(egui::ColorImage::from_rgba_premultiplied([frame.size[0] as usize, frame.size[1] as usize], &frame.rgba))
+    }
+
+    /// Waits for the frame copied for the color test and hands it over, this is a one-off.
+    fn check_colors(&mut self, readback: &mut capture::FrameReadback) {
+        let r = &self.gpu_resources;
+        readback.submitted();
+        let _ = r.device.poll(wgpu::Maintain::Wait);
+        let Some(frame) = readback.poll(&r.device).pop() else {
+            self.toasts.error("Failed to read back the surface");
+            return;
+        };
+        let adjusted = self.settings.post_effect == settings::PostEffect::ColorAdjust
+            && (self.settings.gamma != 1.0 || self.settings.brightness != 0.0);
+        self.ui_color_test.check(&frame, r.surface_config.format, r.surface_format, adjusted);
+    }
+
+    /// Recreates the egui renderer after the format or options it was created with changed.
+    fn rebuild_ui_renderer(&mut self) {
+        // They are baked into its pipeline, so the renderer has to be rebuilt. That drops all of its textures too,
+        // rebuilding the font atlas makes egui upload them again.
+        self.ui_painter = self.gpu_resources.create_ui_renderer();
+        self.tessellation_cache.invalidate_upload();
+        // The viewport windows' surfaces are configured for the old format or belong to the old device.
+        self.viewports.borrow_mut().close_all();
+        self.ui_stats.textures_dropped();
+        self.native_textures.restore(&mut self.ui_painter, &self.gpu_resources.device);
+        self.free_ui_caches();
+    }
+
+    /// Moves all rendering over to a different adapter. Only the GPU side is rebuilt,
+    /// the egui context and everything shown in it stays as it is.
+    fn switch_adapter(&mut self, adapter: &wgpu::AdapterInfo) {
+        log::info!("Switching to adapter {:?}", adapter.name);
+        // Nothing of the old device may still be in use once it's dropped.
+        let _ = self.gpu_resources.device.poll(wgpu::Maintain::Wait);
+        let config = GpuConfig {
+            frame_latency: self.gpu_resources.surface_config.desired_maximum_frame_latency,
+            ..self.gpu_resources.config.clone()
+        };
+        // Some platforms allow only one surface per window at a time.
+        self.gpu_resources.drop_surface();
+
+        match pollster::block_on(GpuResources::new(&self.window, &config, Some(adapter))) {
+            Ok(gpu_resources) => {
+                self.gpu_resources = gpu_resources;
+                self.ui_gpu_timer = None;
+                self.set_max_texture_side(self.gpu_resources.max_texture_side);
+                // Native textures belong to the old device, whoever made them has to create them again.
+                self.native_textures = native_textures::NativeTextures::default();
+                self.ui_image_demo.forget_textures();
+                self.rebuild_ui_renderer();
+            },
+            Err(error) => {
+                log::error!("Failed to switch to adapter {:?}: {error}", adapter.name);
+                self.toasts.error(i18n::tr_format("notices.switch_failed", &[("adapter", &adapter.name), ("error", &error)]));
+                self.gpu_resources.create_surface(&self.window);
+            },
+        }
+        self.schedule_repaint(Duration::ZERO);
+    }
+
+    fn paint_ui(&mut self, frame: &PreparedFrame, render_pass: &mut wgpu::RenderPass<'static>) {
+        profiler::profile_scope!("render");
+        self.watchdog.set_phase(watchdog::Phase::Render);
+        self.ui_painter.render(render_pass, &frame.clipped_primitives, &frame.screen_descriptor);
+    }
+
+    /// Has to be called for every built frame once it was painted, or dropped because there was no surface texture.
+    fn finish_ui(&mut self, frame: PreparedFrame) {
+        for id in frame.textures_to_free {
+            self.ui_painter.free_texture(&id);
+            self.ui_stats.texture_freed(id);
+        }
+        if self.trim_report.is_some_and(|report| report.after.is_none()) {
+            let after = self.cache_usage();
+            self.trim_report.as_mut().unwrap().after = Some(after);
+        }
+    }
+
+    /// Applies changed settings that need more than just being read at the right time.
+    fn apply_settings(&mut self) {
+        let frame_latency = self.settings.frame_latency;
+        if self.gpu_resources.surface_config.desired_maximum_frame_latency != frame_latency {
+            self.gpu_resources.surface_config.desired_maximum_frame_latency = frame_latency;
+            self.gpu_resources.resize(self.window.inner_size());
+        }
+        let config = &self.gpu_resources.config;
+        let settings = &self.settings;
+        if (settings.dithering, settings.srgb_view, settings.msaa_samples) != (config.dithering, config.srgb_view, config.msaa_samples) {
+            self.gpu_resources.config.dithering = self.settings.dithering;
+            self.gpu_resources.config.srgb_view = self.settings.srgb_view;
+            self.gpu_resources.config.msaa_samples = self.settings.msaa_samples;
+            self.gpu_resources.update_view_format();
+            self.gpu_resources.resize(self.window.inner_size());
+            self.rebuild_ui_renderer();
+        }
+        if i18n::language() != self.settings.language {
+            i18n::set_language(self.settings.language);
+            self.schedule_repaint(Duration::ZERO);
+        }
+        let ctx = self.ui_state.egui_ctx().clone();
+        let theme = self.settings.theme.preference();
+        if ctx.options(|options| options.theme_preference) != theme {
+            ctx.set_theme(theme);
+        }
+        // Ctrl +/- change egui's zoom factor directly, that's taken over unless the settings changed it themselves.
+        // With pixel snapping egui gets the closest zoom that lands on the snapped pixels per point instead, a step from
+        // the keyboard rounds away from the current one so it can't end up back where it was. Neither goes below the
+        // minimum UI scale, snapping rounds up from there.
+        let snap = self.settings.pixel_snap;
+        let native_pixels_per_point = self.window.scale_factor() as f32;
+        let min_zoom = self.settings.min_ui_scale;
+        let snap_zoom = |zoom: f32, round: fn(f32) -> f32| {
+            let snapped = snap.zoom_factor(zoom.max(min_zoom), native_pixels_per_point, round);
+            if snapped < min_zoom { snap.zoom_factor(min_zoom, native_pixels_per_point, f32::ceil) } else { snapped }
+        };
+        let mut zoom_factor = snap_zoom(self.settings.zoom_factor, f32::round);
+        if self.settings.zoom_factor == self.applied_zoom_factor && ctx.zoom_factor() != self.snapped_zoom_factor {
+            let round = if ctx.zoom_factor() > self.snapped_zoom_factor { f32::ceil } else { f32::floor };
+            zoom_factor = snap_zoom(ctx.zoom_factor(), round);
+            self.settings.zoom_factor = zoom_factor;
+        }
+        if ctx.zoom_factor() != zoom_factor {
+            ctx.set_zoom_factor(zoom_factor);
+        }
+        self.applied_zoom_factor = self.settings.zoom_factor;
+        self.snapped_zoom_factor = zoom_factor;
+        if ctx.tessellation_options(|options| options.feathering) != self.settings.feathering {
+            ctx.tessellation_options_mut(|options| options.feathering = self.settings.feathering);
+        }
+        let motion = motion::MotionPrefs { reduce_motion: self.settings.motion.reduced(self.system_motion.reduces()) };
+        if motion::MotionPrefs::get(&ctx) != motion {
+            motion.set(&ctx);
+        }
+        if self.applied_window_level != Some(self.settings.window_level) {
+            self.applied_window_level = Some(self.settings.window_level);
+            self.window_commands.push(window_commands::WindowCommand::SetWindowLevel(self.settings.window_level.winit()));
+        }
+        if self.applied_skip_taskbar != self.settings.skip_taskbar {
+            self.applied_skip_taskbar = self.settings.skip_taskbar;
+            self.window_commands.push(window_commands::WindowCommand::SetSkipTaskbar(self.settings.skip_taskbar));
+        }
+        if self.applied_resizable != self.settings.resizable {
+            self.applied_resizable = self.settings.resizable;
+            self.window_commands.push(window_commands::WindowCommand::SetResizable(self.settings.resizable));
+        }
+        #[cfg(all(target_os = "windows", feature = "tray"))]
+        if let Some(tray) = &self.tray {
+            tray.set_start_in_tray(self.settings.start_in_tray);
+        }
+        if let Some(change) = self.surface_change.take() {
+            self.apply_surface_change(change);
+        }
+        if let Some(adapter) = self.adapter_switch.take() {
+            self.switch_adapter(&adapter);
+        }
+    }
+
+    /// Shows the directory of the config file in the file manager, creating it first if needed.
+    fn open_config_folder(&mut self) {
+        let Some(dir) = self.config_file.path().and_then(std::path::Path::parent) else {
+            return;
+        };
+        if let Err(error) = storage::open_folder(dir) {
+            log::error!("Failed to open {}: {error}", dir.display());
+            self.toasts.error(i18n::tr_format("notices.config_folder_failed", &[("error", &error)]));
+        }
+    }
+
+    /// Asks for a demo state file starting at the current one, `then` gets it once it was picked.
+    fn pick_file(&mut self, kind: dialogs::DialogKind, title: &str, then: fn(&mut Self, PathBuf)) {
+        let path = self.file_path.clone().unwrap_or_else(default_save_path);
+        let request = dialogs::DialogRequest::new(kind, title.trim_end_matches('…'), &path).with_filter(tr("dialogs.filter"), &["json"]);
+        let picked = self.dialogs.pick_file(request, move |app: &mut Self, path| {
+            if let Some(path) = path {
+                then(app, path);
+            }
+        });
+        if !picked {
+            self.toasts.warning(tr("dialogs.busy"));
+        }
+    }
+
+    /// Writes the demo state to `path` in the background.
+    fn save_file(&mut self, path: PathBuf) {
+        let contents = save_file::SaveFile::new(&self.ui_gallery, &self.settings).to_string();
+        let events = self.file_events_tx.clone();
+        self.spawn_task(format!("Save {}", file_name(&path)), move |progress| {
+            match storage::write_atomic(&path, &contents) {
+                Ok(()) => {
+                    let _ = events.send(FileEvent::Saved { path, contents });
+                },
+                Err(error) => progress.fail(error.to_string()),
+            }
+        });
+    }
+
+    /// Reads and parses `path` in the background, it's applied once that's done.
+    fn open_file(&mut self, path: PathBuf) {
+        let events = self.file_events_tx.clone();
+        self.spawn_task(format!("Open {}", file_name(&path)), move |progress| {
+            match save_file::SaveFile::read(&path) {
+                Ok(file) => {
+                    let _ = events.send(FileEvent::Opened { path, file });
+                },
+                Err(error) => progress.fail(error),
+            }
+        });
+    }
+
+    fn on_file_event(&mut self, event: FileEvent) {
+        match event {
+            FileEvent::Saved { path, contents } => {
+                self.file_path = Some(path);
+                self.saved_state = contents;
+            },
+            FileEvent::Opened { path, file } => {
+                file.apply(&mut self.ui_gallery, &mut self.settings);
+                self.file_path = Some(path);
+                // Compared as it would be saved now, so values that didn't survive loading don't count as changes.
+                self.saved_state = save_file::SaveFile::new(&self.ui_gallery, &self.settings).to_string();
+            },
+        }
+    }
+
+    /// Queues a title change if the title or what its suffix shows changed.
+    fn update_title(&mut self) {
+        self.unsaved_changes = save_file::SaveFile::new(&self.ui_gallery, &self.settings).to_string() != self.saved_state;
+        let file_name = self.file_path.as_deref().map(file_name);
+        let title = self.settings.window_title(file_name.as_deref(), self.stats.achieved_fps(), self.unsaved_changes);
+        if title != self.window_title {
+            self.window_commands.push(window_commands::WindowCommand::SetTitle(title));
+        }
+    }
+
+    /// Flashes the taskbar button or bounces the dock icon, unless the user is looking at the window already.
+    /// Taken back once the window is focused.
+    fn request_attention(&mut self, urgency: UserAttentionType) {
+        if !self.window.has_focus() {
+            self.window.request_user_attention(Some(urgency));
+            self.attention_requested = true;
+        }
+    }
+
+    /// Applies the queued window commands in order, see [`window_commands::WindowCommands`].
+    fn apply_window_commands(&mut self, event_loop: &ActiveEventLoop) {
+        for command in self.window_commands.take() {
+            match command {
+                window_commands::WindowCommand::SetTitle(title) => {
+                    self.window.set_title(&title);
+                    self.window_title = title;
+                },
+                window_commands::WindowCommand::SetFullscreen(fullscreen) => {
+                    self.window.set_fullscreen(fullscreen.then_some(Fullscreen::Borderless(None)));
+                    // X11 and Windows can lose always on top on the way in or out of fullscreen.
+                    self.window.set_window_level(self.window_level);
+                },
+                window_commands::WindowCommand::SetWindowLevel(level) => {
+                    self.window_level = level;
+                    self.window.set_window_level(level);
+                },
+                window_commands::WindowCommand::SetSkipTaskbar(skip) => {
+                    #[cfg(target_os = "windows")]
+                    winit::platform::windows::WindowExtWindows::set_skip_taskbar(&*self.window, skip);
+                    #[cfg(not(target_os = "windows"))]
+                    if skip {
+                        log::info!("Hiding the window from the taskbar isn't supported on this platform");
+                    }
+                },
+                window_commands::WindowCommand::TaskProgress(progress) => self.taskbar.set(progress),
+                window_commands::WindowCommand::TaskCompleted => self.request_attention(UserAttentionType::Informational),
+                window_commands::WindowCommand::TaskFailed => self.request_attention(UserAttentionType::Critical),
+                // Not supported on mobile platforms, where winit ignores it.
+                window_commands::WindowCommand::SetResizable(resizable) => self.window.set_resizable(resizable),
+                window_commands::WindowCommand::SetCursorGrab(grab) => self.set_cursor_grab(grab),
+                window_commands::WindowCommand::SetMinInnerSize(size) => {
+                    self.window.set_min_inner_size(size.map(|[width, height]| LogicalSize::new(width, height)));
+                },
+                window_commands::WindowCommand::RequestExit if self.unsaved_changes => {
+                    // Made again in case the language changed.
+                    self.ui_exit_confirm = exit_confirm_modal();
+                    self.ui_exit_confirm.open();
+                    self.schedule_repaint(Duration::ZERO);
+                },
+                window_commands::WindowCommand::RequestExit | window_commands::WindowCommand::Exit => {
+                    // Tasks get a head start on stopping, `shutdown` waits for them once the event loop is done.
+                    self.tasks.cancel_all();
+                    // Whatever comes after it has no window left to apply to.
+                    event_loop.exit();
+                    return;
+                },
+            }
+        }
+    }
+
+    /// Hides the cursor and keeps it from leaving the window, mouse motion then goes to the camera demo instead of egui.
+    /// Releasing the grab shows the cursor again where it was before.
+    fn set_cursor_grab(&mut self, grab: bool) {
+        if grab == self.cursor_grabbed {
+            return;
+        }
+
+        if grab {
+            // A focused text field would otherwise keep the keyboard and show a caret while nothing can be clicked.
+            self.ui_state.egui_ctx().memory_mut(|memory| memory.stop_text_input());
+            // Locking isn't supported on Windows and confining isn't on macOS, so each falls back to the other.
+            let result = self.window.set_cursor_grab(CursorGrabMode::Locked)
+                .or_else(|_| self.window.set_cursor_grab(CursorGrabMode::Confined));
+            if let Err(error) = result {
+                log::warn!("Can't grab the cursor: {error}");
+                self.toasts.error("This platform can't grab the cursor");
+                return;
+            }
+            self.window.set_cursor_visible(false);
+            self.grab_position = self.cursor_position;
+        } else {
+            if let Err(error) = self.window.set_cursor_grab(CursorGrabMode::None) {
+                log::warn!("Can't release the cursor grab: {error}");
+            }
+            self.window.set_cursor_visible(true);
+            // A confined cursor has been moving around invisibly, a locked one may have drifted on some platforms.
+            if let Some(position) = self.grab_position.take() {
+                let _ = self.window.set_cursor_position(position);
+            }
+        }
+        self.cursor_grabbed = grab;
+        self.schedule_repaint(Duration::ZERO);
+    }
+
+    /// Lets mouse input through to whatever is below the window when `hittest` is false.
+    fn set_cursor_hittest(&mut self, hittest: bool) {
+        if hittest != self.cursor_hittest {
+            // Not every platform supports this, the overlay then just stays clickable everywhere.
+            match self.window.set_cursor_hittest(hittest) {
+                Ok(()) => self.cursor_hittest = hittest,
+                Err(error) => {
+                    log::warn!("Can't change cursor hittest: {error}");
+                    self.overlay = false;
+                },
+            }
+        }
+    }
+
+    /// Keeps pointer input away from egui while the cursor is grabbed, returns true if `event` was handled here.
+    fn on_grabbed_event(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            // The hidden cursor would still hover and click whatever egui thinks is below it.
+            WindowEvent::CursorMoved { .. } | WindowEvent::MouseWheel { .. } => true,
+            WindowEvent::MouseInput { state: winit::event::ElementState::Pressed, .. } => {
+                self.set_cursor_grab(false);
+                true
+            },
+            WindowEvent::KeyboardInput { event, .. }
+                if event.state.is_pressed() && event.logical_key == winit::keyboard::Key::Named(winit::keyboard::NamedKey::Escape) => {
+                self.set_cursor_grab(false);
+                true
+            },
+            // Alt-tabbing away must not leave the cursor stuck, the OS usually releases it anyway.
+            WindowEvent::Focused(false) => {
+                self.set_cursor_grab(false);
+                false
+            },
+            _ => false,
+        }
+    }
+
+    /// Keeps touch gestures and the mouse from fighting over egui's single pointer, returns true if `event` is dropped.
+    fn on_touch_pointer_event(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::Touch(touch) => {
+                match touch.phase {
+                    TouchPhase::Started if self.touch_pointer.is_none() => self.touch_pointer = Some(touch.id),
+                    // egui_winit only sends `PointerGone` for a cancelled touch, the emulated button would stay down
+                    // and the next touch would continue the old drag.
+                    TouchPhase::Cancelled if self.touch_pointer == Some(touch.id) => {
+                        self.touch_pointer = None;
+                        let pos = self.ui_state.egui_ctx().input(|i| i.pointer.latest_pos()).unwrap_or_default();
+                        let modifiers = self.ui_state.egui_input().modifiers;
+                        self.ui_state.egui_input_mut().events.push(egui::Event::PointerButton {
+                            pos,
+                            button: egui::PointerButton::Primary,
+                            pressed: false,
+                            modifiers,
+                        });
+                    },
+                    TouchPhase::Ended if self.touch_pointer == Some(touch.id) => self.touch_pointer = None,
+                    _ => {},
+                }
+                false
+            },
+            // A mouse moving or clicking mid-gesture would yank the pointer away from the finger. Some platforms also
+            // synthesize mouse events from touches, which would arrive twice otherwise.
+            WindowEvent::CursorMoved { .. } | WindowEvent::MouseInput { .. } => self.touch_pointer.is_some(),
+            // A touch that never ended must not keep the mouse locked out for good.
+            WindowEvent::Focused(false) => {
+                self.touch_pointer = None;
+                false
+            },
+            _ => false,
+        }
+    }
+
+    fn on_device_event(&mut self, event: &DeviceEvent) {
+        if let DeviceEvent::MouseMotion { delta } = event {
+            if self.cursor_grabbed {
+                self.ui_camera.rotate(*delta);
+                self.schedule_repaint(Duration::ZERO);
+                return;
+            }
+        }
+
+        // Without hittest the window gets no cursor events, so raw mouse motion is the only hint that the pointer
+        // may be back over the UI. Re-enabling hittest makes winit report the position, and the next frame decides.
+        if !self.cursor_hittest && matches!(event, DeviceEvent::MouseMotion { .. }) {
+            self.set_cursor_hittest(true);
+        }
+        self.on_raw_input(RawInput::Device(event));
+    }
+
+    /// Runs the fixed updates that are due at `now`, returns how many.
+    fn update_simulations(&mut self, now: Instant) -> u32 {
+        // With reduced motion the ball holds still.
+        let reduce_motion = motion::MotionPrefs::get(self.ui_state.egui_ctx()).reduce_motion;
+        if !self.ui_bouncing_ball.is_open() || reduce_motion {
+            self.timestep.reset();
+            return 0;
+        }
+        let steps = self.timestep.advance(now);
+        let dt = self.timestep.dt();
+        for _ in 0..steps {
+            let simulations: [&mut dyn FixedUpdate; 1] = [&mut self.ui_bouncing_ball];
+            for simulation in simulations {
+                simulation.update(dt);
+            }
+        }
+        steps
+    }
+
+    /// Hands input that egui had no use for to the app, see [`RawInputHandler`].
+    fn on_raw_input(&mut self, input: RawInput) {
+        let handlers: [&mut dyn RawInputHandler; 1] = [&mut self.scene];
+        if handlers.into_iter().any(|handler| handler.on_raw_input(input)) {
+            self.schedule_repaint(Duration::ZERO);
+        }
+    }
+
+    /// Runs `task` on the task thread pool, its progress shows up in the "Tasks" window.
+    fn spawn_task(&mut self, name: impl Into<String>, task: impl FnOnce(tasks::ProgressHandle) + Send + 'static) -> tasks::TaskId {
+        self.tasks.spawn(name, task)
+    }
+
+    /// Makes sure a frame is rendered within `delay`, earlier requests win.
+    fn schedule_repaint(&mut self, delay: Duration) {
+        // egui uses `Duration::MAX` for "no repaint needed", which doesn't fit into an `Instant`.
+        if let Some(at) = Instant::now().checked_add(delay) {
+            self.repaint_at = Some(self.repaint_at.map_or(at, |current| current.min(at)));
+        }
+    }
+
+    fn on_user_event(&mut self, event: AppEvent) {
+        match event {
+            AppEvent::RequestRepaint { viewport_id, delay, cumulative_pass_nr } => {
+                let ctx = self.ui_state.egui_ctx();
+                // Requests made while a pass was running are already part of that pass' `repaint_delay`.
+                if viewport_id != self.ui_state.egui_input().viewport_id {
+                    self.viewports.borrow_mut().schedule_repaint(viewport_id, delay);
+                } else if cumulative_pass_nr == ctx.cumulative_pass_nr() {
+                    self.schedule_repaint(delay);
+                }
+            },
+            AppEvent::LogsUpdated => {
+                if self.ui_log_console.is_open() {
+                    self.schedule_repaint(Duration::ZERO);
+                }
+            },
+            AppEvent::WindowCommands => {},
+            AppEvent::Dialog(event) => {
+                self.dialogs.on_event(event);
+                self.schedule_repaint(Duration::ZERO);
+            },
+            #[cfg(feature = "gamepad")]
+            AppEvent::Gamepad(event) => {
+                self.gamepad.on_event(event);
+                self.schedule_repaint(Duration::ZERO);
+            },
+            #[cfg(all(target_os = "windows", feature = "tray"))]
+            AppEvent::Tray(event) => match event {
+                tray::TrayEvent::ToggleWindow => self.set_hidden_to_tray(!self.hidden_to_tray),
+                tray::TrayEvent::ToggleStartInTray => {
+                    // No frame applies the settings while the window is hidden.
+                    self.settings.start_in_tray = !self.settings.start_in_tray;
+                    if let Some(tray) = &self.tray {
+                        tray.set_start_in_tray(self.settings.start_in_tray);
+                    }
+                    self.autosave.mark_dirty(Instant::now());
+                },
+                tray::TrayEvent::Quit => {
+                    // The exit confirmation may have to be answered first.
+                    self.set_hidden_to_tray(false);
+                    self.window_commands.push(window_commands::WindowCommand::RequestExit);
+                },
+            },
+            #[cfg(feature = "dev")]
+            AppEvent::ShadersChanged(paths) => {
+                log::info!("Reloading the shaders after {} changed", paths.join(", "));
+                // Events are handled between frames, so the next one is the first to use the new pipelines.
+                match self.gpu_resources.reload_shaders() {
+                    Ok(()) => self.toasts.success("Reloaded the shaders"),
+                    Err(error) => {
+                        log::error!("Failed to compile the shaders, keeping the previous ones: {error}");
+                        self.toasts.error("Failed to compile the shaders, see the log for the error");
+                    },
+                }
+                self.schedule_repaint(Duration::ZERO);
+            },
+        }
+    }
+
+    fn on_surface_error(&mut self, error: wgpu::SurfaceError, event_loop: &ActiveEventLoop) {
+        match error {
+            // The swapchain no longer matches the window, this happens e.g. around resizes.
+            wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated => {
+                self.gpu_resources.resize(self.window.inner_size());
+                self.window.request_redraw();
+            },
+            wgpu::SurfaceError::OutOfMemory => {
+                log::error!("Out of memory while acquiring the next frame, exiting.");
+                event_loop.exit();
+            },
+            // Happens now and then when the GPU is busy, it's tried again with the next frame.
+            wgpu::SurfaceError::Timeout => {
+                log::warn!("Timed out acquiring the next frame");
+                self.window.request_redraw();
+            },
+            error => {
+                log::warn!("Failed to acquire the next frame: {error}");
+                if !std::mem::replace(&mut self.frame_error_shown, true) {
+                    self.toasts.error(i18n::tr_format("notices.frame_failed", &[("error", &error)]));
+                }
+                self.window.request_redraw();
+            },
+        }
+    }
+
+    /// Hides the window to the tray or shows it again, nothing happens without a tray.
+    #[cfg(all(target_os = "windows", feature = "tray"))]
+    fn set_hidden_to_tray(&mut self, hidden: bool) {
+        let Some(tray) = &self.tray else {
+            return;
+        };
+        tray.set_window_visible(!hidden);
+        self.hidden_to_tray = hidden;
+        self.window.set_visible(!hidden);
+        if !hidden {
+            self.window.focus_window();
+            self.schedule_repaint(Duration::ZERO);
+        }
+    }
+
+    /// Whether the window is hidden to the tray, which is never the case without the `tray` feature.
+    fn is_hidden_to_tray(&self) -> bool {
+        #[cfg(all(target_os = "windows", feature = "tray"))]
+        {
+            self.hidden_to_tray
+        }
+        #[cfg(not(all(target_os = "windows", feature = "tray")))]
+        {
+            false
+        }
+    }
+
+    /// Hides the window to the tray instead of quitting if the settings say so. Returns whether it did.
+    fn close_to_tray(&mut self) -> bool {
+        #[cfg(all(target_os = "windows", feature = "tray"))]
+        if self.settings.close_to_tray && self.tray.is_some() {
+            self.set_hidden_to_tray(true);
+            return true;
+        }
+        false
+    }
+
+    /// The window may be on a different monitor after it was moved or the display configuration changed.
+    fn update_refresh_rate(&mut self) {
+        self.frame_clock.set_refresh_rate(self.window.current_monitor().and_then(|monitor| monitor.refresh_rate_millihertz()));
+    }
+
+    fn update_placement(&mut self) {
+        self.placement = placement::WindowPlacement::from_window(&self.window, self.placement);
+        self.autosave.mark_dirty(Instant::now());
+    }
+
+    /// The config and the state as they're to be saved, nothing in an instance that only reads them.
+    fn persisted_files(&self) -> Vec<autosave::PersistedFile> {
+        if self.read_only {
+            return Vec::new();
+        }
+        let config = config::Config { settings: self.settings.clone(), ..self.config.clone() };
+        let mut files: Vec<_> = self.config_file.contents(&config).into_iter().collect();
+        if let Some(path) = &self.state_path {
+            let mut state = self.state.clone();
+            if let Some(placement) = &self.placement {
+                placement.save(&mut state);
+            }
+            self.ui_reorder.save(&mut state);
+            self.layouts.save(&mut state);
+            self.ui_debug.save(&mut state);
+            files.push(autosave::PersistedFile { path: path.clone(), contents: state.to_string() });
+        }
+        files
+    }
+
+    /// Saves what changed on a task once it's due, see [`autosave::Autosave`]. Returns when the next save is due.
+    fn autosave(&mut self, now: Instant) -> Option<Instant> {
+        let after = self.settings.autosave_after();
+        self.autosave.poll();
+        if self.autosave.is_due(now, after) {
+            let files = self.persisted_files();
+            self.autosave.save(&mut self.tasks, files);
+        }
+        self.autosave.deadline(after)
+    }
+
+    /// Runs when the event loop is exiting, before the app is dropped. The order matters: tasks and the recording may
+    /// still be writing files, see [`shutdown::shutdown`], the state is saved while the egui context it's read from is
+    /// still there, and the GPU has to be done with the last frames before the resources they use are dropped.
+    fn shutdown(&mut self) {
+        shutdown::shutdown(self, Instant::now() + SHUTDOWN_TIMEOUT);
+        let _ = self.gpu_resources.device.poll(wgpu::Maintain::Wait);
+    }
+
+    /// Renders the first frame into the still hidden window and shows it afterwards, so the window never appears
+    /// without the UI in it. The surface may not be ready yet right after creating the window, a frame that fails is
+    /// tried again after reconfiguring it, a few times before the window is shown anyway.
+    fn show_first_frame(&mut self) {
+        // The theme and zoom are otherwise only applied after the first frame.
+        self.apply_settings();
+        for attempt in 1..=FIRST_FRAME_ATTEMPTS {
+            let result = self.do_render();
+            self.watchdog.frame_finished();
+            match result {
+                Ok(()) => break,
+                Err(error) => {
+                    log::warn!("Failed to render the first frame, attempt {attempt} of {FIRST_FRAME_ATTEMPTS}: {error}");
+                    self.gpu_resources.resize(self.window.inner_size());
+                },
+            }
+        }
+        #[cfg(all(target_os = "windows", feature = "tray"))]
+        if self.settings.start_in_tray && self.tray.is_some() {
+            self.set_hidden_to_tray(true);
+            return;
+        }
+        self.window.set_visible(true);
+    }
+
+    /// Recreates the surface after the app was suspended and kicks off rendering again.
+    fn resume(&mut self) {
+        self.gpu_resources.create_surface(&self.window);
+        self.window.request_redraw();
+    }
+
+    /// Frees what can be had back when it's needed: egui's images and font atlas, the last tessellated frame, the
+    /// demo textures of a closed window and, at `level`, the older log records. Nothing the next frame draws is
+    /// lost, its glyphs are rasterized into the new atlas as they come up.
+    fn trim_memory(&mut self, level: memory_trim::TrimLevel) {
+        let before = self.cache_usage();
+        self.free_ui_caches();
+        self.tessellation_cache.clear();
+        for id in self.ui_image_demo.take_unused_textures() {
+            self.native_textures.free(&mut self.ui_painter, id);
+        }
+        self.logs.truncate(level.log_records_kept());
+        log::info!("Trimmed memory ({level:?})");
+        self.trim_report = Some(memory_trim::TrimReport { level, before, after: None });
+        // Rebuilds the atlas and takes the numbers after.
+        self.schedule_repaint(Duration::ZERO);
+    }
+
+    fn cache_usage(&self) -> memory_trim::CacheUsage {
+        let ctx = self.ui_state.egui_ctx();
+        let loaders = ctx.loaders();
+        let image_bytes = loaders.bytes.lock().iter().map(|loader| loader.byte_size()).sum::<usize>()
+            + loaders.image.lock().iter().map(|loader| loader.byte_size()).sum::<usize>();
+        memory_trim::CacheUsage {
+            font_atlas: ctx.fonts(|fonts| fonts.font_image_size()),
+            textures: self.ui_stats.texture_count(),
+            native_textures: self.native_textures.count(),
+            image_bytes,
+            log_records: self.logs.records().len(),
+        }
+    }
+
+    /// Drops everything egui can rebuild on its own: loaded images and the font atlas.
+    /// The fonts are re-rasterized lazily on the next frame, so the atlas only grows back to what is actually visible.
+    fn free_ui_caches(&mut self) {
+        self.ui_state.egui_ctx().forget_all_images();
+        self.rebuild_font_atlas();
+    }
+
+    /// Has egui start the font atlas over in the next frame, with only the glyphs that are used from then on.
+    fn rebuild_font_atlas(&mut self) {
+        // Setting the fonts again reloads them with a new atlas and drops the layouts that point into the old one. egui
+        // skips definitions that equal the current ones though, so every other reset adds an empty family no text uses.
+        let reset = egui::FontFamily::Name("font_atlas_reset".into());
+        let mut definitions = self.fonts.clone();
+        if self.ui_state.egui_ctx().fonts(|fonts| !fonts.lock().fonts.definitions().families.contains_key(&reset)) {
+            definitions.families.insert(reset, Vec::new());
+        }
+        self.ui_state.egui_ctx().set_fonts(definitions);
+    }
+
+    /// The font atlas grew bigger than the GPU's textures, with many large glyphs or with egui told too big a size.
+    /// It starts over at half the size, this frame's text is drawn with the atlas of the frame before.
+    fn on_font_atlas_overflow(&mut self) {
+        let limit = self.gpu_resources.texture_limit();
+        let side = fonts::reduced_atlas_side(self.gpu_resources.max_texture_side, limit);
+        log::warn!("The font atlas outgrew the GPU's textures of {limit} pixels, limiting it to {side}");
+        self.toasts.warning(i18n::tr_format("notices.font_atlas_overflow", &[("side", &side)]));
+        self.set_max_texture_side(side);
+        self.rebuild_font_atlas();
+        self.schedule_repaint(Duration::ZERO);
+    }
+
+    /// The Debug menu's way to try out [`Self::on_font_atlas_overflow`]. Textures are limited to half of what egui
+    /// is told, and the next frame puts lots of big glyphs into the font atlas. That would take gigabytes with a
+    /// real overflow of the usual limits of 8192 or 16384 pixels.
+    fn inflate_font_atlas(&mut self) {
+        let r = &mut self.gpu_resources;
+        let limit = r.max_texture_side / 2;
+        log::info!("Simulating a texture limit of {limit} pixels and inflating the font atlas");
+        r.simulated_texture_limit = Some(limit);
+        self.inflating_font_atlas = true;
+        self.schedule_repaint(Duration::ZERO);
+    }
+
+    /// Tells egui how big textures may get, the main window and the viewport windows have to agree or egui would
+    /// rebuild the font atlas whenever it goes from one to the other.
+    fn set_max_texture_side(&mut self, side: usize) {
+        self.gpu_resources.max_texture_side = side;
+        self.ui_state.set_max_texture_side(side);
+        self.viewports.borrow_mut().set_max_texture_side(side);
+    }
+
+    /// Limits the egui screen rect to the part of the window not covered by system bars or the soft keyboard.
+    #[cfg(target_os = "android")]
+    fn apply_content_rect(&self, raw_input: &mut egui::RawInput) {
+        use winit::platform::android::WindowExtAndroid;
+
+        let content_rect = self.window.content_rect();
+        let pixels_per_point = self.ui_state.egui_ctx().pixels_per_point();
+        let min = egui::pos2(content_rect.left as f32, content_rect.top as f32) / pixels_per_point;
+        let max = egui::pos2(content_rect.right as f32, content_rect.bottom as f32) / pixels_per_point;
+        if max.x > min.x && max.y > min.y {
+            raw_input.screen_rect = Some(egui::Rect::from_min_max(min, max));
+        }
+    }
+
+    /// Shows the soft keyboard while a text field has focus, winit doesn't do this for us on Android.
+    #[cfg(target_os = "android")]
+    fn update_soft_input(&mut self, wants_keyboard_input: bool) {
+        if wants_keyboard_input != self.soft_input_visible {
+            if wants_keyboard_input {
+                self.android_app.show_soft_input(false);
+            } else {
+                self.android_app.hide_soft_input(false);
+            }
+            self.soft_input_visible = wants_keyboard_input;
+        }
+    }
+
+    fn do_render(&mut self) -> Result<(), wgpu::SurfaceError> {
+        if self.gpu_resources.surface.is_none() {
+            return Ok(());
+        }
+        #[cfg(feature = "profiling")]
+        profiler::new_frame();
+        profiler::profile_scope!("do_render");
+
+        // This frame satisfies every pending request, egui reports what it needs next in `build_ui`.
+        self.repaint_at = None;
+        let now = Instant::now();
+        self.watchdog.frame_started();
+        self.pacing.frame_started(now);
+        self.stats.frame_started(now);
+        self.gpu_resources.prepare_offscreen(&self.settings);
+        self.gpu_resources.prepare_msaa();
+
+        // Acquiring blocks until the swapchain has a free image. Normally the UI is built before that so the CPU work
+        // overlaps with the wait, in low latency mode afterwards so the input is as fresh as possible when presented.
+        let low_latency = self.settings.low_latency;
+        let mut ce = self.gpu_resources.device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        let early_frame = (!low_latency).then(|| self.ui_frame(&mut ce));
+        if low_latency && self.settings.wait_for_gpu {
+            // Don't even start acquiring while the previous frame is still queued up on the GPU.
+            let _ = self.gpu_resources.device.poll(wgpu::Maintain::Wait);
+        }
+
+        let acquire_start = Instant::now();
+        self.watchdog.set_phase(watchdog::Phase::Acquire);
+        let acquired_texture = {
+            profiler::profile_scope!("acquire");
+            self.gpu_resources.surface.as_ref().unwrap().get_current_texture()
+        };
+        let output = match acquired_texture {
+            Ok(output) => output,
+            Err(error) => {
+                if let Some(frame) = early_frame {
+                    self.finish_ui(frame);
+                }
+                return Err(error);
+            },
+        };
+        let acquired = Instant::now();
+        let frame = early_frame.unwrap_or_else(|| self.ui_frame(&mut ce));
+        let surface_view = output.texture.create_view(&wgpu::TextureViewDescriptor {
+            format: Some(self.gpu_resources.surface_format),
+            ..Default::default()
+        });
+        // With a render scale or an effect the UI goes offscreen first and gets post-processed onto the surface afterwards.
+        let offscreen_view = self.gpu_resources.offscreen.as_ref().map(|target| target.view().clone());
+        let overdraw = self.ui_debug.overdraw;
+        if overdraw {
+            let r = &mut self.gpu_resources;
+            let primitives = &frame.clipped_primitives;
+            r.overdraw.prepare(&r.device, &r.queue, r.surface_format, r.msaa_samples, primitives, &frame.screen_descriptor);
+        }
+
+        {
+            let render_pass = ce.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: None,
+                color_attachments: &[Some(offscreen::MsaaTarget::color_attachment(
+                    self.gpu_resources.msaa.as_ref(),
+                    offscreen_view.as_ref().unwrap_or(&surface_view),
+                    // The overdraw view adds up from black.
+                    if overdraw { wgpu::Color::BLACK } else { self.gpu_resources.clear_color() },
+                ))],
+                depth_stencil_attachment: None,
+                timestamp_writes: match &mut self.bench {
+                    Some(bench) => bench.timestamp_writes(),
+                    None => self.ui_gpu_timer.as_mut().and_then(|timer| timer.timestamp_writes(0)),
+                },
+                occlusion_query_set: None,
+            });
+
+            let mut rp_static = render_pass.forget_lifetime();
+            if overdraw {
+                self.gpu_resources.overdraw.paint(&mut rp_static);
+            } else {
+                self.paint_ui(&frame, &mut rp_static);
+            }
+        }
+        if let Some(offscreen) = &self.gpu_resources.offscreen {
+            let r = &self.gpu_resources;
+            r.post_processor.run(&r.device, &r.queue, &mut ce, offscreen, &surface_view, &self.settings);
+        }
+        if let Some(bench) = &mut self.bench {
+            bench.resolve(&mut ce);
+        } else if let Some(timer) = &mut self.ui_gpu_timer {
+            timer.resolve(&mut ce);
+        }
+        if let Some(recording) = &mut self.recording {
+            recording.capture(&self.gpu_resources.device, &mut ce, &output.texture, now);
+        }
+        let mut color_readback = std::mem::take(&mut self.color_readback).then(|| capture::FrameReadback::new(1));
+        if let Some(readback) = &mut color_readback {
+            readback.copy(&self.gpu_resources.device, &mut ce, &output.texture, now);
+        }
+
+        self.gpu_resources.queue.submit(std::iter::once(ce.finish()));
+        if let Some(recording) = &mut self.recording {
+            recording.submitted(&self.gpu_resources.device);
+        }
+        if let Some(mut readback) = color_readback {
+            self.check_colors(&mut readback);
+        }
+        {
+            profiler::profile_scope!("present");
+            self.watchdog.set_phase(watchdog::Phase::Present);
+            output.present();
+        }
+        self.frame_error_shown = false;
+        self.stats.frame_presented(acquired - acquire_start, acquired.elapsed());
+        if let Some(timer) = &mut self.ui_gpu_timer {
+            timer.submitted();
+            if let Some((_, time)) = timer.poll(&self.gpu_resources.device, false).pop() {
+                self.ui_stats.gpu_time = Some(time);
+            }
+        }
+        if let Some(bench) = &mut self.bench {
+            if bench.frame_finished(&self.gpu_resources.device, now.elapsed()) {
+                println!("{}", bench.report(&self.gpu_resources.device));
+                self.window_commands.push(window_commands::WindowCommand::Exit);
+            }
+        }
+        self.finish_ui(frame);
+        self.apply_settings();
+
+        Ok(())
+    }
+
+    /// Handles events of viewport windows, returns false if `window_id` isn't one.
+    fn on_viewport_window_event(&mut self, event: &WindowEvent, window_id: winit::window::WindowId) -> bool {
+        let Some(id) = self.viewports.borrow().find(window_id) else {
+            return false;
+        };
+        if matches!(event, WindowEvent::RedrawRequested) {
+            self.render_viewport(id);
+            return true;
+        }
+
+        let rerun = self.viewports.borrow_mut().on_window_event(id, &self.gpu_resources.device, event);
+        match rerun {
+            Some(egui::ViewportId::ROOT) => self.schedule_repaint(Duration::ZERO),
+            Some(rerun) => self.viewports.borrow_mut().schedule_repaint(rerun, Duration::ZERO),
+            None => {},
+        }
+        true
+    }
+
+    /// Runs a pass of the deferred viewport `id` and paints it into its window.
+    fn render_viewport(&mut self, id: egui::ViewportId) {
+        let ctx = self.ui_state.egui_ctx().clone();
+        let Some((raw_input, ui_cb)) = self.viewports.borrow_mut().begin_deferred(id, &ctx) else {
+            return;
+        };
+        let mut output = ctx.run(raw_input, |ctx| ui_cb(ctx));
+        let viewport_output = std::mem::take(&mut output.viewport_output);
+
+        // Immediate viewports opened by this one ran during the pass, their textures changed first.
+        self.paint_immediate_viewports(&ctx);
+        let gpu = self.gpu_resources.viewport_gpu();
+        let mut viewports = self.viewports.borrow_mut();
+        viewports.paint(id, &gpu, &mut self.ui_painter, &ctx, output);
+        self.tessellation_cache.invalidate_upload();
+        viewports.add_output(viewport_output);
+    }
+
+    fn paint_immediate_viewports(&mut self, ctx: &egui::Context) {
+        let gpu = self.gpu_resources.viewport_gpu();
+        let mut viewports = self.viewports.borrow_mut();
+        for (id, output) in viewports.take_immediate_frames() {
+            viewports.paint(id, &gpu, &mut self.ui_painter, ctx, output);
+            self.tessellation_cache.invalidate_upload();
+        }
+    }
+
+    /// Opens and closes viewport windows to match what the UI showed, and wakes those that are due for a frame.
+    /// Returns when the next viewport frame is due.
+    fn update_viewports(&mut self, event_loop: &ActiveEventLoop) -> Option<Instant> {
+        let mut viewports = self.viewports.borrow_mut();
+        viewports.sync(event_loop, &self.gpu_resources.viewport_gpu(), self.ui_state.egui_ctx());
+        viewports.request_redraws(Instant::now())
+    }
+
+    fn on_window_event(&mut self, event: &winit::event::WindowEvent, window_id: winit::window::WindowId) -> bool {
+        if self.window.id() == window_id {
+            if let WindowEvent::CursorMoved { position, .. } = event {
+                self.cursor_position = Some(*position);
+            }
+            if matches!(event, WindowEvent::KeyboardInput { .. } | WindowEvent::MouseInput { .. } | WindowEvent::MouseWheel { .. }
+                | WindowEvent::CursorMoved { .. } | WindowEvent::Touch(_)) {
+                self.idle_trim.input(Instant::now());
+            }
+            // Hovering changes nothing that's saved.
+            if matches!(event, WindowEvent::KeyboardInput { .. } | WindowEvent::MouseInput { .. } | WindowEvent::MouseWheel { .. }
+                | WindowEvent::Touch(_)) {
+                self.autosave.mark_dirty(Instant::now());
+            }
+            if self.cursor_grabbed && self.on_grabbed_event(event) {
+                return true;
+            }
+            if self.on_touch_pointer_event(event) {
+                return true;
+            }
+
+            if let WindowEvent::KeyboardInput { event, .. } = event {
+                let command = self.ui_state.egui_input().modifiers.command;
+                self.paste_shortcut |= event.state.is_pressed() && match &event.logical_key {
+                    winit::keyboard::Key::Named(winit::keyboard::NamedKey::Paste) => true,
+                    winit::keyboard::Key::Character(key) => command && key.eq_ignore_ascii_case("v"),
+                    _ => false,
+                };
+            }
+            let response = self.ui_state.on_window_event(&self.window, event);
+            if response.repaint {
+                self.schedule_repaint(Duration::ZERO);
+            }
+            if !response.consumed {
+                self.on_raw_input(RawInput::Window(event));
+            }
+
+            response.consumed
+        } else {
+            false
+        }
+    }
+}
+
+impl ApplicationHandler<AppEvent> for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        // On Android `resumed` fires every time the activity comes back to the foreground, but only
+        // the native window is gone at that point, so everything except the surface is reused.
+        match self.app_resources.as_mut() {
+            Some(app_resources) => app_resources.resume(),
+            None => match AppResources::new_blocking(event_loop, self.event_loop_proxy.clone(), self.logs.clone(), &self.options) {
+                Ok(mut app_resources) => {
+                    app_resources.show_first_frame();
+                    app_resources.window.request_redraw();
+                    self.app_resources = Some(app_resources);
+                },
+                Err(error) => {
+                    init_error::show_fatal_error(&error);
+                    self.failed = true;
+                    event_loop.exit();
+                },
+            },
+        }
+    }
+
+    fn device_event(&mut self, _event_loop: &ActiveEventLoop, _device_id: winit::event::DeviceId, event: DeviceEvent) {
+        if let Some(app_resources) = self.app_resources.as_mut() {
+            app_resources.on_device_event(&event);
+        }
+    }
+
+    fn user_event(&mut self, _event_loop: &ActiveEventLoop, event: AppEvent) {
+        if let Some(app_resources) = self.app_resources.as_mut() {
+            app_resources.on_user_event(event);
+        }
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        let Some(app_resources) = self.app_resources.as_mut() else {
+            return;
+        };
+
+        app_resources.apply_window_commands(event_loop);
+        if event_loop.exiting() {
+            return;
+        }
+        // The benchmark measures how fast frames can be rendered, so nothing may wait between them.
+        if app_resources.bench.is_some() {
+            app_resources.window.request_redraw();
+            event_loop.set_control_flow(ControlFlow::Poll);
+            return;
+        }
+
+        // Viewport windows don't go through the frame pacing, they only need waking up in time.
+        let viewport_frame = app_resources.update_viewports(event_loop);
+        let idle_trim_after = app_resources.settings.idle_trim_after();
+        if app_resources.idle_trim.take_due(Instant::now(), idle_trim_after) {
+            app_resources.trim_memory(memory_trim::TrimLevel::Idle);
+        }
+        let next_save = app_resources.autosave(Instant::now());
+
+        // Frames egui asked for still go through the background throttling, which can delay or drop them. None are
+        // rendered while the window is hidden to the tray, showing it again asks for one.
+        let next_frame = app_resources.repaint_at
+            .filter(|_| !app_resources.is_hidden_to_tray())
+            .and_then(|at| app_resources.pacing.next_frame_at(at, &app_resources.settings));
+        let now = Instant::now();
+        let wake_at = match next_frame {
+            // Timers are only accurate to a millisecond or so, the last bit of the wait is spun for frame limiter precision.
+            Some(at) if at <= now + FRAME_SPIN_THRESHOLD => {
+                while Instant::now() < at {
+                    std::hint::spin_loop();
+                }
+                app_resources.window.request_redraw();
+                None
+            },
+            Some(at) => Some(at - FRAME_SPIN_THRESHOLD),
+            None => None,
+        };
+        let wake_at = [wake_at, viewport_frame, app_resources.idle_trim.deadline(idle_trim_after), next_save].into_iter().flatten().min();
+        event_loop.set_control_flow(wake_at.map_or(ControlFlow::Wait, ControlFlow::WaitUntil));
+    }
+
+    fn suspended(&mut self, _event_loop: &ActiveEventLoop) {
+        if let Some(app_resources) = self.app_resources.as_mut() {
+            app_resources.gpu_resources.drop_surface();
+        }
+    }
+
+    fn exiting(&mut self, _event_loop: &ActiveEventLoop) {
+        if let Some(mut app_resources) = self.app_resources.take() {
+            // Quitting from the crash screen, or after going on from it, is a normal exit.
+            self.failed |= app_resources.crash.gave_up();
+            app_resources.shutdown();
+        }
+    }
+
+    fn memory_warning(&mut self, _event_loop: &ActiveEventLoop) {
+        if let Some(app_resources) = self.app_resources.as_mut() {
+            app_resources.trim_memory(memory_trim::TrimLevel::Full);
+        }
+    }
+
+    fn window_event(
+            &mut self,
+            event_loop: &ActiveEventLoop,
+            window_id: winit::window::WindowId,
+            event: winit::event::WindowEvent,
+        ) {
+        if self.get_app_resources().on_viewport_window_event(&event, window_id) {
+            return;
+        }
+        if self.get_app_resources().on_window_event(&event, window_id) {
+            return;
+        }
+
+        match event {
+            WindowEvent::CloseRequested => {
+                let app_resources = self.get_app_resources();
+                if !app_resources.close_to_tray() {
+                    app_resources.window_commands.push(window_commands::WindowCommand::RequestExit);
+                }
+            },
+            // While suspended there is no surface to draw into, `resume` kicks the loop off again.
+            // No new frames once exiting, the last one may already be waited for.
+            WindowEvent::RedrawRequested if event_loop.exiting() => {},
+            WindowEvent::RedrawRequested if self.get_app_resources().gpu_resources.surface.is_some() => {
+                self.render(event_loop);
+            },
+            WindowEvent::Resized(physical_size) => {
+                self.get_app_resources().gpu_resources.resize(physical_size);
+                self.get_app_resources().update_placement();
+                // Windows runs a modal loop of its own while the window is resized, nothing gets redrawn until the
+                // mouse is released and the last frame is stretched meanwhile. Drawing right away makes the UI reflow
+                // with every step. Elsewhere the frame requested for the resize comes soon enough anyway.
+                if cfg!(target_os = "windows") && physical_size.width > 0 && physical_size.height > 0 {
+                    self.render(event_loop);
+                }
+            },
+            WindowEvent::Moved(_) => {
+                let app_resources = self.get_app_resources();
+                app_resources.update_placement();
+                app_resources.update_refresh_rate();
+            },
+            WindowEvent::ScaleFactorChanged { .. } => {
+                self.get_app_resources().update_refresh_rate();
+            },
+            WindowEvent::Occluded(occluded) => {
+                let app_resources = self.get_app_resources();
+                if app_resources.pacing.set_occluded(occluded) {
+                    app_resources.schedule_repaint(Duration::ZERO);
+                }
+            },
+            WindowEvent::Focused(focused) => {
+                let app_resources = self.get_app_resources();
+                if focused && app_resources.attention_requested {
+                    // Some platforms keep flashing the taskbar button until told otherwise.
+                    app_resources.window.request_user_attention(None);
+                    app_resources.attention_requested = false;
+                }
+                if app_resources.pacing.set_focused(focused) {
+                    app_resources.schedule_repaint(Duration::ZERO);
+                }
+            },
+            _ => (),
+        }
+    }
+}
+
+/// Options for [`GpuResources::new`].
+#[derive(Debug, Clone)]
+struct GpuConfig {
+    /// Pick a surface alpha mode that blends with the desktop, if there is one.
+    transparent: bool,
+    /// How many frames may be queued up on the GPU, see [`clamp_frame_latency`].
+    frame_latency: u32,
+    /// Lets egui add a little noise to its output, which hides banding in dark gradients.
+    dithering: bool,
+    /// How colors get encoded for the screen:
+    /// - `true`: the UI is drawn through an sRGB view of the surface, the GPU encodes and blending happens in linear space.
+    /// - `false`: the UI is drawn to the non-sRGB format, egui's shader encodes and blending happens in gamma space,
+    ///   which is what egui is designed for and matches how it looks on the web.
+    ///
+    /// The surface itself always uses the non-sRGB format if there is one, with the sRGB variant as an extra view format.
+    srgb_view: bool,
+    /// Samples per pixel of the UI, lowered to what the adapter supports.
+    msaa_samples: u32,
+    /// From the config, they only matter when the GPU is set up.
+    preferences: config::GpuPreferences,
+}
+
+/// Asks before quitting with unsaved changes, in the current language.
+fn exit_confirm_modal() -> ui::Modal {
+    ui::Modal::new("exit_confirm", tr("exit.title"))
+        .with_buttons(&[(ui::ModalChoice::Ok, tr("exit.quit")), (ui::ModalChoice::Cancel, tr("exit.cancel"))])
+}
+
+/// Where File → Save As… suggests saving to first.
+fn default_save_path() -> PathBuf {
+    storage::data_dir().unwrap_or_default().join("demo.json")
+}
+
+fn file_name(path: &std::path::Path) -> String {
+    path.file_name().unwrap_or(path.as_os_str()).to_string_lossy().into_owned()
+}
+
+/// The configured present mode if the surface supports it, otherwise the first one it lists.
+fn present_mode(capabilities: &wgpu::SurfaceCapabilities, configured: Option<wgpu::PresentMode>) -> wgpu::PresentMode {
+    let default = capabilities.present_modes[0];
+    match configured {
+        // wgpu picks a supported mode for these itself.
+        Some(mode @ (wgpu::PresentMode::AutoVsync | wgpu::PresentMode::AutoNoVsync)) => mode,
+        Some(mode) if capabilities.present_modes.contains(&mode) => mode,
+        Some(mode) => {
+            log::warn!("The surface doesn't support the {mode:?} present mode, using {default:?}");
+            default
+        },
+        None => default,
+    }
+}
+
+/// Limits `desired_maximum_frame_latency` to the range that's actually useful and supported by all backends.
+fn clamp_frame_latency(frame_latency: u32) -> u32 {
+    let clamped = frame_latency.clamp(1, 3);
+    if clamped != frame_latency {
+        log::warn!("Frame latency {frame_latency} is out of range, using {clamped} instead");
+    }
+    clamped
+}
+
+#[allow(dead_code)]
+struct GpuResources {
+    instance: wgpu::Instance,
+    /// `None` while the app is suspended, Android destroys the native window in that case.
+    surface: Option<wgpu::Surface<'static>>,
+    adapter: wgpu::Adapter,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    /// Format of the surface view everything is rendered through, the sRGB or non-sRGB variant of the surface format.
+    surface_format: wgpu::TextureFormat,
+    surface_config: wgpu::SurfaceConfiguration,
+    /// Whether the surface may be viewed with a different sRGB-ness than its format.
+    view_formats_supported: bool,
+    /// What this was created with, kept for switching adapters.
+    config: GpuConfig,
+    /// The UI resolution relative to the surface, see [`Self::prepare_offscreen`].
+    render_scale: f32,
+    /// Where the UI is drawn when it's scaled or post-processed, `None` when it goes straight into the surface.
+    offscreen: Option<offscreen::OffscreenTarget>,
+    /// What `config.msaa_samples` came down to for the current view format.
+    msaa_samples: u32,
+    /// Drawn into instead of the surface or offscreen target with MSAA, `None` without.
+    msaa: Option<offscreen::MsaaTarget>,
+    post_processor: postprocess::PostProcessor,
+    /// Draws the UI instead of its renderer while the Debug menu's overdraw view is on.
+    overdraw: overdraw::OverdrawView,
+    /// What the device was created with, for the Surface Info window.
+    capabilities: capabilities::CapabilityReport,
+    /// The texture size egui is told it can use, the device's limit unless the font atlas outgrew it before.
+    max_texture_side: usize,
+    /// A smaller texture limit than the device's, set from the Debug menu to try out how the font atlas overflowing
+    /// is handled.
+    simulated_texture_limit: Option<usize>,
+}
+
+impl GpuResources {
+    /// Uses the `preferred` adapter if it's still there and can present to the window, otherwise the first one that can,
+    /// going by the power preference of the config.
+    async fn new(window: &Arc<Window>, config: &GpuConfig, preferred: Option<&wgpu::AdapterInfo>) -> Result<GpuResources, InitError> {
+        let backends = config.preferences.backends;
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+            backends,
+            ..Default::default()
+        });
+
+        let surface = instance.create_surface(window.clone())?;
+        let mut adapters: Vec<_> = instance.enumerate_adapters(backends).into_iter()
+            .filter(| adapter | adapter.is_surface_supported(&surface))
+            .collect();
+        let first_type = match config.preferences.power_preference {
+            wgpu::PowerPreference::None => None,
+            wgpu::PowerPreference::LowPower => Some(wgpu::DeviceType::IntegratedGpu),
+            wgpu::PowerPreference::HighPerformance => Some(wgpu::DeviceType::DiscreteGpu),
+        };
+        adapters.sort_by_key(|adapter| first_type.is_some_and(|first_type| adapter.get_info().device_type != first_type));
+        let index = preferred
+            .and_then(|preferred| adapters.iter().position(|adapter| adapter.get_info() == *preferred))
+            .unwrap_or_else(|| {
+                if let Some(preferred) = preferred {
+                    log::warn!("Adapter {:?} can't present to the window, using the default one", preferred.name);
+                }
+                0
+            });
+        if adapters.is_empty() {
+            return Err(InitError::NoAdapter);
+        }
+        let adapter = adapters.swap_remove(index);
+
+        let (required_features, required_limits, capability_report) = capabilities::negotiate(&adapter);
+        let (device, queue) = adapter.request_device(&wgpu::DeviceDescriptor {
+            label: Some("Egui Test Device"),
+            required_features,
+            required_limits,
+            ..Default::default() }, None).await?;
+
+        log::info!("Using adapter {:?}", adapter.get_info());
+        capability_report.log();
+
+        let capabilities = surface.get_capabilities(&adapter);
+        let preferred_format = capabilities.formats
+            .iter().copied().find(|f| f.is_srgb()).unwrap_or(capabilities.formats[0]);
+        // The base format has to be the non-sRGB one so both views are allowed, see `GpuConfig::srgb_view`.
+        let base_format = Some(preferred_format.remove_srgb_suffix())
+            .filter(|format| capabilities.formats.contains(format))
+            .unwrap_or(preferred_format);
+
+        let alpha_mode = if config.transparent {
+            [wgpu::CompositeAlphaMode::PreMultiplied, wgpu::CompositeAlphaMode::PostMultiplied]
+                .into_iter()
+                .find(|mode| capabilities.alpha_modes.contains(mode))
+                .unwrap_or_else(|| {
+                    log::warn!("Surface doesn't support transparency, falling back to opaque");
+                    capabilities.alpha_modes[0]
+                })
+        } else {
+            capabilities.alpha_modes[0]
+        };
+
+        // Copying out of the surface is needed for recordings, but not every platform allows it.
+        let usage = wgpu::TextureUsages::RENDER_ATTACHMENT | (capabilities.usages & wgpu::TextureUsages::COPY_SRC);
+
+        let size = window.inner_size();
+        let surface_config = wgpu::SurfaceConfiguration {
+            usage,
+            format: base_format,
+            width: size.width,
+            height: size.height,
+            present_mode: present_mode(&capabilities, config.preferences.present_mode),
+            alpha_mode,
+            view_formats: vec![],
+            desired_maximum_frame_latency: clamp_frame_latency(config.frame_latency),
+        };
+        let post_processor = postprocess::PostProcessor::new(&device, base_format);
+        let view_formats_supported = adapter.get_downlevel_capabilities().flags.contains(wgpu::DownlevelFlags::SURFACE_VIEW_FORMATS);
+
+        let max_texture_side = device.limits().max_texture_dimension_2d as usize;
+        let mut gpu_resources = Self {
+            instance,
+            surface: Some(surface),
+            adapter,
+            device,
+            queue,
+            surface_format: base_format,
+            surface_config,
+            view_formats_supported,
+            config: config.clone(),
+            render_scale: 1.0,
+            offscreen: None,
+            msaa_samples: 1,
+            msaa: None,
+            post_processor,
+            overdraw: overdraw::OverdrawView::default(),
+            capabilities: capability_report,
+            max_texture_side,
+            simulated_texture_limit: None,
+        };
+        gpu_resources.update_view_format();
+        gpu_resources.resize(size);
+        Ok(gpu_resources)
+    }
+
+    /// Picks the view format for `config.srgb_view` and rebuilds the passes using it, the surface has to be reconfigured afterwards.
+    fn update_view_format(&mut self) {
+        let base = self.surface_config.format;
+        let wanted = if self.config.srgb_view { base.add_srgb_suffix() } else { base.remove_srgb_suffix() };
+        self.surface_config.view_formats.clear();
+        self.surface_format = if wanted == base {
+            base
+        } else if self.view_formats_supported {
+            self.surface_config.view_formats.push(wanted);
+            wanted
+        } else {
+            log::warn!("The surface can't be viewed as {wanted:?}, staying with {base:?}");
+            base
+        };
+        self.post_processor = postprocess::PostProcessor::new(&self.device, self.surface_format);
+        self.msaa_samples = offscreen::supported_sample_count(&self.adapter, &self.device, self.surface_format, self.config.msaa_samples);
+    }
+
+    /// Rebuilds the pipelines from the shader files, the old ones stay if they don't compile.
+    #[cfg(feature = "dev")]
+    fn reload_shaders(&mut self) -> Result<(), String> {
+        self.post_processor = postprocess::PostProcessor::try_new(&self.device, self.surface_format)?;
+        Ok(())
+    }
+
+    /// The biggest texture side that can be uploaded.
+    fn texture_limit(&self) -> usize {
+        self.simulated_texture_limit.unwrap_or(self.device.limits().max_texture_dimension_2d as usize)
+    }
+
+    fn viewport_gpu(&self) -> viewports::ViewportGpu<'_> {
+        viewports::ViewportGpu {
+            instance: &self.instance,
+            adapter: &self.adapter,
+            device: &self.device,
+            queue: &self.queue,
+            surface_format: self.surface_config.format,
+            view_format: self.surface_format,
+            msaa_samples: self.msaa_samples,
+            max_texture_side: self.max_texture_side,
+        }
+    }
+
+    fn create_ui_renderer(&self) -> egui_wgpu::Renderer {
+        egui_wgpu::Renderer::new(&self.device, self.surface_format, None, self.msaa_samples, self.config.dithering)
+    }
+
+    /// Transparent surfaces start out fully see-through, egui only covers the parts with UI on them.
+    fn clear_color(&self) -> wgpu::Color {
+        match self.surface_config.alpha_mode {
+            wgpu::CompositeAlphaMode::PreMultiplied | wgpu::CompositeAlphaMode::PostMultiplied => wgpu::Color::TRANSPARENT,
+            _ => wgpu::Color::BLACK,
+        }
+    }
+
+    /// Size of what the UI is rendered into, the offscreen target or the surface itself.
+    fn render_size(&self) -> [u32; 2] {
+        match &self.offscreen {
+            Some(target) => target.size(),
+            None => [self.surface_config.width, self.surface_config.height],
+        }
+    }
+
+    /// Makes sure the offscreen target matches the settings and the current surface, recreating it after resizes.
+    /// Without scaling or post-processing it's dropped, so the UI is drawn into the surface without any extra copy.
+    fn prepare_offscreen(&mut self, settings: &settings::Settings) {
+        let scale = settings.render_scale.clamp(0.5, 2.0);
+        self.render_scale = scale;
+        if !settings.needs_offscreen() {
+            self.offscreen = None;
+            return;
+        }
+
+        let max_length = self.device.limits().max_texture_dimension_2d;
+        let size = [self.surface_config.width, self.surface_config.height]
+            .map(|length| ((length as f32 * scale).round() as u32).clamp(1, max_length));
+        let format = self.surface_format;
+        if self.offscreen.as_ref().is_none_or(|target| target.size() != size || target.format() != format) {
+            self.offscreen = Some(offscreen::OffscreenTarget::new(&self.device, format, size));
+        }
+    }
+
+    /// Keeps the multisampled buffer in line with the render size, see [`offscreen::MsaaTarget`].
+    fn prepare_msaa(&mut self) {
+        let size = self.render_size();
+        offscreen::MsaaTarget::prepare(&mut self.msaa, &self.device, self.surface_format, size, self.msaa_samples);
+    }
+
+    /// Switches to a present mode that doesn't wait for the display, if the surface has one.
+    fn disable_vsync(&mut self) {
+        let Some(surface) = &self.surface else {
+            return;
+        };
+        let present_modes = surface.get_capabilities(&self.adapter).present_modes;
+        match [wgpu::PresentMode::Immediate, wgpu::PresentMode::Mailbox].into_iter().find(|mode| present_modes.contains(mode)) {
+            Some(present_mode) => {
+                self.surface_config.present_mode = present_mode;
+                self.resize(PhysicalSize::new(self.surface_config.width, self.surface_config.height));
+            },
+            None => log::warn!("The surface only supports vsync, frame times are limited by the refresh rate"),
+        }
+    }
+
+    fn create_surface(&mut self, window: &Arc<Window>) {
+        self.surface = Some(self.instance.create_surface(window.clone()).unwrap());
+        self.resize(window.inner_size());
+    }
+
+    fn drop_surface(&mut self) {
+        self.surface = None;
+    }
+
+    fn resize(&mut self, size: PhysicalSize<u32>) {
+        self.surface_config.width = size.width;
+        self.surface_config.height = size.height;
+        // wgpu rejects zero sized surfaces, which Android reports briefly while rotating.
+        if let Some(surface) = self.surface.as_ref().filter(|_| size.width > 0 && size.height > 0) {
+            surface.configure(&self.device, &self.surface_config);
+        }
+    }
+}
+
+/// Runs the app until its window is closed, the desktop entry point. The process exits with 1 if it failed to start.
+pub fn run() {
+    let logs = logging::init();
+    crash::install_panic_hook();
+    let event_loop = EventLoop::with_user_event().build().unwrap();
+    let mut app = App::new(event_loop.create_proxy(), logs, AppOptions::from_args());
+    event_loop.run_app(&mut app).unwrap();
+    if app.failed {
+        std::process::exit(1);
+    }
+}
+
+#[cfg(target_os = "android")]
+#[no_mangle]
+fn android_main(android_app: winit::platform::android::activity::AndroidApp) {
+    use winit::platform::android::EventLoopBuilderExtAndroid;
+
+    let logs = logging::init();
+    crash::install_panic_hook();
+    let event_loop = EventLoop::with_user_event().with_android_app(android_app).build().unwrap();
+    let mut app = App::new(event_loop.create_proxy(), logs, AppOptions::default());
+    event_loop.run_app(&mut app).unwrap();
+}
@@ -0,0 +1,173 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::time::{Duration, Instant};
+
+use crate::i18n::tr;
+use crate::storage;
+use crate::tasks::TaskRunner;
+
+/// A file that persists between runs, with what's to be in it.
+pub struct PersistedFile {
+    pub path: PathBuf,
+    pub contents: String,
+}
+
+/// Saves the files that persist between runs a while after they changed, so a crash or a killed process doesn't
+/// lose more than that. The files are written on a task, atomically with [`storage::write_atomic`], and only those
+/// that differ from what this instance saved or loaded last.
+pub struct Autosave {
+    /// Something that's saved may have changed. Set on input, what's saved can't change without it.
+    dirty: bool,
+    /// When it became dirty, the save is due an interval after.
+    dirty_since: Instant,
+    /// What's in each file as far as this instance knows.
+    saved: BTreeMap<PathBuf, String>,
+    /// Sends back the files the running save wrote, `None` while there's none.
+    saving: Option<Receiver<Vec<PersistedFile>>>,
+}
+
+impl Autosave {
+    pub fn new(now: Instant) -> Self {
+        Self { dirty: false, dirty_since: now, saved: BTreeMap::new(), saving: None }
+    }
+
+    /// Takes `files` as what's in them, right after they were loaded. They're only written once that changes, which
+    /// leaves a config that was edited by hand as it is.
+    pub fn remember(&mut self, files: Vec<PersistedFile>) {
+        self.saved.extend(files.into_iter().map(|file| (file.path, file.contents)));
+    }
+
+    pub fn mark_dirty(&mut self, now: Instant) {
+        if !self.dirty {
+            self.dirty = true;
+            self.dirty_since = now;
+        }
+    }
+
+    /// When to save, `None` while it's turned off, there's nothing to save or a save is still running.
+    pub fn deadline(&self, after: Option<Duration>) -> Option<Instant> {
+        after.filter(|_| self.dirty && self.saving.is_none()).map(|after| self.dirty_since + after)
+    }
+
+    pub fn is_due(&self, now: Instant, after: Option<Duration>) -> bool {
+        self.deadline(after).is_some_and(|at| at <= now)
+    }
+
+    /// Takes note of what the last save wrote, once it's done. Files it failed to write are tried again with the next
+    /// one, the task runner reports why.
+    pub fn poll(&mut self) {
+        let Some(saving) = &self.saving else {
+            return;
+        };
+        match saving.try_recv() {
+            Ok(written) => {
+                self.saved.extend(written.into_iter().map(|file| (file.path, file.contents)));
+                self.saving = None;
+            },
+            Err(TryRecvError::Empty) => {},
+            // The task panicked, the task runner reports that as well.
+            Err(TryRecvError::Disconnected) => self.saving = None,
+        }
+    }
+
+    /// Writes the `files` that changed on a task.
+    pub fn save(&mut self, tasks: &mut TaskRunner, files: Vec<PersistedFile>) {
+        self.dirty = false;
+        let changed = self.changed(files);
+        if changed.is_empty() {
+            return;
+        }
+        let (sender, receiver) = mpsc::channel();
+        tasks.spawn_quiet(tr("autosave.task"), move |progress| {
+            let mut written = Vec::new();
+            for file in changed {
+                match storage::write_atomic(&file.path, &file.contents) {
+                    Ok(()) => written.push(file),
+                    Err(error) => progress.fail(format!("{}: {error}", file.path.display())),
+                }
+            }
+            let _ = sender.send(written);
+        });
+        self.saving = Some(receiver);
+    }
+
+    /// Writes the `files` that changed right away, on exit when there's no frame to wait for the task anymore. A save
+    /// that's still running was waited for with the task runner's shutdown.
+    pub fn save_now(&mut self, files: Vec<PersistedFile>) {
+        self.poll();
+        self.dirty = false;
+        for file in self.changed(files) {
+            match storage::write_atomic(&file.path, &file.contents) {
+                Ok(()) => {
+                    self.saved.insert(file.path, file.contents);
+                },
+                Err(error) => log::error!("Failed to save {}: {error}", file.path.display()),
+            }
+        }
+    }
+
+    fn changed(&self, files: Vec<PersistedFile>) -> Vec<PersistedFile> {
+        files.into_iter().filter(|file| self.saved.get(&file.path) != Some(&file.contents)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wait_for_save(autosave: &mut Autosave, tasks: &mut TaskRunner) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while autosave.saving.is_some() && Instant::now() < deadline {
+            tasks.poll();
+            autosave.poll();
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn only_changed_files_are_saved_once_due() {
+        let dir = std::env::temp_dir().join(format!("egui_wgpu_native-autosave-{}", std::process::id()));
+        let path = dir.join("state.toml");
+        let file = |contents: &str| PersistedFile { path: path.clone(), contents: contents.to_owned() };
+        let mut tasks = TaskRunner::new(&egui::Context::default(), None);
+        let start = Instant::now();
+        let after = Some(Duration::from_secs(30));
+        let mut autosave = Autosave::new(start);
+        autosave.remember(vec![file("a = 1\n")]);
+
+        assert_eq!(autosave.deadline(after), None);
+        autosave.mark_dirty(start + Duration::from_secs(1));
+        autosave.mark_dirty(start + Duration::from_secs(20));
+        assert!(!autosave.is_due(start + Duration::from_secs(30), after));
+        assert!(!autosave.is_due(start + Duration::from_secs(31), None));
+        assert!(autosave.is_due(start + Duration::from_secs(31), after));
+
+        // Unchanged, so there's nothing to write.
+        autosave.save(&mut tasks, vec![file("a = 1\n")]);
+        assert!(autosave.saving.is_none() && !path.exists());
+        assert_eq!(autosave.deadline(after), None);
+
+        autosave.mark_dirty(start + Duration::from_secs(40));
+        autosave.save(&mut tasks, vec![file("a = 2\n")]);
+        assert_eq!(autosave.deadline(after), None, "no second save while one is running");
+        wait_for_save(&mut autosave, &mut tasks);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "a = 2\n");
+        assert!(tasks.tasks().is_empty(), "a save that completed doesn't stay in the task list");
+
+        // A crash left a newer, complete temp file behind.
+        std::fs::write(path.with_extension("tmp"), "a = 3\n").unwrap();
+        assert!(storage::recover_temp(&path, |text| storage::Document::parse(text).is_ok()).unwrap());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "a = 3\n");
+        std::fs::write(path.with_extension("tmp"), "a = ").unwrap();
+        assert!(!storage::recover_temp(&path, |text| storage::Document::parse(text).is_ok()).unwrap());
+        assert!(!path.with_extension("tmp").exists());
+
+        let lock = storage::InstanceLock::acquire(&dir).unwrap();
+        assert!(lock.is_some());
+        assert!(storage::InstanceLock::acquire(&dir).unwrap().is_none(), "a second instance only reads");
+        drop(lock);
+        assert!(storage::InstanceLock::acquire(&dir).unwrap().is_some());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
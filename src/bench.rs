@@ -0,0 +1,268 @@
+use std::fmt::Write;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+/// Timestamp readbacks in flight, GPU times of frames beyond that are skipped.
+const GPU_TIMER_SLOTS: u32 = 4;
+
+/// Parts of a frame that are timed separately.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Phase {
+    /// Taking the input, running the UI and tessellating it.
+    Cpu,
+    /// Uploading textures and vertex/index buffers.
+    Upload,
+}
+
+#[derive(Default)]
+struct FrameTimes {
+    cpu: Duration,
+    upload: Duration,
+}
+
+/// Renders a fixed number of frames as fast as possible and collects their timings, see `--bench`.
+pub struct Benchmark {
+    frames: u32,
+    /// Frames at the start that aren't part of the results, caches and drivers are still warming up then.
+    warmup: u32,
+    rendered: u32,
+    current: FrameTimes,
+    total: Vec<Duration>,
+    cpu: Vec<Duration>,
+    upload: Vec<Duration>,
+    gpu: Vec<Duration>,
+    gpu_timer: Option<GpuTimer>,
+}
+
+impl Benchmark {
+    /// GPU times are only measured if the device was created with `TIMESTAMP_QUERY`.
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, frames: u32) -> Self {
+        let gpu_timer = device.features().contains(wgpu::Features::TIMESTAMP_QUERY).then(|| GpuTimer::new(device, queue));
+        if gpu_timer.is_none() {
+            log::warn!("Timestamp queries aren't supported, the benchmark won't measure GPU times");
+        }
+        Self {
+            frames,
+            warmup: frames / 10,
+            rendered: 0,
+            current: FrameTimes::default(),
+            total: Vec::new(),
+            cpu: Vec::new(),
+            upload: Vec::new(),
+            gpu: Vec::new(),
+            gpu_timer,
+        }
+    }
+
+    pub fn add(&mut self, phase: Phase, duration: Duration) {
+        match phase {
+            Phase::Cpu => self.current.cpu += duration,
+            Phase::Upload => self.current.upload += duration,
+        }
+    }
+
+    /// For the render pass whose GPU time is measured, `None` if there is no free timer.
+    pub fn timestamp_writes(&mut self) -> Option<wgpu::RenderPassTimestampWrites<'_>> {
+        self.gpu_timer.as_mut()?.timestamp_writes(self.rendered)
+    }
+
+    /// Has to be called before `ce` is submitted, after the pass from [`Self::timestamp_writes`] ended.
+    pub fn resolve(&mut self, ce: &mut wgpu::CommandEncoder) {
+        if let Some(gpu_timer) = &mut self.gpu_timer {
+            gpu_timer.resolve(ce);
+        }
+    }
+
+    /// Records a submitted frame, returns true once all frames were rendered.
+    pub fn frame_finished(&mut self, device: &wgpu::Device, total: Duration) -> bool {
+        let times = std::mem::take(&mut self.current);
+        if self.rendered >= self.warmup {
+            self.total.push(total);
+            self.cpu.push(times.cpu);
+            self.upload.push(times.upload);
+        }
+        self.rendered += 1;
+
+        if let Some(gpu_timer) = &mut self.gpu_timer {
+            gpu_timer.submitted();
+            self.gpu.extend(gpu_timer.poll(device, false).into_iter().filter(|&(frame, _)| frame >= self.warmup).map(|(_, time)| time));
+        }
+        self.rendered >= self.frames
+    }
+
+    /// Waits for the outstanding GPU times and formats the results as a table.
+    pub fn report(&mut self, device: &wgpu::Device) -> String {
+        if let Some(gpu_timer) = &mut self.gpu_timer {
+            self.gpu.extend(gpu_timer.poll(device, true).into_iter().filter(|&(frame, _)| frame >= self.warmup).map(|(_, time)| time));
+        }
+
+        let mut report = format!("{} frames after {} warmup frames\n", self.total.len(), self.warmup);
+        let _ = writeln!(report, "{:<8} {:>9} {:>9} {:>9} {:>9} {:>9}", "", "min", "avg", "p95", "p99", "max");
+        let gpu_name = if self.gpu_timer.is_some() { "gpu" } else { "gpu n/a" };
+        for (name, times) in [("frame", &mut self.total), ("cpu", &mut self.cpu), ("upload", &mut self.upload), (gpu_name, &mut self.gpu)] {
+            let _ = write!(report, "{name:<8}");
+            match Percentiles::new(times) {
+                Some(p) => {
+                    for time in [p.min, p.avg, p.p95, p.p99, p.max] {
+                        let _ = write!(report, " {:>7.3}ms", time.as_secs_f64() * 1000.0);
+                    }
+                },
+                None => report.push_str("         -"),
+            }
+            report.push('\n');
+        }
+        report
+    }
+}
+
+struct Percentiles {
+    min: Duration,
+    avg: Duration,
+    p95: Duration,
+    p99: Duration,
+    max: Duration,
+}
+
+impl Percentiles {
+    fn new(times: &mut [Duration]) -> Option<Self> {
+        times.sort_unstable();
+        let percentile = |p: f64| times[((times.len() - 1) as f64 * p).round() as usize];
+        Some(Self {
+            min: *times.first()?,
+            avg: times.iter().sum::<Duration>() / times.len() as u32,
+            p95: percentile(0.95),
+            p99: percentile(0.99),
+            max: *times.last()?,
+        })
+    }
+}
+
+enum SlotState {
+    Free,
+    /// The timestamps are written by a pass that isn't submitted yet.
+    Written,
+    /// Waiting for `map_async`, its callback sets whether mapping worked.
+    Mapping(Arc<OnceLock<bool>>),
+}
+
+struct TimerSlot {
+    frame: u32,
+    resolve_buffer: wgpu::Buffer,
+    readback_buffer: wgpu::Buffer,
+    state: SlotState,
+}
+
+/// Measures how long a render pass took on the GPU with a begin and end timestamp, without waiting for the results.
+/// Needs a device with `TIMESTAMP_QUERY`.
+pub struct GpuTimer {
+    query_set: wgpu::QuerySet,
+    /// Nanoseconds per timestamp tick.
+    period: f32,
+    slots: Vec<TimerSlot>,
+    /// The slot handed out by `timestamp_writes` and not resolved yet.
+    pending: Option<usize>,
+}
+
+impl GpuTimer {
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        let size = 2 * std::mem::size_of::<u64>() as u64;
+        let slots = (0..GPU_TIMER_SLOTS)
+            .map(|_| TimerSlot {
+                frame: 0,
+                resolve_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Timestamp Resolve"),
+                    size,
+                    usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+                    mapped_at_creation: false,
+                }),
+                readback_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Timestamp Readback"),
+                    size,
+                    usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                    mapped_at_creation: false,
+                }),
+                state: SlotState::Free,
+            })
+            .collect();
+        Self {
+            query_set: device.create_query_set(&wgpu::QuerySetDescriptor {
+                label: Some("Benchmark Timestamps"),
+                ty: wgpu::QueryType::Timestamp,
+                count: 2 * GPU_TIMER_SLOTS,
+            }),
+            period: queue.get_timestamp_period(),
+            slots,
+            pending: None,
+        }
+    }
+
+    /// For the render pass to measure, `frame` is handed back with its time. `None` while all slots are still in use.
+    pub fn timestamp_writes(&mut self, frame: u32) -> Option<wgpu::RenderPassTimestampWrites<'_>> {
+        let index = self.slots.iter().position(|slot| matches!(slot.state, SlotState::Free))?;
+        self.slots[index].frame = frame;
+        self.pending = Some(index);
+        Some(wgpu::RenderPassTimestampWrites {
+            query_set: &self.query_set,
+            beginning_of_pass_write_index: Some(2 * index as u32),
+            end_of_pass_write_index: Some(2 * index as u32 + 1),
+        })
+    }
+
+    /// Has to be called before `ce` is submitted, after the pass from [`Self::timestamp_writes`] ended.
+    pub fn resolve(&mut self, ce: &mut wgpu::CommandEncoder) {
+        let Some(index) = self.pending.take() else {
+            return;
+        };
+        let slot = &mut self.slots[index];
+        let first = 2 * index as u32;
+        ce.resolve_query_set(&self.query_set, first..first + 2, &slot.resolve_buffer, 0);
+        ce.copy_buffer_to_buffer(&slot.resolve_buffer, 0, &slot.readback_buffer, 0, slot.resolve_buffer.size());
+        slot.state = SlotState::Written;
+    }
+
+    /// Starts reading back what was resolved into the command buffers submitted since the last call.
+    pub fn submitted(&mut self) {
+        for slot in &mut self.slots {
+            if matches!(slot.state, SlotState::Written) {
+                let mapped = Arc::new(OnceLock::new());
+                let callback_mapped = mapped.clone();
+                slot.readback_buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+                    let _ = callback_mapped.set(result.is_ok());
+                });
+                slot.state = SlotState::Mapping(mapped);
+            }
+        }
+    }
+
+    /// Returns the GPU times that are available by now, per frame number. With `wait` it blocks until all are.
+    pub fn poll(&mut self, device: &wgpu::Device, wait: bool) -> Vec<(u32, Duration)> {
+        let _ = device.poll(if wait { wgpu::Maintain::Wait } else { wgpu::Maintain::Poll });
+
+        let mut times = Vec::new();
+        for slot in &mut self.slots {
+            let SlotState::Mapping(mapped) = &slot.state else {
+                continue;
+            };
+            match mapped.get() {
+                None => continue,
+                Some(false) => {
+                    slot.state = SlotState::Free;
+                    continue;
+                },
+                Some(true) => {},
+            }
+
+            let [begin, end] = {
+                let data = slot.readback_buffer.slice(..).get_mapped_range();
+                let timestamp = |offset: usize| u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+                [timestamp(0), timestamp(8)]
+            };
+            slot.readback_buffer.unmap();
+            slot.state = SlotState::Free;
+
+            let nanos = end.saturating_sub(begin) as f64 * self.period as f64;
+            times.push((slot.frame, Duration::from_nanos(nanos as u64)));
+        }
+        times
+    }
+}
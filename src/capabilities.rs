@@ -0,0 +1,86 @@
+/// An optional device feature the app makes use of.
+#[derive(Debug, Clone)]
+pub struct FeatureRequest {
+    pub feature: wgpu::Features,
+    /// What stops working without it.
+    pub used_for: &'static str,
+    pub granted: bool,
+}
+
+/// A limit the app would like to be higher than the baseline.
+#[derive(Debug, Clone)]
+pub struct LimitRequest {
+    pub name: &'static str,
+    pub used_for: &'static str,
+    pub requested: u32,
+    /// The adapter's limit if that's lower than requested.
+    pub granted: u32,
+}
+
+/// What the device was asked for and what the adapter could give, see [`negotiate`].
+#[derive(Debug, Clone)]
+pub struct CapabilityReport {
+    /// The limits everything not in `limits` is at, the WebGPU defaults or the lower ones of older GPUs.
+    pub base_limits: &'static str,
+    pub features: Vec<FeatureRequest>,
+    pub limits: Vec<LimitRequest>,
+}
+
+impl CapabilityReport {
+    pub fn log(&self) {
+        log::info!("Device limits based on the {}", self.base_limits);
+        for request in &self.features {
+            if request.granted {
+                log::info!("Device feature {:?} enabled", request.feature);
+            } else {
+                log::warn!("Device feature {:?} isn't supported, no {}", request.feature, request.used_for);
+            }
+        }
+        for request in &self.limits {
+            if request.granted < request.requested {
+                log::warn!("{} is limited to {} instead of {}, less {}", request.name, request.granted, request.requested, request.used_for);
+            } else {
+                log::info!("{} raised to {}", request.name, request.granted);
+            }
+        }
+    }
+}
+
+/// Textures up to this size are asked for, e.g. so the font atlas has room for large CJK text and pasted
+/// screenshots of high resolution displays fit. The WebGPU default is 8192.
+const WANTED_TEXTURE_SIZE: u32 = 16384;
+
+/// The features and limits to request from `adapter`: what the app can use, cut down to what the adapter has.
+/// Asking for anything more would fail `request_device`, the subsystems check the device for what they got.
+pub fn negotiate(adapter: &wgpu::Adapter) -> (wgpu::Features, wgpu::Limits, CapabilityReport) {
+    let available = adapter.features();
+    let wanted = [
+        (wgpu::Features::TIMESTAMP_QUERY, "GPU times in the benchmark and stress test"),
+        // Only 1× and 4× are guaranteed otherwise. Asked for whatever MSAA is set to, that can change at any time.
+        (wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES, "MSAA with 2× or 8×"),
+    ];
+    let features: Vec<_> = wanted.into_iter()
+        .map(|(feature, used_for)| FeatureRequest { feature, used_for, granted: available.contains(feature) })
+        .collect();
+    let required_features = features.iter()
+        .filter(|request| request.granted)
+        .fold(wgpu::Features::empty(), |all, request| all | request.feature);
+
+    // Older GPUs, e.g. on GL, don't even reach the defaults.
+    let adapter_limits = adapter.limits();
+    let (mut required_limits, base_limits) = if wgpu::Limits::default().check_limits(&adapter_limits) {
+        (wgpu::Limits::default(), "WebGPU defaults")
+    } else {
+        (wgpu::Limits::downlevel_webgl2_defaults().using_resolution(adapter_limits.clone()), "downlevel defaults")
+    };
+    let texture_size = LimitRequest {
+        name: "max_texture_dimension_2d",
+        used_for: "room in the font atlas and for big images",
+        requested: WANTED_TEXTURE_SIZE,
+        granted: WANTED_TEXTURE_SIZE.min(adapter_limits.max_texture_dimension_2d),
+    };
+    required_limits.max_texture_dimension_2d = required_limits.max_texture_dimension_2d.max(texture_size.granted);
+
+    let report = CapabilityReport { base_limits, features, limits: vec![texture_size] };
+    (required_features, required_limits, report)
+}
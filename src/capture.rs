@@ -0,0 +1,168 @@
+use std::sync::{Arc, OnceLock};
+use std::time::Instant;
+
+/// Pixels read back from the GPU, always RGBA8 with tightly packed rows.
+pub struct CapturedFrame {
+    pub size: [u32; 2],
+    pub rgba: Vec<u8>,
+    /// When the frame was rendered.
+    pub time: Instant,
+}
+
+/// Whether textures of `format` can be read back, only plain 8 bit color formats are supported.
+pub fn is_supported_format(format: wgpu::TextureFormat) -> bool {
+    matches!(
+        format,
+        wgpu::TextureFormat::Rgba8Unorm
+            | wgpu::TextureFormat::Rgba8UnormSrgb
+            | wgpu::TextureFormat::Bgra8Unorm
+            | wgpu::TextureFormat::Bgra8UnormSrgb
+    )
+}
+
+enum SlotState {
+    Free,
+    /// The copy is recorded into a command encoder that isn't submitted yet.
+    Copied,
+    /// Waiting for `map_async`, its callback sets whether mapping worked.
+    Mapping(Arc<OnceLock<bool>>),
+}
+
+struct Slot {
+    buffer: wgpu::Buffer,
+    size: [u32; 2],
+    bytes_per_row: u32,
+    bgra: bool,
+    time: Instant,
+    state: SlotState,
+}
+
+/// Copies rendered frames into a fixed number of staging buffers and hands them out once the GPU is done,
+/// without ever waiting for it. When all buffers are busy the frame is skipped instead.
+pub struct FrameReadback {
+    slots: Vec<Slot>,
+    max_slots: usize,
+}
+
+impl FrameReadback {
+    pub fn new(max_slots: usize) -> Self {
+        Self {
+            slots: Vec::new(),
+            max_slots,
+        }
+    }
+
+    /// Records a copy of `texture` into `ce`, returns false if there was no free buffer for it.
+    /// [`Self::submitted`] has to be called once `ce` was submitted.
+    pub fn copy(&mut self, device: &wgpu::Device, ce: &mut wgpu::CommandEncoder, texture: &wgpu::Texture, time: Instant) -> bool {
+        let size = [texture.width(), texture.height()];
+        let bytes_per_row = (size[0] * 4).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let buffer_size = bytes_per_row as u64 * size[1] as u64;
+
+        // Buffers of the wrong size are left over from before a resize.
+        self.slots.retain(|slot| !matches!(slot.state, SlotState::Free) || slot.buffer.size() == buffer_size);
+        let index = match self.slots.iter().position(|slot| matches!(slot.state, SlotState::Free)) {
+            Some(index) => index,
+            None if self.slots.len() < self.max_slots => {
+                self.slots.push(Slot {
+                    buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                        label: Some("Frame Readback"),
+                        size: buffer_size,
+                        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                        mapped_at_creation: false,
+                    }),
+                    size,
+                    bytes_per_row,
+                    bgra: false,
+                    time,
+                    state: SlotState::Free,
+                });
+                self.slots.len() - 1
+            },
+            None => return false,
+        };
+
+        let slot = &mut self.slots[index];
+        ce.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo {
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::TexelCopyBufferInfo {
+                buffer: &slot.buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(bytes_per_row),
+                    rows_per_image: None,
+                },
+            },
+            texture.size(),
+        );
+        slot.size = size;
+        slot.bytes_per_row = bytes_per_row;
+        slot.bgra = matches!(texture.format(), wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb);
+        slot.time = time;
+        slot.state = SlotState::Copied;
+        true
+    }
+
+    /// Starts mapping the buffers copied into since the last call.
+    pub fn submitted(&mut self) {
+        for slot in &mut self.slots {
+            if matches!(slot.state, SlotState::Copied) {
+                let mapped = Arc::new(OnceLock::new());
+                let callback_mapped = mapped.clone();
+                slot.buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+                    if let Err(error) = &result {
+                        log::warn!("Failed to map a readback buffer: {error}");
+                    }
+                    let _ = callback_mapped.set(result.is_ok());
+                });
+                slot.state = SlotState::Mapping(mapped);
+            }
+        }
+    }
+
+    /// Returns every frame the GPU finished copying, oldest first.
+    pub fn poll(&mut self, device: &wgpu::Device) -> Vec<CapturedFrame> {
+        let _ = device.poll(wgpu::Maintain::Poll);
+
+        let mut frames = Vec::new();
+        for slot in &mut self.slots {
+            let SlotState::Mapping(mapped) = &slot.state else {
+                continue;
+            };
+            match mapped.get() {
+                None => continue,
+                Some(false) => {
+                    slot.state = SlotState::Free;
+                    continue;
+                },
+                Some(true) => {},
+            }
+
+            let [width, height] = slot.size;
+            let mut rgba = Vec::with_capacity(width as usize * height as usize * 4);
+            {
+                let data = slot.buffer.slice(..).get_mapped_range();
+                for row in data.chunks_exact(slot.bytes_per_row as usize).take(height as usize) {
+                    rgba.extend_from_slice(&row[..width as usize * 4]);
+                }
+            }
+            slot.buffer.unmap();
+            slot.state = SlotState::Free;
+
+            if slot.bgra {
+                for pixel in rgba.chunks_exact_mut(4) {
+                    pixel.swap(0, 2);
+                }
+            }
+            frames.push(CapturedFrame { size: slot.size, rgba, time: slot.time });
+        }
+
+        frames.sort_by_key(|frame| frame.time);
+        frames
+    }
+}
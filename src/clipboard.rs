@@ -0,0 +1,122 @@
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+/// Where a pasted image goes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PasteTarget {
+    ImageDemo,
+    Painting,
+}
+
+/// Returned by [`ClipboardImages::poll`].
+pub enum ClipboardResult {
+    Pasted(PasteTarget, egui::ColorImage),
+    Copied,
+    /// Meant for a toast.
+    Failed(String),
+}
+
+enum Request {
+    /// Images with a side longer than `max_side` pixels are turned away, they couldn't be uploaded.
+    Paste { target: PasteTarget, max_side: usize },
+    Copy(egui::ColorImage),
+}
+
+/// Copies images to and pastes them from the system clipboard. egui-winit only handles text. Converting a big
+/// screenshot can take a while, and the app it comes from may be slow to hand it over, so this happens on a thread
+/// of its own. That thread also keeps owning what was copied, which on Linux is gone once the clipboard is closed.
+pub struct ClipboardImages {
+    requests: Sender<Request>,
+    results: Receiver<ClipboardResult>,
+}
+
+impl ClipboardImages {
+    /// `ctx` is repainted whenever a request is done.
+    pub fn new(ctx: &egui::Context) -> Self {
+        let (requests_tx, requests_rx) = mpsc::channel();
+        let (results_tx, results_rx) = mpsc::channel();
+        let ctx = ctx.clone();
+        let spawned = thread::Builder::new().name("clipboard".into()).spawn(move || {
+            let mut clipboard = platform::Clipboard::default();
+            for request in requests_rx {
+                let result = match request {
+                    Request::Paste { target, max_side } => clipboard.paste(max_side).map(|image| ClipboardResult::Pasted(target, image)),
+                    Request::Copy(image) => clipboard.copy(&image).map(|()| ClipboardResult::Copied),
+                };
+                if results_tx.send(result.unwrap_or_else(ClipboardResult::Failed)).is_err() {
+                    return;
+                }
+                ctx.request_repaint();
+            }
+        });
+        if let Err(error) = spawned {
+            log::error!("Failed to start the clipboard thread: {error}");
+        }
+        Self { requests: requests_tx, results: results_rx }
+    }
+
+    pub fn paste(&self, target: PasteTarget, max_side: usize) {
+        let _ = self.requests.send(Request::Paste { target, max_side });
+    }
+
+    pub fn copy(&self, image: egui::ColorImage) {
+        let _ = self.requests.send(Request::Copy(image));
+    }
+
+    /// What was done since the last call.
+    pub fn poll(&self) -> Vec<ClipboardResult> {
+        self.results.try_iter().collect()
+    }
+}
+
+#[cfg(not(target_os = "android"))]
+mod platform {
+    use std::borrow::Cow;
+
+    /// Opened on first use, so a platform without a clipboard only fails once something is copied or pasted.
+    #[derive(Default)]
+    pub struct Clipboard(Option<arboard::Clipboard>);
+
+    impl Clipboard {
+        fn open(&mut self) -> Result<&mut arboard::Clipboard, String> {
+            if self.0.is_none() {
+                self.0 = Some(arboard::Clipboard::new().map_err(|error| format!("Can't open the clipboard: {error}"))?);
+            }
+            Ok(self.0.as_mut().unwrap())
+        }
+
+        pub fn paste(&mut self, max_side: usize) -> Result<egui::ColorImage, String> {
+            let image = self.open()?.get_image().map_err(|error| match error {
+                arboard::Error::ContentNotAvailable => "There's no image on the clipboard".to_owned(),
+                arboard::Error::ConversionFailure => "The image on the clipboard has a format that isn't supported".to_owned(),
+                error => format!("Can't paste the image: {error}"),
+            })?;
+            if image.width.max(image.height) > max_side {
+                return Err(format!("The image on the clipboard is too big, {} × {} pixels", image.width, image.height));
+            }
+            Ok(egui::ColorImage::from_rgba_unmultiplied([image.width, image.height], &image.bytes))
+        }
+
+        pub fn copy(&mut self, image: &egui::ColorImage) -> Result<(), String> {
+            let bytes: Vec<u8> = image.pixels.iter().flat_map(|pixel| pixel.to_srgba_unmultiplied()).collect();
+            let data = arboard::ImageData { width: image.width(), height: image.height(), bytes: Cow::Owned(bytes) };
+            self.open()?.set_image(data).map_err(|error| format!("Can't copy the image: {error}"))
+        }
+    }
+}
+
+#[cfg(target_os = "android")]
+mod platform {
+    #[derive(Default)]
+    pub struct Clipboard;
+
+    impl Clipboard {
+        pub fn paste(&mut self, _max_side: usize) -> Result<egui::ColorImage, String> {
+            Err("Pasting images isn't supported on Android".to_owned())
+        }
+
+        pub fn copy(&mut self, _image: &egui::ColorImage) -> Result<(), String> {
+            Err("Copying images isn't supported on Android".to_owned())
+        }
+    }
+}
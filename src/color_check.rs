@@ -0,0 +1,111 @@
+use crate::capture::CapturedFrame;
+
+/// Off by this much in a channel still counts as the same color, dithering and the shaders' rounding add a little.
+const TOLERANCE: u8 = 3;
+
+/// A patch of one color on the color test card, read back from the pixel in its middle.
+#[derive(Debug, Clone)]
+pub struct ColorProbe {
+    pub name: String,
+    /// What the UI painted, sRGB encoded like every egui color.
+    pub color: egui::Color32,
+    /// In physical pixels of the window, which are the surface's.
+    pub pixel: [u32; 2],
+}
+
+/// What the colors read back from the surface say about how it's set up.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorVerdict {
+    /// They are what the UI painted.
+    Matches,
+    /// Too bright, as if sRGB encoded twice: already encoded colors went through a view that encodes them again.
+    EncodedTwice,
+    /// Too dark, as if never encoded: linear colors went into a view that stores them as they are.
+    NotEncoded,
+    /// The color adjustment post effect changes them on purpose, nothing can be said.
+    Adjusted,
+    /// Off in some other way, or the patches weren't in view.
+    Different,
+}
+
+/// The result of reading back the color test card, see [`ColorCheck::new`].
+#[derive(Debug, Clone)]
+pub struct ColorCheck {
+    pub surface_format: wgpu::TextureFormat,
+    pub view_format: wgpu::TextureFormat,
+    /// The color read back for each probe, `None` where it was outside the frame.
+    pub samples: Vec<(ColorProbe, Option<egui::Color32>)>,
+    pub verdict: ColorVerdict,
+}
+
+impl ColorCheck {
+    /// Compares the probes with `frame`, copied from a surface of `surface_format` that was drawn to as
+    /// `view_format`. In either case the bytes should come out as the sRGB colors egui painted: an sRGB view encodes
+    /// the linear colors egui-wgpu writes to it, otherwise egui-wgpu writes encoded ones.
+    pub fn new(
+        frame: &CapturedFrame,
+        probes: &[ColorProbe],
+        surface_format: wgpu::TextureFormat,
+        view_format: wgpu::TextureFormat,
+        adjusted: bool,
+    ) -> Self {
+        let samples: Vec<_> = probes.iter().map(|probe| (probe.clone(), sample(frame, probe.pixel))).collect();
+        let verdict = if adjusted { ColorVerdict::Adjusted } else { verdict(&samples) };
+        Self { surface_format, view_format, samples, verdict }
+    }
+}
+
+fn sample(frame: &CapturedFrame, [x, y]: [u32; 2]) -> Option<egui::Color32> {
+    let [width, height] = frame.size;
+    if x >= width || y >= height {
+        return None;
+    }
+    let index = (y as usize * width as usize + x as usize) * 4;
+    let pixel = frame.rgba.get(index..index + 3)?;
+    Some(egui::Color32::from_rgb(pixel[0], pixel[1], pixel[2]))
+}
+
+fn verdict(samples: &[(ColorProbe, Option<egui::Color32>)]) -> ColorVerdict {
+    let all_close = |channel: fn(u8) -> u8| {
+        !samples.is_empty() && samples.iter().all(|(probe, measured)| {
+            let [r, g, b, _] = probe.color.to_array();
+            let expected = [r, g, b].map(channel);
+            measured.is_some_and(|measured| {
+                expected.iter().zip(&measured.to_array()).all(|(expected, measured)| expected.abs_diff(*measured) <= TOLERANCE)
+            })
+        })
+    };
+    if all_close(|value| value) {
+        ColorVerdict::Matches
+    } else if all_close(|value| egui::ecolor::gamma_u8_from_linear_f32(value as f32 / 255.0)) {
+        ColorVerdict::EncodedTwice
+    } else if all_close(|value| (egui::ecolor::linear_f32_from_gamma_u8(value) * 255.0).round() as u8) {
+        ColorVerdict::NotEncoded
+    } else {
+        ColorVerdict::Different
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tells_colors_encoded_twice_from_ones_never_encoded() {
+        let probes: Vec<_> = [0, 64, 128, 255].into_iter()
+            .enumerate()
+            .map(|(x, value)| ColorProbe { name: format!("sRGB {value}"), color: egui::Color32::from_gray(value), pixel: [x as u32, 0] })
+            .collect();
+        let check = |pixels: [u8; 4]| {
+            let rgba = pixels.iter().flat_map(|&value| [value, value, value, 255]).collect();
+            let frame = CapturedFrame { size: [4, 1], rgba, time: std::time::Instant::now() };
+            let format = wgpu::TextureFormat::Bgra8UnormSrgb;
+            ColorCheck::new(&frame, &probes, format, format, false).verdict
+        };
+
+        assert_eq!(check([0, 65, 127, 255]), ColorVerdict::Matches);
+        assert_eq!(check([0, 137, 188, 255]), ColorVerdict::EncodedTwice);
+        assert_eq!(check([0, 13, 55, 255]), ColorVerdict::NotEncoded);
+        assert_eq!(check([0, 64, 128, 200]), ColorVerdict::Different);
+    }
+}
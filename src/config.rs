@@ -0,0 +1,260 @@
+use std::path::{Path, PathBuf};
+
+use crate::autosave::PersistedFile;
+use crate::json::Json;
+use crate::settings::Settings;
+use crate::storage::{self, Document, Value};
+
+/// Which section of the file each setting is kept in, by its name in [`Settings::to_json`].
+const SETTING_SECTIONS: &[(&str, &[&str])] = &[
+    ("window", &["title", "title_suffix", "window_level", "skip_taskbar", "close_to_tray", "start_in_tray", "resizable"]),
+    ("gpu", &["frame_latency", "dithering", "srgb_view", "msaa_samples"]),
+    ("rendering", &[
+        "throttle_in_background", "unfocused_fps", "trim_when_idle", "idle_trim_minutes", "limit_fps", "target_fps", "low_latency", "wait_for_gpu",
+        "render_scale", "scale_filter", "post_effect", "fxaa_span_max", "gamma", "brightness",
+    ]),
+    ("ui", &["theme", "language", "zoom_factor", "pixel_snap", "feathering", "motion", "min_ui_scale"]),
+    ("input", &["line_scroll_speed", "pixel_scroll_speed", "smooth_scrolling", "shift_scroll_horizontal", "key_repeat", "on_screen_keyboard"]),
+    ("saving", &["autosave", "autosave_seconds"]),
+];
+
+const BACKENDS: &[(&str, wgpu::Backends)] = &[
+    ("all", wgpu::Backends::all()),
+    ("primary", wgpu::Backends::PRIMARY),
+    ("vulkan", wgpu::Backends::VULKAN),
+    ("metal", wgpu::Backends::METAL),
+    ("dx12", wgpu::Backends::DX12),
+    ("gl", wgpu::Backends::GL),
+];
+
+const POWER_PREFERENCES: &[(&str, wgpu::PowerPreference)] = &[
+    ("none", wgpu::PowerPreference::None),
+    ("low_power", wgpu::PowerPreference::LowPower),
+    ("high_performance", wgpu::PowerPreference::HighPerformance),
+];
+
+/// `"default"` keeps whatever the surface lists first.
+const PRESENT_MODES: &[(&str, Option<wgpu::PresentMode>)] = &[
+    ("default", None),
+    ("auto_vsync", Some(wgpu::PresentMode::AutoVsync)),
+    ("auto_no_vsync", Some(wgpu::PresentMode::AutoNoVsync)),
+    ("fifo", Some(wgpu::PresentMode::Fifo)),
+    ("fifo_relaxed", Some(wgpu::PresentMode::FifoRelaxed)),
+    ("mailbox", Some(wgpu::PresentMode::Mailbox)),
+    ("immediate", Some(wgpu::PresentMode::Immediate)),
+];
+
+/// How the GPU gets picked and set up, only read at startup.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GpuPreferences {
+    pub backends: wgpu::Backends,
+    /// Discrete GPUs go first with `HighPerformance`, integrated ones with `LowPower`.
+    pub power_preference: wgpu::PowerPreference,
+    /// Used if the surface supports it.
+    pub present_mode: Option<wgpu::PresentMode>,
+}
+
+impl Default for GpuPreferences {
+    fn default() -> Self {
+        Self {
+            backends: wgpu::Backends::all(),
+            power_preference: wgpu::PowerPreference::None,
+            present_mode: None,
+        }
+    }
+}
+
+/// Startup configuration from `config.toml`, meant to be edited by hand as well.
+/// The settings in it are written back on exit, everything else is only read.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Config {
+    pub settings: Settings,
+    /// Same as `--overlay` and `--custom-decorations`, either one turns them on.
+    pub overlay: bool,
+    pub custom_decorations: bool,
+    pub gpu: GpuPreferences,
+}
+
+impl Config {
+    /// Keys that are missing keep their defaults, invalid values too but those are reported in the returned warnings.
+    pub fn from_document(document: &Document) -> (Self, Vec<String>) {
+        let mut warnings = Vec::new();
+
+        let json: Json = SETTING_SECTIONS.iter()
+            .flat_map(|(section, keys)| keys.iter().map(move |key| (*section, *key)))
+            .filter_map(|(section, key)| Some((key, value_to_json(document.get(section, key)?))))
+            .collect();
+        let settings = Settings::from_json(&json);
+        // `Settings::from_json` skips what it can't use, a value of a different kind than the one it reads back as
+        // was one of those. Numbers that were only clamped into range aren't worth a warning.
+        let read_back = settings.to_json();
+        for (section, keys) in SETTING_SECTIONS {
+            for key in *keys {
+                let (Some(given), Some(used)) = (json.get(key), read_back.get(key)) else {
+                    continue;
+                };
+                let accepted = match (given, used) {
+                    (Json::Number(_), Json::Number(_)) => true,
+                    _ => given == used,
+                };
+                if !accepted {
+                    warnings.push(format!("[{section}] {key} = {} is invalid, using {used}", document.get(section, key).unwrap()));
+                }
+            }
+        }
+
+        let mut config = Self { settings, ..Self::default() };
+        let mut read = |section: &str, key: &str, apply: &mut dyn FnMut(&Value) -> bool| {
+            if let Some(value) = document.get(section, key) {
+                if !apply(value) {
+                    warnings.push(format!("[{section}] {key} = {value} is invalid, using the default"));
+                }
+            }
+        };
+        read("window", "overlay", &mut |value| set_bool(value, &mut config.overlay));
+        read("window", "custom_decorations", &mut |value| set_bool(value, &mut config.custom_decorations));
+        read("gpu", "backend", &mut |value| set_named(value, BACKENDS, &mut config.gpu.backends));
+        read("gpu", "power_preference", &mut |value| set_named(value, POWER_PREFERENCES, &mut config.gpu.power_preference));
+        read("gpu", "present_mode", &mut |value| set_named(value, PRESENT_MODES, &mut config.gpu.present_mode));
+
+        (config, warnings)
+    }
+
+    /// Writes everything into `document`, keys that aren't known here are left alone.
+    pub fn write_to(&self, document: &mut Document) {
+        let json = self.settings.to_json();
+        for (section, keys) in SETTING_SECTIONS {
+            for key in *keys {
+                if let Some(value) = json.get(key).and_then(json_to_value) {
+                    document.set(section, key, value);
+                }
+            }
+        }
+        document.set("window", "overlay", self.overlay);
+        document.set("window", "custom_decorations", self.custom_decorations);
+        document.set("gpu", "backend", name_of(BACKENDS, self.gpu.backends));
+        document.set("gpu", "power_preference", name_of(POWER_PREFERENCES, self.gpu.power_preference));
+        document.set("gpu", "present_mode", name_of(PRESENT_MODES, self.gpu.present_mode));
+    }
+}
+
+/// `config.toml` and what was read from it.
+pub struct ConfigFile {
+    path: Option<PathBuf>,
+    document: Document,
+    /// False if the file couldn't be read, it's left as it is then instead of being overwritten with the defaults.
+    writable: bool,
+}
+
+impl ConfigFile {
+    /// Where the config is looked for without `--config`.
+    pub fn default_path() -> Option<PathBuf> {
+        storage::data_dir().map(|dir| dir.join("config.toml"))
+    }
+
+    /// Never fails, problems with the file end up in the returned warnings and the defaults are used instead.
+    pub fn load(path: Option<PathBuf>) -> (Self, Config, Vec<String>) {
+        let Some(path) = path else {
+            return (Self { path: None, document: Document::default(), writable: false }, Config::default(), Vec::new());
+        };
+        match Document::load(&path) {
+            Ok(document) => {
+                let (config, warnings) = Config::from_document(&document);
+                (Self { path: Some(path), document, writable: true }, config, warnings)
+            },
+            Err(error) => {
+                let warning = format!("Failed to read {}, using the default config: {error}", path.display());
+                (Self { path: Some(path), document: Document::default(), writable: false }, Config::default(), vec![warning])
+            },
+        }
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// The file with `config` written into it, keeping what else was in there. `None` if it's not to be written.
+    pub fn contents(&self, config: &Config) -> Option<PersistedFile> {
+        let path = self.path.clone().filter(|_| self.writable)?;
+        let mut document = self.document.clone();
+        config.write_to(&mut document);
+        Some(PersistedFile { path, contents: document.to_string() })
+    }
+}
+
+fn set_bool(value: &Value, target: &mut bool) -> bool {
+    match value {
+        Value::Bool(value) => {
+            *target = *value;
+            true
+        },
+        _ => false,
+    }
+}
+
+fn set_named<T: Copy>(value: &Value, names: &[(&str, T)], target: &mut T) -> bool {
+    let found = match value {
+        Value::String(text) => names.iter().find(|(name, _)| name == text),
+        _ => None,
+    };
+    if let Some((_, value)) = found {
+        *target = *value;
+    }
+    found.is_some()
+}
+
+fn name_of<T: PartialEq>(names: &[(&'static str, T)], value: T) -> &'static str {
+    names.iter().find(|(_, named)| *named == value).map_or(names[0].0, |(name, _)| name)
+}
+
+fn value_to_json(value: &Value) -> Json {
+    match value {
+        Value::Bool(value) => Json::Bool(*value),
+        Value::Int(value) => Json::Number(*value as f64),
+        Value::Float(value) => Json::Number(*value),
+        Value::String(value) => Json::String(value.clone()),
+    }
+}
+
+/// Whole numbers are written without a decimal point, so counts like the frame latency read naturally.
+fn json_to_value(json: &Json) -> Option<Value> {
+    match json {
+        Json::Bool(value) => Some(Value::Bool(*value)),
+        Json::Number(value) if value.fract() == 0.0 && value.abs() < 1e15 => Some(Value::Int(*value as i64)),
+        // The settings keep their numbers as `f32`, going through its shortest form drops the digits widening added.
+        Json::Number(value) => Some(Value::Float((*value as f32).to_string().parse().unwrap_or(*value))),
+        Json::String(value) => Some(Value::String(value.clone())),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_keys_and_sections_are_ignored_and_kept() {
+        let document = Document::parse(
+            "[window]\nresizable = false\nfuture_option = 3\n\n[plugins]\nenabled = true\n\n[gpu] # only read at startup\nbackend = \"vulkan\"\n",
+        ).unwrap();
+        let (config, warnings) = Config::from_document(&document);
+        assert_eq!(warnings, Vec::<String>::new());
+        assert!(!config.settings.resizable);
+        assert_eq!(config.gpu.backends, wgpu::Backends::VULKAN);
+
+        let mut written = document.clone();
+        config.write_to(&mut written);
+        let written = Document::parse(&written.to_string()).unwrap();
+        assert_eq!(written.get_i64("window", "future_option"), Some(3));
+        assert_eq!(written.get_bool("plugins", "enabled"), Some(true));
+        assert_eq!(Config::from_document(&written), (config, Vec::new()));
+    }
+
+    #[test]
+    fn invalid_values_keep_their_defaults_with_a_warning() {
+        let document = Document::parse("[window]\nresizable = \"no\"\n[gpu]\nbackend = \"glide\"\n").unwrap();
+        let (config, warnings) = Config::from_document(&document);
+        assert_eq!(config, Config::default());
+        assert_eq!(warnings.len(), 2, "{warnings:?}");
+    }
+}
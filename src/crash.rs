@@ -0,0 +1,187 @@
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::storage;
+
+/// Panics of the UI in a row, without a frame that went through in between, after which the app gives up and exits.
+const MAX_PANICS_IN_A_ROW: u32 = 3;
+
+/// What the panic hook found out about the last panic on the UI thread, picked up by [`CrashState::on_panic`].
+static LAST_PANIC: Mutex<Option<CrashReport>> = Mutex::new(None);
+
+/// A panic of the UI, with what's needed to report it.
+#[derive(Debug, Clone)]
+pub struct CrashReport {
+    /// Where it panicked and with what message.
+    pub message: String,
+    pub backtrace: String,
+}
+
+impl CrashReport {
+    pub fn text(&self) -> String {
+        format!("{} {} crashed\n{}\n\nBacktrace:\n{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"), self.message, self.backtrace)
+    }
+
+    fn write(&self, path: &Path) -> std::io::Result<()> {
+        storage::write_atomic(path, &self.text())
+    }
+}
+
+/// Where the panic hook writes the report of the last crash.
+pub fn last_report_path() -> Option<PathBuf> {
+    storage::data_dir().map(|dir| dir.join("crash.txt"))
+}
+
+/// Wraps the current panic hook: panics on the calling thread, the UI thread, are also written to
+/// [`last_report_path`] with a backtrace, in case the crash screen can't be shown. Other threads, like the task
+/// pool's, report their panics themselves.
+pub fn install_panic_hook() {
+    let ui_thread = thread::current().id();
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if thread::current().id() == ui_thread {
+            let report = CrashReport { message: info.to_string(), backtrace: Backtrace::force_capture().to_string() };
+            if let Some(path) = last_report_path() {
+                if let Err(error) = report.write(&path) {
+                    eprintln!("Failed to write the crash report to {}: {error}", path.display());
+                }
+            }
+            if let Ok(mut last) = LAST_PANIC.lock() {
+                *last = Some(report);
+            }
+        }
+        previous(info);
+    }));
+}
+
+/// Picked by the user on the crash screen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CrashAction {
+    /// Go on with a reset UI state.
+    Continue,
+    Quit,
+}
+
+/// Whether the UI panicked and the crash screen is shown instead of it, see [`Self::show`].
+#[derive(Default)]
+pub struct CrashState {
+    report: Option<CrashReport>,
+    panics_in_a_row: u32,
+    /// Where the report was saved with the button, or why that failed.
+    saved: Option<Result<PathBuf, String>>,
+}
+
+impl CrashState {
+    pub fn is_crashed(&self) -> bool {
+        self.report.is_some()
+    }
+
+    /// Whether the UI kept panicking and the app should exit with an error.
+    pub fn gave_up(&self) -> bool {
+        self.panics_in_a_row >= MAX_PANICS_IN_A_ROW
+    }
+
+    /// Switches to the crash screen with the report the panic hook made, or one made from `payload` if the hook
+    /// was replaced. Returns `false` once there were too many panics in a row.
+    pub fn on_panic(&mut self, payload: Box<dyn Any + Send>) -> bool {
+        let report = LAST_PANIC.lock().ok().and_then(|mut last| last.take()).unwrap_or_else(|| {
+            let message = payload.downcast_ref::<&str>().map(|message| message.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "Box<dyn Any>".to_owned());
+            CrashReport { message, backtrace: "(not captured)".to_owned() }
+        });
+        log::error!("The UI panicked: {}", report.message);
+        self.report = Some(report);
+        self.saved = None;
+        self.panics_in_a_row += 1;
+        !self.gave_up()
+    }
+
+    /// Called for every frame of the app's UI that went through.
+    pub fn frame_succeeded(&mut self) {
+        self.panics_in_a_row = 0;
+    }
+
+    /// Kept to plain egui widgets and untranslated text, so as little as possible can fail again.
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<CrashAction> {
+        let report = self.report.as_ref()?;
+        let mut action = None;
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("Something went wrong");
+            ui.label("The UI crashed. The report below has the details, please include it when reporting the problem.");
+            if let Some(path) = last_report_path() {
+                ui.weak(format!("It was also written to {}", path.display()));
+            }
+            ui.horizontal(|ui| {
+                if ui.button("Copy report").clicked() {
+                    ctx.copy_text(report.text());
+                }
+                if ui.button("Save report").clicked() {
+                    self.saved = Some(save_report(report));
+                }
+                if ui.button("Continue").on_hover_text("Resets the widget gallery and tries again").clicked() {
+                    action = Some(CrashAction::Continue);
+                }
+                if ui.button("Quit").clicked() {
+                    action = Some(CrashAction::Quit);
+                }
+            });
+            match &self.saved {
+                Some(Ok(path)) => {
+                    ui.label(format!("Saved to {}", path.display()));
+                },
+                Some(Err(error)) => {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                },
+                None => {},
+            }
+            ui.separator();
+            ui.label(egui::RichText::new(&report.message).strong());
+            egui::ScrollArea::both().auto_shrink(false).show(ui, |ui| {
+                ui.monospace(&report.backtrace);
+            });
+        });
+        if action == Some(CrashAction::Continue) {
+            self.report = None;
+        }
+        action
+    }
+}
+
+/// Next to the last report, with a timestamped name so the next crash doesn't replace it.
+fn save_report(report: &CrashReport) -> Result<PathBuf, String> {
+    let dir = storage::data_dir().ok_or("There's no folder to save it to on this platform")?;
+    let path = dir.join(format!("crash-{}.txt", SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()));
+    report.write(&path).map_err(|error| format!("Failed to save the report: {error}"))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gives_up_on_the_third_panic_in_a_row() {
+        let mut crash = CrashState::default();
+        let panic = || Box::new("boom") as Box<dyn Any + Send>;
+        assert!(crash.on_panic(panic()));
+        assert!(crash.on_panic(panic()));
+        crash.frame_succeeded();
+        assert!(crash.on_panic(panic()));
+        assert!(crash.on_panic(panic()));
+        assert!(!crash.on_panic(panic()));
+        assert!(crash.gave_up());
+    }
+
+    #[test]
+    fn a_recovered_panic_is_not_giving_up() {
+        let mut crash = CrashState::default();
+        assert!(crash.on_panic(Box::new("boom")));
+        assert!(crash.is_crashed());
+        assert!(!crash.gave_up());
+    }
+}
@@ -0,0 +1,121 @@
+use winit::window::{CursorIcon, ResizeDirection, Window, WindowAttributes};
+
+use crate::watchdog::Watchdog;
+
+const TITLE_BAR_HEIGHT: f32 = 28.0;
+/// Width of the invisible resize zones along the window border, in points.
+const RESIZE_BORDER: f32 = 5.0;
+
+/// Changes the window attributes so the title bar can be drawn by egui instead of the OS.
+pub fn window_attributes(attributes: WindowAttributes) -> WindowAttributes {
+    #[cfg(target_os = "macos")]
+    {
+        // The OS buttons stay, only the title bar itself becomes part of the content.
+        use winit::platform::macos::WindowAttributesExtMacOS;
+        attributes
+            .with_titlebar_transparent(true)
+            .with_title_hidden(true)
+            .with_fullsize_content_view(true)
+    }
+    #[cfg(not(target_os = "macos"))]
+    attributes.with_decorations(false)
+}
+
+/// What the user clicked in the title bar.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TitleBarAction {
+    None,
+    Close,
+}
+
+/// Draws the title bar at the top of the window, has to be shown before any other panel.
+/// On Windows a drag doesn't return before the mouse is released, which `watchdog` is told about.
+pub fn title_bar(ctx: &egui::Context, window: &Window, watchdog: &Watchdog) -> TitleBarAction {
+    let mut action = TitleBarAction::None;
+
+    egui::TopBottomPanel::top("title_bar")
+        .exact_height(TITLE_BAR_HEIGHT)
+        .show(ctx, |ui| {
+            let bar_rect = ui.max_rect();
+            // Registered first so the buttons added afterwards are on top of it and get their clicks.
+            let bar_response = ui.interact(bar_rect, ui.id().with("drag"), egui::Sense::click_and_drag());
+
+            ui.painter().text(
+                bar_rect.center(),
+                egui::Align2::CENTER_CENTER,
+                window.title(),
+                egui::FontId::proportional(14.0),
+                ui.visuals().text_color(),
+            );
+
+            if bar_response.double_clicked() {
+                window.set_maximized(!window.is_maximized());
+            } else if bar_response.drag_started_by(egui::PointerButton::Primary) {
+                let _ = watchdog.modal_loop(|| window.drag_window());
+            }
+
+            // macOS draws its own traffic-light buttons on top of the bar.
+            #[cfg(not(target_os = "macos"))]
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                ui.spacing_mut().item_spacing.x = 0.0;
+                let button = |text| egui::Button::new(egui::RichText::new(text).size(14.0)).frame(false).min_size(egui::vec2(TITLE_BAR_HEIGHT, TITLE_BAR_HEIGHT));
+
+                if ui.add(button("🗙")).on_hover_text("Close").clicked() {
+                    action = TitleBarAction::Close;
+                }
+                let maximized = window.is_maximized();
+                if ui.add(button(if maximized { "🗗" } else { "🗖" })).on_hover_text(if maximized { "Restore" } else { "Maximize" }).clicked() {
+                    window.set_maximized(!maximized);
+                }
+                if ui.add(button("🗕")).on_hover_text("Minimize").clicked() {
+                    window.set_minimized(true);
+                }
+            });
+        });
+
+    action
+}
+
+/// Starts an OS resize when the border of the window is dragged, and shows the matching cursor while hovering it.
+/// Needs to run after the UI so the cursor icon isn't overwritten by widgets.
+pub fn resize_handles(ctx: &egui::Context, window: &Window, watchdog: &Watchdog) {
+    // The macOS window keeps its native border, which already handles resizing.
+    if cfg!(target_os = "macos") || window.is_maximized() || !window.is_resizable() {
+        return;
+    }
+
+    let (pos, pressed) = ctx.input(|i| (i.pointer.hover_pos(), i.pointer.primary_pressed()));
+    let Some(direction) = pos.and_then(|pos| resize_direction(ctx.screen_rect(), pos)) else {
+        return;
+    };
+
+    ctx.set_cursor_icon(match CursorIcon::from(direction) {
+        CursorIcon::NResize | CursorIcon::SResize => egui::CursorIcon::ResizeVertical,
+        CursorIcon::EResize | CursorIcon::WResize => egui::CursorIcon::ResizeHorizontal,
+        CursorIcon::NeResize | CursorIcon::SwResize => egui::CursorIcon::ResizeNeSw,
+        _ => egui::CursorIcon::ResizeNwSe,
+    });
+
+    if pressed {
+        let _ = watchdog.modal_loop(|| window.drag_resize_window(direction));
+    }
+}
+
+fn resize_direction(screen: egui::Rect, pos: egui::Pos2) -> Option<ResizeDirection> {
+    let left = pos.x < screen.left() + RESIZE_BORDER;
+    let right = pos.x > screen.right() - RESIZE_BORDER;
+    let top = pos.y < screen.top() + RESIZE_BORDER;
+    let bottom = pos.y > screen.bottom() - RESIZE_BORDER;
+
+    match (left, right, top, bottom) {
+        (true, _, true, _) => Some(ResizeDirection::NorthWest),
+        (_, true, true, _) => Some(ResizeDirection::NorthEast),
+        (true, _, _, true) => Some(ResizeDirection::SouthWest),
+        (_, true, _, true) => Some(ResizeDirection::SouthEast),
+        (true, ..) => Some(ResizeDirection::West),
+        (_, true, ..) => Some(ResizeDirection::East),
+        (_, _, true, _) => Some(ResizeDirection::North),
+        (.., true) => Some(ResizeDirection::South),
+        _ => None,
+    }
+}
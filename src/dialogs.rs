@@ -0,0 +1,364 @@
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+
+use crate::i18n::tr;
+use crate::ui::FileDialog;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DialogKind {
+    Open,
+    Save,
+}
+
+/// A kind of file a dialog offers, by its extensions without the dot.
+#[derive(Debug, Clone, Copy)]
+pub struct FileFilter {
+    pub name: &'static str,
+    pub extensions: &'static [&'static str],
+}
+
+#[derive(Debug, Clone)]
+pub struct DialogRequest {
+    pub kind: DialogKind,
+    pub title: String,
+    /// Picked at first, the dialog starts in its folder.
+    pub path: PathBuf,
+    pub filters: Vec<FileFilter>,
+}
+
+impl DialogRequest {
+    pub fn new(kind: DialogKind, title: impl Into<String>, path: &Path) -> Self {
+        Self { kind, title: title.into(), path: path.to_owned(), filters: Vec::new() }
+    }
+
+    /// Filters come in the order they're added, the first one is picked at first.
+    pub fn with_filter(mut self, name: &'static str, extensions: &'static [&'static str]) -> Self {
+        self.filters.push(FileFilter { name, extensions });
+        self
+    }
+}
+
+/// The end of a dialog, sent from its thread through the event loop to [`Dialogs::on_event`].
+#[derive(Debug)]
+pub struct DialogEvent {
+    id: u64,
+    outcome: Outcome,
+}
+
+#[derive(Debug)]
+enum Outcome {
+    Picked(Option<PathBuf>),
+    /// None of the dialog programs of the platform are installed.
+    Unavailable,
+}
+
+type Callback<T> = Box<dyn FnOnce(&mut T, Option<PathBuf>)>;
+
+enum State {
+    /// The dialog program, until it's done. Killed when waiting for it is cancelled.
+    Native(Arc<Mutex<Option<Child>>>),
+    /// Asks in a text field instead, without a dialog program.
+    TextField(FileDialog),
+    Done(Option<PathBuf>),
+}
+
+struct Pending<T> {
+    id: u64,
+    request: DialogRequest,
+    callback: Callback<T>,
+    state: State,
+}
+
+/// File dialogs that never block the event loop: the platform's dialog is a program of its own that runs on a
+/// thread of ours, so the window keeps rendering while it's up. Where there's no such program the path is asked for
+/// in a text field. `T` is what the callbacks get to change when a file was picked, on the UI thread in the frame
+/// after.
+///
+/// There's only ever one dialog at a time. What's below is dimmed and doesn't take input meanwhile, as with a modal.
+pub struct Dialogs<T> {
+    /// Hands the events of the dialog threads to the event loop.
+    post: Arc<dyn Fn(DialogEvent) + Send + Sync>,
+    /// The programs to try, in order. Only replaced in tests.
+    commands: fn(&DialogRequest) -> Vec<Command>,
+    next_id: u64,
+    pending: Option<Pending<T>>,
+}
+
+impl<T> Dialogs<T> {
+    /// `post` is called from the dialog thread once it's done, with an event for [`Self::on_event`].
+    pub fn new(post: impl Fn(DialogEvent) + Send + Sync + 'static) -> Self {
+        Self { post: Arc::new(post), commands, next_id: 0, pending: None }
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Shows a dialog for `request` and calls `callback` with what was picked, `None` when it was cancelled.
+    /// While another dialog is up nothing happens and false is returned, that one stays as it is.
+    pub fn pick_file(&mut self, request: DialogRequest, callback: impl FnOnce(&mut T, Option<PathBuf>) + 'static) -> bool {
+        if let Some(pending) = &self.pending {
+            log::warn!("Not opening the dialog \"{}\", \"{}\" is still open", request.title, pending.request.title);
+            return false;
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        let child = Arc::new(Mutex::new(None));
+        let post = self.post.clone();
+        let commands = (self.commands)(&request);
+        let thread_child = child.clone();
+        let spawned = std::thread::Builder::new().name("file dialog".into()).spawn(move || {
+            post(DialogEvent { id, outcome: run(commands, &thread_child) });
+        });
+        let state = match spawned {
+            Ok(_) => State::Native(child),
+            Err(error) => {
+                log::error!("Failed to start the file dialog thread: {error}");
+                State::TextField(text_field(&request))
+            },
+        };
+        self.pending = Some(Pending { id, request, callback: Box::new(callback), state });
+        true
+    }
+
+    pub fn on_event(&mut self, event: DialogEvent) {
+        let Some(pending) = self.pending.as_mut().filter(|pending| pending.id == event.id) else {
+            return;
+        };
+        // A dialog that was cancelled meanwhile is already done.
+        if matches!(pending.state, State::Native(_)) {
+            pending.state = match event.outcome {
+                Outcome::Picked(path) => State::Done(path),
+                Outcome::Unavailable => State::TextField(text_field(&pending.request)),
+            };
+        }
+    }
+
+    /// Dims the window while a dialog program is up, with a button to give up on it, or shows the text field.
+    pub fn show(&mut self, ctx: &egui::Context) {
+        let Some(pending) = &mut self.pending else {
+            return;
+        };
+        match &mut pending.state {
+            State::Native(child) => {
+                let mut cancel = false;
+                egui::Modal::new(egui::Id::new("file_dialog_pending")).show(ctx, |ui| {
+                    ui.label(tr("dialogs.waiting"));
+                    ui.weak(tr("dialogs.waiting_hint"));
+                    cancel = ui.button(tr("dialogs.cancel")).clicked();
+                });
+                if cancel {
+                    if let Some(child) = child.lock().unwrap().as_mut() {
+                        let _ = child.kill();
+                    }
+                    pending.state = State::Done(None);
+                }
+            },
+            State::TextField(dialog) => {
+                if let Some(path) = dialog.show(ctx) {
+                    pending.state = State::Done(path);
+                }
+            },
+            State::Done(_) => {},
+        }
+    }
+
+    /// The callback of a dialog that's done, to be called with the path.
+    pub fn take_finished(&mut self) -> Option<(Callback<T>, Option<PathBuf>)> {
+        if !matches!(self.pending.as_ref()?.state, State::Done(_)) {
+            return None;
+        }
+        let pending = self.pending.take()?;
+        let State::Done(path) = pending.state else {
+            unreachable!();
+        };
+        Some((pending.callback, path))
+    }
+}
+
+fn text_field(request: &DialogRequest) -> FileDialog {
+    let confirm = match request.kind {
+        DialogKind::Open => tr("dialogs.open"),
+        DialogKind::Save => tr("dialogs.save"),
+    };
+    FileDialog::new(&request.title, confirm, &request.path)
+}
+
+/// Runs the first of `commands` that's installed and returns the path it printed. The child is put into `child`
+/// while it runs, so it can be killed from the UI thread.
+fn run(commands: Vec<Command>, child: &Mutex<Option<Child>>) -> Outcome {
+    for mut command in commands {
+        let program = command.get_program().to_string_lossy().into_owned();
+        let spawned = command.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::null()).spawn();
+        let mut running = match spawned {
+            Ok(running) => running,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(error) => {
+                log::error!("Failed to open the file dialog with {program}: {error}");
+                return Outcome::Picked(None);
+            },
+        };
+        let mut stdout = running.stdout.take().unwrap();
+        *child.lock().unwrap() = Some(running);
+        // Ends when the program does, or when it's killed.
+        let mut output = Vec::new();
+        let read = stdout.read_to_end(&mut output);
+        let status = child.lock().unwrap().take().map(|mut running| running.wait());
+        // They all exit with an error when the dialog is cancelled.
+        let picked = matches!(status, Some(Ok(status)) if status.success()) && read.is_ok();
+        return Outcome::Picked(picked.then(|| path_from_output(output)).flatten());
+    }
+    Outcome::Unavailable
+}
+
+/// The path a dialog program printed, without the line break after it.
+fn path_from_output(mut output: Vec<u8>) -> Option<PathBuf> {
+    while output.last().is_some_and(|byte| matches!(byte, b'\n' | b'\r')) {
+        output.pop();
+    }
+    if output.is_empty() {
+        return None;
+    }
+    #[cfg(unix)]
+    let path = PathBuf::from(<std::ffi::OsString as std::os::unix::ffi::OsStringExt>::from_vec(output));
+    #[cfg(not(unix))]
+    let path = PathBuf::from(String::from_utf8_lossy(&output).into_owned());
+    Some(path)
+}
+
+/// The dialog programs of the platform for `request`, to be tried in order.
+fn commands(request: &DialogRequest) -> Vec<Command> {
+    if cfg!(target_os = "windows") {
+        vec![powershell(request)]
+    } else if cfg!(target_os = "macos") {
+        vec![osascript(request)]
+    } else if cfg!(target_os = "android") {
+        Vec::new()
+    } else {
+        vec![zenity(request), kdialog(request)]
+    }
+}
+
+/// GNOME's and most other desktops' dialog.
+fn zenity(request: &DialogRequest) -> Command {
+    let mut command = Command::new("zenity");
+    command.arg("--file-selection").arg(format!("--title={}", request.title)).arg(format!("--filename={}", request.path.display()));
+    if request.kind == DialogKind::Save {
+        command.args(["--save", "--confirm-overwrite"]);
+    }
+    for filter in &request.filters {
+        let patterns: Vec<_> = filter.extensions.iter().map(|extension| format!("*.{extension}")).collect();
+        command.arg(format!("--file-filter={} | {}", filter.name, patterns.join(" ")));
+    }
+    command
+}
+
+/// KDE's dialog.
+fn kdialog(request: &DialogRequest) -> Command {
+    let mut command = Command::new("kdialog");
+    command.arg("--title").arg(&request.title);
+    command.arg(match request.kind {
+        DialogKind::Open => "--getopenfilename",
+        DialogKind::Save => "--getsavefilename",
+    });
+    command.arg(&request.path);
+    let filters: Vec<_> = request.filters.iter()
+        .map(|filter| {
+            let patterns: Vec<_> = filter.extensions.iter().map(|extension| format!("*.{extension}")).collect();
+            format!("{} ({})", filter.name, patterns.join(" "))
+        })
+        .collect();
+    if !filters.is_empty() {
+        command.arg(filters.join("\n"));
+    }
+    command
+}
+
+/// The AppleScript file dialogs, which only filter when opening and then by extension alone.
+fn osascript(request: &DialogRequest) -> Command {
+    let quote = |text: &str| format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""));
+    let folder = request.path.parent().unwrap_or(Path::new("/")).display().to_string();
+    let location = format!("with prompt {} default location POSIX file {}", quote(&request.title), quote(&folder));
+    let script = match request.kind {
+        DialogKind::Open => {
+            let extensions: Vec<_> = request.filters.iter().flat_map(|filter| filter.extensions).map(|extension| quote(extension)).collect();
+            let of_type = if extensions.is_empty() { String::new() } else { format!(" of type {{{}}}", extensions.join(", ")) };
+            format!("POSIX path of (choose file {location}{of_type})")
+        },
+        DialogKind::Save => {
+            let name = request.path.file_name().map_or(String::new(), |name| name.to_string_lossy().into_owned());
+            format!("POSIX path of (choose file name {location} default name {})", quote(&name))
+        },
+    };
+    let mut command = Command::new("osascript");
+    command.arg("-e").arg(script);
+    command
+}
+
+/// The Windows Forms dialogs through PowerShell, which prints nothing when they're cancelled.
+fn powershell(request: &DialogRequest) -> Command {
+    let quote = |text: &str| format!("'{}'", text.replace('\'', "''"));
+    let class = match request.kind {
+        DialogKind::Open => "OpenFileDialog",
+        DialogKind::Save => "SaveFileDialog",
+    };
+    let filter: Vec<_> = request.filters.iter()
+        .map(|filter| {
+            let patterns: Vec<_> = filter.extensions.iter().map(|extension| format!("*.{extension}")).collect();
+            format!("{} ({})|{}", filter.name, patterns.join(", "), patterns.join(";"))
+        })
+        .collect();
+    let folder = request.path.parent().map_or(String::new(), |folder| folder.display().to_string());
+    let name = request.path.file_name().map_or(String::new(), |name| name.to_string_lossy().into_owned());
+    let script = format!(
+        "Add-Type -AssemblyName System.Windows.Forms; $d = New-Object System.Windows.Forms.{class}; $d.Title = {}; \
+         $d.InitialDirectory = {}; $d.FileName = {}; $d.Filter = {}; if ($d.ShowDialog() -eq 'OK') {{ $d.FileName }}",
+        quote(&request.title), quote(&folder), quote(&name), quote(&filter.join("|")),
+    );
+    let mut command = Command::new("powershell");
+    command.args(["-NoProfile", "-NonInteractive", "-Command", &script]);
+    // CREATE_NO_WINDOW, otherwise a console window flashes up behind the dialog.
+    #[cfg(target_os = "windows")]
+    std::os::windows::process::CommandExt::creation_flags(&mut command, 0x0800_0000);
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui_driver::UiDriver;
+
+    #[derive(Default)]
+    struct App {
+        dialogs: Option<Dialogs<Vec<Option<PathBuf>>>>,
+        picked: Vec<Option<PathBuf>>,
+    }
+
+    fn show(app: &mut App, ctx: &egui::Context) {
+        let dialogs = app.dialogs.as_mut().unwrap();
+        dialogs.show(ctx);
+        if let Some((callback, path)) = dialogs.take_finished() {
+            callback(&mut app.picked, path);
+        }
+    }
+
+    #[test]
+    fn text_field_is_used_without_a_dialog_program_and_one_dialog_is_up_at_a_time() {
+        let (sender, events) = std::sync::mpsc::channel();
+        let mut dialogs = Dialogs::new(move |event| sender.send(event).unwrap());
+        dialogs.commands = |_| Vec::new();
+        let request = DialogRequest::new(DialogKind::Save, "Save", Path::new("demo.json")).with_filter("Demo state", &["json"]);
+
+        assert!(dialogs.pick_file(request.clone(), |picked: &mut Vec<_>, path| picked.push(path)));
+        assert!(!dialogs.pick_file(request, |_, _| panic!("a second dialog was opened")));
+        dialogs.on_event(events.recv().unwrap());
+        assert!(matches!(dialogs.pending.as_ref().unwrap().state, State::TextField(_)));
+
+        let mut driver = UiDriver::new(App { dialogs: Some(dialogs), ..Default::default() }, show, egui::vec2(800.0, 600.0));
+        driver.click_label(tr("dialogs.save"));
+        assert_eq!(driver.app().picked, [Some(PathBuf::from("demo.json"))]);
+        assert!(!driver.app().dialogs.as_ref().unwrap().is_pending());
+    }
+}
@@ -0,0 +1,96 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Fonts covering Chinese, Japanese and Korean that come with the common platforms, the first one that exists is used.
+/// None of them is bundled, an installed one costs nothing but the memory it takes when it's loaded.
+#[cfg(feature = "fallback-fonts")]
+const SYSTEM_CJK_FONTS: &[&str] = &[
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/truetype/droid/DroidSansFallbackFull.ttf",
+    "/usr/share/fonts/wenquanyi/wqy-microhei/wqy-microhei.ttc",
+    "/system/fonts/NotoSansCJK-Regular.ttc",
+    "/System/Library/Fonts/Hiragino Sans GB.ttc",
+    "/Library/Fonts/Arial Unicode.ttf",
+    "C:\\Windows\\Fonts\\msyh.ttc",
+    "C:\\Windows\\Fonts\\YuGothM.ttc",
+    "C:\\Windows\\Fonts\\malgun.ttf",
+];
+
+/// The default fonts, which already have most emoji, with the font files in `extra` appended and with the
+/// `fallback-fonts` feature a CJK font of the system after those. Files that can't be used are logged and skipped.
+/// `loading` is called with each file before it's read, that can take a while for big ones.
+pub fn definitions(extra: &[PathBuf], mut loading: impl FnMut(&Path)) -> egui::FontDefinitions {
+    let mut definitions = egui::FontDefinitions::default();
+    for path in extra {
+        loading(path);
+        if let Err(error) = add_fallback_file(&mut definitions, path) {
+            log::warn!("Failed to load the font {}: {error}", path.display());
+        }
+    }
+    #[cfg(feature = "fallback-fonts")]
+    match SYSTEM_CJK_FONTS.iter().map(Path::new).filter(|path| path.exists()).find(|path| {
+        loading(path);
+        add_fallback_file(&mut definitions, path).is_ok()
+    }) {
+        Some(path) => log::info!("Using {} for CJK text", path.display()),
+        None => log::info!("There's no known CJK font on this system, CJK text will show up as boxes"),
+    }
+    definitions
+}
+
+/// Appends a font to the proportional and monospace families, where it's only used for characters none of the fonts
+/// before it have. `name` has to be unique among the fonts in `definitions`.
+pub fn add_fallback(definitions: &mut egui::FontDefinitions, name: &str, data: egui::FontData) {
+    definitions.font_data.insert(name.to_owned(), Arc::new(data));
+    for family in [egui::FontFamily::Proportional, egui::FontFamily::Monospace] {
+        definitions.families.entry(family).or_default().push(name.to_owned());
+    }
+}
+
+/// [`add_fallback`] with the first font of a TTF, OTF or TTC file, named after its path.
+pub fn add_fallback_file(definitions: &mut egui::FontDefinitions, path: &Path) -> std::io::Result<()> {
+    let bytes = std::fs::read(path)?;
+    // egui panics on fonts it can't parse, so at least anything that isn't a font file at all is turned away here.
+    const SIGNATURES: [&[u8]; 4] = [b"\x00\x01\x00\x00", b"OTTO", b"true", b"ttcf"];
+    if !SIGNATURES.iter().any(|signature| bytes.starts_with(signature)) {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "not a TrueType or OpenType font"));
+    }
+    add_fallback(definitions, &path.display().to_string(), egui::FontData::from_owned(bytes));
+    Ok(())
+}
+
+/// The font atlas isn't kept smaller than this after it overflowed, unless the GPU can't do more. Below it egui
+/// would keep starting the atlas over with the sizes text usually has.
+const MIN_ATLAS_SIDE: usize = 2048;
+
+/// Whether the region of a texture `delta` writes fits into textures of `max_side` pixels, egui-wgpu fails to
+/// create or write it otherwise.
+pub fn delta_fits(delta: &egui::epaint::ImageDelta, max_side: usize) -> bool {
+    let [width, height] = delta.image.size();
+    let [x, y] = delta.pos.unwrap_or([0, 0]);
+    x + width <= max_side && y + height <= max_side
+}
+
+/// The texture side to tell egui after the font atlas outgrew `gpu_limit` with `current`: half of it, within
+/// what the GPU allows.
+pub fn reduced_atlas_side(current: usize, gpu_limit: usize) -> usize {
+    (current / 2).max(MIN_ATLAS_SIDE).min(gpu_limit)
+}
+
+/// Lays out every character the fonts have up to U+2000, in two big sizes, which fills the font atlas with large
+/// glyphs. For trying out what happens when it grows too big.
+pub fn inflate_atlas(ctx: &egui::Context) {
+    ctx.fonts(|fonts| {
+        for size in [96.0, 192.0] {
+            let font = egui::FontId::proportional(size);
+            let text: String = (0x20..0x2000)
+                .filter_map(char::from_u32)
+                .filter(|&c| fonts.has_glyph(&font, c))
+                .collect();
+            // Laying it out is enough to put the glyphs into the atlas.
+            let _ = fonts.layout_no_wrap(text, font, egui::Color32::WHITE);
+        }
+    });
+}
@@ -0,0 +1,81 @@
+use std::time::{Duration, Instant};
+
+/// Frames further apart than this, e.g. after the window was hidden, only advance animations by this much.
+const MAX_STEP: Duration = Duration::from_millis(100);
+
+/// The timing of the frame being built, readable from UI code with [`FrameTime::get`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameTime {
+    /// Since the app started, never goes backwards.
+    pub time: Duration,
+    /// Since the previous frame, at most [`MAX_STEP`] and zero for the first one.
+    pub dt: Duration,
+    /// Of the monitor the window is on, `None` if the platform doesn't say.
+    pub refresh_rate_hz: Option<f32>,
+}
+
+impl FrameTime {
+    pub fn get(ctx: &egui::Context) -> Option<Self> {
+        ctx.data(|data| data.get_temp(egui::Id::NULL))
+    }
+
+    /// How long the monitor shows a frame.
+    pub fn display_interval(&self) -> Option<Duration> {
+        self.refresh_rate_hz.map(|hz| Duration::from_secs_f32(1.0 / hz))
+    }
+}
+
+/// Steps once per frame. Animations that go by it run at the same speed at any frame rate, and frames for them are
+/// requested one display interval apart instead of as soon as possible.
+pub struct FrameClock {
+    start: Instant,
+    last: Option<Instant>,
+    refresh_rate_hz: Option<f32>,
+    current: FrameTime,
+}
+
+impl Default for FrameClock {
+    fn default() -> Self {
+        Self {
+            start: Instant::now(),
+            last: None,
+            refresh_rate_hz: None,
+            current: FrameTime { time: Duration::ZERO, dt: Duration::ZERO, refresh_rate_hz: None },
+        }
+    }
+}
+
+impl FrameClock {
+    /// From `MonitorHandle::refresh_rate_millihertz`, to be updated whenever the window may have changed monitors.
+    pub fn set_refresh_rate(&mut self, millihertz: Option<u32>) {
+        let hz = millihertz.filter(|&millihertz| millihertz > 0).map(|millihertz| millihertz as f32 / 1000.0);
+        if hz != self.refresh_rate_hz {
+            if let Some(hz) = hz {
+                log::info!("The window is on a {hz:.2} Hz monitor");
+            }
+            self.refresh_rate_hz = hz;
+        }
+    }
+
+    /// Starts a frame at `now` and puts its timing into `ctx` for the UI.
+    pub fn tick(&mut self, ctx: &egui::Context, now: Instant) -> FrameTime {
+        let dt = self.last.map_or(Duration::ZERO, |last| now.saturating_duration_since(last).min(MAX_STEP));
+        self.last = Some(now);
+        self.current = FrameTime {
+            time: now.saturating_duration_since(self.start),
+            dt,
+            refresh_rate_hz: self.refresh_rate_hz,
+        };
+        ctx.data_mut(|data| data.insert_temp(egui::Id::NULL, self.current));
+        self.current
+    }
+
+    /// When egui asks for a frame after `delay`: an immediate one, which is what animations ask for, comes one display
+    /// interval after the current frame started, which keeps them in step with the monitor.
+    pub fn repaint_delay(&self, delay: Duration, now: Instant) -> Duration {
+        match (self.last, self.current.display_interval()) {
+            (Some(last), Some(interval)) if delay.is_zero() => (last + interval).saturating_duration_since(now),
+            _ => delay,
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// Stick deflections below this are treated as centered.
+const DEADZONE: f32 = 0.5;
+/// How long a direction has to be held before it repeats, and how fast it repeats then.
+const REPEAT_DELAY: Duration = Duration::from_millis(400);
+const REPEAT_INTERVAL: Duration = Duration::from_millis(120);
+/// Points scrolled per second with the right stick all the way over.
+const SCROLL_SPEED: f32 = 800.0;
+
+// Button and axis numbers of the Xbox layout, which most Linux drivers (xpad, xone, hid-generic) map controllers to.
+const BUTTON_A: u8 = 0;
+const BUTTON_B: u8 = 1;
+const AXIS_LEFT_X: u8 = 0;
+const AXIS_LEFT_Y: u8 = 1;
+const AXIS_RIGHT_X: u8 = 3;
+const AXIS_RIGHT_Y: u8 = 4;
+const AXIS_DPAD_X: u8 = 6;
+const AXIS_DPAD_Y: u8 = 7;
+
+/// Sent from the threads reading the controllers, see [`spawn`].
+#[derive(Debug, Clone)]
+pub enum GamepadEvent {
+    Connected { id: u32, name: String },
+    Disconnected { id: u32 },
+    /// Charge in percent, for controllers that report it.
+    Battery { id: u32, percent: u8 },
+    Button { id: u32, button: u8, pressed: bool },
+    /// `value` goes from -1 (left or up) to 1 (right or down).
+    Axis { id: u32, axis: u8, value: f32 },
+}
+
+pub struct Controller {
+    pub name: String,
+    pub battery: Option<u8>,
+    axes: BTreeMap<u8, f32>,
+}
+
+impl Controller {
+    fn axis(&self, axis: u8) -> f32 {
+        self.axes.get(&axis).copied().unwrap_or(0.0)
+    }
+}
+
+/// Turns controller input into the key and scroll events egui already handles, so the UI can be used without a mouse:
+/// the d-pad and left stick move the focus (or a focused slider), A presses Enter, B presses Escape and the right stick scrolls.
+#[derive(Default)]
+pub struct GamepadNav {
+    controllers: BTreeMap<u32, Controller>,
+    /// Button presses waiting for the next frame.
+    events: Vec<egui::Event>,
+    /// The arrow key that is held down and when it repeats next.
+    held: Option<(egui::Key, Instant)>,
+    last_process: Option<Instant>,
+}
+
+impl GamepadNav {
+    pub fn controllers(&self) -> impl Iterator<Item = &Controller> {
+        self.controllers.values()
+    }
+
+    pub fn on_event(&mut self, event: GamepadEvent) {
+        match event {
+            GamepadEvent::Connected { id, name } => {
+                log::info!("Controller connected: {name}");
+                self.controllers.insert(id, Controller { name, battery: None, axes: BTreeMap::new() });
+            },
+            GamepadEvent::Disconnected { id } => {
+                if let Some(controller) = self.controllers.remove(&id) {
+                    log::info!("Controller disconnected: {}", controller.name);
+                }
+            },
+            GamepadEvent::Battery { id, percent } => {
+                if let Some(controller) = self.controllers.get_mut(&id) {
+                    controller.battery = Some(percent);
+                }
+            },
+            GamepadEvent::Button { id, button, pressed: true } if self.controllers.contains_key(&id) => {
+                let key = match button {
+                    BUTTON_A => egui::Key::Enter,
+                    BUTTON_B => egui::Key::Escape,
+                    _ => return,
+                };
+                // egui clicks a focused widget when Enter is pressed, the release can follow right away.
+                for pressed in [true, false] {
+                    self.events.push(key_event(key, pressed, false));
+                }
+            },
+            GamepadEvent::Button { .. } => {},
+            GamepadEvent::Axis { id, axis, value } => {
+                if let Some(controller) = self.controllers.get_mut(&id) {
+                    controller.axes.insert(axis, value);
+                }
+            },
+        }
+    }
+
+    /// Adds the events since the last frame to `raw_input`, returns when a held direction should repeat.
+    /// Arrow keys only move a focus that's already somewhere, with `nothing_focused` the first press sends Tab instead.
+    pub fn process(&mut self, raw_input: &mut egui::RawInput, nothing_focused: bool, now: Instant) -> Option<Duration> {
+        raw_input.events.append(&mut self.events);
+        let dt = self.last_process.map_or(0.0, |last| (now - last).as_secs_f32().min(0.1));
+        self.last_process = Some(now);
+
+        let direction = self.controllers.values().find_map(|controller| {
+            let dpad = direction_key(controller.axis(AXIS_DPAD_X), controller.axis(AXIS_DPAD_Y));
+            dpad.or_else(|| direction_key(controller.axis(AXIS_LEFT_X), controller.axis(AXIS_LEFT_Y)))
+        });
+        match (direction, self.held) {
+            (Some(key), Some((held, repeat_at))) if key == held => {
+                if now >= repeat_at {
+                    raw_input.events.push(key_event(key, true, true));
+                    self.held = Some((key, now + REPEAT_INTERVAL));
+                }
+            },
+            (Some(key), _) => {
+                let sent = if nothing_focused { egui::Key::Tab } else { key };
+                raw_input.events.push(key_event(sent, true, false));
+                raw_input.events.push(key_event(sent, false, false));
+                self.held = Some((key, now + REPEAT_DELAY));
+            },
+            (None, _) => self.held = None,
+        }
+
+        // The content moves the opposite way of the stick. Like the mouse wheel this scrolls what's below the pointer.
+        let scroll = self.controllers.values()
+            .map(|controller| egui::vec2(controller.axis(AXIS_RIGHT_X), controller.axis(AXIS_RIGHT_Y)))
+            .find(|stick| stick.length() > DEADZONE);
+        if let Some(stick) = scroll {
+            raw_input.events.push(egui::Event::MouseWheel {
+                unit: egui::MouseWheelUnit::Point,
+                delta: -stick * SCROLL_SPEED * dt,
+                modifiers: egui::Modifiers::NONE,
+            });
+            return Some(Duration::ZERO);
+        }
+        self.held.map(|(_, repeat_at)| repeat_at.saturating_duration_since(now))
+    }
+}
+
+fn key_event(key: egui::Key, pressed: bool, repeat: bool) -> egui::Event {
+    egui::Event::Key { key, physical_key: None, pressed, repeat, modifiers: egui::Modifiers::NONE }
+}
+
+/// The arrow key for a stick position, along whichever axis is deflected more.
+fn direction_key(x: f32, y: f32) -> Option<egui::Key> {
+    if x.abs().max(y.abs()) < DEADZONE {
+        None
+    } else if x.abs() > y.abs() {
+        Some(if x < 0.0 { egui::Key::ArrowLeft } else { egui::Key::ArrowRight })
+    } else {
+        Some(if y < 0.0 { egui::Key::ArrowUp } else { egui::Key::ArrowDown })
+    }
+}
+
+/// Watches for controllers on a background thread and calls `send` with their input.
+#[cfg(target_os = "linux")]
+pub fn spawn(send: impl Fn(GamepadEvent) + Send + Sync + 'static) {
+    linux::spawn(std::sync::Arc::new(send));
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn spawn(_send: impl Fn(GamepadEvent) + Send + Sync + 'static) {
+    log::warn!("Gamepads are only supported on Linux so far");
+}
+
+/// Reads the joystick devices of the kernel, `/dev/input/js*`, which need no extra libraries or permissions on most distributions.
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::GamepadEvent;
+    use std::collections::{BTreeMap, BTreeSet};
+    use std::io::Read;
+    use std::path::PathBuf;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    const MAX_DEVICES: u32 = 8;
+    /// How often new controllers and battery levels are looked for.
+    const SCAN_INTERVAL: Duration = Duration::from_secs(2);
+
+    const JS_EVENT_BUTTON: u8 = 0x01;
+    const JS_EVENT_AXIS: u8 = 0x02;
+    /// Set on the events describing the initial state right after opening.
+    const JS_EVENT_INIT: u8 = 0x80;
+
+    type Sender = Arc<dyn Fn(GamepadEvent) + Send + Sync>;
+
+    pub fn spawn(send: Sender) {
+        let connected = Arc::new(Mutex::new(BTreeSet::new()));
+        let spawned = std::thread::Builder::new().name("gamepad scan".into()).spawn(move || {
+            let mut batteries = BTreeMap::new();
+            loop {
+                for id in 0..MAX_DEVICES {
+                    if !connected.lock().unwrap().contains(&id) {
+                        if let Ok(file) = std::fs::File::open(format!("/dev/input/js{id}")) {
+                            connected.lock().unwrap().insert(id);
+                            batteries.remove(&id);
+                            spawn_reader(id, file, send.clone(), connected.clone());
+                        }
+                        continue;
+                    }
+                    if let Some(percent) = battery(id).filter(|percent| batteries.get(&id) != Some(percent)) {
+                        batteries.insert(id, percent);
+                        send(GamepadEvent::Battery { id, percent });
+                    }
+                }
+                std::thread::sleep(SCAN_INTERVAL);
+            }
+        });
+        if let Err(error) = spawned {
+            log::warn!("Failed to start looking for gamepads: {error}");
+        }
+    }
+
+    fn spawn_reader(id: u32, mut file: std::fs::File, send: Sender, connected: Arc<Mutex<BTreeSet<u32>>>) {
+        let name = std::fs::read_to_string(sys_path(id).join("device/name"))
+            .map(|name| name.trim().to_owned())
+            .unwrap_or_else(|_| format!("Gamepad {id}"));
+        send(GamepadEvent::Connected { id, name });
+
+        let reader = move || {
+            // struct js_event { __u32 time; __s16 value; __u8 type; __u8 number; }
+            let mut event = [0; 8];
+            while file.read_exact(&mut event).is_ok() {
+                let value = i16::from_ne_bytes([event[4], event[5]]);
+                let number = event[7];
+                match event[6] & !JS_EVENT_INIT {
+                    JS_EVENT_BUTTON => send(GamepadEvent::Button { id, button: number, pressed: value != 0 }),
+                    JS_EVENT_AXIS => send(GamepadEvent::Axis { id, axis: number, value: value as f32 / i16::MAX as f32 }),
+                    _ => {},
+                }
+            }
+            // Reading fails once the controller is unplugged.
+            connected.lock().unwrap().remove(&id);
+            send(GamepadEvent::Disconnected { id });
+        };
+        if let Err(error) = std::thread::Builder::new().name(format!("gamepad {id}")).spawn(reader) {
+            log::warn!("Failed to read gamepad {id}: {error}");
+        }
+    }
+
+    fn sys_path(id: u32) -> PathBuf {
+        PathBuf::from(format!("/sys/class/input/js{id}"))
+    }
+
+    /// Wireless controllers with a battery driver list it under their HID device.
+    fn battery(id: u32) -> Option<u8> {
+        let supplies = std::fs::read_dir(sys_path(id).join("device/device/power_supply")).ok()?;
+        supplies.flatten().find_map(|supply| std::fs::read_to_string(supply.path().join("capacity")).ok()?.trim().parse().ok())
+    }
+}
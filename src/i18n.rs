@@ -0,0 +1,90 @@
+use std::collections::{BTreeSet, HashMap};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use crate::json::{json_enum, FromJson, Json};
+use crate::storage::{Document, Value};
+
+/// Languages the UI texts are translated to, each with a catalog in `src/locales`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Language {
+    #[default]
+    English,
+    German,
+}
+
+json_enum!(Language { English => "en", German => "de" });
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::German];
+
+    /// What the language calls itself, so it can be found in the selector without understanding the current one.
+    pub fn native_name(self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::German => "Deutsch",
+        }
+    }
+
+    fn catalog_source(self) -> &'static str {
+        match self {
+            Language::English => include_str!("locales/en.toml"),
+            Language::German => include_str!("locales/de.toml"),
+        }
+    }
+}
+
+/// Index into [`Language::ALL`]. Texts are looked up from all over the UI code, so this is global rather than passed around.
+static LANGUAGE: AtomicUsize = AtomicUsize::new(0);
+
+pub fn language() -> Language {
+    Language::ALL[LANGUAGE.load(Ordering::Relaxed)]
+}
+
+/// Takes effect for everything drawn from the next pass on, there's no state to update.
+pub fn set_language(language: Language) {
+    let index = Language::ALL.iter().position(|&l| l == language).unwrap_or(0);
+    LANGUAGE.store(index, Ordering::Relaxed);
+}
+
+/// The text for `key` in the current language. Falls back to English and then to the key itself,
+/// each missing key is only logged the first time it's looked up.
+pub fn tr(key: &'static str) -> &'static str {
+    let language = language();
+    if let Some(text) = catalog(language).get(key) {
+        return text;
+    }
+
+    static MISSING: Mutex<BTreeSet<(Language, &str)>> = Mutex::new(BTreeSet::new());
+    if MISSING.lock().unwrap().insert((language, key)) {
+        log::warn!("No {language:?} text for {key:?}");
+    }
+    catalog(Language::English).get(key).map_or(key, String::as_str)
+}
+
+/// [`tr`] with `{name}` placeholders replaced by the given values.
+pub fn tr_format(key: &'static str, args: &[(&str, &dyn std::fmt::Display)]) -> String {
+    args.iter().fold(tr(key).to_owned(), |text, (name, value)| text.replace(&format!("{{{name}}}"), &value.to_string()))
+}
+
+type Catalog = HashMap<String, String>;
+
+fn catalog(language: Language) -> &'static Catalog {
+    static CATALOGS: OnceLock<HashMap<Language, Catalog>> = OnceLock::new();
+    let catalogs = CATALOGS.get_or_init(|| Language::ALL.into_iter().map(|language| (language, parse_catalog(language))).collect());
+    &catalogs[&language]
+}
+
+/// Catalogs are in the same TOML subset as the other files, `file = "File"` under `[menu]` is the key `menu.file`.
+fn parse_catalog(language: Language) -> Catalog {
+    let document = Document::parse(language.catalog_source()).unwrap_or_else(|error| {
+        log::error!("The {language:?} catalog is broken, {error}");
+        Document::default()
+    });
+    document.entries()
+        .filter_map(|(section, key, value)| match value {
+            Value::String(text) => Some((format!("{section}.{key}"), text.clone())),
+            _ => None,
+        })
+        .collect()
+}
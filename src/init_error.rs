@@ -0,0 +1,69 @@
+use std::fmt;
+
+/// Why the app couldn't start.
+#[derive(Debug)]
+pub enum InitError {
+    WindowCreation(winit::error::OsError),
+    SurfaceCreation(wgpu::CreateSurfaceError),
+    /// No adapter can present to the window.
+    NoAdapter,
+    DeviceRequestFailed(wgpu::RequestDeviceError),
+}
+
+impl fmt::Display for InitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InitError::WindowCreation(error) => write!(f, "Failed to create the window: {error}"),
+            InitError::SurfaceCreation(error) => write!(f, "Failed to create a surface for the window: {error}"),
+            InitError::NoAdapter => f.write_str("No graphics adapter can draw to the window"),
+            InitError::DeviceRequestFailed(error) => write!(f, "Failed to set up the graphics device: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for InitError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            InitError::WindowCreation(error) => Some(error),
+            InitError::SurfaceCreation(error) => Some(error),
+            InitError::NoAdapter => None,
+            InitError::DeviceRequestFailed(error) => Some(error),
+        }
+    }
+}
+
+impl From<winit::error::OsError> for InitError {
+    fn from(error: winit::error::OsError) -> Self {
+        InitError::WindowCreation(error)
+    }
+}
+
+impl From<wgpu::CreateSurfaceError> for InitError {
+    fn from(error: wgpu::CreateSurfaceError) -> Self {
+        InitError::SurfaceCreation(error)
+    }
+}
+
+impl From<wgpu::RequestDeviceError> for InitError {
+    fn from(error: wgpu::RequestDeviceError) -> Self {
+        InitError::DeviceRequestFailed(error)
+    }
+}
+
+/// Tells the user why the app is about to quit. Only Windows gets a message box, there is no portable way to show one
+/// without a window, elsewhere it goes to the log (and with it stderr).
+pub fn show_fatal_error(error: &InitError) {
+    log::error!("{error}");
+
+    #[cfg(target_os = "windows")]
+    {
+        use windows_sys::Win32::UI::WindowsAndMessaging::{MessageBoxW, MB_ICONERROR};
+
+        let wide = |text: &str| text.encode_utf16().chain(std::iter::once(0)).collect::<Vec<u16>>();
+        let (text, caption) = (wide(&error.to_string()), wide(env!("CARGO_PKG_NAME")));
+        // SAFETY: both strings are null terminated and outlive the call, a null window makes the box ownerless.
+        unsafe {
+            MessageBoxW(std::ptr::null_mut(), text.as_ptr(), caption.as_ptr(), MB_ICONERROR);
+        }
+    }
+}
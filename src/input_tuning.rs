@@ -0,0 +1,116 @@
+use std::time::{Duration, Instant};
+
+use crate::settings::Settings;
+
+/// How long an arrow key has to be held before it repeats, and how fast it repeats then, about what desktops default to.
+const REPEAT_DELAY: Duration = Duration::from_millis(500);
+const REPEAT_INTERVAL: Duration = Duration::from_millis(33);
+const REPEATED_KEYS: [egui::Key; 4] = [egui::Key::ArrowUp, egui::Key::ArrowDown, egui::Key::ArrowLeft, egui::Key::ArrowRight];
+/// Time constant of smooth scrolling, about two thirds of a wheel step are scrolled after it.
+const SMOOTHING_TIME: f32 = 0.05;
+/// Smooth scrolling stops and scrolls the rest at once below this, in points.
+const SMOOTHING_REST: f32 = 1.0;
+/// Assumed for the first frame of a scroll, the time since the frame before says nothing while the app was idle.
+const FIRST_SCROLL_STEP: f32 = 1.0 / 60.0;
+/// egui smooths scroll events of this many points or more itself, the scrolled steps are sent in smaller pieces.
+const EGUI_SMOOTHED_SCROLL: f32 = 8.0;
+
+/// Adjusts the input egui-winit made from the window events to the input settings before egui sees it: scales the
+/// scroll speed, scrolls mouse wheel steps over several frames, and repeats held arrow keys if the platform doesn't.
+#[derive(Default)]
+pub struct InputTuning {
+    /// Mouse wheel steps that haven't been scrolled yet, in points.
+    pending_scroll: egui::Vec2,
+    last_process: Option<Instant>,
+    /// The arrow key held down and when it repeats next.
+    held: Option<(egui::Key, Instant)>,
+    /// Set once the platform repeated a key itself, nothing is repeated here from then on.
+    platform_repeats: bool,
+}
+
+impl InputTuning {
+    /// Changes the events in `raw_input`, returns when the next frame is needed to go on scrolling or repeating.
+    /// `line_scroll_speed` is egui's, the points a mouse wheel step scrolls.
+    pub fn process(&mut self, raw_input: &mut egui::RawInput, settings: &Settings, line_scroll_speed: f32, now: Instant) -> Option<Duration> {
+        let dt = match self.last_process {
+            Some(last) if self.pending_scroll != egui::Vec2::ZERO => (now - last).as_secs_f32().min(0.1),
+            _ => FIRST_SCROLL_STEP,
+        };
+        self.last_process = Some(now);
+
+        let mut wheel_steps = egui::Vec2::ZERO;
+        raw_input.events.retain_mut(|event| match event {
+            egui::Event::MouseWheel { unit, delta, modifiers } => {
+                *delta *= match unit {
+                    egui::MouseWheelUnit::Point => settings.pixel_scroll_speed,
+                    egui::MouseWheelUnit::Line => settings.line_scroll_speed,
+                    egui::MouseWheelUnit::Page => 1.0,
+                };
+                if !settings.shift_scroll_horizontal {
+                    modifiers.shift = false;
+                }
+                // Zooming and touchpads, which already scroll smoothly, are left to egui.
+                let zoom = modifiers.ctrl || modifiers.command || modifiers.mac_cmd;
+                if *unit != egui::MouseWheelUnit::Line || zoom {
+                    return true;
+                }
+                let mut points = *delta * line_scroll_speed;
+                // What egui does with Shift, the pieces scrolled later are sent without modifiers.
+                if modifiers.shift {
+                    points = egui::vec2(points.x + points.y, 0.0);
+                }
+                wheel_steps += points;
+                false
+            },
+            _ => true,
+        });
+        self.pending_scroll += wheel_steps;
+        let mut scroll_repaint = None;
+        if self.pending_scroll != egui::Vec2::ZERO {
+            let step = if settings.smooth_scrolling && self.pending_scroll.length() > SMOOTHING_REST {
+                self.pending_scroll * (1.0 - (-dt / SMOOTHING_TIME).exp())
+            } else {
+                self.pending_scroll
+            };
+            self.pending_scroll -= step;
+            let pieces = (step.length() / EGUI_SMOOTHED_SCROLL).floor() + 1.0;
+            for _ in 0..pieces as usize {
+                raw_input.events.push(egui::Event::MouseWheel {
+                    unit: egui::MouseWheelUnit::Point,
+                    delta: step / pieces,
+                    modifiers: egui::Modifiers::NONE,
+                });
+            }
+            scroll_repaint = (self.pending_scroll != egui::Vec2::ZERO).then_some(Duration::ZERO);
+        }
+
+        let repeat_repaint = self.repeat_keys(raw_input, settings.key_repeat, now);
+        scroll_repaint.into_iter().chain(repeat_repaint).min()
+    }
+
+    fn repeat_keys(&mut self, raw_input: &mut egui::RawInput, enabled: bool, now: Instant) -> Option<Duration> {
+        for event in &raw_input.events {
+            match event {
+                // egui-winit never sets `repeat`, a platform repeat is another press without a release in between.
+                egui::Event::Key { key, pressed: true, .. } if self.held.is_some_and(|(held, _)| held == *key) => {
+                    self.platform_repeats = true;
+                },
+                egui::Event::Key { key, pressed: true, .. } if REPEATED_KEYS.contains(key) => {
+                    self.held = Some((*key, now + REPEAT_DELAY));
+                },
+                egui::Event::Key { key, pressed: false, .. } if self.held.is_some_and(|(held, _)| held == *key) => self.held = None,
+                egui::Event::WindowFocused(false) => self.held = None,
+                _ => {},
+            }
+        }
+        if !enabled || self.platform_repeats {
+            self.held = None;
+        }
+        let (key, repeat_at) = self.held?;
+        if now >= repeat_at {
+            raw_input.events.push(egui::Event::Key { key, physical_key: None, pressed: true, repeat: true, modifiers: raw_input.modifiers });
+            self.held = Some((key, now + REPEAT_INTERVAL));
+        }
+        self.held.map(|(_, repeat_at)| repeat_at.saturating_duration_since(now))
+    }
+}
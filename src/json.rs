@@ -0,0 +1,482 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A JSON value, with just enough around it to read and write the demo's save files.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(BTreeMap<String, Json>),
+}
+
+#[derive(Debug)]
+pub struct JsonError {
+    /// Byte offset into the text.
+    pub offset: usize,
+    pub message: &'static str,
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at byte {}", self.message, self.offset)
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+impl Json {
+    pub fn parse(text: &str) -> Result<Self, JsonError> {
+        let mut parser = Parser { text, offset: 0 };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.offset != text.len() {
+            return Err(parser.error("trailing characters"));
+        }
+        Ok(value)
+    }
+
+    /// The member `key` of an object, `None` for anything else.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => members.get(key),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Number(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    /// Overwrites `target` with the member `key`, if there is one of the right type.
+    pub fn read_into<T: FromJson>(&self, key: &str, target: &mut T) {
+        if let Some(value) = self.get(key).and_then(T::from_json) {
+            *target = value;
+        }
+    }
+
+    fn write(&self, f: &mut fmt::Formatter<'_>, indent: usize) -> fmt::Result {
+        match self {
+            Json::Null => f.write_str("null"),
+            Json::Bool(value) => write!(f, "{value}"),
+            // JSON has no infinity or NaN.
+            Json::Number(value) if !value.is_finite() => f.write_str("null"),
+            Json::Number(value) => write!(f, "{value}"),
+            Json::String(value) => write_string(f, value),
+            Json::Array(values) if values.is_empty() => f.write_str("[]"),
+            Json::Array(values) => {
+                // Arrays in the save files are short lists of numbers, they stay on one line.
+                f.write_str("[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    value.write(f, indent)?;
+                }
+                f.write_str("]")
+            },
+            Json::Object(members) if members.is_empty() => f.write_str("{}"),
+            Json::Object(members) => {
+                f.write_str("{\n")?;
+                for (i, (key, value)) in members.iter().enumerate() {
+                    write!(f, "{:width$}", "", width = (indent + 1) * 2)?;
+                    write_string(f, key)?;
+                    f.write_str(": ")?;
+                    value.write(f, indent + 1)?;
+                    f.write_str(if i + 1 < members.len() { ",\n" } else { "\n" })?;
+                }
+                write!(f, "{:width$}}}", "", width = indent * 2)
+            },
+        }
+    }
+}
+
+/// Pretty-printed with two spaces of indentation.
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, 0)
+    }
+}
+
+impl From<bool> for Json {
+    fn from(value: bool) -> Self {
+        Json::Bool(value)
+    }
+}
+
+impl From<f32> for Json {
+    fn from(value: f32) -> Self {
+        Json::Number(value as f64)
+    }
+}
+
+impl From<u32> for Json {
+    fn from(value: u32) -> Self {
+        Json::Number(value as f64)
+    }
+}
+
+impl From<&str> for Json {
+    fn from(value: &str) -> Self {
+        Json::String(value.to_owned())
+    }
+}
+
+impl<const N: usize> From<[u8; N]> for Json {
+    fn from(values: [u8; N]) -> Self {
+        Json::Array(values.iter().map(|&value| Json::Number(value as f64)).collect())
+    }
+}
+
+impl<K: Into<String>> FromIterator<(K, Json)> for Json {
+    fn from_iter<I: IntoIterator<Item = (K, Json)>>(members: I) -> Self {
+        Json::Object(members.into_iter().map(|(key, value)| (key.into(), value)).collect())
+    }
+}
+
+/// Types that can be read back from what their `Into<Json>` produced.
+pub trait FromJson: Sized {
+    fn from_json(json: &Json) -> Option<Self>;
+}
+
+impl FromJson for bool {
+    fn from_json(json: &Json) -> Option<Self> {
+        json.as_bool()
+    }
+}
+
+impl FromJson for f32 {
+    fn from_json(json: &Json) -> Option<Self> {
+        json.as_f64().map(|value| value as f32)
+    }
+}
+
+impl FromJson for u32 {
+    fn from_json(json: &Json) -> Option<Self> {
+        json.as_f64().filter(|value| value.fract() == 0.0 && (0.0..=u32::MAX as f64).contains(value)).map(|value| value as u32)
+    }
+}
+
+impl FromJson for String {
+    fn from_json(json: &Json) -> Option<Self> {
+        json.as_str().map(str::to_owned)
+    }
+}
+
+impl<const N: usize> FromJson for [u8; N] {
+    fn from_json(json: &Json) -> Option<Self> {
+        let values = json.as_array().filter(|values| values.len() == N)?;
+        let mut array = [0; N];
+        for (target, value) in array.iter_mut().zip(values) {
+            *target = value.as_f64().filter(|value| (0.0..=255.0).contains(value))? as u8;
+        }
+        Some(array)
+    }
+}
+
+/// Implements the JSON conversions of an enum through the names of its variants.
+macro_rules! json_enum {
+    ($name:ident { $($variant:ident => $text:literal),* $(,)? }) => {
+        impl From<$name> for Json {
+            fn from(value: $name) -> Self {
+                Json::String(match value {
+                    $($name::$variant => $text,)*
+                }.to_owned())
+            }
+        }
+
+        impl FromJson for $name {
+            fn from_json(json: &Json) -> Option<Self> {
+                match json.as_str()? {
+                    $($text => Some($name::$variant),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+pub(crate) use json_enum;
+
+fn write_string(f: &mut fmt::Formatter<'_>, value: &str) -> fmt::Result {
+    f.write_str("\"")?;
+    for c in value.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{c}")?,
+        }
+    }
+    f.write_str("\"")
+}
+
+/// Nesting deeper than this is rejected instead of overflowing the stack.
+const MAX_DEPTH: usize = 128;
+
+struct Parser<'a> {
+    text: &'a str,
+    offset: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &'static str) -> JsonError {
+        JsonError { offset: self.offset, message }
+    }
+
+    fn rest(&self) -> &str {
+        &self.text[self.offset..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.offset += rest.len() - rest.trim_start_matches([' ', '\t', '\n', '\r']).len();
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        let found = self.rest().starts_with(token);
+        if found {
+            self.offset += token.len();
+        }
+        found
+    }
+
+    fn value(&mut self) -> Result<Json, JsonError> {
+        self.nested_value(0)
+    }
+
+    fn nested_value(&mut self, depth: usize) -> Result<Json, JsonError> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("nested too deeply"));
+        }
+        self.skip_whitespace();
+        match self.rest().chars().next() {
+            None => Err(self.error("unexpected end")),
+            Some('n') if self.eat("null") => Ok(Json::Null),
+            Some('t') if self.eat("true") => Ok(Json::Bool(true)),
+            Some('f') if self.eat("false") => Ok(Json::Bool(false)),
+            Some('"') => self.string().map(Json::String),
+            Some('[') => {
+                self.offset += 1;
+                let mut values = Vec::new();
+                self.skip_whitespace();
+                if self.eat("]") {
+                    return Ok(Json::Array(values));
+                }
+                loop {
+                    values.push(self.nested_value(depth + 1)?);
+                    self.skip_whitespace();
+                    if self.eat("]") {
+                        return Ok(Json::Array(values));
+                    }
+                    if !self.eat(",") {
+                        return Err(self.error("expected `,` or `]`"));
+                    }
+                }
+            },
+            Some('{') => {
+                self.offset += 1;
+                let mut members = BTreeMap::new();
+                self.skip_whitespace();
+                if self.eat("}") {
+                    return Ok(Json::Object(members));
+                }
+                loop {
+                    self.skip_whitespace();
+                    if !self.rest().starts_with('"') {
+                        return Err(self.error("expected a key"));
+                    }
+                    let key = self.string()?;
+                    self.skip_whitespace();
+                    if !self.eat(":") {
+                        return Err(self.error("expected `:`"));
+                    }
+                    members.insert(key, self.nested_value(depth + 1)?);
+                    self.skip_whitespace();
+                    if self.eat("}") {
+                        return Ok(Json::Object(members));
+                    }
+                    if !self.eat(",") {
+                        return Err(self.error("expected `,` or `}`"));
+                    }
+                }
+            },
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number(),
+            Some(_) => Err(self.error("unexpected character")),
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, JsonError> {
+        let rest = self.rest();
+        let len = rest.find(|c: char| !matches!(c, '0'..='9' | '-' | '+' | '.' | 'e' | 'E')).unwrap_or(rest.len());
+        let value = rest[..len].parse().map_err(|_| self.error("invalid number"))?;
+        self.offset += len;
+        Ok(Json::Number(value))
+    }
+
+    /// Parses a string starting at the opening quote.
+    fn string(&mut self) -> Result<String, JsonError> {
+        self.offset += 1;
+        let mut value = String::new();
+        loop {
+            let c = self.next_char()?;
+            match c {
+                '"' => return Ok(value),
+                '\\' => {
+                    let escape = self.next_char()?;
+                    value.push(match escape {
+                        '"' => '"',
+                        '\\' => '\\',
+                        '/' => '/',
+                        'b' => '\u{8}',
+                        'f' => '\u{c}',
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        'u' => self.unicode_escape()?,
+                        _ => return Err(self.error("invalid escape")),
+                    });
+                },
+                c if (c as u32) < 0x20 => return Err(self.error("control character in string")),
+                c => value.push(c),
+            }
+        }
+    }
+
+    /// Takes the next character of a string.
+    fn next_char(&mut self) -> Result<char, JsonError> {
+        let c = self.rest().chars().next().ok_or_else(|| self.error("unterminated string"))?;
+        self.offset += c.len_utf8();
+        Ok(c)
+    }
+
+    /// The four hex digits after `\u`, and a second escape if they're the first half of a surrogate pair.
+    fn unicode_escape(&mut self) -> Result<char, JsonError> {
+        let first = self.hex4()?;
+        let code = if (0xD800..0xDC00).contains(&first) {
+            if !self.eat("\\u") {
+                return Err(self.error("unpaired surrogate"));
+            }
+            let second = self.hex4()?;
+            if !(0xDC00..0xE000).contains(&second) {
+                return Err(self.error("unpaired surrogate"));
+            }
+            0x10000 + ((first - 0xD800) << 10) + (second - 0xDC00)
+        } else {
+            first
+        };
+        char::from_u32(code).ok_or_else(|| self.error("invalid unicode escape"))
+    }
+
+    fn hex4(&mut self) -> Result<u32, JsonError> {
+        let digits = self.rest().get(..4)
+            .filter(|digits| digits.chars().all(|c| c.is_ascii_hexdigit()))
+            .ok_or_else(|| self.error("invalid unicode escape"))?;
+        let value = u32::from_str_radix(digits, 16).map_err(|_| self.error("invalid unicode escape"))?;
+        self.offset += 4;
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(text: &str) -> &'static str {
+        Json::parse(text).unwrap_err().message
+    }
+
+    #[test]
+    fn round_trips_through_the_pretty_printer() {
+        let json: Json = [
+            ("null", Json::Null),
+            ("flag", true.into()),
+            ("number", Json::Number(-1.25e-3)),
+            ("whole", 42u32.into()),
+            ("text", "quote \" backslash \\ newline \n tab \t bell \u{7} unicode é 🦀".into()),
+            ("list", [1u8, 2, 255].into()),
+            ("empty_list", Json::Array(Vec::new())),
+            ("nested", [("inner", Json::Object(BTreeMap::new())), ("deeper", [("x", 0.5f32.into())].into_iter().collect())].into_iter().collect()),
+        ].into_iter().collect();
+        let text = json.to_string();
+        assert_eq!(Json::parse(&text).unwrap(), json);
+        assert_eq!(Json::parse(" \r\n\t[ 1 , {\"a\" : [ ] } ]\n").unwrap(), Json::Array(vec![Json::Number(1.0), [("a", Json::Array(Vec::new()))].into_iter().collect()]));
+        // There's no infinity in JSON.
+        assert_eq!(Json::Number(f64::INFINITY).to_string(), "null");
+    }
+
+    #[test]
+    fn escapes_and_surrogate_pairs() {
+        assert_eq!(Json::parse(r#""\"\\\/\b\f\n\r\t""#).unwrap(), Json::String("\"\\/\u{8}\u{c}\n\r\t".to_owned()));
+        assert_eq!(Json::parse(r#""é你""#).unwrap(), Json::String("é你".to_owned()));
+        assert_eq!(Json::parse(r#""🦀""#).unwrap(), Json::String("🦀".to_owned()));
+        assert_eq!(error(r#""\ud83e""#), "unpaired surrogate");
+        assert_eq!(error(r#""\ud83eA""#), "unpaired surrogate");
+        assert_eq!(error(r#""\udd80""#), "invalid unicode escape");
+        assert_eq!(error(r#""\u+04a""#), "invalid unicode escape");
+        assert_eq!(error(r#""\u00""#), "invalid unicode escape");
+        assert_eq!(error(r#""\x""#), "invalid escape");
+        assert_eq!(error("\"a\nb\""), "control character in string");
+    }
+
+    #[test]
+    fn truncated_input_is_an_error() {
+        let text = Json::from_iter([("list", Json::from([1u8, 2])), ("text", "abc".into())]).to_string();
+        for end in 0..text.trim_end().len() {
+            assert!(Json::parse(&text[..end]).is_err(), "{:?} parsed", &text[..end]);
+        }
+        assert_eq!(error(""), "unexpected end");
+        assert_eq!(error("\"abc"), "unterminated string");
+        assert_eq!(error("[1,"), "unexpected end");
+        assert_eq!(error("{\"a\""), "expected `:`");
+        assert_eq!(error("tru"), "unexpected character");
+    }
+
+    #[test]
+    fn deep_nesting_is_rejected() {
+        let nested = |depth: usize| "[".repeat(depth) + &"]".repeat(depth);
+        assert!(Json::parse(&nested(MAX_DEPTH + 1)).is_ok());
+        assert_eq!(error(&nested(MAX_DEPTH + 2)), "nested too deeply");
+        // Far past the limit, where recursing all the way would overflow the stack.
+        assert_eq!(error(&"[".repeat(1_000_000)), "nested too deeply");
+    }
+
+    #[test]
+    fn trailing_garbage_is_rejected() {
+        assert_eq!(error("{} {}"), "trailing characters");
+        assert_eq!(error("1 2"), "trailing characters");
+        assert_eq!(error("[1]]"), "trailing characters");
+        assert_eq!(error("nullx"), "trailing characters");
+        assert!(Json::parse("{}\n\n").is_ok());
+        assert_eq!(Json::parse("[1] x").unwrap_err().offset, 4);
+    }
+}
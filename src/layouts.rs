@@ -0,0 +1,226 @@
+use std::collections::BTreeMap;
+
+use crate::storage::{Document, Value};
+use crate::ui::ToolWindow;
+
+/// Sections of the state file the profiles are kept in, followed by their index.
+const SECTION_PREFIX: &str = "layout.";
+
+/// Where a tool window was and whether it was open.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindowLayout {
+    pub open: bool,
+    /// The whole window including its title bar in points, `None` if it hadn't been shown yet.
+    pub rect: Option<egui::Rect>,
+}
+
+/// A named snapshot of the tool windows, see [`LayoutProfiles`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayoutProfile {
+    pub name: String,
+    /// By [`ToolWindow::key`]. Windows that are gone since are kept, so they're still there if they come back.
+    windows: BTreeMap<String, WindowLayout>,
+}
+
+impl LayoutProfile {
+    /// Snapshots the windows in `open`, their rects are taken from egui's memory of the last frame.
+    pub fn capture(ctx: &egui::Context, name: String, open: impl IntoIterator<Item = (ToolWindow, bool)>) -> Self {
+        let windows = open.into_iter()
+            .map(|(window, open)| {
+                let rect = ctx.memory(|memory| memory.area_rect(window.id()));
+                (window.key().to_owned(), WindowLayout { open, rect })
+            })
+            .collect();
+        Self { name, windows }
+    }
+
+    /// The layout for `window`, `None` for windows that didn't exist yet when the profile was saved. Those stay as
+    /// they are when switching to it.
+    pub fn window(&self, window: ToolWindow) -> Option<WindowLayout> {
+        self.windows.get(window.key()).copied()
+    }
+
+    /// Moves and sizes the windows that are in the profile, from their next frame on, see [`take_pending_rect`].
+    /// Their open flags are up to the caller.
+    pub fn apply(&self, ctx: &egui::Context) {
+        for window in ToolWindow::WITH_LAYOUT {
+            if let Some(rect) = self.window(*window).and_then(|layout| layout.rect) {
+                ctx.data_mut(|data| data.insert_temp(pending_id(*window), PendingRect(Some(rect))));
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct PendingRect(Option<egui::Rect>);
+
+fn pending_id(window: ToolWindow) -> egui::Id {
+    window.id().with("pending_layout")
+}
+
+/// The rect `window` should get in this frame because a profile was just switched to. Taken once, the window keeps
+/// it by itself afterwards.
+pub fn take_pending_rect(ctx: &egui::Context, window: ToolWindow) -> Option<egui::Rect> {
+    ctx.data_mut(|data| data.remove_temp::<PendingRect>(pending_id(window))).and_then(|pending| pending.0)
+}
+
+/// The size of the contents of a window with the title bar and frame of `ctx`'s style, that's what
+/// `egui::Window::fixed_size` takes. Worked out the way `egui::Window` does.
+pub fn content_size(ctx: &egui::Context, outer_size: egui::Vec2) -> egui::Vec2 {
+    let style = ctx.style();
+    let frame = egui::Frame::window(&style);
+    let title_height = ctx.fonts(|fonts| fonts.row_height(&egui::TextStyle::Heading.resolve(&style)))
+        .max(style.spacing.interact_size.y)
+        + frame.inner_margin.sum().y;
+    outer_size - frame.total_margin().sum() - egui::vec2(0.0, title_height + frame.stroke.width)
+}
+
+/// Saved window layouts the user switches between, kept in the state file.
+#[derive(Debug, Default)]
+pub struct LayoutProfiles {
+    profiles: Vec<LayoutProfile>,
+    /// The profile switched to last, where cycling goes on from.
+    current: Option<usize>,
+}
+
+impl LayoutProfiles {
+    pub fn load(document: &Document) -> Self {
+        let mut sections: BTreeMap<usize, BTreeMap<&str, &Value>> = BTreeMap::new();
+        for (section, key, value) in document.entries() {
+            if let Some(index) = section.strip_prefix(SECTION_PREFIX).and_then(|index| index.parse().ok()) {
+                sections.entry(index).or_default().insert(key, value);
+            }
+        }
+        let profiles = sections.into_values()
+            .filter_map(|entries| {
+                let Some(Value::String(name)) = entries.get("name") else {
+                    return None;
+                };
+                let int = |key: String| match entries.get(key.as_str()) {
+                    Some(Value::Int(value)) => Some(*value as f32),
+                    _ => None,
+                };
+                let windows = entries.iter()
+                    .filter_map(|(key, value)| Some((key.strip_suffix(".open")?, value)))
+                    .map(|(window, value)| {
+                        let open = matches!(value, Value::Bool(true));
+                        let min = int(format!("{window}.x")).zip(int(format!("{window}.y")));
+                        let size = int(format!("{window}.width")).zip(int(format!("{window}.height")));
+                        let rect = min.zip(size).map(|((x, y), (width, height))| {
+                            egui::Rect::from_min_size(egui::pos2(x, y), egui::vec2(width, height))
+                        });
+                        (window.to_owned(), WindowLayout { open, rect })
+                    })
+                    .collect();
+                Some(LayoutProfile { name: name.clone(), windows })
+            })
+            .collect();
+        Self { profiles, current: None }
+    }
+
+    /// Replaces the profiles in `document`, including ones that were deleted since it was loaded.
+    pub fn save(&self, document: &mut Document) {
+        let old: Vec<String> = document.entries()
+            .map(|(section, _, _)| section)
+            .filter(|section| section.starts_with(SECTION_PREFIX))
+            .map(str::to_owned)
+            .collect();
+        for section in old {
+            document.remove_section(&section);
+        }
+        for (index, profile) in self.profiles.iter().enumerate() {
+            let section = format!("{SECTION_PREFIX}{index}");
+            document.set(&section, "name", profile.name.as_str());
+            for (window, layout) in &profile.windows {
+                document.set(&section, &format!("{window}.open"), layout.open);
+                if let Some(rect) = layout.rect {
+                    document.set(&section, &format!("{window}.x"), rect.min.x.round() as i64);
+                    document.set(&section, &format!("{window}.y"), rect.min.y.round() as i64);
+                    document.set(&section, &format!("{window}.width"), rect.width().round() as i64);
+                    document.set(&section, &format!("{window}.height"), rect.height().round() as i64);
+                }
+            }
+        }
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.profiles.iter().map(|profile| profile.name.clone()).collect()
+    }
+
+    pub fn current(&self) -> Option<&str> {
+        self.current.map(|index| self.profiles[index].name.as_str())
+    }
+
+    /// Replaces the profile with the same name, or adds it at the end.
+    pub fn save_profile(&mut self, profile: LayoutProfile) {
+        let index = match self.profiles.iter().position(|existing| existing.name == profile.name) {
+            Some(index) => {
+                self.profiles[index] = profile;
+                index
+            },
+            None => {
+                self.profiles.push(profile);
+                self.profiles.len() - 1
+            },
+        };
+        self.current = Some(index);
+    }
+
+    pub fn delete(&mut self, name: &str) {
+        let Some(index) = self.profiles.iter().position(|profile| profile.name == name) else {
+            return;
+        };
+        self.profiles.remove(index);
+        self.current = match self.current {
+            Some(current) if current == index => None,
+            Some(current) if current > index => Some(current - 1),
+            current => current,
+        };
+    }
+
+    /// Makes the profile called `name` the current one and returns it.
+    pub fn switch_to(&mut self, name: &str) -> Option<&LayoutProfile> {
+        self.current = Some(self.profiles.iter().position(|profile| profile.name == name)?);
+        self.current.map(|index| &self.profiles[index])
+    }
+
+    /// Goes on to the profile after the current one, from the last back to the first.
+    pub fn switch_to_next(&mut self) -> Option<&LayoutProfile> {
+        if self.profiles.is_empty() {
+            return None;
+        }
+        let index = self.current.map_or(0, |current| (current + 1) % self.profiles.len());
+        self.current = Some(index);
+        Some(&self.profiles[index])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profiles_survive_the_state_file_and_keep_windows_they_dont_know() {
+        let rect = egui::Rect::from_min_size(egui::pos2(10.0, 20.0), egui::vec2(300.0, 200.0));
+        let mut windows = BTreeMap::new();
+        windows.insert(ToolWindow::LogConsole.key().to_owned(), WindowLayout { open: true, rect: Some(rect) });
+        windows.insert("plot".to_owned(), WindowLayout { open: false, rect: None });
+        let mut profiles = LayoutProfiles::default();
+        profiles.save_profile(LayoutProfile { name: "Debugging".to_owned(), windows });
+        profiles.save_profile(LayoutProfile { name: "Empty".to_owned(), windows: BTreeMap::new() });
+        profiles.delete("Empty");
+
+        let mut document = Document::default();
+        // Left over from a profile that was deleted in an earlier run.
+        document.set("layout.5", "name", "Gone");
+        profiles.save(&mut document);
+        let text = document.to_string();
+        let mut loaded = LayoutProfiles::load(&Document::parse(&text).unwrap());
+
+        assert_eq!(loaded.names(), ["Debugging"]);
+        let profile = loaded.switch_to_next().unwrap();
+        assert_eq!(profile.window(ToolWindow::LogConsole), Some(WindowLayout { open: true, rect: Some(rect) }));
+        assert_eq!(profile.window(ToolWindow::Settings), None);
+        assert!(text.contains("plot.open = false"));
+    }
+}
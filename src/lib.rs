@@ -0,0 +1,61 @@
+//! The app, as a library so Android can load it, see `android_main`. The binary only calls [`run`].
+
+mod autosave;
+mod bench;
+mod capabilities;
+mod capture;
+mod clipboard;
+mod color_check;
+mod config;
+mod crash;
+mod decorations;
+mod dialogs;
+mod fonts;
+mod frame_clock;
+#[cfg(feature = "gamepad")]
+mod gamepad;
+mod i18n;
+mod init_error;
+mod input_tuning;
+mod json;
+mod layouts;
+mod loading;
+mod logging;
+mod memory_trim;
+mod motion;
+mod native_textures;
+mod offscreen;
+mod overdraw;
+mod on_screen_keyboard;
+mod pacing;
+mod palette;
+mod placement;
+mod postprocess;
+mod profiler;
+mod raw_input;
+mod recording;
+mod replay;
+mod save_file;
+mod scene;
+mod settings;
+mod shaders;
+mod shutdown;
+mod stats;
+mod storage;
+mod taskbar;
+mod tasks;
+mod tessellation_cache;
+mod timestep;
+mod toasts;
+#[cfg(all(target_os = "windows", feature = "tray"))]
+mod tray;
+mod ui;
+#[cfg(test)]
+mod ui_driver;
+mod undo;
+mod viewports;
+mod watchdog;
+mod window_commands;
+mod app;
+
+pub use app::run;
@@ -0,0 +1,236 @@
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+
+use crate::fonts;
+use crate::i18n::{tr, tr_format};
+use crate::motion::MotionPrefs;
+use crate::tasks::{ProgressHandle, TaskId, TaskRunner};
+
+/// What an app needs before its UI can come up and takes too long to load on the UI thread, like models, fonts or
+/// big config files. It's loaded on a task while the window shows the loading screen, see [`Loader`].
+pub trait EguiApp: Clone + Send + 'static {
+    type Loaded: Send + 'static;
+
+    /// Runs on a task thread. The progress and the last line logged to `progress` are shown on the loading screen,
+    /// an error is shown with a button to run it again.
+    fn load(&self, progress: &ProgressHandle) -> Result<Self::Loaded, String>;
+}
+
+/// The demo only loads fonts: the files given with `--font` and, with the `fallback-fonts` feature, a CJK font of
+/// the system.
+#[derive(Debug, Clone)]
+pub struct DemoApp {
+    pub font_paths: Vec<PathBuf>,
+}
+
+pub struct DemoResources {
+    pub fonts: egui::FontDefinitions,
+}
+
+impl EguiApp for DemoApp {
+    type Loaded = DemoResources;
+
+    fn load(&self, progress: &ProgressHandle) -> Result<DemoResources, String> {
+        // The system font comes last and may not be there, so it only counts as one more step.
+        let steps = self.font_paths.len() + 1;
+        let mut step = 0;
+        let fonts = fonts::definitions(&self.font_paths, |path| {
+            progress.set_progress(step as f32 / steps as f32);
+            progress.log(tr_format("loading.font", &[("font", &path.display())]));
+            step += 1;
+        });
+        progress.set_progress(1.0);
+        Ok(DemoResources { fonts })
+    }
+}
+
+/// Picked on the loading screen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LoadAction {
+    Quit,
+}
+
+enum Phase<T> {
+    Loading {
+        task: TaskId,
+        result: Receiver<Result<T, String>>,
+    },
+    Failed(String),
+    Done,
+}
+
+/// Runs [`EguiApp::load`] on a task and shows how far it is until it's done. The window, the GPU and egui are set up
+/// before, so the loading screen is up right away and the window can be moved and closed meanwhile.
+pub struct Loader<A: EguiApp> {
+    app: A,
+    phase: Phase<A::Loaded>,
+}
+
+impl<A: EguiApp> Loader<A> {
+    pub fn start(app: A, tasks: &mut TaskRunner) -> Self {
+        let mut loader = Self { app, phase: Phase::Done };
+        loader.spawn(tasks);
+        loader
+    }
+
+    fn spawn(&mut self, tasks: &mut TaskRunner) {
+        let (sender, result) = mpsc::channel();
+        let app = self.app.clone();
+        let task = tasks.spawn(tr("loading.task"), move |progress| {
+            let loaded = app.load(&progress);
+            if let Err(error) = &loaded {
+                progress.fail(error.clone());
+            }
+            let _ = sender.send(loaded);
+        });
+        self.phase = Phase::Loading { task, result };
+    }
+
+    pub fn is_done(&self) -> bool {
+        matches!(self.phase, Phase::Done)
+    }
+
+    /// What was loaded, once the task finished. Called after `tasks` was polled, the task's end is part of the
+    /// loading screen and never shows up as a finished task after it.
+    pub fn poll(&mut self, tasks: &TaskRunner) -> Option<A::Loaded> {
+        let Phase::Loading { task, result } = &self.phase else {
+            return None;
+        };
+        let finished = tasks.tasks().iter().find(|t| t.id == *task).is_none_or(|task| task.status.is_finished());
+        if !finished {
+            return None;
+        }
+        match result.try_recv() {
+            Ok(Ok(loaded)) => {
+                self.phase = Phase::Done;
+                Some(loaded)
+            },
+            Ok(Err(error)) => {
+                log::error!("Loading failed: {error}");
+                self.phase = Phase::Failed(error);
+                None
+            },
+            // The task panicked before it sent anything, the task pool logged that.
+            Err(TryRecvError::Empty | TryRecvError::Disconnected) => {
+                self.phase = Phase::Failed(tr("loading.panicked").to_owned());
+                None
+            },
+        }
+    }
+
+    /// The logo, a spinner and the progress, or what went wrong with buttons to try again or quit.
+    pub fn show(&mut self, ctx: &egui::Context, tasks: &mut TaskRunner) -> Option<LoadAction> {
+        let mut action = None;
+        let mut retry = false;
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.add_space(ui.available_height() / 3.0);
+                logo(ui);
+                ui.heading(env!("CARGO_PKG_NAME"));
+                ui.add_space(8.0);
+                match &self.phase {
+                    Phase::Loading { task, .. } => {
+                        let task = tasks.tasks().iter().find(|t| t.id == *task);
+                        // A spinner that stands still would look stuck.
+                        if MotionPrefs::get(ctx).reduce_motion {
+                            ui.label("…");
+                        } else {
+                            ui.add(egui::Spinner::new().size(24.0));
+                        }
+                        ui.add(egui::ProgressBar::new(task.map_or(0.0, |task| task.progress)).desired_width(240.0));
+                        ui.weak(task.and_then(|task| task.log.last()).map_or(tr("loading.starting"), String::as_str));
+                    },
+                    Phase::Failed(error) => {
+                        ui.colored_label(ui.visuals().error_fg_color, tr("loading.failed"));
+                        ui.label(error);
+                        ui.add_space(8.0);
+                        if ui.button(tr("loading.retry")).clicked() {
+                            retry = true;
+                        }
+                        if ui.button(tr("loading.quit")).clicked() {
+                            action = Some(LoadAction::Quit);
+                        }
+                    },
+                    Phase::Done => {},
+                }
+            });
+        });
+        if retry {
+            self.spawn(tasks);
+        }
+        action
+    }
+}
+
+/// A rounded square in the accent color with the initial in it.
+fn logo(ui: &mut egui::Ui) {
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(64.0, 64.0), egui::Sense::hover());
+    let painter = ui.painter();
+    painter.rect_filled(rect, 12.0, ui.visuals().selection.bg_fill);
+    painter.text(rect.center(), egui::Align2::CENTER_CENTER, "e", egui::FontId::proportional(40.0), ui.visuals().selection.stroke.color);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    /// Fails the first time.
+    #[derive(Clone)]
+    struct FlakyApp(Arc<AtomicBool>);
+
+    impl EguiApp for FlakyApp {
+        type Loaded = &'static str;
+
+        fn load(&self, progress: &ProgressHandle) -> Result<&'static str, String> {
+            progress.set_progress(0.5);
+            match self.0.swap(true, Ordering::Relaxed) {
+                false => Err("not there yet".to_owned()),
+                true => Ok("loaded"),
+            }
+        }
+    }
+
+    fn poll_until_finished(loader: &mut Loader<FlakyApp>, tasks: &mut TaskRunner) -> Option<&'static str> {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while matches!(loader.phase, Phase::Loading { .. }) && Instant::now() < deadline {
+            tasks.poll();
+            if let Some(loaded) = loader.poll(tasks) {
+                return Some(loaded);
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        None
+    }
+
+    #[test]
+    fn failed_loading_is_shown_until_retried() {
+        let ctx = egui::Context::default();
+        let mut tasks = TaskRunner::new(&ctx, None);
+        let mut loader = Loader::start(FlakyApp(Arc::default()), &mut tasks);
+
+        assert_eq!(poll_until_finished(&mut loader, &mut tasks), None);
+        assert!(matches!(&loader.phase, Phase::Failed(error) if error == "not there yet"));
+        // The retry button, found by its text.
+        let raw_input = || egui::RawInput {
+            screen_rect: Some(egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(800.0, 600.0))),
+            ..Default::default()
+        };
+        let output = ctx.run(raw_input(), |ctx| assert_eq!(loader.show(ctx, &mut tasks), None));
+        let pos = crate::ui_driver::text_rect(&output, tr("loading.retry")).expect("the retry button should be shown").center();
+        for pressed in [true, false] {
+            let mut input = raw_input();
+            input.events = vec![
+                egui::Event::PointerMoved(pos),
+                egui::Event::PointerButton { pos, button: egui::PointerButton::Primary, pressed, modifiers: egui::Modifiers::NONE },
+            ];
+            let _ = ctx.run(input, |ctx| assert_eq!(loader.show(ctx, &mut tasks), None));
+        }
+
+        assert_eq!(poll_until_finished(&mut loader, &mut tasks), Some("loaded"));
+        assert!(loader.is_done());
+    }
+}
@@ -0,0 +1,115 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Oldest records are dropped once the buffer holds this many.
+const CAPACITY: usize = 10_000;
+
+pub struct LogRecord {
+    /// Milliseconds since the unix epoch.
+    pub timestamp_ms: u64,
+    pub level: log::Level,
+    pub target: String,
+    pub message: String,
+}
+
+impl LogRecord {
+    /// Time of day in UTC, `hh:mm:ss.mmm`.
+    pub fn time_of_day(&self) -> String {
+        let ms = self.timestamp_ms % (24 * 60 * 60 * 1000);
+        format!("{:02}:{:02}:{:02}.{:03}", ms / 3_600_000, ms / 60_000 % 60, ms / 1000 % 60, ms % 1000)
+    }
+}
+
+/// Ring buffer shared between the logger, which can be called from any thread, and the UI thread.
+pub struct LogBuffer {
+    records: Mutex<VecDeque<LogRecord>>,
+    /// Set when records arrived since the UI last looked, so the wake callback only fires once per batch.
+    pending: AtomicBool,
+    wake: OnceLock<Box<dyn Fn() + Send + Sync>>,
+}
+
+impl LogBuffer {
+    pub fn records(&self) -> MutexGuard<'_, VecDeque<LogRecord>> {
+        self.pending.store(false, Ordering::Relaxed);
+        self.records.lock().unwrap()
+    }
+
+    pub fn clear(&self) {
+        self.records.lock().unwrap().clear();
+    }
+
+    /// Drops all but the newest `keep` records and gives the memory of the others back.
+    pub fn truncate(&self, keep: usize) {
+        let mut records = self.records.lock().unwrap();
+        let excess = records.len().saturating_sub(keep);
+        records.drain(..excess);
+        records.shrink_to_fit();
+    }
+
+    /// `wake` is called from whatever thread logged something new, at most once until [`Self::records`] is called.
+    pub fn set_wake_callback(&self, wake: impl Fn() + Send + Sync + 'static) {
+        let _ = self.wake.set(Box::new(wake));
+    }
+
+    fn push(&self, record: LogRecord) {
+        {
+            let mut records = self.records.lock().unwrap();
+            if records.len() == CAPACITY {
+                records.pop_front();
+            }
+            records.push_back(record);
+        }
+
+        if !self.pending.swap(true, Ordering::Relaxed) {
+            if let Some(wake) = self.wake.get() {
+                wake();
+            }
+        }
+    }
+}
+
+struct Logger {
+    buffer: Arc<LogBuffer>,
+}
+
+impl log::Log for Logger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        // Debug output of wgpu and friends is far too chatty, only keep our own.
+        metadata.level() <= log::Level::Info || metadata.target().starts_with(env!("CARGO_CRATE_NAME"))
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        // Everything is formatted before touching the buffer so the lock is only held for the push.
+        let record = LogRecord {
+            timestamp_ms: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64),
+            level: record.level(),
+            target: record.target().to_owned(),
+            message: record.args().to_string(),
+        };
+        eprintln!("{} {:<5} {}: {}", record.time_of_day(), record.level, record.target, record.message);
+        self.buffer.push(record);
+    }
+
+    fn flush(&self) {}
+}
+
+/// Installs the global logger and returns the buffer it writes into.
+pub fn init() -> Arc<LogBuffer> {
+    let buffer = Arc::new(LogBuffer {
+        records: Mutex::new(VecDeque::new()),
+        pending: AtomicBool::new(false),
+        wake: OnceLock::new(),
+    });
+
+    if log::set_boxed_logger(Box::new(Logger { buffer: buffer.clone() })).is_ok() {
+        log::set_max_level(log::LevelFilter::Debug);
+    }
+
+    buffer
+}
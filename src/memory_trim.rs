@@ -0,0 +1,94 @@
+use std::time::{Duration, Instant};
+
+/// How much `AppResources::trim_memory` lets go of.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TrimLevel {
+    /// After a while without input: what comes back by itself as soon as it's needed again.
+    Idle,
+    /// The system is running out of memory or it was asked for from the Debug menu, the log keeps less as well.
+    Full,
+}
+
+impl TrimLevel {
+    /// The newest log records that are kept, the console can't show the older ones anymore.
+    pub fn log_records_kept(self) -> usize {
+        match self {
+            Self::Idle => 1000,
+            Self::Full => 100,
+        }
+    }
+}
+
+/// What the caches that can be trimmed hold at a point in time.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CacheUsage {
+    /// Width and height of egui's font atlas, in pixels.
+    pub font_atlas: [usize; 2],
+    /// Textures uploaded to the UI renderer, the font atlas is one of them.
+    pub textures: usize,
+    pub native_textures: usize,
+    /// Bytes held by egui's image loaders, decoded images and the files they were decoded from.
+    pub image_bytes: usize,
+    pub log_records: usize,
+}
+
+/// What a trim freed. The numbers after are only taken once the next frame was rendered, the font atlas grows back
+/// to what that frame draws and the freed textures are only gone from the renderer after it.
+#[derive(Debug, Clone, Copy)]
+pub struct TrimReport {
+    pub level: TrimLevel,
+    pub before: CacheUsage,
+    pub after: Option<CacheUsage>,
+}
+
+/// Trims once after a while without input, until there's input again.
+#[derive(Debug)]
+pub struct IdleTrim {
+    last_input: Instant,
+    trimmed: bool,
+}
+
+impl IdleTrim {
+    pub fn new(now: Instant) -> Self {
+        Self { last_input: now, trimmed: false }
+    }
+
+    pub fn input(&mut self, now: Instant) {
+        self.last_input = now;
+        self.trimmed = false;
+    }
+
+    /// When to trim if there's no input until then, `None` while it's turned off or already done.
+    pub fn deadline(&self, after: Option<Duration>) -> Option<Instant> {
+        after.filter(|_| !self.trimmed).map(|after| self.last_input + after)
+    }
+
+    /// Whether the trim is due at `now`, only true once per idle period.
+    pub fn take_due(&mut self, now: Instant, after: Option<Duration>) -> bool {
+        let due = self.deadline(after).is_some_and(|at| at <= now);
+        self.trimmed |= due;
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn idle_trim_is_due_once_per_idle_period() {
+        let start = Instant::now();
+        let after = Some(Duration::from_secs(60));
+        let mut idle = IdleTrim::new(start);
+
+        assert!(!idle.take_due(start + Duration::from_secs(59), after));
+        assert!(!idle.take_due(start + Duration::from_secs(61), None));
+        assert!(idle.take_due(start + Duration::from_secs(61), after));
+        assert!(!idle.take_due(start + Duration::from_secs(200), after));
+        assert_eq!(idle.deadline(after), None);
+
+        idle.input(start + Duration::from_secs(300));
+        assert_eq!(idle.deadline(after), Some(start + Duration::from_secs(360)));
+        assert!(idle.take_due(start + Duration::from_secs(360), after));
+    }
+}
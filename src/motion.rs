@@ -0,0 +1,92 @@
+use std::sync::mpsc::{self, Receiver};
+
+use crate::i18n::tr;
+use crate::tasks::TaskRunner;
+
+/// Whether animations should be left out, readable from UI code with [`MotionPrefs::get`]. Animated widgets show
+/// their final state right away instead, e.g. toasts vanish without fading and the scene stands still.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct MotionPrefs {
+    pub reduce_motion: bool,
+}
+
+impl MotionPrefs {
+    /// Animations run until the app sets anything else.
+    pub fn get(ctx: &egui::Context) -> Self {
+        ctx.data(|data| data.get_temp(egui::Id::new("motion_prefs"))).unwrap_or_default()
+    }
+
+    /// Also turns off egui's own animations, like collapsing headers opening and windows fading in.
+    pub fn set(self, ctx: &egui::Context) {
+        ctx.data_mut(|data| data.insert_temp(egui::Id::new("motion_prefs"), self));
+        let defaults = egui::Style::default();
+        ctx.all_styles_mut(|style| {
+            if self.reduce_motion {
+                style.animation_time = 0.0;
+                style.scroll_animation = egui::style::ScrollAnimation::none();
+            } else {
+                style.animation_time = defaults.animation_time;
+                style.scroll_animation = defaults.scroll_animation;
+            }
+        });
+    }
+}
+
+/// Whether the system asks for fewer animations, asked once at startup. On Linux and macOS that runs a command, so it's
+/// asked on a task to not hold up the first frame. Animations run until the answer is there.
+pub struct SystemMotion {
+    reduces: Option<bool>,
+    /// Sends the answer back, `None` once it arrived.
+    query: Option<Receiver<Option<bool>>>,
+}
+
+impl SystemMotion {
+    pub fn query(tasks: &mut TaskRunner) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        tasks.spawn_quiet(tr("motion.task"), move |_| {
+            let _ = sender.send(system_reduces_motion());
+        });
+        Self { reduces: None, query: Some(receiver) }
+    }
+
+    /// `None` while the system didn't say (yet).
+    pub fn reduces(&mut self) -> Option<bool> {
+        if let Some(reduces) = self.query.as_ref().and_then(|query| query.try_recv().ok()) {
+            self.reduces = reduces;
+            self.query = None;
+        }
+        self.reduces
+    }
+}
+
+/// Whether the system asks for fewer animations, `None` if that can't be found out. winit and egui don't say, so this
+/// asks the system settings directly.
+fn system_reduces_motion() -> Option<bool> {
+    #[cfg(target_os = "windows")]
+    {
+        use windows_sys::Win32::UI::WindowsAndMessaging::{SystemParametersInfoW, SPI_GETCLIENTAREAANIMATION};
+
+        let mut animations: i32 = 1;
+        // SAFETY: The action writes a BOOL to the pointer it's given.
+        let ok = unsafe { SystemParametersInfoW(SPI_GETCLIENTAREAANIMATION, 0, (&mut animations as *mut i32).cast(), 0) };
+        (ok != 0).then_some(animations == 0)
+    }
+    #[cfg(target_os = "macos")]
+    {
+        command_output("defaults", &["read", "com.apple.universalaccess", "reduceMotion"]).map(|value| value == "1")
+    }
+    #[cfg(all(unix, not(any(target_os = "macos", target_os = "android"))))]
+    {
+        command_output("gsettings", &["get", "org.gnome.desktop.interface", "enable-animations"]).map(|value| value == "false")
+    }
+    #[cfg(any(target_os = "android", not(any(unix, target_os = "windows"))))]
+    {
+        None
+    }
+}
+
+#[cfg(all(unix, not(target_os = "android")))]
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new(program).args(args).stderr(std::process::Stdio::null()).output().ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
@@ -0,0 +1,195 @@
+use std::collections::BTreeMap;
+
+struct Entry {
+    view: wgpu::TextureView,
+    sampler: wgpu::SamplerDescriptor<'static>,
+}
+
+/// wgpu textures shown in egui, remembered so they survive the renderer being rebuilt.
+#[derive(Default)]
+pub struct NativeTextures {
+    /// By the number of their `TextureId::User`.
+    entries: BTreeMap<u64, Entry>,
+}
+
+impl NativeTextures {
+    pub fn register(
+        &mut self,
+        renderer: &mut egui_wgpu::Renderer,
+        device: &wgpu::Device,
+        view: &wgpu::TextureView,
+        sampler: wgpu::SamplerDescriptor<'static>,
+    ) -> egui::TextureId {
+        let id = renderer.register_native_texture_with_sampler_options(device, view, sampler.clone());
+        if let egui::TextureId::User(number) = id {
+            self.entries.insert(number, Entry { view: view.clone(), sampler });
+        }
+        id
+    }
+
+    /// Swaps the texture behind `id`, keeping its sampler options.
+    pub fn update(&mut self, renderer: &mut egui_wgpu::Renderer, device: &wgpu::Device, id: egui::TextureId, view: &wgpu::TextureView) {
+        let Some(entry) = self.entry_mut(id) else {
+            return;
+        };
+        entry.view = view.clone();
+        renderer.update_egui_texture_from_wgpu_texture_with_sampler_options(device, view, entry.sampler.clone(), id);
+    }
+
+    pub fn set_sampler(&mut self, renderer: &mut egui_wgpu::Renderer, device: &wgpu::Device, id: egui::TextureId, sampler: wgpu::SamplerDescriptor<'static>) {
+        let Some(entry) = self.entry_mut(id) else {
+            return;
+        };
+        entry.sampler = sampler;
+        renderer.update_egui_texture_from_wgpu_texture_with_sampler_options(device, &entry.view, entry.sampler.clone(), id);
+    }
+
+    /// Frees the texture behind `id` in the renderer and forgets it, the id isn't handed out again.
+    pub fn free(&mut self, renderer: &mut egui_wgpu::Renderer, id: egui::TextureId) {
+        if let egui::TextureId::User(number) = id {
+            self.entries.remove(&number);
+        }
+        renderer.free_texture(&id);
+    }
+
+    pub fn count(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Registers everything again with a freshly created renderer, under the same ids as before.
+    pub fn restore(&self, renderer: &mut egui_wgpu::Renderer, device: &wgpu::Device) {
+        let Some((&last, _)) = self.entries.last_key_value() else {
+            return;
+        };
+        // A new renderer hands out user ids counting up from 0, so ids of freed textures get a placeholder that
+        // is freed again right away.
+        let placeholder = create_texture(device, [1, 1], 1).create_view(&wgpu::TextureViewDescriptor::default());
+        for number in 0..=last {
+            let id = match self.entries.get(&number) {
+                Some(entry) => renderer.register_native_texture_with_sampler_options(device, &entry.view, entry.sampler.clone()),
+                None => {
+                    let id = renderer.register_native_texture(device, &placeholder, wgpu::FilterMode::Nearest);
+                    renderer.free_texture(&id);
+                    id
+                },
+            };
+            debug_assert_eq!(id, egui::TextureId::User(number));
+        }
+    }
+
+    fn entry_mut(&mut self, id: egui::TextureId) -> Option<&mut Entry> {
+        match id {
+            egui::TextureId::User(number) => self.entries.get_mut(&number),
+            egui::TextureId::Managed(_) => None,
+        }
+    }
+}
+
+/// An sRGB texture that can be shown in egui, with room for `mip_levels` levels.
+pub fn create_texture(device: &wgpu::Device, size: [u32; 2], mip_levels: u32) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Native Texture"),
+        size: wgpu::Extent3d { width: size[0], height: size[1], depth_or_array_layers: 1 },
+        mip_level_count: mip_levels,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        // egui expects this format for native textures.
+        format: wgpu::TextureFormat::Rgba8UnormSrgb,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        view_formats: &[],
+    })
+}
+
+/// Uploads RGBA pixels into `texture`, and with `mipmaps` also every smaller level, box filtered on the CPU.
+pub fn upload(queue: &wgpu::Queue, texture: &wgpu::Texture, rgba: &[u8], mipmaps: bool) {
+    let mut size = [texture.width(), texture.height()];
+    let mut level = rgba.to_vec();
+    let levels = if mipmaps { texture.mip_level_count() } else { 1 };
+
+    for mip_level in 0..levels {
+        queue.write_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture,
+                mip_level,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &level,
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(size[0] * 4),
+                rows_per_image: None,
+            },
+            wgpu::Extent3d { width: size[0], height: size[1], depth_or_array_layers: 1 },
+        );
+        (level, size) = downsample(&level, size);
+    }
+}
+
+/// Halves the size, averaging each 2×2 block. Good enough for demo textures, even though it ignores sRGB.
+fn downsample(rgba: &[u8], [width, height]: [u32; 2]) -> (Vec<u8>, [u32; 2]) {
+    let size = [(width / 2).max(1), (height / 2).max(1)];
+    let mut out = Vec::with_capacity(size[0] as usize * size[1] as usize * 4);
+    for y in 0..size[1] {
+        for x in 0..size[0] {
+            for channel in 0..4 {
+                let sample = |sx: u32, sy: u32| rgba[((sy.min(height - 1) * width + sx.min(width - 1)) * 4 + channel) as usize] as u32;
+                let sum = sample(x * 2, y * 2) + sample(x * 2 + 1, y * 2) + sample(x * 2, y * 2 + 1) + sample(x * 2 + 1, y * 2 + 1);
+                out.push((sum / 4) as u8);
+            }
+        }
+    }
+    (out, size)
+}
+
+/// Sampler options for native textures, `mipmaps` only has an effect on textures that have them.
+pub fn sampler(nearest: bool, mipmaps: bool) -> wgpu::SamplerDescriptor<'static> {
+    let filter = if nearest { wgpu::FilterMode::Nearest } else { wgpu::FilterMode::Linear };
+    wgpu::SamplerDescriptor {
+        label: Some("Native Texture"),
+        mag_filter: filter,
+        min_filter: filter,
+        mipmap_filter: if mipmaps { wgpu::FilterMode::Linear } else { wgpu::FilterMode::Nearest },
+        lod_max_clamp: if mipmaps { 32.0 } else { 0.0 },
+        ..Default::default()
+    }
+}
+
+/// A 16×16 smiley in one of a few colors, for showing off nearest filtering.
+pub fn demo_pixel_art(variant: u32) -> Vec<u8> {
+    const ART: [&str; 16] = [
+        "....########....",
+        "..##........##..",
+        ".#............#.",
+        ".#............#.",
+        "#...##....##...#",
+        "#...##....##...#",
+        "#..............#",
+        "#..............#",
+        "#..............#",
+        "#..#........#..#",
+        "#...#......#...#",
+        ".#...######...#.",
+        ".#............#.",
+        "..##........##..",
+        "....########....",
+        "................",
+    ];
+    let colors = [[255, 200, 40], [80, 200, 255], [120, 230, 90], [240, 90, 160]];
+    let [r, g, b] = colors[variant as usize % colors.len()];
+
+    ART.iter()
+        .flat_map(|row| row.bytes())
+        .flat_map(|cell| if cell == b'#' { [r, g, b, 255] } else { [30, 30, 30, 255] })
+        .collect()
+}
+
+/// A fine black and white checkerboard, which flickers and shows moiré when scaled down without mipmaps.
+pub fn demo_checkerboard(size: u32) -> Vec<u8> {
+    (0..size * size)
+        .flat_map(|i| {
+            let value = if (i % size / 4 + i / size / 4).is_multiple_of(2) { 255 } else { 0 };
+            [value, value, value, 255]
+        })
+        .collect()
+}
@@ -0,0 +1,268 @@
+use crate::settings::ScaleFilter;
+use crate::shaders;
+
+/// A color texture the UI gets drawn into instead of the surface, e.g. to render it at a different resolution.
+pub struct OffscreenTarget {
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+}
+
+impl OffscreenTarget {
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat, size: [u32; 2]) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Offscreen Target"),
+            size: wgpu::Extent3d { width: size[0], height: size[1], depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        Self { texture, view }
+    }
+
+    pub fn size(&self) -> [u32; 2] {
+        [self.texture.width(), self.texture.height()]
+    }
+
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.texture.format()
+    }
+
+    pub fn view(&self) -> &wgpu::TextureView {
+        &self.view
+    }
+}
+
+/// Multisampled color buffer the UI is drawn into with MSAA on, it's resolved into the actual target at the end of the pass.
+pub struct MsaaTarget {
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+}
+
+impl MsaaTarget {
+    /// Keeps `target` if it still matches, replaces it otherwise. Without multisampling it's dropped.
+    pub fn prepare(target: &mut Option<Self>, device: &wgpu::Device, format: wgpu::TextureFormat, size: [u32; 2], sample_count: u32) {
+        if sample_count <= 1 {
+            *target = None;
+            return;
+        }
+        let matches = |target: &Self| {
+            let texture = &target.texture;
+            [texture.width(), texture.height()] == size && texture.format() == format && texture.sample_count() == sample_count
+        };
+        if target.as_ref().is_none_or(|target| !matches(target)) {
+            let texture = device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("MSAA Target"),
+                size: wgpu::Extent3d { width: size[0], height: size[1], depth_or_array_layers: 1 },
+                mip_level_count: 1,
+                sample_count,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+                view_formats: &[],
+            });
+            let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+            *target = Some(Self { texture, view });
+        }
+    }
+
+    /// Draws into `msaa` when there is one and resolves into `view`, otherwise straight into `view`.
+    pub fn color_attachment<'a>(msaa: Option<&'a Self>, view: &'a wgpu::TextureView, clear: wgpu::Color) -> wgpu::RenderPassColorAttachment<'a> {
+        let load = wgpu::LoadOp::Clear(clear);
+        match msaa {
+            // The samples aren't needed anymore once they're resolved.
+            Some(msaa) => wgpu::RenderPassColorAttachment {
+                view: &msaa.view,
+                resolve_target: Some(view),
+                ops: wgpu::Operations { load, store: wgpu::StoreOp::Discard },
+            },
+            None => wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations { load, store: wgpu::StoreOp::Store },
+            },
+        }
+    }
+}
+
+/// The highest sample count up to `wanted` that `format` can be rendered with on `device`. What `adapter` supports
+/// beyond the guaranteed counts needs `TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES`.
+pub fn supported_sample_count(adapter: &wgpu::Adapter, device: &wgpu::Device, format: wgpu::TextureFormat, wanted: u32) -> u32 {
+    let flags = if device.features().contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES) {
+        adapter.get_texture_format_features(format).flags
+    } else {
+        format.guaranteed_format_features(device.features()).flags
+    };
+    let resolvable = flags.contains(wgpu::TextureFormatFeatureFlags::MULTISAMPLE_RESOLVE);
+    let supported = [8, 4, 2].into_iter()
+        .find(|&count| resolvable && count <= wanted && flags.sample_count_supported(count))
+        .unwrap_or(1);
+    if supported != wanted {
+        log::warn!("{wanted}× MSAA isn't supported for {format:?}, using {supported}×");
+    }
+    supported
+}
+
+/// Size of the uniform buffer of a [`FullscreenPass`]: the source texel size, padding, and four shader specific parameters.
+const UNIFORMS_SIZE: u64 = 8 * 4;
+
+/// Draws an [`OffscreenTarget`] over the whole of another texture with a fragment shader, stretching it to fit.
+///
+/// Only the `fs_main` fragment shader is supplied per pass, it can use the `source` texture, `source_sampler`
+/// and `uniforms` declared in [`shaders::FULLSCREEN`].
+pub struct FullscreenPass {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    uniforms: wgpu::Buffer,
+    linear_sampler: wgpu::Sampler,
+    nearest_sampler: wgpu::Sampler,
+}
+
+impl FullscreenPass {
+    /// Plain copy of the source, only scaling it.
+    pub fn blit(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+        Self::new(device, format, "Blit", &shaders::BLIT.source())
+    }
+
+    /// `format` is the format of the texture drawn onto, sources can have any filterable format.
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat, label: &str, fragment_wgsl: &str) -> Self {
+        let label = Some(label);
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label,
+            source: wgpu::ShaderSource::Wgsl(format!("{}\n{fragment_wgsl}", shaders::FULLSCREEN.source()).into()),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label,
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(UNIFORMS_SIZE),
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label,
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label,
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                // No blending, the source already contains the final pixels including alpha.
+                targets: &[Some(format.into())],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let uniforms = device.create_buffer(&wgpu::BufferDescriptor {
+            label,
+            size: UNIFORMS_SIZE,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let sampler = |filter| device.create_sampler(&wgpu::SamplerDescriptor {
+            label,
+            mag_filter: filter,
+            min_filter: filter,
+            ..Default::default()
+        });
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            uniforms,
+            linear_sampler: sampler(wgpu::FilterMode::Linear),
+            nearest_sampler: sampler(wgpu::FilterMode::Nearest),
+        }
+    }
+
+    /// Draws `source` onto `target`, `params` end up in the uniforms of the shader.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        ce: &mut wgpu::CommandEncoder,
+        source: &OffscreenTarget,
+        target: &wgpu::TextureView,
+        filter: ScaleFilter,
+        params: [f32; 4],
+    ) {
+        let [width, height] = source.size();
+        let uniforms = [1.0 / width as f32, 1.0 / height as f32, 0.0, 0.0, params[0], params[1], params[2], params[3]];
+        let bytes: Vec<u8> = uniforms.iter().flat_map(|value| value.to_ne_bytes()).collect();
+        queue.write_buffer(&self.uniforms, 0, &bytes);
+
+        let sampler = match filter {
+            ScaleFilter::Linear => &self.linear_sampler,
+            ScaleFilter::Nearest => &self.nearest_sampler,
+        };
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: wgpu::BindingResource::TextureView(source.view()) },
+                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::Sampler(sampler) },
+                wgpu::BindGroupEntry { binding: 2, resource: self.uniforms.as_entire_binding() },
+            ],
+        });
+
+        let mut render_pass = ce.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: None,
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                // Every pixel gets overwritten, there's nothing worth loading.
+                ops: wgpu::Operations { load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT), store: wgpu::StoreOp::Store },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
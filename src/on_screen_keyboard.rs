@@ -0,0 +1,258 @@
+use crate::i18n::tr;
+
+/// Touch targets, in points. Keys get narrower on small windows but never lower.
+const KEY_HEIGHT: f32 = 44.0;
+const KEY_GAP: f32 = 4.0;
+/// Ten keys across on the widest rows.
+const COLUMNS: f32 = 10.0;
+/// Room kept between a text field and the keyboard when a window is lifted above it.
+const LIFT_MARGIN: f32 = 8.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Page {
+    Letters,
+    Numbers,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum KeyCap {
+    Char(char),
+    Shift,
+    Backspace,
+    Page(Page),
+    Space,
+    Enter,
+    /// Sends Escape, which takes the focus off the field and with it the keyboard.
+    Escape,
+}
+
+impl KeyCap {
+    /// How wide the key is, in the width of a letter key.
+    fn width(self) -> f32 {
+        match self {
+            Self::Char(_) | Self::Escape => 1.0,
+            Self::Shift | Self::Backspace | Self::Page(_) | Self::Enter => 1.5,
+            Self::Space => 4.0,
+        }
+    }
+}
+
+fn rows(page: Page) -> [Vec<KeyCap>; 4] {
+    let chars = |chars: &str| chars.chars().map(KeyCap::Char).collect::<Vec<_>>();
+    match page {
+        Page::Letters => [
+            chars("qwertyuiop"),
+            chars("asdfghjkl"),
+            [vec![KeyCap::Shift], chars("zxcvbnm"), vec![KeyCap::Backspace]].concat(),
+            vec![KeyCap::Page(Page::Numbers), KeyCap::Char(','), KeyCap::Space, KeyCap::Char('.'), KeyCap::Enter, KeyCap::Escape],
+        ],
+        Page::Numbers => [
+            chars("1234567890"),
+            chars("-/:;()&@\""),
+            [chars("#+=?!'*"), vec![KeyCap::Backspace]].concat(),
+            vec![KeyCap::Page(Page::Letters), KeyCap::Char(','), KeyCap::Space, KeyCap::Char('.'), KeyCap::Enter, KeyCap::Escape],
+        ],
+    }
+}
+
+/// Asks the tool window on `layer` to move up by this much, it has the focused field under the keyboard.
+#[derive(Debug, Clone, Copy)]
+struct Lift {
+    layer: egui::Id,
+    by: f32,
+}
+
+fn lift_id() -> egui::Id {
+    egui::Id::new("on_screen_keyboard_lift")
+}
+
+/// How far the window on `layer` has to move up in this pass to get its focused field out from under the keyboard.
+/// Taken once.
+pub fn take_lift(ctx: &egui::Context, layer: egui::Id) -> Option<f32> {
+    let lift = ctx.data(|data| data.get_temp::<Lift>(lift_id())).filter(|lift| lift.layer == layer)?;
+    ctx.data_mut(|data| data.remove::<Lift>(lift_id()));
+    Some(lift.by)
+}
+
+/// A keyboard along the bottom of the window for touch screens without a real one, up while a text field has the
+/// focus. Presses on its keys never reach egui, which would take the focus off the field for a press anywhere
+/// else. They become the text and key events of the press instead, see [`Self::process`].
+#[derive(Default)]
+pub struct OnScreenKeyboard {
+    /// The widget with the focus the keyboard is up for.
+    target: Option<egui::Id>,
+    numbers: bool,
+    /// Upper case for the next letter.
+    shift: bool,
+    /// Where the panel and its keys were in the last pass, in points.
+    panel: Option<egui::Rect>,
+    keys: Vec<(egui::Rect, KeyCap)>,
+    /// The key under the press that's held, or `None` for a press on the panel between the keys.
+    held: Option<Option<KeyCap>>,
+}
+
+impl OnScreenKeyboard {
+    /// Takes primary presses on the keyboard out of `raw_input`, along with their releases, and adds what the keys
+    /// type. Does nothing while the keyboard isn't up.
+    pub fn process(&mut self, raw_input: &mut egui::RawInput, enabled: bool) {
+        let Some(panel) = self.panel.filter(|_| enabled) else {
+            self.held = None;
+            return;
+        };
+        let mut pressed = Vec::new();
+        raw_input.events.retain(|event| match event {
+            egui::Event::PointerButton { pos, button: egui::PointerButton::Primary, pressed: true, .. } if panel.contains(*pos) => {
+                let key = self.keys.iter().find(|(rect, _)| rect.contains(*pos)).map(|(_, key)| *key);
+                pressed.extend(key);
+                self.held = Some(key);
+                false
+            },
+            egui::Event::PointerButton { button: egui::PointerButton::Primary, pressed: false, .. } if self.held.is_some() => {
+                self.held = None;
+                false
+            },
+            _ => true,
+        });
+        for key in pressed {
+            self.press(key, raw_input);
+        }
+    }
+
+    fn press(&mut self, key: KeyCap, raw_input: &mut egui::RawInput) {
+        let mut tap = |key| {
+            for pressed in [true, false] {
+                raw_input.events.push(egui::Event::Key { key, physical_key: None, pressed, repeat: false, modifiers: raw_input.modifiers });
+            }
+        };
+        match key {
+            KeyCap::Char(c) => {
+                let text = if self.shift { c.to_uppercase().collect() } else { c.to_string() };
+                raw_input.events.push(egui::Event::Text(text));
+                self.shift = false;
+            },
+            KeyCap::Shift => self.shift = !self.shift,
+            KeyCap::Backspace => tap(egui::Key::Backspace),
+            KeyCap::Page(page) => self.numbers = page == Page::Numbers,
+            KeyCap::Space => raw_input.events.push(egui::Event::Text(" ".to_owned())),
+            KeyCap::Enter => tap(egui::Key::Enter),
+            KeyCap::Escape => tap(egui::Key::Escape),
+        }
+    }
+
+    /// Shows the keyboard while a widget has the focus, before the other panels so theirs is what's left. A window
+    /// with the focused field under the keyboard is lifted above it, see [`take_lift`]. A scroll area around the
+    /// field gets a smaller viewport and follows the cursor once something is typed.
+    pub fn show(&mut self, ctx: &egui::Context, enabled: bool) {
+        let focused = ctx.memory(|memory| memory.focused()).filter(|_| enabled && ctx.wants_keyboard_input());
+        if focused != self.target {
+            self.target = focused;
+            self.numbers = false;
+            self.shift = false;
+        }
+        self.keys.clear();
+        self.panel = None;
+        let Some(target) = self.target else {
+            return;
+        };
+
+        let page = if self.numbers { Page::Numbers } else { Page::Letters };
+        let panel = egui::TopBottomPanel::bottom("on_screen_keyboard").resizable(false).show(ctx, |ui| {
+            let rows = rows(page);
+            let key_width = ((ui.available_width() - KEY_GAP * (COLUMNS - 1.0)) / COLUMNS).max(1.0);
+            let height = rows.len() as f32 * (KEY_HEIGHT + KEY_GAP) - KEY_GAP;
+            let (area, _) = ui.allocate_exact_size(egui::vec2(ui.available_width(), height), egui::Sense::hover());
+            for (index, row) in rows.iter().enumerate() {
+                let row_width = row.iter().map(|key| key.width() * key_width + KEY_GAP).sum::<f32>() - KEY_GAP;
+                let mut x = area.center().x - row_width / 2.0;
+                let y = area.top() + index as f32 * (KEY_HEIGHT + KEY_GAP);
+                for &key in row {
+                    let rect = egui::Rect::from_min_size(egui::pos2(x, y), egui::vec2(key.width() * key_width, KEY_HEIGHT));
+                    self.paint_key(ui, rect, key);
+                    self.keys.push((rect, key));
+                    x += rect.width() + KEY_GAP;
+                }
+            }
+        });
+        let panel = panel.response.rect;
+        self.panel = Some(panel);
+
+        if let Some(field) = ctx.read_response(target) {
+            let covered = field.rect.bottom() + LIFT_MARGIN - panel.top();
+            if covered > 0.0 {
+                ctx.data_mut(|data| data.insert_temp(lift_id(), Lift { layer: field.layer_id.id, by: covered }));
+            }
+        }
+    }
+
+    fn paint_key(&self, ui: &egui::Ui, rect: egui::Rect, key: KeyCap) {
+        let visuals = ui.visuals();
+        let style = if self.held == Some(Some(key)) || (key == KeyCap::Shift && self.shift) {
+            &visuals.widgets.active
+        } else {
+            &visuals.widgets.inactive
+        };
+        let label: String = match key {
+            KeyCap::Char(c) if self.shift => c.to_uppercase().collect(),
+            KeyCap::Char(c) => c.to_string(),
+            KeyCap::Shift => tr("keyboard.shift").to_owned(),
+            KeyCap::Backspace => tr("keyboard.backspace").to_owned(),
+            KeyCap::Page(Page::Numbers) => "?123".to_owned(),
+            KeyCap::Page(Page::Letters) => "ABC".to_owned(),
+            KeyCap::Space => tr("keyboard.space").to_owned(),
+            KeyCap::Enter => tr("keyboard.enter").to_owned(),
+            KeyCap::Escape => "Esc".to_owned(),
+        };
+        let font = match key {
+            KeyCap::Char(_) => egui::FontId::proportional(20.0),
+            _ => egui::FontId::proportional(14.0),
+        };
+        let painter = ui.painter();
+        painter.rect(rect, style.corner_radius, style.weak_bg_fill, style.bg_stroke, egui::StrokeKind::Inside);
+        painter.text(rect.center(), egui::Align2::CENTER_CENTER, label, font, style.fg_stroke.color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_type_into_the_focused_field_without_taking_its_focus() {
+        let ctx = egui::Context::default();
+        let field = egui::Id::new("field");
+        let mut keyboard = OnScreenKeyboard::default();
+        let mut text = String::new();
+        let screen = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(800.0, 600.0));
+        let mut run = |keyboard: &mut OnScreenKeyboard, mut raw_input: egui::RawInput| {
+            raw_input.screen_rect = Some(screen);
+            keyboard.process(&mut raw_input, true);
+            let _ = ctx.run(raw_input, |ctx| {
+                keyboard.show(ctx, true);
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    ui.add(egui::TextEdit::singleline(&mut text).id(field));
+                });
+            });
+        };
+
+        ctx.memory_mut(|memory| memory.request_focus(field));
+        run(&mut keyboard, egui::RawInput::default());
+        run(&mut keyboard, egui::RawInput::default());
+        let tap = |keyboard: &OnScreenKeyboard, wanted: KeyCap| {
+            let pos = keyboard.keys.iter().find(|(_, key)| *key == wanted).unwrap().0.center();
+            let button = |pressed| egui::Event::PointerButton { pos, button: egui::PointerButton::Primary, pressed, modifiers: egui::Modifiers::NONE };
+            egui::RawInput { events: vec![egui::Event::PointerMoved(pos), button(true), button(false)], ..Default::default() }
+        };
+        let input = tap(&keyboard, KeyCap::Shift);
+        run(&mut keyboard, input);
+        let input = tap(&keyboard, KeyCap::Char('q'));
+        run(&mut keyboard, input);
+        let input = tap(&keyboard, KeyCap::Char('w'));
+        run(&mut keyboard, input);
+
+        assert!(ctx.memory(|memory| memory.has_focus(field)));
+        let input = tap(&keyboard, KeyCap::Escape);
+        run(&mut keyboard, input);
+        assert!(keyboard.panel.is_none());
+        assert_eq!(text, "Qw");
+    }
+}
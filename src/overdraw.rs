@@ -0,0 +1,195 @@
+use std::ops::Range;
+
+use crate::shaders;
+
+/// Bytes of a vertex in the overdraw view's buffer, only the position is kept.
+const VERTEX_SIZE: u64 = 2 * 4;
+const UNIFORMS_SIZE: u64 = 4 * 4;
+
+struct Pipeline {
+    pipeline: wgpu::RenderPipeline,
+    bind_group: wgpu::BindGroup,
+    uniforms: wgpu::Buffer,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+}
+
+/// A mesh of the frame with the scissor rect of its clip rect, in pixels.
+struct Draw {
+    scissor: [u32; 4],
+    indices: Range<u32>,
+    base_vertex: i32,
+}
+
+/// Draws the UI's primitives with a pipeline of its own that adds them up, so what's drawn over many times shows up
+/// brighter, see `overdraw.wgsl`. It has its own buffers too and leaves the renderer's alone, which still gets the
+/// frame uploaded as usual and can take over again in the next frame. Paint callbacks aren't drawn.
+#[derive(Default)]
+pub struct OverdrawView {
+    /// Made for the format and sample count of the target once it's first needed.
+    pipeline: Option<Pipeline>,
+    vertices: Option<wgpu::Buffer>,
+    indices: Option<wgpu::Buffer>,
+    draws: Vec<Draw>,
+}
+
+impl OverdrawView {
+    /// Uploads `primitives` for [`Self::paint`] into a target of `format` and `sample_count` with the size in
+    /// `screen`.
+    pub fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+        primitives: &[egui::ClippedPrimitive],
+        screen: &egui_wgpu::ScreenDescriptor,
+    ) {
+        if self.pipeline.as_ref().is_none_or(|pipeline| (pipeline.format, pipeline.sample_count) != (format, sample_count)) {
+            self.pipeline = Some(create_pipeline(device, format, sample_count));
+        }
+        let pipeline = self.pipeline.as_ref().unwrap();
+        let [width, height] = screen.size_in_pixels;
+        let ppp = screen.pixels_per_point;
+        let uniforms = [width as f32 / ppp, height as f32 / ppp, 0.0, 0.0];
+        queue.write_buffer(&pipeline.uniforms, 0, &uniforms.iter().flat_map(|value| value.to_ne_bytes()).collect::<Vec<_>>());
+
+        let mut vertices = Vec::<f32>::new();
+        let mut indices = Vec::<u32>::new();
+        self.draws.clear();
+        for primitive in primitives {
+            let egui::epaint::Primitive::Mesh(mesh) = &primitive.primitive else {
+                continue;
+            };
+            // Rounded and clamped the way egui-wgpu does it, so the same pixels are covered.
+            let clip = primitive.clip_rect;
+            let to_pixels = |points: f32, limit: u32| ((points * ppp).round().max(0.0) as u32).min(limit);
+            let min = [to_pixels(clip.min.x, width), to_pixels(clip.min.y, height)];
+            let max = [to_pixels(clip.max.x, width).max(min[0]), to_pixels(clip.max.y, height).max(min[1])];
+            if max[0] == min[0] || max[1] == min[1] || mesh.indices.is_empty() {
+                continue;
+            }
+            let first_index = indices.len() as u32;
+            self.draws.push(Draw {
+                scissor: [min[0], min[1], max[0] - min[0], max[1] - min[1]],
+                indices: first_index..first_index + mesh.indices.len() as u32,
+                base_vertex: (vertices.len() / 2) as i32,
+            });
+            vertices.extend(mesh.vertices.iter().flat_map(|vertex| [vertex.pos.x, vertex.pos.y]));
+            indices.extend_from_slice(&mesh.indices);
+        }
+        let vertices: Vec<u8> = vertices.iter().flat_map(|value| value.to_ne_bytes()).collect();
+        let indices: Vec<u8> = indices.iter().flat_map(|index| index.to_ne_bytes()).collect();
+        write_growing(device, queue, &mut self.vertices, wgpu::BufferUsages::VERTEX, &vertices);
+        write_growing(device, queue, &mut self.indices, wgpu::BufferUsages::INDEX, &indices);
+    }
+
+    /// Draws what was prepared last into `render_pass`, which should start out black to see it best.
+    pub fn paint(&self, render_pass: &mut wgpu::RenderPass<'_>) {
+        let (Some(pipeline), Some(vertices), Some(indices)) = (&self.pipeline, &self.vertices, &self.indices) else {
+            return;
+        };
+        if self.draws.is_empty() {
+            return;
+        }
+        render_pass.set_pipeline(&pipeline.pipeline);
+        render_pass.set_bind_group(0, &pipeline.bind_group, &[]);
+        render_pass.set_vertex_buffer(0, vertices.slice(..));
+        render_pass.set_index_buffer(indices.slice(..), wgpu::IndexFormat::Uint32);
+        for draw in &self.draws {
+            let [x, y, width, height] = draw.scissor;
+            render_pass.set_scissor_rect(x, y, width, height);
+            render_pass.draw_indexed(draw.indices.clone(), draw.base_vertex, 0..1);
+        }
+    }
+}
+
+/// Writes `data` into `buffer`, replacing it first with one twice as big as needed when it's too small.
+fn write_growing(device: &wgpu::Device, queue: &wgpu::Queue, buffer: &mut Option<wgpu::Buffer>, usage: wgpu::BufferUsages, data: &[u8]) {
+    if data.is_empty() {
+        return;
+    }
+    if buffer.as_ref().is_none_or(|buffer| buffer.size() < data.len() as u64) {
+        *buffer = Some(device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Overdraw View"),
+            size: (data.len() as u64 * 2).next_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT),
+            usage: usage | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        }));
+    }
+    queue.write_buffer(buffer.as_ref().unwrap(), 0, data);
+}
+
+fn create_pipeline(device: &wgpu::Device, format: wgpu::TextureFormat, sample_count: u32) -> Pipeline {
+    let label = Some("Overdraw View");
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label,
+        source: wgpu::ShaderSource::Wgsl(shaders::OVERDRAW.source()),
+    });
+    let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label,
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::VERTEX,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: wgpu::BufferSize::new(UNIFORMS_SIZE),
+            },
+            count: None,
+        }],
+    });
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label,
+        bind_group_layouts: &[&bind_group_layout],
+        push_constant_ranges: &[],
+    });
+    // Adds up, the alpha too so the drawn parts of transparent windows are opaque.
+    let additive = wgpu::BlendComponent {
+        src_factor: wgpu::BlendFactor::One,
+        dst_factor: wgpu::BlendFactor::One,
+        operation: wgpu::BlendOperation::Add,
+    };
+    let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label,
+        layout: Some(&layout),
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: Some("vs_main"),
+            compilation_options: Default::default(),
+            buffers: &[wgpu::VertexBufferLayout {
+                array_stride: VERTEX_SIZE,
+                step_mode: wgpu::VertexStepMode::Vertex,
+                attributes: &wgpu::vertex_attr_array![0 => Float32x2],
+            }],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: Some("fs_main"),
+            compilation_options: Default::default(),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState { color: additive, alpha: additive }),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        // egui's meshes don't have a consistent winding.
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState { count: sample_count, ..Default::default() },
+        multiview: None,
+        cache: None,
+    });
+    let uniforms = device.create_buffer(&wgpu::BufferDescriptor {
+        label,
+        size: UNIFORMS_SIZE,
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label,
+        layout: &bind_group_layout,
+        entries: &[wgpu::BindGroupEntry { binding: 0, resource: uniforms.as_entire_binding() }],
+    });
+    Pipeline { pipeline, bind_group, uniforms, format, sample_count }
+}
@@ -0,0 +1,60 @@
+use std::time::{Duration, Instant};
+
+use crate::settings::Settings;
+
+/// Decides when a requested frame may actually be rendered, depending on whether anyone can see the window.
+pub struct FramePacing {
+    occluded: bool,
+    focused: bool,
+    last_frame: Option<Instant>,
+}
+
+impl Default for FramePacing {
+    fn default() -> Self {
+        Self {
+            occluded: false,
+            focused: true,
+            last_frame: None,
+        }
+    }
+}
+
+impl FramePacing {
+    /// When a frame requested for `requested` should be rendered, `None` means not at all for now.
+    /// The frame limiter and the unfocused cap both only ever push frames back, the stricter one wins.
+    pub fn next_frame_at(&self, requested: Instant, settings: &Settings) -> Option<Instant> {
+        let throttled = settings.throttle_in_background && !self.focused;
+        if settings.throttle_in_background && self.occluded {
+            return None;
+        }
+
+        let max_fps = [settings.frame_limit(), throttled.then_some(settings.unfocused_fps)]
+            .into_iter()
+            .flatten()
+            .reduce(f32::min);
+        let Some(max_fps) = max_fps else {
+            return Some(requested);
+        };
+
+        let interval = Duration::from_secs_f32(1.0 / max_fps.max(0.1));
+        Some(self.last_frame.map_or(requested, |last| requested.max(last + interval)))
+    }
+
+    pub fn frame_started(&mut self, now: Instant) {
+        self.last_frame = Some(now);
+    }
+
+    /// Returns true when the window just became visible again and needs a fresh frame.
+    pub fn set_occluded(&mut self, occluded: bool) -> bool {
+        let uncovered = self.occluded && !occluded;
+        self.occluded = occluded;
+        uncovered
+    }
+
+    /// Returns true when the window just gained focus and should drop the throttled frame rate right away.
+    pub fn set_focused(&mut self, focused: bool) -> bool {
+        let gained = !self.focused && focused;
+        self.focused = focused;
+        gained
+    }
+}
@@ -0,0 +1,79 @@
+use crate::i18n::tr;
+
+/// A small window of its own holding a single panel of UI, like the tool palettes of drawing apps. It has no system
+/// title bar but a row of its own with buttons to keep it above other windows and to collapse it to that row, and it
+/// sizes itself to its contents, at most as big as the monitor. Platforms with only one window get an `egui::Window`.
+pub struct Palette {
+    id: egui::ViewportId,
+    title: String,
+    pinned: bool,
+    collapsed: bool,
+    /// What the contents needed in the last pass. The window is asked for it through the viewport builder, which
+    /// only sends an `InnerSize` command when it changes.
+    size: egui::Vec2,
+}
+
+impl Palette {
+    pub fn new(id_salt: &str, title: String) -> Self {
+        Self {
+            id: egui::ViewportId::from_hash_of(("palette", id_salt)),
+            title,
+            pinned: false,
+            collapsed: false,
+            size: egui::vec2(240.0, 160.0),
+        }
+    }
+
+    /// Shows the window with `add_contents` in it, as part of the current pass so the contents can borrow from it.
+    /// Returns false once the user closed it, it's gone when it isn't shown in the next pass.
+    pub fn show(&mut self, ctx: &egui::Context, mut add_contents: impl FnMut(&mut egui::Ui)) -> bool {
+        let level = if self.pinned { egui::WindowLevel::AlwaysOnTop } else { egui::WindowLevel::Normal };
+        let builder = egui::ViewportBuilder::default()
+            .with_title(self.title.clone())
+            .with_decorations(false)
+            .with_resizable(false)
+            .with_window_level(level)
+            .with_inner_size(self.size);
+        ctx.show_viewport_immediate(self.id, builder, |ctx, class| {
+            if class == egui::ViewportClass::Embedded {
+                let mut open = true;
+                egui::Window::new(&self.title)
+                    .id(egui::Id::new(self.id))
+                    .open(&mut open)
+                    .resizable(false)
+                    .show(ctx, |ui| add_contents(ui));
+                return open;
+            }
+
+            // The window is no bigger than the monitor, anything that doesn't fit scrolls.
+            let monitor_size = ctx.input(|i| i.viewport().monitor_size).unwrap_or(egui::Vec2::splat(f32::INFINITY));
+            let mut open = !ctx.input(|i| i.viewport().close_requested());
+            let area = egui::Area::new(egui::Id::new(self.id)).fixed_pos(egui::Pos2::ZERO).show(ctx, |ui| {
+                egui::Frame::window(ui.style()).corner_radius(0.0).shadow(egui::Shadow::NONE).show(ui, |ui| {
+                    // Nothing in here may take all the width there is, or the window could never get narrower.
+                    ui.horizontal(|ui| {
+                        let title = ui.add(egui::Label::new(egui::RichText::new(&self.title).strong()).sense(egui::Sense::drag()));
+                        if title.drag_started() {
+                            ctx.send_viewport_cmd(egui::ViewportCommand::StartDrag);
+                        }
+                        ui.toggle_value(&mut self.pinned, "📌").on_hover_text(tr("palette.pin"));
+                        let (collapse, hint) = if self.collapsed { ("🗖", "palette.expand") } else { ("🗕", "palette.collapse") };
+                        if ui.button(collapse).on_hover_text(tr(hint)).clicked() {
+                            self.collapsed = !self.collapsed;
+                        }
+                        if ui.button("🗙").on_hover_text(tr("palette.close")).clicked() {
+                            open = false;
+                        }
+                    });
+                    if !self.collapsed {
+                        let max_height = monitor_size.y - ui.min_rect().height() - ui.spacing().window_margin.sum().y;
+                        egui::ScrollArea::vertical().max_height(max_height).show(ui, |ui| add_contents(ui));
+                    }
+                });
+            });
+
+            self.size = area.response.rect.size().min(monitor_size).ceil();
+            open
+        })
+    }
+}
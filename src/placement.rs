@@ -0,0 +1,148 @@
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+use winit::monitor::MonitorHandle;
+use winit::window::{Window, WindowAttributes};
+
+use crate::storage::Document;
+
+/// Part of a window that has to stay on some monitor for the placement to count as visible.
+const MIN_VISIBLE: i32 = 64;
+
+/// Where the window was and how big it was, remembered between runs.
+/// Position and size are those of the last non-maximized state, so un-maximizing restores them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindowPlacement {
+    /// `None` on platforms that don't tell us (Wayland), the OS picks a position then.
+    pub position: Option<PhysicalPosition<i32>>,
+    pub size: PhysicalSize<u32>,
+    pub maximized: bool,
+}
+
+impl WindowPlacement {
+    pub fn load(document: &Document) -> Option<Self> {
+        let width = document.get_i64("window", "width")?;
+        let height = document.get_i64("window", "height")?;
+        let position = document.get_i64("window", "x")
+            .zip(document.get_i64("window", "y"))
+            .map(|(x, y)| PhysicalPosition::new(x as i32, y as i32));
+
+        Some(Self {
+            position,
+            size: PhysicalSize::new(width.max(1) as u32, height.max(1) as u32),
+            maximized: document.get_bool("window", "maximized").unwrap_or(false),
+        })
+    }
+
+    pub fn save(&self, document: &mut Document) {
+        if let Some(position) = self.position {
+            document.set("window", "x", position.x as i64);
+            document.set("window", "y", position.y as i64);
+        }
+        document.set("window", "width", self.size.width as i64);
+        document.set("window", "height", self.size.height as i64);
+        document.set("window", "maximized", self.maximized);
+    }
+
+    /// Reads the current placement, `None` while that wouldn't be worth restoring (minimized).
+    pub fn from_window(window: &Window, previous: Option<Self>) -> Option<Self> {
+        if window.is_minimized() == Some(true) {
+            return previous;
+        }
+
+        let maximized = window.is_maximized();
+        match previous {
+            // Keep the restored rect from before maximizing.
+            Some(previous) if maximized => Some(Self { maximized, ..previous }),
+            _ => Some(Self {
+                position: window.outer_position().ok(),
+                size: window.inner_size(),
+                maximized,
+            }),
+        }
+    }
+
+    /// Moves the placement onto one of `monitors` if it isn't visible on any of them anymore,
+    /// e.g. because it was last used on a second monitor that got disconnected, and shrinks it to fit.
+    pub fn clamp_to_monitors(mut self, monitors: &[MonitorHandle], primary: Option<&MonitorHandle>) -> Self {
+        let Some(position) = self.position else {
+            return self;
+        };
+
+        let target = monitors.iter()
+            .find(|monitor| overlaps(monitor, position, self.size))
+            .or(primary)
+            .or(monitors.first());
+        let Some(target) = target else {
+            return self;
+        };
+
+        let monitor_pos = target.position();
+        let monitor_size = target.size();
+        // winit can't tell us about task bars and docks, so leave a little room around the edges.
+        let max_width = (monitor_size.width as f32 * 0.9) as u32;
+        let max_height = (monitor_size.height as f32 * 0.9) as u32;
+        self.size.width = self.size.width.min(max_width).max(1);
+        self.size.height = self.size.height.min(max_height).max(1);
+
+        if !overlaps(target, position, self.size) {
+            self.position = Some(centered(target, self.size));
+        } else {
+            let max_x = monitor_pos.x + monitor_size.width as i32 - self.size.width as i32;
+            let max_y = monitor_pos.y + monitor_size.height as i32 - self.size.height as i32;
+            self.position = Some(PhysicalPosition::new(
+                position.x.clamp(monitor_pos.x, max_x.max(monitor_pos.x)),
+                position.y.clamp(monitor_pos.y, max_y.max(monitor_pos.y)),
+            ));
+        }
+
+        self
+    }
+
+    pub fn apply(&self, mut attributes: WindowAttributes) -> WindowAttributes {
+        attributes = attributes.with_inner_size(self.size).with_maximized(self.maximized);
+        if let Some(position) = self.position {
+            attributes = attributes.with_position(position);
+        }
+        attributes
+    }
+}
+
+/// Moves the window to the center of the monitor after the one it's currently on.
+pub fn move_to_next_monitor(window: &Window) {
+    let monitors: Vec<_> = window.available_monitors().collect();
+    if monitors.len() < 2 {
+        return;
+    }
+
+    let current = window.current_monitor();
+    let index = monitors.iter().position(|monitor| Some(monitor) == current.as_ref()).unwrap_or(0);
+    let next = &monitors[(index + 1) % monitors.len()];
+
+    let was_maximized = window.is_maximized();
+    if was_maximized {
+        window.set_maximized(false);
+    }
+    window.set_outer_position(centered(next, window.outer_size()));
+    if was_maximized {
+        window.set_maximized(true);
+    }
+}
+
+fn centered(monitor: &MonitorHandle, size: PhysicalSize<u32>) -> PhysicalPosition<i32> {
+    let monitor_pos = monitor.position();
+    let monitor_size = monitor.size();
+    PhysicalPosition::new(
+        monitor_pos.x + (monitor_size.width as i32 - size.width as i32) / 2,
+        monitor_pos.y + (monitor_size.height as i32 - size.height as i32) / 2,
+    )
+}
+
+/// Whether at least a [`MIN_VISIBLE`] square of the window rect is on the monitor.
+fn overlaps(monitor: &MonitorHandle, position: PhysicalPosition<i32>, size: PhysicalSize<u32>) -> bool {
+    let monitor_pos = monitor.position();
+    let monitor_size = monitor.size();
+    let left = position.x.max(monitor_pos.x);
+    let right = (position.x + size.width as i32).min(monitor_pos.x + monitor_size.width as i32);
+    let top = position.y.max(monitor_pos.y);
+    let bottom = (position.y + size.height as i32).min(monitor_pos.y + monitor_size.height as i32);
+    right - left >= MIN_VISIBLE && bottom - top >= MIN_VISIBLE
+}
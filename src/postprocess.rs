@@ -0,0 +1,56 @@
+use crate::offscreen::{FullscreenPass, OffscreenTarget};
+use crate::settings::{PostEffect, Settings};
+use crate::shaders;
+#[cfg(feature = "dev")]
+use crate::shaders::ShaderFile;
+
+/// The last pass of a frame that needed an offscreen target, draws it onto the surface with the selected effect.
+pub struct PostProcessor {
+    blit: FullscreenPass,
+    fxaa: FullscreenPass,
+    color_adjust: FullscreenPass,
+}
+
+impl PostProcessor {
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+        Self {
+            blit: FullscreenPass::blit(device, format),
+            fxaa: FullscreenPass::new(device, format, "FXAA", &shaders::FXAA.source()),
+            color_adjust: FullscreenPass::new(device, format, "Color Adjust", &shaders::COLOR_ADJUST.source()),
+        }
+    }
+
+    /// The files the passes are built from, a changed one takes a new post-processor.
+    #[cfg(feature = "dev")]
+    pub const SHADERS: [ShaderFile; 4] = [shaders::FULLSCREEN, shaders::BLIT, shaders::FXAA, shaders::COLOR_ADJUST];
+
+    /// [`Self::new`], but with the shaders' validation errors returned instead of reported as uncaptured errors,
+    /// which would panic. Meant for shaders that were just edited.
+    #[cfg(feature = "dev")]
+    pub fn try_new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Result<Self, String> {
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let post_processor = Self::new(device, format);
+        match pollster::block_on(device.pop_error_scope()) {
+            Some(error) => Err(error.to_string()),
+            None => Ok(post_processor),
+        }
+    }
+
+    /// Draws `source` onto `target`, scaling it if the sizes differ.
+    pub fn run(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        ce: &mut wgpu::CommandEncoder,
+        source: &OffscreenTarget,
+        target: &wgpu::TextureView,
+        settings: &Settings,
+    ) {
+        let (pass, params) = match settings.post_effect {
+            PostEffect::None => (&self.blit, [0.0; 4]),
+            PostEffect::Fxaa => (&self.fxaa, [settings.fxaa_span_max, 1.0 / 8.0, 0.0, 0.0]),
+            PostEffect::ColorAdjust => (&self.color_adjust, [settings.gamma, settings.brightness, 0.0, 0.0]),
+        };
+        pass.draw(device, queue, ce, source, target, settings.scale_filter, params);
+    }
+}
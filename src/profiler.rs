@@ -0,0 +1,107 @@
+//! A minimal frame profiler for the render thread, only compiled in with the `profiling` feature.
+//! Without it [`profile_scope!`] expands to nothing, so release builds don't pay for the scopes.
+
+/// Measures the rest of the enclosing block as a named scope of the current frame.
+macro_rules! profile_scope {
+    ($name:expr) => {
+        #[cfg(feature = "profiling")]
+        let _profile_scope = $crate::profiler::scope($name);
+    };
+}
+pub(crate) use profile_scope;
+
+#[cfg(feature = "profiling")]
+pub use enabled::*;
+
+#[cfg(feature = "profiling")]
+mod enabled {
+    use std::cell::RefCell;
+    use std::collections::VecDeque;
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    /// Frames kept for the profiler window.
+    const HISTORY: usize = 300;
+
+    pub struct ScopeRecord {
+        pub name: &'static str,
+        /// Relative to the start of the frame.
+        pub start: Duration,
+        pub duration: Duration,
+        /// How many scopes this one is nested in.
+        pub depth: usize,
+    }
+
+    pub struct FrameRecord {
+        pub scopes: Vec<ScopeRecord>,
+        /// Until the last scope ended, time spent waiting for the next frame doesn't count.
+        pub duration: Duration,
+    }
+
+    struct State {
+        frame_start: Instant,
+        depth: usize,
+        current: Vec<ScopeRecord>,
+        history: VecDeque<Arc<FrameRecord>>,
+    }
+
+    thread_local! {
+        static STATE: RefCell<State> = RefCell::new(State {
+            frame_start: Instant::now(),
+            depth: 0,
+            current: Vec::new(),
+            history: VecDeque::new(),
+        });
+    }
+
+    pub struct ScopeGuard {
+        index: usize,
+        start: Instant,
+    }
+
+    impl Drop for ScopeGuard {
+        fn drop(&mut self) {
+            STATE.with_borrow_mut(|state| {
+                state.depth = state.depth.saturating_sub(1);
+                // The frame may have ended while the scope was open, its record is gone then.
+                if let Some(record) = state.current.get_mut(self.index) {
+                    record.duration = self.start.elapsed();
+                }
+            });
+        }
+    }
+
+    pub fn scope(name: &'static str) -> ScopeGuard {
+        let start = Instant::now();
+        STATE.with_borrow_mut(|state| {
+            state.current.push(ScopeRecord {
+                name,
+                start: start.saturating_duration_since(state.frame_start),
+                duration: Duration::ZERO,
+                depth: state.depth,
+            });
+            state.depth += 1;
+            ScopeGuard { index: state.current.len() - 1, start }
+        })
+    }
+
+    /// Ends the current frame and starts the next one, call once per frame.
+    pub fn new_frame() {
+        let now = Instant::now();
+        STATE.with_borrow_mut(|state| {
+            let scopes = std::mem::take(&mut state.current);
+            let duration = scopes.iter().map(|scope| scope.start + scope.duration).max().unwrap_or_default();
+            let frame = FrameRecord { scopes, duration };
+            if state.history.len() == HISTORY {
+                state.history.pop_front();
+            }
+            state.history.push_back(Arc::new(frame));
+            state.frame_start = now;
+        });
+    }
+
+    /// The recorded frames of this thread, oldest first.
+    pub fn frames() -> Vec<Arc<FrameRecord>> {
+        STATE.with_borrow(|state| state.history.iter().cloned().collect())
+    }
+}
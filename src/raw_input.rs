@@ -0,0 +1,19 @@
+use winit::event::{DeviceEvent, WindowEvent};
+
+/// An event of the main window egui didn't consume, or a raw device event, which egui never sees.
+#[derive(Debug, Clone, Copy)]
+pub enum RawInput<'a> {
+    Window(&'a WindowEvent),
+    // The demo scene only needs the keyboard.
+    #[allow(dead_code)]
+    Device(&'a DeviceEvent),
+}
+
+/// Lets app code outside the UI, like an embedded game or visualization, react to input.
+///
+/// egui always gets an event first. Keyboard input only gets here while no widget has the focus, pointer input only
+/// while the pointer isn't over the UI. Handlers are asked in order until one of them uses the event.
+pub trait RawInputHandler {
+    /// Returns true if the event was used, which also asks for a repaint.
+    fn on_raw_input(&mut self, input: RawInput<'_>) -> bool;
+}
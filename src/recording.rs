@@ -0,0 +1,272 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::capture::{CapturedFrame, FrameReadback};
+
+/// Frames waiting for the writer thread, together with the readback buffers this bounds the memory a recording takes.
+const QUEUED_FRAMES: usize = 4;
+const READBACK_BUFFERS: usize = 3;
+/// GIFs are recorded at a lower rate and resolution, they get huge otherwise.
+const GIF_FRAME_INTERVAL: Duration = Duration::from_millis(66);
+const GIF_MAX_WIDTH: u32 = 640;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum RecordingFormat {
+    /// Numbered PNG files in a folder, every presented frame.
+    #[default]
+    PngSequence,
+    Gif,
+}
+
+/// Captures presented frames and writes them out on a separate thread until it's stopped.
+pub struct Recording {
+    format: RecordingFormat,
+    path: PathBuf,
+    started: Instant,
+    last_capture: Option<Instant>,
+    readback: FrameReadback,
+    sender: SyncSender<CapturedFrame>,
+    writer: thread::JoinHandle<()>,
+    dropped: u32,
+}
+
+impl Recording {
+    /// Starts writing to a new file or folder with a timestamped name in `dir`.
+    pub fn start(format: RecordingFormat, dir: &Path) -> io::Result<Self> {
+        let name = format!("recording-{}", std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs());
+        let path = match format {
+            RecordingFormat::PngSequence => dir.join(name),
+            RecordingFormat::Gif => dir.join(name).with_extension("gif"),
+        };
+
+        let (sender, receiver) = mpsc::sync_channel(QUEUED_FRAMES);
+        let writer: Box<dyn FrameWriter> = match format {
+            RecordingFormat::PngSequence => {
+                fs::create_dir_all(&path)?;
+                Box::new(PngSequenceWriter { dir: path.clone(), next_index: 0 })
+            },
+            RecordingFormat::Gif => {
+                fs::create_dir_all(dir)?;
+                Box::new(GifWriter::new(BufWriter::new(File::create(&path)?)))
+            },
+        };
+        let writer_path = path.clone();
+        let writer = thread::Builder::new()
+            .name("Recording Writer".into())
+            .spawn(move || write_frames(writer, receiver, &writer_path))?;
+
+        log::info!("Recording to {}", path.display());
+        Ok(Self {
+            format,
+            path,
+            started: Instant::now(),
+            last_capture: None,
+            readback: FrameReadback::new(READBACK_BUFFERS),
+            sender,
+            writer,
+            dropped: 0,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Frames that were skipped because the GPU or the writer couldn't keep up.
+    pub fn dropped(&self) -> u32 {
+        self.dropped
+    }
+
+    /// Copies `texture` into `ce` if this frame should be part of the recording.
+    pub fn capture(&mut self, device: &wgpu::Device, ce: &mut wgpu::CommandEncoder, texture: &wgpu::Texture, now: Instant) {
+        if self.format == RecordingFormat::Gif && self.last_capture.is_some_and(|last| now < last + GIF_FRAME_INTERVAL) {
+            return;
+        }
+        if self.readback.copy(device, ce, texture, now) {
+            self.last_capture = Some(now);
+        } else {
+            self.dropped += 1;
+        }
+    }
+
+    /// Has to be called after every submit, passes finished frames on to the writer without blocking.
+    /// Stops the recording and waits until `deadline` for the file to be finalized, for when the process is about to end
+    /// and would take the writer thread with it. Dropping the recording finalizes it in the background instead.
+    pub fn finish(self, deadline: Instant) {
+        let Self { path, sender, writer, .. } = self;
+        drop(sender);
+        if crate::tasks::join_until(vec![writer], deadline) > 0 {
+            log::warn!("Gave up waiting for the recording {} to be written", path.display());
+        }
+    }
+
+    pub fn submitted(&mut self, device: &wgpu::Device) {
+        self.readback.submitted();
+        for frame in self.readback.poll(device) {
+            match self.sender.try_send(frame) {
+                Ok(()) => {},
+                Err(TrySendError::Full(_)) => self.dropped += 1,
+                // The writer gave up after an error, which it already logged.
+                Err(TrySendError::Disconnected(_)) => self.dropped += 1,
+            }
+        }
+    }
+}
+
+trait FrameWriter: Send {
+    fn write(&mut self, frame: &CapturedFrame) -> io::Result<()>;
+    fn finish(&mut self) -> io::Result<()>;
+}
+
+/// Runs until the [`Recording`] is dropped, which closes the channel and finalizes the output.
+fn write_frames(mut writer: Box<dyn FrameWriter>, receiver: Receiver<CapturedFrame>, path: &Path) {
+    let result = receiver.iter().try_for_each(|frame| writer.write(&frame)).and_then(|()| writer.finish());
+    match result {
+        Ok(()) => log::info!("Saved the recording to {}", path.display()),
+        Err(error) => log::error!("Failed to write the recording to {}: {error}", path.display()),
+    }
+}
+
+struct PngSequenceWriter {
+    dir: PathBuf,
+    next_index: u32,
+}
+
+impl FrameWriter for PngSequenceWriter {
+    fn write(&mut self, frame: &CapturedFrame) -> io::Result<()> {
+        let path = self.dir.join(format!("frame-{:05}.png", self.next_index));
+        self.next_index += 1;
+
+        let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), frame.size[0], frame.size[1]);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        // Encoding has to keep up with the frame rate, the files being a bit bigger is fine.
+        encoder.set_compression(png::Compression::Fast);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&frame.rgba)?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Writes an animated GIF with a fixed 6×7×6 color cube palette, which is fast and good enough for UI recordings.
+struct GifWriter<W: Write> {
+    out: W,
+    /// Size of the GIF, set from the first frame. Frames are downscaled by `step` to get there.
+    size: Option<([u16; 2], u32)>,
+    /// Frames are written one behind, their delay is only known once the next one arrives.
+    pending: Option<(Vec<u8>, Instant)>,
+}
+
+impl<W: Write + Send> GifWriter<W> {
+    fn new(out: W) -> Self {
+        Self {
+            out,
+            size: None,
+            pending: None,
+        }
+    }
+
+    fn write_header(&mut self, size: [u16; 2]) -> io::Result<()> {
+        self.out.write_all(b"GIF89a")?;
+        self.out.write_all(&size[0].to_le_bytes())?;
+        self.out.write_all(&size[1].to_le_bytes())?;
+        // Global color table with 256 entries, no background color or aspect ratio.
+        self.out.write_all(&[0xF7, 0, 0])?;
+        for index in 0..=255u8 {
+            self.out.write_all(&palette_color(index))?;
+        }
+        // Loop forever.
+        self.out.write_all(b"\x21\xFF\x0BNETSCAPE2.0\x03\x01\x00\x00\x00")
+    }
+
+    fn write_frame(&mut self, indices: &[u8], delay: Duration) -> io::Result<()> {
+        let [width, height] = self.size.unwrap().0;
+        let delay = (delay.as_millis() / 10).clamp(2, u16::MAX as u128) as u16;
+        self.out.write_all(&[0x21, 0xF9, 0x04, 0x00])?;
+        self.out.write_all(&delay.to_le_bytes())?;
+        self.out.write_all(&[0x00, 0x00])?;
+
+        self.out.write_all(&[0x2C, 0, 0, 0, 0])?;
+        self.out.write_all(&width.to_le_bytes())?;
+        self.out.write_all(&height.to_le_bytes())?;
+        self.out.write_all(&[0x00])?;
+
+        let data = weezl::encode::Encoder::new(weezl::BitOrder::Lsb, 8)
+            .encode(indices)
+            .map_err(|error| io::Error::other(format!("{error:?}")))?;
+        self.out.write_all(&[8])?;
+        for block in data.chunks(255) {
+            self.out.write_all(&[block.len() as u8])?;
+            self.out.write_all(block)?;
+        }
+        self.out.write_all(&[0])
+    }
+}
+
+impl<W: Write + Send> FrameWriter for GifWriter<W> {
+    fn write(&mut self, frame: &CapturedFrame) -> io::Result<()> {
+        let [width, height] = frame.size;
+        let (size, step) = match self.size {
+            Some(size) => size,
+            None => {
+                let step = width.div_ceil(GIF_MAX_WIDTH).max(1);
+                let size = [(width / step).min(u16::MAX as u32) as u16, (height / step).min(u16::MAX as u32) as u16];
+                self.write_header(size)?;
+                *self.size.insert((size, step))
+            },
+        };
+        if [width / step, height / step] != size.map(u32::from) {
+            // A GIF can't change its size, frames after a resize are left out.
+            return Ok(());
+        }
+
+        let mut indices = Vec::with_capacity(size[0] as usize * size[1] as usize);
+        for y in 0..size[1] as u32 {
+            for x in 0..size[0] as u32 {
+                let offset = ((y * step * width + x * step) * 4) as usize;
+                let [r, g, b] = [frame.rgba[offset], frame.rgba[offset + 1], frame.rgba[offset + 2]];
+                indices.push(palette_index(r, g, b));
+            }
+        }
+
+        if let Some((previous, time)) = self.pending.replace((indices, frame.time)) {
+            self.write_frame(&previous, frame.time - time)?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if let Some((indices, _)) = self.pending.take() {
+            self.write_frame(&indices, GIF_FRAME_INTERVAL)?;
+        }
+        if self.size.is_some() {
+            self.out.write_all(&[0x3B])?;
+        }
+        self.out.flush()
+    }
+}
+
+/// Index into the color cube, 6 levels of red and blue and 7 of green.
+fn palette_index(r: u8, g: u8, b: u8) -> u8 {
+    let level = |value: u8, levels: u32| (value as u32 * (levels - 1) + 127) / 255;
+    (level(r, 6) * 42 + level(g, 7) * 6 + level(b, 6)) as u8
+}
+
+fn palette_color(index: u8) -> [u8; 3] {
+    // The 4 entries after the 252 of the cube are unused.
+    let index = (index as u32).min(251);
+    let value = |level: u32, levels: u32| (level * 255 / (levels - 1)) as u8;
+    [value(index / 42, 6), value(index / 6 % 7, 7), value(index % 6, 6)]
+}
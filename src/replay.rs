@@ -0,0 +1,309 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::storage::{self, Value};
+
+const HEADER: &str = "# egui input recording v1";
+
+/// Input of one frame, only the parts that drive the UI.
+pub struct RecordedFrame {
+    /// Seconds since the recording started.
+    time: f64,
+    screen_rect: Option<egui::Rect>,
+    modifiers: egui::Modifiers,
+    focused: bool,
+    events: Vec<egui::Event>,
+}
+
+/// Records the `egui::RawInput` of every frame, or plays a recording back in place of the real input.
+#[derive(Default)]
+pub enum InputReplay {
+    #[default]
+    Idle,
+    Recording {
+        started: Instant,
+        frames: Vec<RecordedFrame>,
+        /// Events that can't be written, like touches and IME, are left out.
+        skipped_events: usize,
+    },
+    Playing {
+        started: Instant,
+        frames: Vec<RecordedFrame>,
+        next: usize,
+        /// The recorded screen size is kept and centered in the window, so positions keep matching the widgets.
+        screen_rect: Option<egui::Rect>,
+        warned_about_size: bool,
+    },
+}
+
+impl InputReplay {
+    pub fn is_idle(&self) -> bool {
+        matches!(self, Self::Idle)
+    }
+
+    pub fn is_recording(&self) -> bool {
+        matches!(self, Self::Recording { .. })
+    }
+
+    pub fn start_recording(&mut self) {
+        *self = Self::Recording {
+            started: Instant::now(),
+            frames: Vec::new(),
+            skipped_events: 0,
+        };
+    }
+
+    pub fn start_playback(&mut self, path: &Path) -> io::Result<()> {
+        let text = fs::read_to_string(path)?;
+        let frames = parse(&text).map_err(|(line, message)| io::Error::new(io::ErrorKind::InvalidData, format!("line {line}: {message}")))?;
+        log::info!("Playing back {} frames of input from {}", frames.len(), path.display());
+        *self = Self::Playing {
+            started: Instant::now(),
+            frames,
+            next: 0,
+            screen_rect: None,
+            warned_about_size: false,
+        };
+        Ok(())
+    }
+
+    /// Ends recording or playback, a recording is written to `path`.
+    pub fn stop(&mut self, path: &Path) -> io::Result<()> {
+        if let Self::Recording { frames, skipped_events, .. } = std::mem::take(self) {
+            if skipped_events > 0 {
+                log::warn!("{skipped_events} input events couldn't be recorded");
+            }
+            storage::write_atomic(path, &serialize(&frames))?;
+            log::info!("Recorded {} frames of input to {}", frames.len(), path.display());
+        }
+        Ok(())
+    }
+
+    /// Records `raw_input`, or replaces it with the recorded input that's due by now.
+    /// While playing back, returns how long until the next recorded frame is due.
+    pub fn process(&mut self, raw_input: &mut egui::RawInput) -> Option<Duration> {
+        match self {
+            Self::Idle => None,
+            Self::Recording { started, frames, skipped_events } => {
+                let events: Vec<_> = raw_input.events.iter().filter(|event| is_recordable(event)).cloned().collect();
+                *skipped_events += raw_input.events.len() - events.len();
+                frames.push(RecordedFrame {
+                    time: started.elapsed().as_secs_f64(),
+                    screen_rect: raw_input.screen_rect,
+                    modifiers: raw_input.modifiers,
+                    focused: raw_input.focused,
+                    events,
+                });
+                None
+            },
+            Self::Playing { started, frames, next, screen_rect, warned_about_size } => {
+                // Real input is dropped entirely, only the recording drives the UI.
+                raw_input.events.clear();
+                let actual = raw_input.screen_rect;
+                let elapsed = started.elapsed().as_secs_f64();
+                while let Some(frame) = frames.get(*next).filter(|frame| frame.time <= elapsed) {
+                    *screen_rect = frame.screen_rect.or(*screen_rect);
+                    let offset = letterbox_offset(*screen_rect, actual);
+                    raw_input.modifiers = frame.modifiers;
+                    raw_input.focused = frame.focused;
+                    raw_input.events.extend(frame.events.iter().map(|event| translated(event, offset)));
+                    *next += 1;
+                }
+
+                if let (Some(recorded), Some(actual)) = (*screen_rect, actual) {
+                    if (actual.width() < recorded.width() || actual.height() < recorded.height()) && !*warned_about_size {
+                        log::warn!("The window is smaller than during the recording, some input may miss its target");
+                        *warned_about_size = true;
+                    }
+                    let offset = letterbox_offset(Some(recorded), Some(actual));
+                    raw_input.screen_rect = Some(egui::Rect::from_min_size(recorded.min + offset, recorded.size()));
+                }
+
+                match frames.get(*next) {
+                    Some(frame) => Some(Duration::from_secs_f64((frame.time - elapsed).max(0.0))),
+                    None => {
+                        log::info!("Input playback finished");
+                        *self = Self::Idle;
+                        None
+                    },
+                }
+            },
+        }
+    }
+}
+
+fn is_recordable(event: &egui::Event) -> bool {
+    use egui::Event;
+    matches!(
+        event,
+        Event::Copy
+            | Event::Cut
+            | Event::Paste(_)
+            | Event::Text(_)
+            | Event::Key { .. }
+            | Event::PointerMoved(_)
+            | Event::MouseMoved(_)
+            | Event::PointerButton { .. }
+            | Event::PointerGone
+            | Event::Zoom(_)
+            | Event::MouseWheel { .. }
+            | Event::WindowFocused(_)
+    )
+}
+
+/// How far the recorded screen has to be moved to be centered in the actual one, it's never moved out to the top left.
+fn letterbox_offset(recorded: Option<egui::Rect>, actual: Option<egui::Rect>) -> egui::Vec2 {
+    match (recorded, actual) {
+        (Some(recorded), Some(actual)) => ((actual.size() - recorded.size()) / 2.0).max(egui::Vec2::ZERO),
+        _ => egui::Vec2::ZERO,
+    }
+}
+
+fn translated(event: &egui::Event, offset: egui::Vec2) -> egui::Event {
+    match event {
+        egui::Event::PointerMoved(pos) => egui::Event::PointerMoved(*pos + offset),
+        egui::Event::PointerButton { pos, button, pressed, modifiers } => egui::Event::PointerButton {
+            pos: *pos + offset,
+            button: *button,
+            pressed: *pressed,
+            modifiers: *modifiers,
+        },
+        event => event.clone(),
+    }
+}
+
+fn modifiers_to_bits(modifiers: egui::Modifiers) -> u8 {
+    [modifiers.alt, modifiers.ctrl, modifiers.shift, modifiers.mac_cmd, modifiers.command]
+        .into_iter()
+        .enumerate()
+        .fold(0, |bits, (i, set)| bits | ((set as u8) << i))
+}
+
+fn modifiers_from_bits(bits: u8) -> egui::Modifiers {
+    let bit = |i: u8| bits & (1 << i) != 0;
+    egui::Modifiers { alt: bit(0), ctrl: bit(1), shift: bit(2), mac_cmd: bit(3), command: bit(4) }
+}
+
+const BUTTONS: [egui::PointerButton; 5] = [
+    egui::PointerButton::Primary,
+    egui::PointerButton::Secondary,
+    egui::PointerButton::Middle,
+    egui::PointerButton::Extra1,
+    egui::PointerButton::Extra2,
+];
+
+const WHEEL_UNITS: [egui::MouseWheelUnit; 3] = [egui::MouseWheelUnit::Point, egui::MouseWheelUnit::Line, egui::MouseWheelUnit::Page];
+
+/// One line per frame starting with `frame`, each followed by one line per event.
+fn serialize(frames: &[RecordedFrame]) -> String {
+    let mut text = format!("{HEADER}\n");
+    for frame in frames {
+        let rect = frame.screen_rect.map_or("-".to_owned(), |rect| format!("{} {} {} {}", rect.min.x, rect.min.y, rect.max.x, rect.max.y));
+        let _ = writeln!(text, "frame {} {} {} {rect}", frame.time, modifiers_to_bits(frame.modifiers), frame.focused as u8);
+
+        for event in &frame.events {
+            let _ = match event {
+                egui::Event::Copy => writeln!(text, "copy"),
+                egui::Event::Cut => writeln!(text, "cut"),
+                egui::Event::Paste(value) => writeln!(text, "paste {}", Value::from(value.as_str())),
+                egui::Event::Text(value) => writeln!(text, "text {}", Value::from(value.as_str())),
+                egui::Event::Key { key, pressed, repeat, modifiers, .. } => {
+                    writeln!(text, "key {} {} {} {}", key.name(), *pressed as u8, *repeat as u8, modifiers_to_bits(*modifiers))
+                },
+                egui::Event::PointerMoved(pos) => writeln!(text, "pointer_moved {} {}", pos.x, pos.y),
+                egui::Event::MouseMoved(delta) => writeln!(text, "mouse_moved {} {}", delta.x, delta.y),
+                egui::Event::PointerButton { pos, button, pressed, modifiers } => {
+                    let button = BUTTONS.iter().position(|b| b == button).unwrap_or(0);
+                    writeln!(text, "pointer_button {} {} {button} {} {}", pos.x, pos.y, *pressed as u8, modifiers_to_bits(*modifiers))
+                },
+                egui::Event::PointerGone => writeln!(text, "pointer_gone"),
+                egui::Event::Zoom(factor) => writeln!(text, "zoom {factor}"),
+                egui::Event::MouseWheel { unit, delta, modifiers } => {
+                    let unit = WHEEL_UNITS.iter().position(|u| u == unit).unwrap_or(0);
+                    writeln!(text, "wheel {unit} {} {} {}", delta.x, delta.y, modifiers_to_bits(*modifiers))
+                },
+                egui::Event::WindowFocused(focused) => writeln!(text, "focused {}", *focused as u8),
+                _ => Ok(()),
+            };
+        }
+    }
+    text
+}
+
+/// Returns the line number and what's wrong on errors.
+fn parse(text: &str) -> Result<Vec<RecordedFrame>, (usize, String)> {
+    let mut frames: Vec<RecordedFrame> = Vec::new();
+
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let error = |message: &str| (i + 1, message.to_owned());
+        let (kind, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let args: Vec<&str> = rest.split_whitespace().collect();
+        let num = |index: usize| -> Result<f32, (usize, String)> {
+            args.get(index).and_then(|arg| arg.parse().ok()).ok_or_else(|| error("expected a number"))
+        };
+        let flag = |index: usize| num(index).map(|value| value != 0.0);
+        let modifiers = |index: usize| num(index).map(|value| modifiers_from_bits(value as u8));
+        let string = || match Value::parse(rest) {
+            Some(Value::String(value)) => Ok(value),
+            _ => Err(error("expected a string")),
+        };
+
+        if kind == "frame" {
+            let time = args.first().and_then(|arg| arg.parse().ok()).ok_or_else(|| error("expected a time"))?;
+            let screen_rect = if args.get(3) == Some(&"-") {
+                None
+            } else {
+                Some(egui::Rect::from_min_max(egui::pos2(num(3)?, num(4)?), egui::pos2(num(5)?, num(6)?)))
+            };
+            frames.push(RecordedFrame {
+                time,
+                screen_rect,
+                modifiers: modifiers(1)?,
+                focused: flag(2)?,
+                events: Vec::new(),
+            });
+            continue;
+        }
+
+        let event = match kind {
+            "copy" => egui::Event::Copy,
+            "cut" => egui::Event::Cut,
+            "paste" => egui::Event::Paste(string()?),
+            "text" => egui::Event::Text(string()?),
+            "key" => egui::Event::Key {
+                key: args.first().and_then(|name| egui::Key::from_name(name)).ok_or_else(|| error("unknown key"))?,
+                physical_key: None,
+                pressed: flag(1)?,
+                repeat: flag(2)?,
+                modifiers: modifiers(3)?,
+            },
+            "pointer_moved" => egui::Event::PointerMoved(egui::pos2(num(0)?, num(1)?)),
+            "mouse_moved" => egui::Event::MouseMoved(egui::vec2(num(0)?, num(1)?)),
+            "pointer_button" => egui::Event::PointerButton {
+                pos: egui::pos2(num(0)?, num(1)?),
+                button: *BUTTONS.get(num(2)? as usize).ok_or_else(|| error("unknown button"))?,
+                pressed: flag(3)?,
+                modifiers: modifiers(4)?,
+            },
+            "pointer_gone" => egui::Event::PointerGone,
+            "zoom" => egui::Event::Zoom(num(0)?),
+            "wheel" => egui::Event::MouseWheel {
+                unit: *WHEEL_UNITS.get(num(0)? as usize).ok_or_else(|| error("unknown wheel unit"))?,
+                delta: egui::vec2(num(1)?, num(2)?),
+                modifiers: modifiers(3)?,
+            },
+            "focused" => egui::Event::WindowFocused(flag(0)?),
+            _ => return Err(error("unknown event")),
+        };
+        frames.last_mut().ok_or_else(|| error("event before the first frame"))?.events.push(event);
+    }
+
+    Ok(frames)
+}
@@ -0,0 +1,84 @@
+use std::fmt;
+use std::path::Path;
+
+use crate::json::Json;
+use crate::settings::Settings;
+use crate::ui::WidgetGallery;
+
+/// Bumped whenever the format changes in a way older builds can't read. Files of older versions are migrated on load.
+const VERSION: u32 = 1;
+
+/// The demo state as saved to a file with File → Save As…
+pub struct SaveFile {
+    gallery: Json,
+    settings: Json,
+}
+
+impl SaveFile {
+    pub fn new(gallery: &WidgetGallery, settings: &Settings) -> Self {
+        Self {
+            gallery: gallery.to_json(),
+            settings: settings.to_json(),
+        }
+    }
+
+    /// What's wrong with the file is returned as an error, to be shown to the user, and nothing is applied then.
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|error| error.to_string())?;
+        Self::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let json = Json::parse(text).map_err(|error| format!("Not a valid JSON file: {error}"))?;
+        let version = json.get("version").and_then(Json::as_f64).ok_or("Not a demo save file")?;
+        if version > VERSION as f64 {
+            return Err(format!("Saved by a newer version of the app (file version {version}, supported up to {VERSION})"));
+        }
+        // Every version so far only added keys, which are left at their defaults when missing.
+        let section = |name| json.get(name).cloned().unwrap_or(Json::Object(Default::default()));
+        Ok(Self {
+            gallery: section("gallery"),
+            settings: section("settings"),
+        })
+    }
+
+    pub fn apply(&self, gallery: &mut WidgetGallery, settings: &mut Settings) {
+        gallery.load_json(&self.gallery);
+        *settings = Settings::from_json(&self.settings);
+    }
+}
+
+impl fmt::Display for SaveFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let json: Json = [
+            ("version", VERSION.into()),
+            ("gallery", self.gallery.clone()),
+            ("settings", self.settings.clone()),
+        ].into_iter().collect();
+        writeln!(f, "{json}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn corrupt_files_are_errors_and_wrong_values_fall_back_to_the_defaults() {
+        let path = std::env::temp_dir().join(format!("egui_wgpu_native-corrupt-{}.json", std::process::id()));
+        std::fs::write(&path, "{\"version\": 1, \"gallery\": {\"name\": \"cut off").unwrap();
+        let error = SaveFile::read(&path).err();
+        let _ = std::fs::remove_file(&path);
+        assert!(error.as_deref().is_some_and(|error| error.starts_with("Not a valid JSON file")), "{error:?}");
+        assert!(SaveFile::read(&path).is_err(), "a missing file is an error too");
+        assert_eq!(SaveFile::parse("[1, 2]").err().as_deref(), Some("Not a demo save file"));
+        assert!(SaveFile::parse("{\"version\": 2}").is_err());
+
+        // Values of the wrong kind are skipped, the defaults stay.
+        let file = SaveFile::parse("{\"version\": 1, \"settings\": {\"zoom_factor\": \"big\", \"resizable\": 0, \"title\": \"Kept\"}}").unwrap();
+        let mut gallery = WidgetGallery::default();
+        let mut settings = Settings { zoom_factor: 2.0, ..Settings::default() };
+        file.apply(&mut gallery, &mut settings);
+        assert_eq!(settings, Settings { title: "Kept".to_owned(), ..Settings::default() });
+    }
+}
@@ -0,0 +1,56 @@
+use winit::event::WindowEvent;
+use winit::keyboard::{Key, NamedKey};
+
+use crate::motion::MotionPrefs;
+use crate::raw_input::{RawInput, RawInputHandler};
+
+/// Radians per second.
+const SPIN_SPEED: f32 = 1.2;
+
+/// A triangle spinning behind the UI, standing in for what an app would render besides it. Space pauses it, and it
+/// stands still with reduced motion.
+pub struct Scene {
+    animating: bool,
+    angle: f32,
+}
+
+impl Default for Scene {
+    fn default() -> Self {
+        Self { animating: true, angle: 0.0 }
+    }
+}
+
+impl Scene {
+    /// Paints into the background of `ui`, so it has to come before the widgets.
+    pub fn paint(&mut self, ui: &egui::Ui) {
+        if self.animating && !MotionPrefs::get(ui.ctx()).reduce_motion {
+            self.angle = (self.angle + ui.input(|i| i.stable_dt) * SPIN_SPEED) % std::f32::consts::TAU;
+            ui.ctx().request_repaint();
+        }
+
+        let rect = ui.max_rect();
+        let radius = rect.width().min(rect.height()) * 0.3;
+        let corners = (0..3)
+            .map(|i| {
+                let angle = self.angle + i as f32 * std::f32::consts::TAU / 3.0;
+                rect.center() + radius * egui::vec2(angle.cos(), angle.sin())
+            })
+            .collect();
+        let color = ui.visuals().faint_bg_color;
+        ui.painter().add(egui::Shape::convex_polygon(corners, color, egui::Stroke::new(1.0, ui.visuals().weak_text_color())));
+    }
+}
+
+impl RawInputHandler for Scene {
+    fn on_raw_input(&mut self, input: RawInput<'_>) -> bool {
+        match input {
+            RawInput::Window(WindowEvent::KeyboardInput { event, .. })
+                if event.state.is_pressed() && !event.repeat && event.logical_key == Key::Named(NamedKey::Space) =>
+            {
+                self.animating = !self.animating;
+                true
+            },
+            _ => false,
+        }
+    }
+}
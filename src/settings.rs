@@ -0,0 +1,380 @@
+use std::time::Duration;
+
+use crate::i18n::Language;
+use crate::json::{json_enum, FromJson, Json};
+
+/// How the UI is stretched onto the window when the render scale isn't 1.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ScaleFilter {
+    #[default]
+    Linear,
+    Nearest,
+}
+
+/// Full-screen effect applied to the UI before it's shown.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum PostEffect {
+    #[default]
+    None,
+    Fxaa,
+    ColorAdjust,
+}
+
+json_enum!(ScaleFilter { Linear => "linear", Nearest => "nearest" });
+json_enum!(PostEffect { None => "none", Fxaa => "fxaa", ColorAdjust => "color_adjust" });
+json_enum!(PixelSnap { Off => "off", Quarter => "quarter", Whole => "whole" });
+json_enum!(Theme { System => "system", Light => "light", Dark => "dark" });
+json_enum!(TitleSuffix { None => "none", Fps => "fps", UnsavedMarker => "unsaved_marker" });
+json_enum!(Motion { System => "system", Reduced => "reduced", Full => "full" });
+json_enum!(WindowLevel { Normal => "normal", AlwaysOnTop => "always_on_top", AlwaysOnBottom => "always_on_bottom" });
+
+/// What is appended to the window title.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TitleSuffix {
+    None,
+    Fps,
+    /// A `*` while there are unsaved changes.
+    #[default]
+    UnsavedMarker,
+}
+
+/// Where the window is stacked relative to other windows.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum WindowLevel {
+    #[default]
+    Normal,
+    AlwaysOnTop,
+    /// Below all other windows, e.g. for a widget on the desktop.
+    AlwaysOnBottom,
+}
+
+impl WindowLevel {
+    pub fn winit(self) -> winit::window::WindowLevel {
+        match self {
+            WindowLevel::Normal => winit::window::WindowLevel::Normal,
+            WindowLevel::AlwaysOnTop => winit::window::WindowLevel::AlwaysOnTop,
+            WindowLevel::AlwaysOnBottom => winit::window::WindowLevel::AlwaysOnBottom,
+        }
+    }
+}
+
+/// What the pixels per point of the UI are rounded to, so thin lines land on whole pixels at fractional display scales.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum PixelSnap {
+    #[default]
+    Off,
+    /// Steps of 0.25, keeps the common 1.25× and 1.5× but not scales that come out of zooming, like 1.375×.
+    Quarter,
+    /// Whole pixels per point, 1.25× is drawn at 1× and 1.5× at 2×.
+    Whole,
+}
+
+impl PixelSnap {
+    /// The zoom factor closest to `zoom_factor` that makes the pixels per point a multiple of the quantum with
+    /// `round`, on a display with `native_pixels_per_point`. It never gets below one quantum.
+    pub fn zoom_factor(self, zoom_factor: f32, native_pixels_per_point: f32, round: fn(f32) -> f32) -> f32 {
+        let quantum = match self {
+            PixelSnap::Off => return zoom_factor,
+            PixelSnap::Quarter => 0.25,
+            PixelSnap::Whole => 1.0,
+        };
+        let pixels_per_point = (round(zoom_factor * native_pixels_per_point / quantum) * quantum).max(quantum);
+        pixels_per_point / native_pixels_per_point
+    }
+}
+
+/// Whether animations are shown, see `MotionPrefs`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Motion {
+    /// Follows the reduced motion setting of the system, full motion where there's none.
+    #[default]
+    System,
+    Reduced,
+    Full,
+}
+
+impl Motion {
+    /// `system` is what the system asks for, if it says.
+    pub fn reduced(self, system: Option<bool>) -> bool {
+        match self {
+            Motion::System => system.unwrap_or(false),
+            Motion::Reduced => true,
+            Motion::Full => false,
+        }
+    }
+}
+
+/// Color scheme of the UI.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Theme {
+    /// Follows the dark mode setting of the system.
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+impl Theme {
+    pub fn preference(self) -> egui::ThemePreference {
+        match self {
+            Theme::System => egui::ThemePreference::System,
+            Theme::Light => egui::ThemePreference::Light,
+            Theme::Dark => egui::ThemePreference::Dark,
+        }
+    }
+}
+
+/// Everything the user can change in the settings window.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    /// Window title, without the suffix.
+    pub title: String,
+    pub title_suffix: TitleSuffix,
+    pub window_level: WindowLevel,
+    /// Leaves the window out of the taskbar, only supported on Windows.
+    pub skip_taskbar: bool,
+    /// Closing the window hides it to the tray icon instead of quitting, only with the `tray` feature on Windows.
+    pub close_to_tray: bool,
+    /// Starts hidden to the tray icon, like `close_to_tray`.
+    pub start_in_tray: bool,
+    pub resizable: bool,
+    /// Saves what persists between runs every `autosave_seconds` while it changes, so a crash doesn't lose it.
+    pub autosave: bool,
+    pub autosave_seconds: u32,
+    /// Stop rendering while the window is hidden and cap the frame rate while it's unfocused.
+    /// Opt-out for apps that need to keep rendering at full rate in the background.
+    pub throttle_in_background: bool,
+    pub unfocused_fps: f32,
+    /// Lets go of caches after `idle_trim_minutes` without input, see `AppResources::trim_memory`.
+    pub trim_when_idle: bool,
+    pub idle_trim_minutes: u32,
+    /// Caps the frame rate independent of vsync, see [`Self::frame_limit`].
+    pub limit_fps: bool,
+    pub target_fps: f32,
+    /// `desired_maximum_frame_latency` of the surface, 1 to 3.
+    pub frame_latency: u32,
+    /// Take the input only after the surface texture was acquired, instead of before.
+    pub low_latency: bool,
+    /// In low latency mode, also wait for the GPU to finish the previous frame before acquiring the next one.
+    pub wait_for_gpu: bool,
+    /// Resolution the UI is rendered at relative to the window, 0.5 to 2.
+    pub render_scale: f32,
+    pub scale_filter: ScaleFilter,
+    /// See `GpuConfig` for what these two do.
+    pub dithering: bool,
+    pub srgb_view: bool,
+    /// Samples per pixel the UI is drawn with, 1 turns multisampling off. Unsupported counts fall back to the next lower one.
+    pub msaa_samples: u32,
+    pub post_effect: PostEffect,
+    /// How far FXAA searches along an edge, in pixels.
+    pub fxaa_span_max: f32,
+    pub gamma: f32,
+    pub brightness: f32,
+    pub theme: Theme,
+    pub language: Language,
+    /// Scales the whole UI on top of the display scale, changed with Ctrl +/- as well.
+    pub zoom_factor: f32,
+    pub pixel_snap: PixelSnap,
+    /// egui's anti-aliasing of shape edges. Without it snapped 1px lines lose their soft fringe, curves get jagged.
+    pub feathering: bool,
+    pub motion: Motion,
+    /// The zoom factor never goes below this, 1 to 2, so Ctrl - can't make the UI too small to read.
+    pub min_ui_scale: f32,
+    /// Multiply what a mouse wheel step and a touchpad scroll, 0.1 to 5.
+    pub line_scroll_speed: f32,
+    pub pixel_scroll_speed: f32,
+    /// Scrolls mouse wheel steps over a few frames instead of at once.
+    pub smooth_scrolling: bool,
+    /// The mouse wheel scrolls sideways while Shift is held.
+    pub shift_scroll_horizontal: bool,
+    /// Repeats held arrow keys on platforms that don't, see `InputTuning`.
+    pub key_repeat: bool,
+    /// Brings up a keyboard on screen for text fields, for touch screens without a real one.
+    pub on_screen_keyboard: bool,
+}
+
+impl Settings {
+    /// Whether the UI has to be rendered offscreen first instead of straight into the surface.
+    pub fn needs_offscreen(&self) -> bool {
+        self.render_scale != 1.0 || self.post_effect != PostEffect::None
+    }
+
+    /// The full window title with the name of the open file, `fps` and `unsaved_changes` are only shown if the suffix
+    /// asks for them.
+    pub fn window_title(&self, file_name: Option<&str>, fps: Option<f32>, unsaved_changes: bool) -> String {
+        let title = match file_name {
+            Some(file_name) => format!("{} - {file_name}", self.title),
+            None => self.title.clone(),
+        };
+        match self.title_suffix {
+            TitleSuffix::None => title,
+            TitleSuffix::Fps => match fps {
+                Some(fps) => format!("{title} ({fps:.0} FPS)"),
+                None => title,
+            },
+            TitleSuffix::UnsavedMarker if unsaved_changes => format!("{title} *"),
+            TitleSuffix::UnsavedMarker => title,
+        }
+    }
+
+    pub fn to_json(&self) -> Json {
+        [
+            ("title", self.title.as_str().into()),
+            ("title_suffix", self.title_suffix.into()),
+            ("window_level", self.window_level.into()),
+            ("skip_taskbar", self.skip_taskbar.into()),
+            ("close_to_tray", self.close_to_tray.into()),
+            ("start_in_tray", self.start_in_tray.into()),
+            ("resizable", self.resizable.into()),
+            ("autosave", self.autosave.into()),
+            ("autosave_seconds", self.autosave_seconds.into()),
+            ("throttle_in_background", self.throttle_in_background.into()),
+            ("unfocused_fps", self.unfocused_fps.into()),
+            ("trim_when_idle", self.trim_when_idle.into()),
+            ("idle_trim_minutes", self.idle_trim_minutes.into()),
+            ("limit_fps", self.limit_fps.into()),
+            ("target_fps", self.target_fps.into()),
+            ("frame_latency", self.frame_latency.into()),
+            ("low_latency", self.low_latency.into()),
+            ("wait_for_gpu", self.wait_for_gpu.into()),
+            ("render_scale", self.render_scale.into()),
+            ("scale_filter", self.scale_filter.into()),
+            ("dithering", self.dithering.into()),
+            ("srgb_view", self.srgb_view.into()),
+            ("msaa_samples", self.msaa_samples.into()),
+            ("post_effect", self.post_effect.into()),
+            ("fxaa_span_max", self.fxaa_span_max.into()),
+            ("gamma", self.gamma.into()),
+            ("brightness", self.brightness.into()),
+            ("theme", self.theme.into()),
+            ("language", self.language.into()),
+            ("zoom_factor", self.zoom_factor.into()),
+            ("pixel_snap", self.pixel_snap.into()),
+            ("feathering", self.feathering.into()),
+            ("motion", self.motion.into()),
+            ("min_ui_scale", self.min_ui_scale.into()),
+            ("line_scroll_speed", self.line_scroll_speed.into()),
+            ("pixel_scroll_speed", self.pixel_scroll_speed.into()),
+            ("smooth_scrolling", self.smooth_scrolling.into()),
+            ("shift_scroll_horizontal", self.shift_scroll_horizontal.into()),
+            ("key_repeat", self.key_repeat.into()),
+            ("on_screen_keyboard", self.on_screen_keyboard.into()),
+        ].into_iter().collect()
+    }
+
+    /// Missing or invalid values are left at their defaults, so files from older versions still load.
+    pub fn from_json(json: &Json) -> Self {
+        let mut settings = Self::default();
+        json.read_into("title", &mut settings.title);
+        json.read_into("title_suffix", &mut settings.title_suffix);
+        json.read_into("window_level", &mut settings.window_level);
+        json.read_into("skip_taskbar", &mut settings.skip_taskbar);
+        json.read_into("close_to_tray", &mut settings.close_to_tray);
+        json.read_into("start_in_tray", &mut settings.start_in_tray);
+        json.read_into("resizable", &mut settings.resizable);
+        json.read_into("autosave", &mut settings.autosave);
+        json.read_into("autosave_seconds", &mut settings.autosave_seconds);
+        json.read_into("throttle_in_background", &mut settings.throttle_in_background);
+        json.read_into("unfocused_fps", &mut settings.unfocused_fps);
+        json.read_into("trim_when_idle", &mut settings.trim_when_idle);
+        json.read_into("idle_trim_minutes", &mut settings.idle_trim_minutes);
+        json.read_into("limit_fps", &mut settings.limit_fps);
+        json.read_into("target_fps", &mut settings.target_fps);
+        json.read_into("frame_latency", &mut settings.frame_latency);
+        json.read_into("low_latency", &mut settings.low_latency);
+        json.read_into("wait_for_gpu", &mut settings.wait_for_gpu);
+        json.read_into("render_scale", &mut settings.render_scale);
+        json.read_into("scale_filter", &mut settings.scale_filter);
+        json.read_into("dithering", &mut settings.dithering);
+        json.read_into("srgb_view", &mut settings.srgb_view);
+        json.read_into("msaa_samples", &mut settings.msaa_samples);
+        json.read_into("post_effect", &mut settings.post_effect);
+        json.read_into("fxaa_span_max", &mut settings.fxaa_span_max);
+        json.read_into("gamma", &mut settings.gamma);
+        json.read_into("brightness", &mut settings.brightness);
+        json.read_into("theme", &mut settings.theme);
+        json.read_into("language", &mut settings.language);
+        json.read_into("zoom_factor", &mut settings.zoom_factor);
+        json.read_into("pixel_snap", &mut settings.pixel_snap);
+        json.read_into("feathering", &mut settings.feathering);
+        json.read_into("motion", &mut settings.motion);
+        json.read_into("min_ui_scale", &mut settings.min_ui_scale);
+        json.read_into("line_scroll_speed", &mut settings.line_scroll_speed);
+        json.read_into("pixel_scroll_speed", &mut settings.pixel_scroll_speed);
+        json.read_into("smooth_scrolling", &mut settings.smooth_scrolling);
+        json.read_into("shift_scroll_horizontal", &mut settings.shift_scroll_horizontal);
+        json.read_into("key_repeat", &mut settings.key_repeat);
+        json.read_into("on_screen_keyboard", &mut settings.on_screen_keyboard);
+        settings.frame_latency = settings.frame_latency.clamp(1, 3);
+        settings.msaa_samples = settings.msaa_samples.clamp(1, 8);
+        settings.autosave_seconds = settings.autosave_seconds.clamp(5, 600);
+        settings.idle_trim_minutes = settings.idle_trim_minutes.clamp(1, 120);
+        settings.zoom_factor = settings.zoom_factor.clamp(0.5, 3.0);
+        settings.min_ui_scale = settings.min_ui_scale.clamp(1.0, 2.0);
+        settings.line_scroll_speed = settings.line_scroll_speed.clamp(0.1, 5.0);
+        settings.pixel_scroll_speed = settings.pixel_scroll_speed.clamp(0.1, 5.0);
+        settings
+    }
+
+    /// How long after a change the state is saved, `None` when it's only saved on exit.
+    pub fn autosave_after(&self) -> Option<Duration> {
+        self.autosave.then(|| Duration::from_secs(u64::from(self.autosave_seconds)))
+    }
+
+    /// How long without input until caches are trimmed, `None` when they aren't.
+    pub fn idle_trim_after(&self) -> Option<Duration> {
+        self.trim_when_idle.then(|| Duration::from_secs(u64::from(self.idle_trim_minutes) * 60))
+    }
+
+    /// Target frame rate of the frame limiter, `None` when uncapped.
+    pub fn frame_limit(&self) -> Option<f32> {
+        self.limit_fps.then_some(self.target_fps)
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            title: "Cool Window".to_owned(),
+            title_suffix: TitleSuffix::default(),
+            window_level: WindowLevel::default(),
+            skip_taskbar: false,
+            close_to_tray: false,
+            start_in_tray: false,
+            resizable: true,
+            autosave: true,
+            autosave_seconds: 30,
+            throttle_in_background: true,
+            unfocused_fps: 10.0,
+            trim_when_idle: false,
+            idle_trim_minutes: 10,
+            limit_fps: false,
+            target_fps: 60.0,
+            frame_latency: 2,
+            low_latency: false,
+            wait_for_gpu: false,
+            render_scale: 1.0,
+            scale_filter: ScaleFilter::default(),
+            dithering: false,
+            srgb_view: true,
+            msaa_samples: 1,
+            post_effect: PostEffect::default(),
+            fxaa_span_max: 8.0,
+            gamma: 1.0,
+            brightness: 0.0,
+            theme: Theme::default(),
+            language: Language::default(),
+            zoom_factor: 1.0,
+            pixel_snap: PixelSnap::default(),
+            feathering: true,
+            motion: Motion::default(),
+            min_ui_scale: 1.0,
+            line_scroll_speed: 1.0,
+            pixel_scroll_speed: 1.0,
+            smooth_scrolling: true,
+            shift_scroll_horizontal: true,
+            key_repeat: true,
+            on_screen_keyboard: false,
+        }
+    }
+}
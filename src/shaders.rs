@@ -0,0 +1,107 @@
+use std::borrow::Cow;
+
+/// A WGSL file in `src/shaders`. Its source is compiled into the binary, with the `dev` feature the file in the source
+/// tree is read instead while it's there, so edits show up without a rebuild, see [`ShaderWatcher`].
+#[derive(Debug, Clone, Copy)]
+pub struct ShaderFile {
+    #[cfg(feature = "dev")]
+    pub path: &'static str,
+    embedded: &'static str,
+}
+
+macro_rules! shader_file {
+    ($name:literal) => {
+        ShaderFile {
+            #[cfg(feature = "dev")]
+            path: concat!(env!("CARGO_MANIFEST_DIR"), "/src/shaders/", $name),
+            embedded: include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/src/shaders/", $name)),
+        }
+    };
+}
+
+/// Vertex shader and bindings shared by all full-screen passes.
+pub const FULLSCREEN: ShaderFile = shader_file!("fullscreen.wgsl");
+pub const BLIT: ShaderFile = shader_file!("blit.wgsl");
+pub const FXAA: ShaderFile = shader_file!("fxaa.wgsl");
+pub const COLOR_ADJUST: ShaderFile = shader_file!("color_adjust.wgsl");
+/// The additive pipeline of `overdraw::OverdrawView`.
+pub const OVERDRAW: ShaderFile = shader_file!("overdraw.wgsl");
+
+impl ShaderFile {
+    #[cfg(not(feature = "dev"))]
+    pub fn source(&self) -> Cow<'static, str> {
+        Cow::Borrowed(self.embedded)
+    }
+
+    /// The file on disk, or what was compiled in if it can't be read, e.g. when the binary was moved to another machine.
+    #[cfg(feature = "dev")]
+    pub fn source(&self) -> Cow<'static, str> {
+        std::fs::read_to_string(self.path).map_or(Cow::Borrowed(self.embedded), Cow::Owned)
+    }
+}
+
+#[cfg(feature = "dev")]
+pub use watcher::ShaderWatcher;
+
+#[cfg(feature = "dev")]
+mod watcher {
+    use std::sync::{Arc, Mutex, Weak};
+    use std::time::{Duration, SystemTime};
+
+    use super::ShaderFile;
+
+    const INTERVAL: Duration = Duration::from_millis(250);
+
+    type Files = Mutex<Vec<(&'static str, Option<SystemTime>)>>;
+
+    /// Looks for changes of the registered shader files on a background thread, which stops once this is dropped.
+    /// There's no file watching API in std, so the modification times are compared a few times per second, which is
+    /// plenty for a file saved by hand.
+    pub struct ShaderWatcher {
+        files: Arc<Files>,
+    }
+
+    impl ShaderWatcher {
+        /// `changed` is called from the watcher thread with the files that were modified since the last call.
+        pub fn new(changed: impl Fn(Vec<&'static str>) + Send + 'static) -> Self {
+            let files = Arc::<Files>::default();
+            let watched = Arc::downgrade(&files);
+            let spawned = std::thread::Builder::new().name("shader watcher".into()).spawn(move || watch(&watched, changed));
+            if let Err(error) = spawned {
+                log::error!("Failed to start watching the shaders: {error}");
+            }
+            Self { files }
+        }
+
+        pub fn watch(&self, file: ShaderFile) {
+            let mut files = self.files.lock().unwrap();
+            if !files.iter().any(|(path, _)| *path == file.path) {
+                files.push((file.path, modified_time(file.path)));
+            }
+        }
+    }
+
+    fn watch(files: &Weak<Files>, changed: impl Fn(Vec<&'static str>)) {
+        loop {
+            std::thread::sleep(INTERVAL);
+            let Some(files) = files.upgrade() else {
+                return;
+            };
+            let mut modified = Vec::new();
+            for (path, last) in files.lock().unwrap().iter_mut() {
+                let time = modified_time(path);
+                if time != *last {
+                    *last = time;
+                    modified.push(*path);
+                }
+            }
+            if !modified.is_empty() {
+                changed(modified);
+            }
+        }
+    }
+
+    fn modified_time(path: &str) -> Option<SystemTime> {
+        std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+    }
+}
@@ -0,0 +1,11 @@
+// Simple gamma and brightness adjustment, done in linear space.
+// params.x: gamma, params.y: brightness offset.
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(source, source_sampler, in.uv);
+    let gamma = max(uniforms.params.x, 0.01);
+    let rgb = pow(max(color.rgb, vec3<f32>(0.0)), vec3<f32>(1.0 / gamma)) + uniforms.params.y;
+    // The colors are premultiplied, so they must not end up brighter than their alpha.
+    return vec4<f32>(clamp(rgb, vec3<f32>(0.0), vec3<f32>(color.a)), color.a);
+}
@@ -0,0 +1,41 @@
+// Fast approximate anti-aliasing, blurs along edges detected from the luma of the neighbouring pixels.
+// params.x: maximum search span in pixels, params.y: how much the span shrinks in dark areas.
+
+const REDUCE_MIN: f32 = 1.0 / 128.0;
+
+fn luma(color: vec3<f32>) -> f32 {
+    return dot(color, vec3<f32>(0.299, 0.587, 0.114));
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = uniforms.texel_size;
+    let span_max = uniforms.params.x;
+    let reduce_mul = uniforms.params.y;
+
+    let luma_nw = luma(textureSample(source, source_sampler, in.uv + vec2<f32>(-1.0, -1.0) * texel).rgb);
+    let luma_ne = luma(textureSample(source, source_sampler, in.uv + vec2<f32>(1.0, -1.0) * texel).rgb);
+    let luma_sw = luma(textureSample(source, source_sampler, in.uv + vec2<f32>(-1.0, 1.0) * texel).rgb);
+    let luma_se = luma(textureSample(source, source_sampler, in.uv + vec2<f32>(1.0, 1.0) * texel).rgb);
+    let luma_m = luma(textureSample(source, source_sampler, in.uv).rgb);
+
+    let luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    let luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    // Perpendicular to the luma gradient, which is the direction of the edge.
+    var dir = vec2<f32>(-((luma_nw + luma_ne) - (luma_sw + luma_se)), (luma_nw + luma_sw) - (luma_ne + luma_se));
+    let dir_reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * reduce_mul, REDUCE_MIN);
+    let rcp_dir_min = 1.0 / (min(abs(dir.x), abs(dir.y)) + dir_reduce);
+    dir = clamp(dir * rcp_dir_min, vec2<f32>(-span_max), vec2<f32>(span_max)) * texel;
+
+    let rgb_a = 0.5 * (
+        textureSample(source, source_sampler, in.uv + dir * (1.0 / 3.0 - 0.5)) +
+        textureSample(source, source_sampler, in.uv + dir * (2.0 / 3.0 - 0.5)));
+    let rgb_b = rgb_a * 0.5 + 0.25 * (
+        textureSample(source, source_sampler, in.uv + dir * -0.5) +
+        textureSample(source, source_sampler, in.uv + dir * 0.5));
+
+    // The wider blur overshoots when it crossed into a different edge, the narrow one is used then.
+    let luma_b = luma(rgb_b.rgb);
+    return select(rgb_b, rgb_a, luma_b < luma_min || luma_b > luma_max);
+}
@@ -0,0 +1,21 @@
+// The overdraw view: every triangle of the UI adds the same bit of color wherever it's drawn, whatever its texture
+// and color. Pixels drawn once are dark red, about 8 times bright red, 32 times yellow and 128 times white.
+
+struct Uniforms {
+    // Size of the target in points, the unit of egui's vertices.
+    screen_size: vec2<f32>,
+    _padding: vec2<f32>,
+}
+
+@group(0) @binding(0) var<uniform> uniforms: Uniforms;
+
+@vertex
+fn vs_main(@location(0) position: vec2<f32>) -> @builtin(position) vec4<f32> {
+    let ndc = position / uniforms.screen_size * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0);
+    return vec4<f32>(ndc, 0.0, 1.0);
+}
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    return vec4<f32>(1.0 / 8.0, 1.0 / 32.0, 1.0 / 128.0, 1.0);
+}
@@ -0,0 +1,109 @@
+use std::time::Instant;
+
+use crate::autosave::{Autosave, PersistedFile};
+use crate::recording::Recording;
+use crate::tasks::TaskRunner;
+
+/// What [`shutdown`] needs to get at.
+pub trait Shutdown {
+    fn tasks(&mut self) -> &mut TaskRunner;
+    fn take_recording(&mut self) -> Option<Recording>;
+    fn autosave(&mut self) -> &mut Autosave;
+    /// Read only after the tasks were joined, so it sees what they changed on their way out.
+    fn persisted_files(&self) -> Vec<PersistedFile>;
+}
+
+/// Stops what may still be writing and saves, in the order that needs: tasks are cancelled and joined first, a save
+/// of the autosave among them, then the recording is finalized and only then the files are saved. Everything waits
+/// until `deadline` at most. Returns how many workers were still busy then, those are left to be killed with the
+/// process.
+pub fn shutdown(app: &mut impl Shutdown, deadline: Instant) -> usize {
+    let busy = app.tasks().shutdown(deadline);
+    if busy > 0 {
+        log::warn!("{busy} background tasks didn't stop in time");
+    }
+    if let Some(recording) = app.take_recording() {
+        recording.finish(deadline);
+    }
+    let files = app.persisted_files();
+    app.autosave().save_now(files);
+    busy
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::{mpsc, Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::storage::Document;
+    use crate::tasks::TaskStatus;
+
+    struct App {
+        tasks: TaskRunner,
+        autosave: Autosave,
+        /// Written by the task when it's cancelled.
+        state: Arc<Mutex<Document>>,
+        path: PathBuf,
+    }
+
+    impl Shutdown for App {
+        fn tasks(&mut self) -> &mut TaskRunner {
+            &mut self.tasks
+        }
+
+        fn take_recording(&mut self) -> Option<Recording> {
+            None
+        }
+
+        fn autosave(&mut self) -> &mut Autosave {
+            &mut self.autosave
+        }
+
+        fn persisted_files(&self) -> Vec<PersistedFile> {
+            vec![PersistedFile { path: self.path.clone(), contents: self.state.lock().unwrap().to_string() }]
+        }
+    }
+
+    #[test]
+    fn shutdown_during_a_running_task_keeps_what_it_saved() {
+        let path = std::env::temp_dir().join(format!("egui_wgpu_native-shutdown-{}.toml", std::process::id()));
+        let mut app = App {
+            tasks: TaskRunner::new(&egui::Context::default(), None),
+            autosave: Autosave::new(Instant::now()),
+            state: Arc::default(),
+            path: path.clone(),
+        };
+        let (started_tx, started_rx) = mpsc::channel();
+
+        let task_state = app.state.clone();
+        app.tasks.spawn("Endless", move |progress| {
+            started_tx.send(()).unwrap();
+            let mut steps = 0;
+            while !progress.is_cancelled() {
+                steps += 1;
+                thread::sleep(Duration::from_millis(1));
+            }
+            // Takes a while on its way out, the save has to wait for it.
+            thread::sleep(Duration::from_millis(50));
+            task_state.lock().unwrap().set("task", "steps", steps);
+        });
+        started_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        // An autosave that's still running, with what the state was before.
+        let files = app.persisted_files();
+        app.autosave.save(&mut app.tasks, files);
+
+        assert_eq!(shutdown(&mut app, Instant::now() + Duration::from_secs(5)), 0);
+        let saved = Document::load(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert!(saved.get_i64("task", "steps").is_some(), "the file is saved after the task finished: {saved}");
+
+        let finished = app.tasks.poll();
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].status, TaskStatus::Cancelled);
+        // Tasks can't be started anymore, but that's no reason to panic.
+        app.tasks.spawn("Too late", |_| panic!("ran after the shutdown"));
+    }
+}
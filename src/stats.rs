@@ -0,0 +1,177 @@
+use std::collections::{HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+/// How many frames the averages are taken over.
+const HISTORY: usize = 120;
+
+/// Numbers about recent frames, shown in the diagnostics window.
+#[derive(Default)]
+pub struct FrameStats {
+    frame_starts: VecDeque<Instant>,
+    /// Time spent waiting for the surface texture.
+    acquire_wait: VecDeque<Duration>,
+    /// Time from getting the surface texture to presenting it, which is how old the input is when it's shown.
+    acquire_to_present: VecDeque<Duration>,
+}
+
+impl FrameStats {
+    pub fn frame_started(&mut self, now: Instant) {
+        push_bounded(&mut self.frame_starts, now);
+    }
+
+    pub fn frame_presented(&mut self, acquire_wait: Duration, acquire_to_present: Duration) {
+        push_bounded(&mut self.acquire_wait, acquire_wait);
+        push_bounded(&mut self.acquire_to_present, acquire_to_present);
+    }
+
+    pub fn average_acquire_wait(&self) -> Option<Duration> {
+        average(&self.acquire_wait)
+    }
+
+    pub fn average_acquire_to_present(&self) -> Option<Duration> {
+        average(&self.acquire_to_present)
+    }
+
+    /// Frames per second actually rendered recently, `None` until there are at least two frames.
+    /// Idle time counts, so with reactive repainting this drops to zero when nothing changes.
+    pub fn achieved_fps(&self) -> Option<f32> {
+        let (first, last) = (self.frame_starts.front()?, self.frame_starts.back()?);
+        let span = last.duration_since(*first).max(Duration::from_millis(1));
+        (self.frame_starts.len() > 1).then(|| (self.frame_starts.len() - 1) as f32 / span.as_secs_f32())
+    }
+}
+
+/// What the UI renderer holds and drew last frame, as far as we can track it from the outside.
+#[derive(Default)]
+pub struct UiStats {
+    /// Textures uploaded to the renderer and not freed since.
+    textures: HashSet<egui::TextureId>,
+    /// Shapes egui painted last frame, before they were tessellated.
+    pub shapes: usize,
+    /// Includes comparing the shapes with the last ones, which is all it takes when they can be reused.
+    pub tessellation_time: Duration,
+    /// Frames that could reuse the primitives of the one before, and ones that had to tessellate.
+    pub tessellation_cache_hits: u64,
+    pub tessellation_cache_misses: u64,
+    /// How long the UI render pass took on the GPU, only measured while the stress test is open.
+    pub gpu_time: Option<Duration>,
+    pub clipped_primitives: usize,
+    pub vertices: usize,
+    pub indices: usize,
+    /// The area the clip rects of the primitives cover together, relative to the window. Only what's in a clip rect
+    /// can be drawn, the overdraw view shows how often it is.
+    pub clip_coverage: f32,
+    /// Submitted by the painting demo last frame.
+    pub painting_points: usize,
+    pub painting_shapes: usize,
+}
+
+impl UiStats {
+    pub fn texture_uploaded(&mut self, id: egui::TextureId) {
+        self.textures.insert(id);
+    }
+
+    pub fn texture_freed(&mut self, id: egui::TextureId) {
+        self.textures.remove(&id);
+    }
+
+    /// Forgets all textures, e.g. when the renderer holding them was replaced.
+    pub fn textures_dropped(&mut self) {
+        self.textures.clear();
+    }
+
+    pub fn texture_count(&self) -> usize {
+        self.textures.len()
+    }
+
+    /// `screen` is the window in points.
+    pub fn frame_tessellated(&mut self, shapes: usize, clipped_primitives: &[egui::ClippedPrimitive], time: Duration, cached: bool, screen: egui::Rect) {
+        if cached {
+            self.tessellation_cache_hits += 1;
+        } else {
+            self.tessellation_cache_misses += 1;
+            let clip_rects: Vec<_> = clipped_primitives.iter().map(|primitive| primitive.clip_rect.intersect(screen)).collect();
+            self.clip_coverage = union_area(&clip_rects) / screen.area().max(1.0);
+        }
+        self.shapes = shapes;
+        self.tessellation_time = time;
+        self.clipped_primitives = clipped_primitives.len();
+        (self.vertices, self.indices) = clipped_primitives.iter()
+            .filter_map(|primitive| match &primitive.primitive {
+                egui::epaint::Primitive::Mesh(mesh) => Some((mesh.vertices.len(), mesh.indices.len())),
+                egui::epaint::Primitive::Callback(_) => None,
+            })
+            .fold((0, 0), |(vertices, indices), (v, i)| (vertices + v, indices + i));
+    }
+
+    pub fn triangles(&self) -> usize {
+        self.indices / 3
+    }
+
+    /// Bytes the renderer needs in its vertex and index buffers for the last frame.
+    pub fn buffer_bytes(&self) -> (usize, usize) {
+        (self.vertices * std::mem::size_of::<egui::epaint::Vertex>(), self.indices * std::mem::size_of::<u32>())
+    }
+}
+
+/// The area covered by at least one of `rects`. The plane is cut into slabs at the left and right edges of the rects,
+/// in each the rects spanning it are vertical intervals that are merged.
+fn union_area(rects: &[egui::Rect]) -> f32 {
+    // Many primitives share their clip rect.
+    let mut rects: Vec<_> = rects.iter().filter(|rect| rect.is_positive()).copied().collect();
+    rects.sort_by(|a, b| [a.min.x, a.min.y, a.max.x, a.max.y].partial_cmp(&[b.min.x, b.min.y, b.max.x, b.max.y]).unwrap());
+    rects.dedup();
+    let mut edges: Vec<f32> = rects.iter().flat_map(|rect| [rect.min.x, rect.max.x]).collect();
+    edges.sort_by(f32::total_cmp);
+    edges.dedup();
+
+    let mut area = 0.0;
+    let mut spans = Vec::new();
+    for slab in edges.windows(2) {
+        let (left, right) = (slab[0], slab[1]);
+        spans.clear();
+        spans.extend(rects.iter().filter(|rect| rect.min.x <= left && right <= rect.max.x).map(|rect| rect.y_range()));
+        spans.sort_by(|a: &egui::Rangef, b| a.min.total_cmp(&b.min));
+        let mut covered = 0.0;
+        let mut current: Option<egui::Rangef> = None;
+        for span in &spans {
+            match &mut current {
+                Some(merged) if span.min <= merged.max => merged.max = merged.max.max(span.max),
+                _ => {
+                    covered += current.map_or(0.0, |merged| merged.span());
+                    current = Some(*span);
+                },
+            }
+        }
+        covered += current.map_or(0.0, |merged| merged.span());
+        area += covered * (right - left);
+    }
+    area
+}
+
+fn push_bounded<T>(history: &mut VecDeque<T>, value: T) {
+    if history.len() == HISTORY {
+        history.pop_front();
+    }
+    history.push_back(value);
+}
+
+fn average(history: &VecDeque<Duration>) -> Option<Duration> {
+    (!history.is_empty()).then(|| history.iter().sum::<Duration>() / history.len() as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlapping_clip_rects_count_once() {
+        let rect = |x: f32, y: f32, w: f32, h: f32| egui::Rect::from_min_size(egui::pos2(x, y), egui::vec2(w, h));
+        assert_eq!(union_area(&[]), 0.0);
+        // The same rect twice, and one inside it.
+        assert_eq!(union_area(&[rect(0.0, 0.0, 10.0, 10.0), rect(0.0, 0.0, 10.0, 10.0), rect(2.0, 2.0, 2.0, 2.0)]), 100.0);
+        // Two overlapping by a 5 × 5 corner and one apart, empty rects add nothing.
+        let rects = [rect(0.0, 0.0, 10.0, 10.0), rect(5.0, 5.0, 10.0, 10.0), rect(20.0, 0.0, 1.0, 1.0), rect(0.0, 0.0, 0.0, 50.0)];
+        assert_eq!(union_area(&rects), 176.0);
+    }
+}
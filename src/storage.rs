@@ -0,0 +1,363 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Directory that files persisting between runs go into, following the platform conventions.
+pub fn data_dir() -> Option<PathBuf> {
+    let base = if cfg!(target_os = "windows") {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+    };
+    base.map(|base| base.join(env!("CARGO_PKG_NAME")))
+}
+
+/// Writes `contents` next to `path` first and then renames it over, so a crash never leaves a half-written file.
+pub fn write_atomic(path: &Path, contents: &str) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let temp_path = temp_path(path);
+    let mut file = fs::File::create(&temp_path)?;
+    file.write_all(contents.as_bytes())?;
+    // On the disk before it's renamed, or a power loss could still leave an empty file behind.
+    file.sync_all()?;
+    fs::rename(&temp_path, path)
+}
+
+/// Where [`write_atomic`] writes `path` before renaming it over.
+fn temp_path(path: &Path) -> PathBuf {
+    path.with_extension("tmp")
+}
+
+/// Puts back the temp file of [`write_atomic`] that a crash left behind before it was renamed, if it's newer than
+/// `path` and `is_complete` accepts what's in it. Otherwise it's removed. Returns whether `path` was recovered.
+pub fn recover_temp(path: &Path, is_complete: impl FnOnce(&str) -> bool) -> io::Result<bool> {
+    let temp_path = temp_path(path);
+    let temp_modified = match fs::metadata(&temp_path) {
+        Ok(metadata) => metadata.modified()?,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(error) => return Err(error),
+    };
+    let newer = fs::metadata(path).and_then(|metadata| metadata.modified()).map_or(true, |modified| temp_modified >= modified);
+    if newer && fs::read_to_string(&temp_path).is_ok_and(|text| is_complete(&text)) {
+        fs::rename(&temp_path, path)?;
+        return Ok(true);
+    }
+    fs::remove_file(&temp_path)?;
+    Ok(false)
+}
+
+/// Held by the instance of the app that saves into [`data_dir`], others only read from it so they don't overwrite
+/// each other's files. It's a lock of the system's, which goes away with the process even when it crashed.
+pub struct InstanceLock {
+    _file: fs::File,
+}
+
+impl InstanceLock {
+    /// `Ok(None)` while another instance holds the lock for `dir`.
+    pub fn acquire(dir: &Path) -> io::Result<Option<Self>> {
+        fs::create_dir_all(dir)?;
+        let file = fs::OpenOptions::new().create(true).truncate(false).write(true).open(dir.join("instance.lock"))?;
+        match file.try_lock() {
+            Ok(()) => Ok(Some(Self { _file: file })),
+            Err(fs::TryLockError::WouldBlock) => Ok(None),
+            Err(fs::TryLockError::Error(error)) => Err(error),
+        }
+    }
+}
+
+/// Shows `dir` in the file manager of the platform, creating it first so there's something to show.
+pub fn open_folder(dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let opener = if cfg!(target_os = "windows") {
+        "explorer"
+    } else if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+    // The file manager keeps running on its own, there's nothing to wait for.
+    std::process::Command::new(opener).arg(dir).spawn().map(drop)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+}
+
+impl Value {
+    /// Parses a single value written by `Display`, e.g. `true`, `1.5` or `"text"`.
+    pub fn parse(text: &str) -> Option<Self> {
+        parse_value(text.trim())
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Int(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Float(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::String(value.to_owned())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::String(value)
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Bool(value) => write!(f, "{value}"),
+            Value::Int(value) => write!(f, "{value}"),
+            // `{:?}` always keeps the decimal point, so the value reads back as a float.
+            Value::Float(value) => write!(f, "{value:?}"),
+            Value::String(value) => {
+                f.write_str("\"")?;
+                for c in value.chars() {
+                    match c {
+                        '"' => f.write_str("\\\"")?,
+                        '\\' => f.write_str("\\\\")?,
+                        '\n' => f.write_str("\\n")?,
+                        '\t' => f.write_str("\\t")?,
+                        c => write!(f, "{c}")?,
+                    }
+                }
+                f.write_str("\"")
+            },
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// A small subset of TOML: `[section]` headers and `key = value` lines with booleans, numbers and basic strings.
+/// Enough for settings files that people might want to edit by hand.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Document {
+    /// Keys outside of any section live under "".
+    sections: BTreeMap<String, BTreeMap<String, Value>>,
+}
+
+impl Document {
+    /// Reads `path`, a missing file is treated as empty.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        match fs::read_to_string(path) {
+            Ok(text) => Ok(Self::parse(&text)?),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let mut document = Self::default();
+        let mut section = String::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let error = |message: &str| ParseError { line: i + 1, message: message.to_owned() };
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[') {
+                let (name, rest) = name.split_once(']').ok_or_else(|| error("unterminated section header"))?;
+                let rest = rest.trim();
+                if !rest.is_empty() && !rest.starts_with('#') {
+                    return Err(error("unexpected text after the section header"));
+                }
+                section = name.trim().to_owned();
+                continue;
+            }
+
+            let (key, value) = line.split_once('=').ok_or_else(|| error("expected `key = value`"))?;
+            let key = key.trim();
+            if key.is_empty() {
+                return Err(error("missing key"));
+            }
+            let value = parse_value(value.trim()).ok_or_else(|| error("invalid value"))?;
+            document.sections.entry(section.clone()).or_default().insert(key.to_owned(), value);
+        }
+
+        Ok(document)
+    }
+
+    pub fn get(&self, section: &str, key: &str) -> Option<&Value> {
+        self.sections.get(section)?.get(key)
+    }
+
+    pub fn set(&mut self, section: &str, key: &str, value: impl Into<Value>) {
+        self.sections.entry(section.to_owned()).or_default().insert(key.to_owned(), value.into());
+    }
+
+    pub fn remove_section(&mut self, section: &str) {
+        self.sections.remove(section);
+    }
+
+    /// Every `(section, key, value)`, sorted by section and key.
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &str, &Value)> {
+        self.sections.iter().flat_map(|(section, entries)| {
+            entries.iter().map(move |(key, value)| (section.as_str(), key.as_str(), value))
+        })
+    }
+
+    pub fn get_bool(&self, section: &str, key: &str) -> Option<bool> {
+        match self.get(section, key)? {
+            Value::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn get_str(&self, section: &str, key: &str) -> Option<&str> {
+        match self.get(section, key)? {
+            Value::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_i64(&self, section: &str, key: &str) -> Option<i64> {
+        match self.get(section, key)? {
+            Value::Int(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Document {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Root keys have to come before the first header, `BTreeMap` puts "" first.
+        for (name, entries) in &self.sections {
+            if !name.is_empty() {
+                writeln!(f, "[{name}]")?;
+            }
+            for (key, value) in entries {
+                writeln!(f, "{key} = {value}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_value(text: &str) -> Option<Value> {
+    // Trailing comments are allowed after everything but strings, where `#` could be part of the value.
+    if let Some(rest) = text.strip_prefix('"') {
+        return parse_string(rest).map(Value::String);
+    }
+    let text = text.split('#').next()?.trim();
+
+    match text {
+        "true" => Some(Value::Bool(true)),
+        "false" => Some(Value::Bool(false)),
+        _ if text.contains(['.', 'e', 'E']) => text.replace('_', "").parse().ok().map(Value::Float),
+        _ => text.replace('_', "").parse().ok().map(Value::Int),
+    }
+}
+
+/// Parses the rest of a basic string after the opening quote.
+fn parse_string(text: &str) -> Option<String> {
+    let mut value = String::new();
+    let mut chars = text.chars();
+    loop {
+        match chars.next()? {
+            '"' => break,
+            '\\' => value.push(match chars.next()? {
+                'n' => '\n',
+                't' => '\t',
+                '"' => '"',
+                '\\' => '\\',
+                _ => return None,
+            }),
+            c => value.push(c),
+        }
+    }
+
+    let rest = chars.as_str().trim();
+    (rest.is_empty() || rest.starts_with('#')).then_some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn comments_are_skipped_wherever_toml_allows_them() {
+        let document = Document::parse(
+            "# leading comment\nroot = 1 # after a number\n\n[window] # after a header\n  title = \"a # b\" # after a string\nenabled = true#tight\n[ gpu ]\nscale = 1.5\n",
+        ).unwrap();
+        assert_eq!(document.get_i64("", "root"), Some(1));
+        assert_eq!(document.get_str("window", "title"), Some("a # b"));
+        assert_eq!(document.get_bool("window", "enabled"), Some(true));
+        assert_eq!(document.get("gpu", "scale"), Some(&Value::Float(1.5)));
+
+        let line_of = |text: &str| Document::parse(text).unwrap_err().line;
+        assert_eq!(line_of("[window\n"), 1);
+        assert_eq!(line_of("\n[window] title = 1\n"), 2);
+        assert_eq!(line_of("title = \"open\n"), 1);
+        assert_eq!(line_of("title = \"a\" b\n"), 1);
+    }
+
+    #[test]
+    fn strings_round_trip_with_escapes() {
+        let mut document = Document::default();
+        document.set("", "root", "top");
+        document.set("text", "quoted", "say \"hi\"\\ now\n\tdone # not a comment");
+        document.set("text", "empty", "");
+        let text = document.to_string();
+        assert!(text.starts_with("root = \"top\"\n"), "root keys come before the first header: {text}");
+        assert_eq!(Document::parse(&text).unwrap(), document);
+        assert!(Document::parse("bad = \"\\q\"\n").is_err(), "unknown escapes are rejected");
+    }
+
+    #[test]
+    fn unknown_sections_and_keys_are_kept() {
+        let text = "[window]\ntitle = \"App\"\nfuture_option = 3\n\n[plugins]\nenabled = false\n";
+        let mut document = Document::parse(text).unwrap();
+        assert_eq!(document.get_i64("window", "future_option"), Some(3));
+        assert_eq!(document.get_bool("plugins", "enabled"), Some(false));
+        document.set("window", "title", "Other");
+        let written = Document::parse(&document.to_string()).unwrap();
+        assert_eq!(written.get_i64("window", "future_option"), Some(3));
+        assert_eq!(written.get_bool("plugins", "enabled"), Some(false));
+        assert_eq!(written.get_str("window", "title"), Some("Other"));
+    }
+}
@@ -0,0 +1,109 @@
+use winit::window::Window;
+
+/// Shows how far the background tasks are on the window's taskbar button. Only Windows has an API for that, elsewhere
+/// this does nothing.
+pub struct TaskbarProgress {
+    #[cfg(target_os = "windows")]
+    taskbar: Option<win32::TaskbarList>,
+}
+
+impl TaskbarProgress {
+    pub fn new(window: &Window) -> Self {
+        #[cfg(target_os = "windows")]
+        {
+            let taskbar = win32::TaskbarList::new(window);
+            if taskbar.is_none() {
+                log::warn!("Progress can't be shown on the taskbar, ITaskbarList3 isn't available");
+            }
+            Self { taskbar }
+        }
+        #[cfg(not(target_os = "windows"))]
+        {
+            let _ = window;
+            Self {}
+        }
+    }
+
+    /// Progress between 0.0 and 1.0, `None` takes it off the button.
+    pub fn set(&mut self, progress: Option<f32>) {
+        #[cfg(target_os = "windows")]
+        if let Some(taskbar) = &self.taskbar {
+            taskbar.set_progress(progress);
+        }
+        #[cfg(not(target_os = "windows"))]
+        let _ = progress;
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod win32 {
+    use std::ffi::c_void;
+
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::System::Com::{CoCreateInstance, CoInitializeEx, CoUninitialize, CLSCTX_INPROC_SERVER, COINIT_APARTMENTTHREADED};
+    use windows::Win32::UI::Shell::{ITaskbarList3, TaskbarList as TaskbarListClass, TBPF_NOPROGRESS, TBPF_NORMAL};
+    use winit::raw_window_handle::{HasWindowHandle, RawWindowHandle};
+    use winit::window::Window;
+
+    /// `SetProgressValue` takes a fraction of two integers.
+    const PROGRESS_TOTAL: u64 = 1000;
+
+    /// A successful `CoInitializeEx` on this thread, balanced with `CoUninitialize` when dropped. winit initialized
+    /// COM already, so this only counts up, but every call that succeeds has to be undone.
+    struct Apartment;
+
+    impl Apartment {
+        fn enter() -> Option<Self> {
+            // SAFETY: No reserved pointer, the apartment model is the one winit uses on this thread.
+            unsafe { CoInitializeEx(None, COINIT_APARTMENTTHREADED) }.is_ok().then_some(Self)
+        }
+    }
+
+    impl Drop for Apartment {
+        fn drop(&mut self) {
+            // SAFETY: Balances the call in `enter`. Only `TaskbarList` holds one, and that can't leave the thread.
+            unsafe { CoUninitialize() };
+        }
+    }
+
+    /// An `ITaskbarList3` for one window, released when dropped.
+    pub struct TaskbarList {
+        list: ITaskbarList3,
+        window: HWND,
+        /// Declared after `list`, so the interface is released before COM is uninitialized.
+        _apartment: Option<Apartment>,
+    }
+
+    impl TaskbarList {
+        pub fn new(window: &Window) -> Option<Self> {
+            let Ok(RawWindowHandle::Win32(handle)) = window.window_handle().map(|handle| handle.as_raw()) else {
+                return None;
+            };
+            // It fails if COM was initialized with another apartment model, it's still usable then.
+            let apartment = Apartment::enter();
+            // SAFETY: The class implements ITaskbarList3, it has to be initialized before anything else is called.
+            let list = unsafe {
+                let list: ITaskbarList3 = CoCreateInstance(&TaskbarListClass, None, CLSCTX_INPROC_SERVER).ok()?;
+                list.HrInit().ok()?;
+                list
+            };
+            Some(Self { list, window: HWND(handle.hwnd.get() as *mut c_void), _apartment: apartment })
+        }
+
+        pub fn set_progress(&self, progress: Option<f32>) {
+            // SAFETY: The window outlives the app's resources. Failures only leave the button as it was.
+            unsafe {
+                match progress {
+                    Some(progress) => {
+                        let _ = self.list.SetProgressState(self.window, TBPF_NORMAL);
+                        let completed = (progress.clamp(0.0, 1.0) * PROGRESS_TOTAL as f32) as u64;
+                        let _ = self.list.SetProgressValue(self.window, completed, PROGRESS_TOTAL);
+                    },
+                    None => {
+                        let _ = self.list.SetProgressState(self.window, TBPF_NOPROGRESS);
+                    },
+                }
+            }
+        }
+    }
+}
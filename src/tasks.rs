@@ -0,0 +1,332 @@
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::window_commands::{WindowCommand, WindowCommands};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TaskId(u64);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TaskStatus {
+    Queued,
+    Running,
+    Completed,
+    Cancelled,
+    Failed,
+}
+
+impl TaskStatus {
+    pub fn is_finished(self) -> bool {
+        matches!(self, TaskStatus::Completed | TaskStatus::Cancelled | TaskStatus::Failed)
+    }
+}
+
+/// Messages sent from the worker threads to the UI thread.
+enum TaskUpdate {
+    Started(TaskId),
+    Progress(TaskId, f32),
+    Log(TaskId, String),
+    Error(TaskId, String),
+    Finished(TaskId, TaskStatus),
+}
+
+/// Handed to every task, lets it report back to the UI and check whether it should stop.
+pub struct ProgressHandle {
+    id: TaskId,
+    updates: Sender<TaskUpdate>,
+    cancelled: Arc<AtomicBool>,
+    failed: Arc<AtomicBool>,
+    ctx: egui::Context,
+}
+
+impl ProgressHandle {
+    /// Progress between 0.0 and 1.0.
+    pub fn set_progress(&self, progress: f32) {
+        self.send(TaskUpdate::Progress(self.id, progress.clamp(0.0, 1.0)));
+    }
+
+    pub fn log(&self, line: impl Into<String>) {
+        self.send(TaskUpdate::Log(self.id, line.into()));
+    }
+
+    /// Marks the task as failed once it returns, for errors that aren't worth a panic.
+    pub fn fail(&self, error: impl Into<String>) {
+        self.failed.store(true, Ordering::Relaxed);
+        self.send(TaskUpdate::Error(self.id, error.into()));
+    }
+
+    /// Tasks are expected to poll this and return early once it's set.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    fn send(&self, update: TaskUpdate) {
+        let _ = self.updates.send(update);
+        // The repaint callback turns this into a user event, so the UI updates even while idle.
+        self.ctx.request_repaint();
+    }
+}
+
+pub struct Task {
+    pub id: TaskId,
+    pub name: String,
+    pub status: TaskStatus,
+    pub progress: f32,
+    pub log: Vec<String>,
+    /// Set by [`ProgressHandle::fail`].
+    pub error: Option<String>,
+    cancelled: Arc<AtomicBool>,
+    /// Started with [`TaskRunner::spawn_quiet`].
+    quiet: bool,
+}
+
+impl Task {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Returned by [`TaskRunner::poll`].
+pub struct FinishedTask {
+    pub name: String,
+    pub status: TaskStatus,
+    pub error: Option<String>,
+}
+
+/// Runs tasks on a small fixed thread pool and keeps track of their state for the UI.
+pub struct TaskRunner {
+    /// `None` after [`Self::shutdown`].
+    jobs: Option<Sender<Job>>,
+    workers: Vec<JoinHandle<()>>,
+    updates_tx: Sender<TaskUpdate>,
+    updates_rx: Receiver<TaskUpdate>,
+    tasks: Vec<Task>,
+    next_id: u64,
+    ctx: egui::Context,
+    /// Where the overall progress and finished tasks are reported for the taskbar, see [`Self::poll`].
+    window_commands: Option<WindowCommands>,
+    /// The last progress reported, in whole percent.
+    reported_progress: Option<u32>,
+}
+
+impl TaskRunner {
+    pub fn new(ctx: &egui::Context, window_commands: Option<WindowCommands>) -> Self {
+        let (jobs_tx, jobs_rx) = mpsc::channel::<Job>();
+        let jobs_rx = Arc::new(Mutex::new(jobs_rx));
+
+        let worker_count = thread::available_parallelism().map_or(2, |n| n.get().clamp(1, 4));
+        let workers = (0..worker_count).map(|i| {
+            let jobs_rx = jobs_rx.clone();
+            thread::Builder::new()
+                .name(format!("task-worker-{i}"))
+                .spawn(move || loop {
+                    // Only hold the lock while waiting for a job, not while running it.
+                    let job = jobs_rx.lock().unwrap().recv();
+                    match job {
+                        Ok(job) => job(),
+                        Err(_) => break,
+                    }
+                })
+                .unwrap()
+        }).collect();
+
+        let (updates_tx, updates_rx) = mpsc::channel();
+
+        Self {
+            jobs: Some(jobs_tx),
+            workers,
+            updates_tx,
+            updates_rx,
+            tasks: Vec::new(),
+            next_id: 0,
+            ctx: ctx.clone(),
+            window_commands,
+            reported_progress: None,
+        }
+    }
+
+    pub fn spawn(&mut self, name: impl Into<String>, task: impl FnOnce(ProgressHandle) + Send + 'static) -> TaskId {
+        self.spawn_with(name.into(), false, task)
+    }
+
+    /// Like [`Self::spawn`], for what runs often in the background and is only worth the user's attention when it
+    /// fails. It doesn't count towards the progress, and it's gone from the list once it completed.
+    pub fn spawn_quiet(&mut self, name: impl Into<String>, task: impl FnOnce(ProgressHandle) + Send + 'static) -> TaskId {
+        self.spawn_with(name.into(), true, task)
+    }
+
+    fn spawn_with(&mut self, name: String, quiet: bool, task: impl FnOnce(ProgressHandle) + Send + 'static) -> TaskId {
+        let id = TaskId(self.next_id);
+        self.next_id += 1;
+
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let handle = ProgressHandle {
+            id,
+            updates: self.updates_tx.clone(),
+            cancelled: cancelled.clone(),
+            failed: Arc::new(AtomicBool::new(false)),
+            ctx: self.ctx.clone(),
+        };
+
+        self.tasks.push(Task {
+            id,
+            name,
+            status: TaskStatus::Queued,
+            progress: 0.0,
+            log: Vec::new(),
+            error: None,
+            cancelled,
+            quiet,
+        });
+
+        let job = move || {
+            handle.send(TaskUpdate::Started(handle.id));
+            let updates = handle.updates.clone();
+            let cancelled = handle.cancelled.clone();
+            let failed = handle.failed.clone();
+            let ctx = handle.ctx.clone();
+
+            let status = match panic::catch_unwind(AssertUnwindSafe(|| task(handle))) {
+                Ok(()) if failed.load(Ordering::Relaxed) => TaskStatus::Failed,
+                Ok(()) if cancelled.load(Ordering::Relaxed) => TaskStatus::Cancelled,
+                Ok(()) => TaskStatus::Completed,
+                Err(_) => TaskStatus::Failed,
+            };
+            let _ = updates.send(TaskUpdate::Finished(id, status));
+            ctx.request_repaint();
+        };
+        match &self.jobs {
+            Some(jobs) => {
+                let _ = jobs.send(Box::new(job));
+            },
+            None => log::warn!("Not starting a task while shutting down"),
+        }
+
+        id
+    }
+
+    /// Applies everything the workers reported since the last call, meant to be called once per frame.
+    /// Returns the tasks that finished in the meantime. Their progress together and how they finished go to the
+    /// window commands too, cancelled tasks aren't worth the user's attention.
+    pub fn poll(&mut self) -> Vec<FinishedTask> {
+        let mut finished = Vec::new();
+
+        while let Ok(update) = self.updates_rx.try_recv() {
+            match update {
+                TaskUpdate::Started(id) => {
+                    if let Some(task) = self.get_mut(id) {
+                        task.status = TaskStatus::Running;
+                    }
+                },
+                TaskUpdate::Progress(id, progress) => {
+                    if let Some(task) = self.get_mut(id) {
+                        task.progress = progress;
+                    }
+                },
+                TaskUpdate::Log(id, line) => {
+                    if let Some(task) = self.get_mut(id) {
+                        task.log.push(line);
+                    }
+                },
+                TaskUpdate::Error(id, error) => {
+                    if let Some(task) = self.get_mut(id) {
+                        task.log.push(error.clone());
+                        task.error = Some(error);
+                    }
+                },
+                TaskUpdate::Finished(id, status) => {
+                    if let Some(task) = self.get_mut(id) {
+                        task.status = status;
+                        if status == TaskStatus::Completed {
+                            task.progress = 1.0;
+                        }
+                        if !task.quiet || status == TaskStatus::Failed {
+                            finished.push(FinishedTask { name: task.name.clone(), status, error: task.error.clone() });
+                        }
+                    }
+                },
+            }
+        }
+        self.tasks.retain(|task| !task.quiet || !matches!(task.status, TaskStatus::Completed | TaskStatus::Cancelled));
+
+        if let Some(commands) = &self.window_commands {
+            for task in &finished {
+                match task.status {
+                    TaskStatus::Completed => commands.push(WindowCommand::TaskCompleted),
+                    TaskStatus::Failed => commands.push(WindowCommand::TaskFailed),
+                    _ => {},
+                }
+            }
+            let progress = self.progress();
+            let percent = progress.map(|progress| (progress * 100.0) as u32);
+            if percent != self.reported_progress {
+                self.reported_progress = percent;
+                commands.push(WindowCommand::TaskProgress(progress));
+            }
+        }
+        finished
+    }
+
+    /// The average progress of the tasks that are queued or running, `None` if there are none.
+    fn progress(&self) -> Option<f32> {
+        let unfinished: Vec<f32> = self.tasks.iter().filter(|task| !task.quiet && !task.status.is_finished()).map(|task| task.progress).collect();
+        (!unfinished.is_empty()).then(|| unfinished.iter().sum::<f32>() / unfinished.len() as f32)
+    }
+
+    pub fn tasks(&self) -> &[Task] {
+        &self.tasks
+    }
+
+    pub fn clear_finished(&mut self) {
+        self.tasks.retain(|task| !task.status.is_finished());
+    }
+
+    /// Asks every task to stop early, they still finish on their own time.
+    pub fn cancel_all(&self) {
+        for task in &self.tasks {
+            task.cancel();
+        }
+    }
+
+    /// Cancels all tasks and waits until `deadline` for the workers to finish what they're running.
+    /// Returns how many workers are still busy then, those are left to be killed with the process.
+    pub fn shutdown(&mut self, deadline: Instant) -> usize {
+        self.cancel_all();
+        // Queued jobs still run, but see that they were cancelled.
+        self.jobs = None;
+        join_until(std::mem::take(&mut self.workers), deadline)
+    }
+
+    fn get_mut(&mut self, id: TaskId) -> Option<&mut Task> {
+        self.tasks.iter_mut().find(|task| task.id == id)
+    }
+}
+
+impl Drop for TaskRunner {
+    /// Dropping the job sender stops the workers once they're idle, running tasks are asked to stop early.
+    fn drop(&mut self) {
+        self.cancel_all();
+    }
+}
+
+/// Joins the threads that finish before `deadline` and returns how many didn't, std has no join with a timeout.
+pub fn join_until(mut threads: Vec<JoinHandle<()>>, deadline: Instant) -> usize {
+    loop {
+        let (finished, running): (Vec<_>, Vec<_>) = threads.into_iter().partition(JoinHandle::is_finished);
+        for thread in finished {
+            // A panic was already reported by the panic hook.
+            let _ = thread.join();
+        }
+        threads = running;
+        if threads.is_empty() || Instant::now() >= deadline {
+            return threads.len();
+        }
+        thread::sleep(Duration::from_millis(5).min(deadline.saturating_duration_since(Instant::now())));
+    }
+}
@@ -0,0 +1,108 @@
+use std::rc::Rc;
+
+/// Everything besides the shapes that `egui::Context::tessellate` depends on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Key {
+    pixels_per_point: f32,
+    /// Feathering is changed by the anti-aliasing setting.
+    options: egui::epaint::TessellationOptions,
+    /// The texture coordinates of text are divided by it, it changes when the atlas grows.
+    font_image_size: [usize; 2],
+}
+
+impl Key {
+    fn of(ctx: &egui::Context, pixels_per_point: f32) -> Self {
+        Self {
+            pixels_per_point,
+            options: ctx.tessellation_options(|options| *options),
+            font_image_size: ctx.fonts(|fonts| fonts.font_image_size()),
+        }
+    }
+}
+
+/// The tessellated UI of the last frame, reused while egui paints exactly the same shapes again, e.g. when something
+/// outside of the UI asks for frames. With the gallery open, comparing unchanged shapes takes about a third of what
+/// tessellating them does, without the upload, and a miss costs a copy of the shapes on top of the tessellation.
+#[derive(Default)]
+pub struct TessellationCache {
+    /// `None` if the last primitives can't be reused.
+    shapes: Option<(Vec<egui::epaint::ClippedShape>, Key)>,
+    primitives: Rc<[egui::ClippedPrimitive]>,
+    /// What the renderer's vertex and index buffers were last written for, `None` if they hold something else.
+    uploaded: Option<([u32; 2], f32)>,
+}
+
+impl TessellationCache {
+    /// The primitives for `shapes`, and whether they are the ones from last time.
+    pub fn tessellate(&mut self, ctx: &egui::Context, shapes: Vec<egui::epaint::ClippedShape>, pixels_per_point: f32) -> (Rc<[egui::ClippedPrimitive]>, bool) {
+        let key = Key::of(ctx, pixels_per_point);
+        if self.shapes.as_ref().is_some_and(|(last_shapes, last_key)| *last_key == key && *last_shapes == shapes) {
+            return (self.primitives.clone(), true);
+        }
+        let primitives: Rc<[_]> = ctx.tessellate(shapes.clone(), pixels_per_point).into();
+        // Paint callbacks have to be prepared every frame, which happens together with the upload.
+        let cacheable = primitives.iter().all(|primitive| matches!(primitive.primitive, egui::epaint::Primitive::Mesh(_)));
+        self.shapes = cacheable.then_some((shapes, key));
+        self.primitives = primitives.clone();
+        self.uploaded = None;
+        (primitives, false)
+    }
+
+    /// Whether the buffers have to be written before the last primitives can be drawn with `screen_descriptor`.
+    /// Once this returned true they are expected to be written.
+    pub fn needs_upload(&mut self, screen_descriptor: &egui_wgpu::ScreenDescriptor) -> bool {
+        let target = Some((screen_descriptor.size_in_pixels, screen_descriptor.pixels_per_point));
+        let needed = self.uploaded != target;
+        self.uploaded = target;
+        needed
+    }
+
+    /// The renderer's buffers were written with something else or the renderer was replaced.
+    pub fn invalidate_upload(&mut self) {
+        self.uploaded = None;
+    }
+
+    /// Lets go of the last shapes and primitives, the next frame is tessellated and uploaded again.
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shapes(ctx: &egui::Context) -> Vec<egui::epaint::ClippedShape> {
+        let raw_input = egui::RawInput {
+            screen_rect: Some(egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(200.0, 100.0))),
+            ..Default::default()
+        };
+        ctx.run(raw_input, |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                ui.label("Text");
+                ui.painter().circle_filled(egui::pos2(50.0, 50.0), 20.0, egui::Color32::RED);
+            });
+        }).shapes
+    }
+
+    #[test]
+    fn changed_tessellation_options_are_a_miss() {
+        let ctx = egui::Context::default();
+        let mut cache = TessellationCache::default();
+        let frame = shapes(&ctx);
+        assert!(!cache.tessellate(&ctx, frame.clone(), 1.0).1);
+        assert!(cache.tessellate(&ctx, frame.clone(), 1.0).1, "the same shapes again are a hit");
+        let (feathered, reused) = cache.tessellate(&ctx, frame.clone(), 2.0);
+        assert!(!reused);
+
+        ctx.tessellation_options_mut(|options| options.feathering = false);
+        let (unfeathered, reused) = cache.tessellate(&ctx, frame.clone(), 2.0);
+        assert!(!reused, "feathering changed");
+        let vertices = |primitives: &[egui::ClippedPrimitive]| primitives.iter().map(|primitive| match &primitive.primitive {
+            egui::epaint::Primitive::Mesh(mesh) => mesh.vertices.len(),
+            egui::epaint::Primitive::Callback(_) => 0,
+        }).sum::<usize>();
+        assert!(vertices(&unfeathered) < vertices(&feathered), "without feathering there are no extra vertices for the edges");
+        assert!(cache.tessellate(&ctx, frame, 2.0).1);
+    }
+}
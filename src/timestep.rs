@@ -0,0 +1,65 @@
+use std::time::{Duration, Instant};
+
+/// Rate that [`FixedUpdate::update`] runs at.
+pub const UPDATE_RATE: u32 = 60;
+/// Steps run at most before one frame. After a longer stall, like a window drag blocking the event loop on Windows,
+/// the rest of the time is dropped instead of trying to catch up, which would only make the next frame take longer.
+const MAX_STEPS: u32 = 8;
+
+/// Simulation state advanced in fixed steps, independent of the frame rate.
+pub trait FixedUpdate {
+    /// `dt` is always `1 / UPDATE_RATE` seconds.
+    fn update(&mut self, dt: f32);
+}
+
+/// Accumulates the time between frames and turns it into whole update steps.
+pub struct FixedTimestep {
+    step: Duration,
+    accumulator: Duration,
+    last: Option<Instant>,
+}
+
+impl Default for FixedTimestep {
+    fn default() -> Self {
+        Self {
+            step: Duration::from_secs(1) / UPDATE_RATE,
+            accumulator: Duration::ZERO,
+            last: None,
+        }
+    }
+}
+
+impl FixedTimestep {
+    pub fn dt(&self) -> f32 {
+        self.step.as_secs_f32()
+    }
+
+    /// How many steps are due at `now`, zero if frames come faster than the update rate.
+    pub fn advance(&mut self, now: Instant) -> u32 {
+        let elapsed = self.last.map_or(Duration::ZERO, |last| now.saturating_duration_since(last));
+        self.last = Some(now);
+        self.accumulator += elapsed;
+
+        let due = (self.accumulator.as_nanos() / self.step.as_nanos()) as u32;
+        let steps = due.min(MAX_STEPS);
+        if due > MAX_STEPS {
+            log::debug!("Simulation fell {} steps behind, skipping them", due - MAX_STEPS);
+            self.accumulator = Duration::ZERO;
+        } else {
+            self.accumulator -= self.step * steps;
+        }
+        steps
+    }
+
+    /// How far it is from the last step to the next one, 0 to 1. Rendering blends the last two states by it,
+    /// so motion stays smooth when frames and steps don't line up.
+    pub fn alpha(&self) -> f32 {
+        self.accumulator.as_secs_f32() / self.step.as_secs_f32()
+    }
+
+    /// Forgets the time since the last frame, for when nothing is simulated for a while.
+    pub fn reset(&mut self) {
+        self.accumulator = Duration::ZERO;
+        self.last = None;
+    }
+}
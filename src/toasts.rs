@@ -0,0 +1,157 @@
+use crate::motion::MotionPrefs;
+
+/// How long a toast takes to fade out once its timeout is over, in seconds.
+const FADE_DURATION: f64 = 0.4;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ToastKind {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl ToastKind {
+    fn color(self, visuals: &egui::Visuals) -> egui::Color32 {
+        match self {
+            ToastKind::Info => visuals.hyperlink_color,
+            ToastKind::Success => egui::Color32::from_rgb(80, 200, 120),
+            ToastKind::Warning => visuals.warn_fg_color,
+            ToastKind::Error => visuals.error_fg_color,
+        }
+    }
+
+    fn icon(self) -> &'static str {
+        match self {
+            ToastKind::Info => "ℹ",
+            ToastKind::Success => "✔",
+            ToastKind::Warning => "⚠",
+            ToastKind::Error => "⚠",
+        }
+    }
+}
+
+struct Toast {
+    id: u64,
+    kind: ToastKind,
+    text: String,
+    /// egui time at which the toast was first shown, `None` until then.
+    shown_at: Option<f64>,
+    /// Set when the close button was clicked, starts the fade right away.
+    closed_at: Option<f64>,
+}
+
+/// Queues notifications and renders them as a stack of areas in one corner of the window.
+pub struct Toasts {
+    toasts: Vec<Toast>,
+    next_id: u64,
+    /// Corner the toasts are stacked in, they grow away from it.
+    pub anchor: egui::Align2,
+    /// Seconds a toast stays fully visible before fading out.
+    pub timeout: f64,
+}
+
+impl Default for Toasts {
+    fn default() -> Self {
+        Self {
+            toasts: Vec::new(),
+            next_id: 0,
+            anchor: egui::Align2::RIGHT_BOTTOM,
+            timeout: 4.0,
+        }
+    }
+}
+
+impl Toasts {
+    pub fn info(&mut self, text: impl Into<String>) {
+        self.add(ToastKind::Info, text.into());
+    }
+
+    pub fn success(&mut self, text: impl Into<String>) {
+        self.add(ToastKind::Success, text.into());
+    }
+
+    pub fn warning(&mut self, text: impl Into<String>) {
+        self.add(ToastKind::Warning, text.into());
+    }
+
+    pub fn error(&mut self, text: impl Into<String>) {
+        self.add(ToastKind::Error, text.into());
+    }
+
+    pub fn add(&mut self, kind: ToastKind, text: String) {
+        self.toasts.push(Toast {
+            id: self.next_id,
+            kind,
+            text,
+            shown_at: None,
+            closed_at: None,
+        });
+        self.next_id += 1;
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        let now = ctx.input(|i| i.time);
+        let timeout = self.timeout;
+        // With reduced motion toasts go away at once instead.
+        let fade_duration = if MotionPrefs::get(ctx).reduce_motion { 0.0 } else { FADE_DURATION };
+        self.toasts.retain(|toast| fade_start(toast, timeout).is_none_or(|start| now < start + fade_duration));
+
+        let margin = 8.0;
+        // Toasts in the bottom corners stack upwards, the others downwards.
+        let direction = if self.anchor.y() == egui::Align::Max { -1.0 } else { 1.0 };
+        let x_offset = match self.anchor.x() {
+            egui::Align::Min => margin,
+            egui::Align::Center => 0.0,
+            egui::Align::Max => -margin,
+        };
+        let mut y_offset = margin * direction;
+        let mut next_repaint = f64::INFINITY;
+
+        for toast in &mut self.toasts {
+            let shown_at = *toast.shown_at.get_or_insert(now);
+            let fade_start = fade_start(toast, timeout).unwrap_or(shown_at + timeout);
+            let opacity = if now < fade_start {
+                next_repaint = next_repaint.min(fade_start - now);
+                1.0
+            } else {
+                next_repaint = 0.0;
+                (1.0 - (now - fade_start) / fade_duration).clamp(0.0, 1.0) as f32
+            };
+
+            let response = egui::Area::new(egui::Id::new(("toast", toast.id)))
+                .anchor(self.anchor, egui::vec2(x_offset, y_offset))
+                .order(egui::Order::Foreground)
+                .show(ctx, |ui| {
+                    ui.set_opacity(opacity);
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.set_max_width(300.0);
+                        ui.horizontal(|ui| {
+                            ui.colored_label(toast.kind.color(ui.visuals()), toast.kind.icon());
+                            ui.label(&toast.text);
+                            if ui.small_button("🗙").clicked() && toast.closed_at.is_none() {
+                                toast.closed_at = Some(now);
+                            }
+                        });
+                    });
+                })
+                .response;
+
+            y_offset += (response.rect.height() + margin) * direction;
+        }
+
+        // Keep animating while fading, otherwise just wake up when the next fade starts.
+        if next_repaint.is_finite() {
+            ctx.request_repaint_after_secs(next_repaint as f32);
+        }
+    }
+}
+
+/// When the toast starts fading out, `None` if it hasn't been shown yet.
+fn fade_start(toast: &Toast, timeout: f64) -> Option<f64> {
+    let timed_out = toast.shown_at.map(|shown_at| shown_at + timeout);
+    match (timed_out, toast.closed_at) {
+        (Some(timed_out), Some(closed_at)) => Some(timed_out.min(closed_at)),
+        (timed_out, closed_at) => timed_out.or(closed_at),
+    }
+}
@@ -0,0 +1,273 @@
+//! An icon in the notification area with a menu to show or hide the window, to start hidden and to quit. Only built on
+//! Windows with the `tray` feature. The icon belongs to a hidden window on a thread of its own, which runs the message
+//! loop that `Shell_NotifyIconW` reports clicks to.
+
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread::JoinHandle;
+use std::{mem, ptr};
+
+use windows_sys::Win32::Foundation::{HWND, LPARAM, LRESULT, POINT, WPARAM};
+use windows_sys::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows_sys::Win32::UI::Shell::{
+    Shell_NotifyIconW, NIF_ICON, NIF_MESSAGE, NIF_TIP, NIM_ADD, NIM_DELETE, NOTIFYICONDATAW,
+};
+use windows_sys::Win32::UI::WindowsAndMessaging::{
+    AppendMenuW, CreatePopupMenu, CreateWindowExW, DefWindowProcW, DestroyMenu, DestroyWindow, DispatchMessageW,
+    GetCursorPos, GetMessageW, LoadIconW, PostMessageW, PostQuitMessage, RegisterClassW, RegisterWindowMessageW,
+    SetForegroundWindow, TrackPopupMenu, TranslateMessage, IDI_APPLICATION, MF_CHECKED, MF_SEPARATOR, MF_STRING,
+    MF_UNCHECKED, MSG, TPM_NONOTIFY, TPM_RETURNCMD, TPM_RIGHTBUTTON, WM_APP, WM_CLOSE, WM_DESTROY, WM_LBUTTONUP,
+    WM_RBUTTONUP, WNDCLASSW,
+};
+
+use crate::i18n::tr;
+
+/// What the icon sends its window for clicks.
+const WM_TRAY: u32 = WM_APP + 1;
+const MENU_TOGGLE_WINDOW: usize = 1;
+const MENU_START_IN_TRAY: usize = 2;
+const MENU_QUIT: usize = 3;
+
+/// Sent from the tray's thread, see [`Tray::new`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TrayEvent {
+    /// The icon was clicked, or Show or Hide picked from its menu.
+    ToggleWindow,
+    ToggleStartInTray,
+    Quit,
+}
+
+/// What the menu shows, kept up to date by the app.
+struct MenuState {
+    window_visible: AtomicBool,
+    start_in_tray: AtomicBool,
+}
+
+/// The tray icon, removed again when dropped.
+pub struct Tray {
+    state: Arc<MenuState>,
+    /// The hidden window the icon belongs to. Window handles aren't `Send`, it's only passed back as a number.
+    window: isize,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Tray {
+    /// `None` if the icon couldn't be added, that's logged. `tooltip` is shown when hovering it.
+    pub fn new(tooltip: &str, send: impl Fn(TrayEvent) + Send + 'static) -> Option<Self> {
+        let state = Arc::new(MenuState {
+            window_visible: AtomicBool::new(true),
+            start_in_tray: AtomicBool::new(false),
+        });
+        let (created_tx, created_rx) = mpsc::channel();
+        let (thread_state, tooltip) = (state.clone(), tooltip.to_owned());
+        let thread = std::thread::Builder::new()
+            .name("tray".into())
+            .spawn(move || run(Box::new(send), thread_state, &tooltip, created_tx))
+            .ok()?;
+        match created_rx.recv() {
+            Ok(Some(window)) => Some(Self { state, window, thread: Some(thread) }),
+            _ => {
+                log::warn!("The tray icon couldn't be added");
+                let _ = thread.join();
+                None
+            },
+        }
+    }
+
+    /// Switches the menu between Hide and Show.
+    pub fn set_window_visible(&self, visible: bool) {
+        self.state.window_visible.store(visible, Ordering::Relaxed);
+    }
+
+    pub fn set_start_in_tray(&self, start_in_tray: bool) {
+        self.state.start_in_tray.store(start_in_tray, Ordering::Relaxed);
+    }
+}
+
+impl Drop for Tray {
+    fn drop(&mut self) {
+        // SAFETY: The window lives until its thread handled this, posting to one that's gone already only fails.
+        unsafe { PostMessageW(self.window as HWND, WM_CLOSE, 0, 0) };
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// What the window procedure needs, it gets nothing but the message.
+struct TrayThread {
+    send: Box<dyn Fn(TrayEvent) + Send>,
+    state: Arc<MenuState>,
+    tooltip: Vec<u16>,
+    /// Broadcast when Explorer restarted, the icon has to be added again then.
+    taskbar_created: u32,
+}
+
+thread_local! {
+    static THREAD: RefCell<Option<TrayThread>> = const { RefCell::new(None) };
+}
+
+fn wide(text: &str) -> Vec<u16> {
+    text.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+/// Creates the window and the icon, reports the window back through `created` and runs the message loop until the
+/// window is closed.
+fn run(
+    send: Box<dyn Fn(TrayEvent) + Send>,
+    state: Arc<MenuState>,
+    tooltip: &str,
+    created: mpsc::Sender<Option<isize>>,
+) {
+    let class_name = wide("egui_wgpu_native_tray");
+    // SAFETY: The strings outlive the calls, the structs are plain data that's valid zeroed.
+    unsafe {
+        let instance = GetModuleHandleW(ptr::null());
+        let class = WNDCLASSW {
+            lpfnWndProc: Some(window_proc),
+            hInstance: instance,
+            lpszClassName: class_name.as_ptr(),
+            ..mem::zeroed()
+        };
+        // Fails if it's registered already, by a tray made earlier, and the window can be created all the same.
+        RegisterClassW(&class);
+        // A top-level window that's never shown rather than a message-only one, those don't get broadcasts.
+        let window = CreateWindowExW(
+            0,
+            class_name.as_ptr(),
+            ptr::null(),
+            0,
+            0,
+            0,
+            0,
+            0,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            instance,
+            ptr::null(),
+        );
+        if window.is_null() {
+            let _ = created.send(None);
+            return;
+        }
+        let thread = TrayThread {
+            send,
+            state,
+            tooltip: wide(tooltip),
+            taskbar_created: RegisterWindowMessageW(wide("TaskbarCreated").as_ptr()),
+        };
+        let added = thread.add_icon(window);
+        THREAD.with(|cell| *cell.borrow_mut() = Some(thread));
+        if !added {
+            DestroyWindow(window);
+            let _ = created.send(None);
+            return;
+        }
+        let _ = created.send(Some(window as isize));
+
+        let mut message: MSG = mem::zeroed();
+        while GetMessageW(&mut message, ptr::null_mut(), 0, 0) > 0 {
+            TranslateMessage(&message);
+            DispatchMessageW(&message);
+        }
+    }
+}
+
+unsafe extern "system" fn window_proc(window: HWND, message: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    // Menus and destroying the window dispatch messages while the state is borrowed, so it's only ever borrowed shared.
+    let handled = THREAD.with(|cell| {
+        let thread = cell.borrow();
+        let thread = thread.as_ref()?;
+        match message {
+            // Without a version set, the mouse message is the whole `lparam`.
+            WM_TRAY => match lparam as u32 {
+                WM_LBUTTONUP => (thread.send)(TrayEvent::ToggleWindow),
+                WM_RBUTTONUP => thread.show_menu(window),
+                _ => {},
+            },
+            WM_CLOSE => {
+                thread.remove_icon(window);
+                // SAFETY: The window belongs to this thread.
+                unsafe { DestroyWindow(window) };
+            },
+            // SAFETY: Ends the message loop of this thread.
+            WM_DESTROY => unsafe { PostQuitMessage(0) },
+            _ if message == thread.taskbar_created => {
+                thread.add_icon(window);
+            },
+            _ => return None,
+        }
+        Some(0)
+    });
+    // SAFETY: Everything else gets the default handling, with what this was called with.
+    handled.unwrap_or_else(|| unsafe { DefWindowProcW(window, message, wparam, lparam) })
+}
+
+impl TrayThread {
+    fn icon_data(&self, window: HWND) -> NOTIFYICONDATAW {
+        // SAFETY: Plain data, zeroed is what the API expects for everything that isn't set.
+        let mut data: NOTIFYICONDATAW = unsafe { mem::zeroed() };
+        data.cbSize = mem::size_of::<NOTIFYICONDATAW>() as u32;
+        data.hWnd = window;
+        data.uID = 1;
+        data
+    }
+
+    fn add_icon(&self, window: HWND) -> bool {
+        let mut data = self.icon_data(window);
+        data.uFlags = NIF_ICON | NIF_MESSAGE | NIF_TIP;
+        data.uCallbackMessage = WM_TRAY;
+        // The tooltip has to end with a null within its 128 characters.
+        let tooltip = &self.tooltip[..self.tooltip.len().min(data.szTip.len())];
+        data.szTip[..tooltip.len()].copy_from_slice(tooltip);
+        *data.szTip.last_mut().unwrap() = 0;
+        // SAFETY: A system icon, and `data` outlives the call.
+        unsafe {
+            data.hIcon = LoadIconW(ptr::null_mut(), IDI_APPLICATION);
+            Shell_NotifyIconW(NIM_ADD, &data) != 0
+        }
+    }
+
+    fn remove_icon(&self, window: HWND) {
+        let data = self.icon_data(window);
+        // SAFETY: `data` outlives the call, removing an icon that isn't there only fails.
+        unsafe { Shell_NotifyIconW(NIM_DELETE, &data) };
+    }
+
+    /// Shows the menu at the cursor and waits for a pick.
+    fn show_menu(&self, window: HWND) {
+        let visible = self.state.window_visible.load(Ordering::Relaxed);
+        let start_in_tray = self.state.start_in_tray.load(Ordering::Relaxed);
+        // SAFETY: The label of each item outlives the call that adds it, the menu is destroyed after it's closed.
+        let command = unsafe {
+            let menu = CreatePopupMenu();
+            if menu.is_null() {
+                return;
+            }
+            let toggle = if visible { tr("tray.hide") } else { tr("tray.show") };
+            AppendMenuW(menu, MF_STRING, MENU_TOGGLE_WINDOW, wide(toggle).as_ptr());
+            let check = if start_in_tray { MF_CHECKED } else { MF_UNCHECKED };
+            AppendMenuW(menu, MF_STRING | check, MENU_START_IN_TRAY, wide(tr("tray.start_in_tray")).as_ptr());
+            AppendMenuW(menu, MF_SEPARATOR, 0, ptr::null());
+            AppendMenuW(menu, MF_STRING, MENU_QUIT, wide(tr("tray.quit")).as_ptr());
+
+            let mut cursor = POINT { x: 0, y: 0 };
+            GetCursorPos(&mut cursor);
+            // Otherwise the menu stays open when clicking somewhere else.
+            SetForegroundWindow(window);
+            let flags = TPM_RETURNCMD | TPM_NONOTIFY | TPM_RIGHTBUTTON;
+            let command = TrackPopupMenu(menu, flags, cursor.x, cursor.y, 0, window, ptr::null());
+            DestroyMenu(menu);
+            command as usize
+        };
+        let event = match command {
+            MENU_TOGGLE_WINDOW => TrayEvent::ToggleWindow,
+            MENU_START_IN_TRAY => TrayEvent::ToggleStartInTray,
+            MENU_QUIT => TrayEvent::Quit,
+            // Closed without a pick.
+            _ => return,
+        };
+        (self.send)(event);
+    }
+}
@@ -1,21 +1,69 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::capabilities::CapabilityReport;
+use crate::capture::CapturedFrame;
+use crate::color_check::{ColorCheck, ColorProbe, ColorVerdict};
+use crate::frame_clock::FrameTime;
+use crate::i18n::{tr, tr_format, Language};
+use crate::json::{json_enum, FromJson, Json};
+use crate::layouts;
+use crate::logging::LogBuffer;
+use crate::memory_trim::{CacheUsage, TrimLevel, TrimReport};
+use crate::motion::MotionPrefs;
+use crate::on_screen_keyboard;
+use crate::palette::Palette;
+use crate::recording::RecordingFormat;
+use crate::settings::{Motion, PixelSnap, PostEffect, ScaleFilter, Settings, Theme, TitleSuffix, WindowLevel};
+use crate::stats::{FrameStats, UiStats};
+use crate::storage::Document;
+use crate::tasks::{TaskRunner, TaskStatus};
+use crate::timestep::{FixedUpdate, UPDATE_RATE};
+use crate::toasts::Toasts;
+use crate::undo::TextHistory;
+use crate::watchdog::Watchdog;
+
 /// Copied from the egui example
 
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 enum Enum {
     First,
     Second,
     Third,
 }
 
+json_enum!(Enum { First => "first", Second => "second", Third => "third" });
+
+impl Enum {
+    fn label(self) -> &'static str {
+        match self {
+            Enum::First => tr("gallery.first"),
+            Enum::Second => tr("gallery.second"),
+            Enum::Third => tr("gallery.third"),
+        }
+    }
+}
+
 /// Shows off one example of each major type of widget.
+/// Undo steps kept for the gallery's text field.
+const GALLERY_UNDO_STEPS: usize = 100;
+const UNDO_SHORTCUT: egui::KeyboardShortcut = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Z);
+const REDO_SHORTCUT: egui::KeyboardShortcut = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND.plus(egui::Modifiers::SHIFT), egui::Key::Z);
+
 pub struct WidgetGallery {
     boolean: bool,
     radio: Enum,
     scalar: f32,
     string: String,
+    string_history: TextHistory,
+    /// Multiline, to try out IME composition that spans lines, e.g. typing Japanese or Chinese.
+    notes: String,
     color: egui::Color32,
     animate_progress_bar: bool,
+    /// Whether the detached window is shown, shared with its UI which runs in a pass of its own.
+    detached_window: Arc<AtomicBool>,
     open: bool
 }
 
@@ -26,13 +74,19 @@ impl Default for WidgetGallery {
             radio: Enum::First,
             scalar: 42.0,
             string: Default::default(),
+            string_history: TextHistory::new(GALLERY_UNDO_STEPS),
+            notes: Default::default(),
             color: egui::Color32::LIGHT_BLUE.linear_multiply(0.5),
             animate_progress_bar: false,
+            detached_window: Arc::new(AtomicBool::new(false)),
             open: true
         }
     }
 }
 
+/// Fixed rather than derived from the window, so tests can focus the editor.
+const GALLERY_NOTES_ID: &str = "gallery_notes";
+
 fn doc_link_label(label: &str, _: &str) -> egui::Label {
     egui::Label::new(label)
 }
@@ -41,84 +95,164 @@ impl WidgetGallery {
     pub fn show(&mut self, ctx: &egui::Context) {
         let mut open = self.open;
 
-        egui::Window::new("Widget Gallery")
+        egui::Window::new(tr("gallery.title"))
+            .tool_window(ctx, ToolWindow::Gallery)
             .open(&mut open)
             .resizable([true, false])
             .default_width(280.0)
-            .show(&ctx, |ui| {
+            .show(ctx, |ui| {
                 self.ui(ui);
             });
+        if self.detached_window.load(Ordering::Relaxed) {
+            show_detached_window(ctx, self.detached_window.clone());
+        }
 
         self.open = open;
     }
 
+    pub fn to_json(&self) -> Json {
+        [
+            ("boolean", self.boolean.into()),
+            ("radio", self.radio.into()),
+            ("scalar", self.scalar.into()),
+            ("string", self.string.as_str().into()),
+            ("notes", self.notes.as_str().into()),
+            ("color", self.color.to_array().into()),
+            ("animate_progress_bar", self.animate_progress_bar.into()),
+        ].into_iter().collect()
+    }
+
+    /// Missing or invalid values are reset to their defaults. Replacing the text is a step that can be undone.
+    pub fn load_json(&mut self, json: &Json) {
+        let defaults = Self::default();
+        self.boolean = defaults.boolean;
+        self.radio = defaults.radio;
+        self.scalar = defaults.scalar;
+        self.animate_progress_bar = defaults.animate_progress_bar;
+        self.notes = defaults.notes;
+        json.read_into("boolean", &mut self.boolean);
+        json.read_into("radio", &mut self.radio);
+        json.read_into("scalar", &mut self.scalar);
+        json.read_into("animate_progress_bar", &mut self.animate_progress_bar);
+        json.read_into("notes", &mut self.notes);
+
+        let mut color = defaults.color.to_array();
+        json.read_into("color", &mut color);
+        self.color = egui::Color32::from_rgba_premultiplied(color[0], color[1], color[2], color[3]);
+
+        let mut string = defaults.string;
+        json.read_into("string", &mut string);
+        if string != self.string {
+            self.string_history.checkpoint(&self.string);
+            self.string = string;
+        }
+    }
+
     fn ui(&mut self, ui: &mut egui::Ui) {
         let Self {
             boolean,
             radio,
             scalar,
             string,
+            string_history,
+            notes,
             color,
             animate_progress_bar,
+            detached_window,
             open: _
         } = self;
 
+        // Handled before the text field so its own undo doesn't get the shortcut too.
+        let string_id = ui.id().with("gallery_string");
+        if ui.memory(|memory| memory.has_focus(string_id)) || !ui.ctx().wants_keyboard_input() {
+            // Redo first, it's the more specific shortcut.
+            if ui.input_mut(|i| i.consume_shortcut(&REDO_SHORTCUT)) {
+                string_history.redo(string);
+            } else if ui.input_mut(|i| i.consume_shortcut(&UNDO_SHORTCUT)) {
+                string_history.undo(string);
+            }
+        }
+
         ui.add(doc_link_label("Label", "label"));
-        ui.label("Welcome to the widget gallery!");
+        ui.label(tr("gallery.welcome"));
         ui.end_row();
 
         ui.add(doc_link_label("Hyperlink", "Hyperlink"));
         use egui::special_emojis::GITHUB;
         ui.hyperlink_to(
-            format!("{GITHUB} egui on GitHub"),
+            format!("{GITHUB} {}", tr("gallery.github")),
             "https://github.com/emilk/egui",
         );
         ui.end_row();
 
         ui.add(doc_link_label("TextEdit", "TextEdit"));
-        ui.add(egui::TextEdit::singleline(string).hint_text("Write something here"));
+        let before = string.clone();
+        if ui.add(egui::TextEdit::singleline(string).id(string_id).hint_text(tr("gallery.string_hint"))).changed() {
+            string_history.user_edit(&before, string, Instant::now());
+        }
+        ui.end_row();
+
+        ui.add(doc_link_label("Multiline", "TextEdit"));
+        ui.add(egui::TextEdit::multiline(notes).id(egui::Id::new(GALLERY_NOTES_ID)).desired_rows(3).hint_text(tr("gallery.notes_hint")));
+        ui.end_row();
+
+        ui.add(doc_link_label("Undo", "undo"));
+        ui.horizontal(|ui| {
+            if ui.add_enabled(string_history.undo_len() > 0, egui::Button::new(tr("gallery.undo"))).on_hover_text(ui.ctx().format_shortcut(&UNDO_SHORTCUT)).clicked() {
+                string_history.undo(string);
+            }
+            if ui.add_enabled(string_history.redo_len() > 0, egui::Button::new(tr("gallery.redo"))).on_hover_text(ui.ctx().format_shortcut(&REDO_SHORTCUT)).clicked() {
+                string_history.redo(string);
+            }
+            if ui.button(tr("gallery.fill_in")).on_hover_text(tr("gallery.fill_in_hint")).clicked() {
+                string_history.checkpoint(string);
+                *string = "The quick brown fox jumps over the lazy dog".to_owned();
+            }
+            ui.weak(format!("{} / {}", string_history.undo_len(), string_history.redo_len()))
+                .on_hover_text(tr("gallery.steps"));
+        });
         ui.end_row();
 
         ui.add(doc_link_label("Button", "button"));
-        if ui.button("Click me!").clicked() {
+        if ui.button(tr("gallery.click_me")).clicked() {
             *boolean = !*boolean;
         }
         ui.end_row();
 
         ui.add(doc_link_label("Link", "link"));
-        if ui.link("Click me!").clicked() {
+        if ui.link(tr("gallery.click_me")).clicked() {
             *boolean = !*boolean;
         }
         ui.end_row();
 
         ui.add(doc_link_label("Checkbox", "checkbox"));
-        ui.checkbox(boolean, "Checkbox");
+        ui.checkbox(boolean, tr("gallery.checkbox"));
         ui.end_row();
 
         ui.add(doc_link_label("RadioButton", "radio"));
         ui.horizontal(|ui| {
-            ui.radio_value(radio, Enum::First, "First");
-            ui.radio_value(radio, Enum::Second, "Second");
-            ui.radio_value(radio, Enum::Third, "Third");
+            ui.radio_value(radio, Enum::First, Enum::First.label());
+            ui.radio_value(radio, Enum::Second, Enum::Second.label());
+            ui.radio_value(radio, Enum::Third, Enum::Third.label());
         });
         ui.end_row();
 
         ui.add(doc_link_label("SelectableLabel", "SelectableLabel"));
         ui.horizontal(|ui| {
-            ui.selectable_value(radio, Enum::First, "First");
-            ui.selectable_value(radio, Enum::Second, "Second");
-            ui.selectable_value(radio, Enum::Third, "Third");
+            ui.selectable_value(radio, Enum::First, Enum::First.label());
+            ui.selectable_value(radio, Enum::Second, Enum::Second.label());
+            ui.selectable_value(radio, Enum::Third, Enum::Third.label());
         });
         ui.end_row();
 
         ui.add(doc_link_label("ComboBox", "ComboBox"));
 
-        egui::ComboBox::from_label("Take your pick")
-            .selected_text(format!("{radio:?}"))
+        egui::ComboBox::from_label(tr("gallery.pick"))
+            .selected_text(radio.label())
             .show_ui(ui, |ui| {
-                ui.selectable_value(radio, Enum::First, "First");
-                ui.selectable_value(radio, Enum::Second, "Second");
-                ui.selectable_value(radio, Enum::Third, "Third");
+                ui.selectable_value(radio, Enum::First, Enum::First.label());
+                ui.selectable_value(radio, Enum::Second, Enum::Second.label());
+                ui.selectable_value(radio, Enum::Third, Enum::Third.label());
             });
         ui.end_row();
 
@@ -134,10 +268,10 @@ impl WidgetGallery {
         let progress = *scalar / 360.0;
         let progress_bar = egui::ProgressBar::new(progress)
             .show_percentage()
-            .animate(*animate_progress_bar);
+            .animate(*animate_progress_bar && !MotionPrefs::get(ui.ctx()).reduce_motion);
         *animate_progress_bar = ui
             .add(progress_bar)
-            .on_hover_text("The progress bar can be animated!")
+            .on_hover_text(tr("gallery.progress_hint"))
             .hovered();
         ui.end_row();
 
@@ -145,21 +279,3221 @@ impl WidgetGallery {
         ui.color_edit_button_srgba(color);
         ui.end_row();
 
+        ui.add(doc_link_label("Viewport", "show_viewport_deferred"));
+        if ui.add_enabled(!detached_window.load(Ordering::Relaxed), egui::Button::new(tr("gallery.open_detached"))).clicked() {
+            detached_window.store(true, Ordering::Relaxed);
+        }
+        ui.end_row();
+
         ui.add(doc_link_label("Separator", "separator"));
         ui.separator();
         ui.end_row();
 
         ui.add(doc_link_label("CollapsingHeader", "collapsing"));
-        ui.collapsing("Click to see what is hidden!", |ui| {
+        ui.collapsing(tr("gallery.collapsing"), |ui| {
             ui.horizontal_wrapped(|ui| {
                 ui.spacing_mut().item_spacing.x = 0.0;
-                ui.label("It's a ");
+                ui.label(tr("gallery.its_a"));
                 ui.add(doc_link_label("Spinner", "spinner"));
                 ui.add_space(4.0);
-                ui.add(egui::Spinner::new());
+                // A spinner that stands still would look stuck.
+                if MotionPrefs::get(ui.ctx()).reduce_motion {
+                    ui.label("…");
+                } else {
+                    ui.add(egui::Spinner::new());
+                }
             });
         });
         ui.end_row();
     }
 }
 
+
+/// A deferred viewport, a window of its own that egui runs a separate pass for. Platforms with only one window
+/// get it embedded as an `egui::Window` instead.
+fn show_detached_window(ctx: &egui::Context, open: Arc<AtomicBool>) {
+    let builder = egui::ViewportBuilder::default()
+        .with_title(tr("detached.title"))
+        .with_inner_size([320.0, 160.0]);
+    ctx.show_viewport_deferred(egui::ViewportId::from_hash_of("detached_window"), builder, move |ctx, class| {
+        let contents = |ui: &mut egui::Ui| {
+            ui.label(tr("detached.text"));
+            ui.label(tr_format("detached.frame", &[("frame", &ctx.cumulative_pass_nr())]));
+            if ui.button(tr("detached.close")).clicked() {
+                open.store(false, Ordering::Relaxed);
+            }
+        };
+        if class == egui::ViewportClass::Embedded {
+            let mut embedded_open = true;
+            egui::Window::new(tr("detached.title")).id(egui::Id::new("detached_window")).open(&mut embedded_open).show(ctx, contents);
+            if !embedded_open {
+                open.store(false, Ordering::Relaxed);
+            }
+        } else {
+            egui::CentralPanel::default().show(ctx, contents);
+            if ctx.input(|i| i.viewport().close_requested()) {
+                open.store(false, Ordering::Relaxed);
+            }
+        }
+        // The main window's pass stops showing the viewport, which closes its window.
+        if !open.load(Ordering::Relaxed) {
+            ctx.request_repaint_of(egui::ViewportId::ROOT);
+        }
+    });
+}
+
+/// Counts up from a background thread, proving that repaints requested off the UI thread wake up the event loop.
+pub struct BackgroundCounter {
+    count: Arc<AtomicU64>,
+    open: bool,
+}
+
+impl BackgroundCounter {
+    pub fn new(ctx: &egui::Context) -> Self {
+        let count = Arc::new(AtomicU64::new(0));
+
+        let thread_count = count.clone();
+        let thread_ctx = ctx.clone();
+        std::thread::spawn(move || loop {
+            std::thread::sleep(Duration::from_millis(500));
+            thread_count.fetch_add(1, Ordering::Relaxed);
+            thread_ctx.request_repaint();
+        });
+
+        Self {
+            count,
+            open: true,
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        egui::Window::new(tr("background.title"))
+            .id(egui::Id::new("background_thread"))
+            .open(&mut self.open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(tr_format("background.ticks", &[("count", &self.count.load(Ordering::Relaxed))]));
+                ui.label(tr("background.hint"));
+            });
+    }
+}
+
+/// Lists the tasks of a [`TaskRunner`] with their progress.
+pub struct TasksWindow {
+    open: bool,
+}
+
+impl Default for TasksWindow {
+    fn default() -> Self {
+        Self {
+            open: true,
+        }
+    }
+}
+
+/// The demo tasks the [`TasksWindow`] can start.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DemoTask {
+    /// Counts to 100 in five seconds and logs along the way.
+    Steps,
+    /// Just takes ten seconds, to try what happens when a task finishes while the app is in the background.
+    Sleep,
+}
+
+impl TasksWindow {
+    /// Returns the demo task the user asked to start.
+    pub fn show(&mut self, ctx: &egui::Context, tasks: &mut TaskRunner) -> Option<DemoTask> {
+        let mut start_demo = None;
+
+        egui::Window::new(tr("tasks.title"))
+            .id(egui::Id::new("tasks_window"))
+            .open(&mut self.open)
+            .default_width(280.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button(tr("tasks.start_demo")).clicked() {
+                        start_demo = Some(DemoTask::Steps);
+                    }
+                    if ui.button(tr("tasks.sleep")).on_hover_text(tr("tasks.sleep_hint")).clicked() {
+                        start_demo = Some(DemoTask::Sleep);
+                    }
+                    if ui.button(tr("tasks.clear_finished")).clicked() {
+                        tasks.clear_finished();
+                    }
+                });
+                ui.separator();
+
+                if tasks.tasks().is_empty() {
+                    ui.label(tr("tasks.none"));
+                }
+
+                for task in tasks.tasks() {
+                    ui.horizontal(|ui| {
+                        ui.label(&task.name);
+                        if task.status == TaskStatus::Running && ui.button(tr("tasks.cancel")).clicked() {
+                            task.cancel();
+                        }
+                    });
+
+                    match task.status {
+                        TaskStatus::Queued | TaskStatus::Running => {
+                            ui.add(egui::ProgressBar::new(task.progress)
+                                .show_percentage()
+                                .animate(task.status == TaskStatus::Running && !MotionPrefs::get(ui.ctx()).reduce_motion));
+                        },
+                        TaskStatus::Completed => {
+                            ui.label(tr("tasks.status_completed"));
+                        },
+                        TaskStatus::Cancelled => {
+                            ui.label(tr("tasks.status_cancelled"));
+                        },
+                        TaskStatus::Failed => {
+                            ui.label(tr("tasks.status_failed"));
+                        },
+                    }
+
+                    if !task.log.is_empty() {
+                        egui::CollapsingHeader::new(tr("tasks.log"))
+                            .id_salt(task.id)
+                            .show(ui, |ui| {
+                                for line in &task.log {
+                                    ui.label(line);
+                                }
+                            });
+                    }
+                    ui.separator();
+                }
+            });
+
+        start_demo
+    }
+}
+
+/// Buttons for firing off test notifications and picking the corner they show up in.
+#[derive(Default)]
+pub struct ToastsDemo {
+    open: bool,
+}
+
+impl ToastsDemo {
+    pub fn open(&mut self) {
+        self.open = true;
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, toasts: &mut Toasts) {
+        egui::Window::new(tr("toasts.title"))
+            .tool_window(ctx, ToolWindow::Toasts)
+            .open(&mut self.open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button(tr("toasts.info")).clicked() {
+                        toasts.info(tr("toasts.info_text"));
+                    }
+                    if ui.button(tr("toasts.success")).clicked() {
+                        toasts.success(tr("toasts.success_text"));
+                    }
+                    if ui.button(tr("toasts.error")).clicked() {
+                        toasts.error(tr("toasts.error_text"));
+                    }
+                });
+
+                egui::ComboBox::from_label(tr("toasts.corner"))
+                    .selected_text(corner_name(toasts.anchor))
+                    .show_ui(ui, |ui| {
+                        for corner in [egui::Align2::LEFT_TOP, egui::Align2::RIGHT_TOP, egui::Align2::LEFT_BOTTOM, egui::Align2::RIGHT_BOTTOM] {
+                            ui.selectable_value(&mut toasts.anchor, corner, corner_name(corner));
+                        }
+                    });
+
+                ui.add(egui::Slider::new(&mut toasts.timeout, 1.0..=10.0).text(tr("toasts.timeout")).suffix(" s"));
+            });
+    }
+}
+
+fn corner_name(corner: egui::Align2) -> &'static str {
+    match (corner.x(), corner.y()) {
+        (egui::Align::Min, egui::Align::Min) => tr("toasts.top_left"),
+        (egui::Align::Max, egui::Align::Min) => tr("toasts.top_right"),
+        (egui::Align::Min, egui::Align::Max) => tr("toasts.bottom_left"),
+        _ => tr("toasts.bottom_right"),
+    }
+}
+
+/// Shows the records collected by [`crate::logging`], newest at the bottom.
+pub struct LogConsoleWindow {
+    open: bool,
+    /// Indexed by `log::Level as usize - 1`.
+    show_levels: [bool; 5],
+    filter: String,
+    /// Enter in the filter shows all records again and jumps to the first match.
+    filter_lifted: bool,
+    auto_scroll: bool,
+    /// The record jumped to, by time and message, and when.
+    highlighted: Option<(u64, String, f64)>,
+}
+
+impl Default for LogConsoleWindow {
+    fn default() -> Self {
+        Self {
+            open: false,
+            show_levels: [true, true, true, true, false],
+            filter: String::new(),
+            filter_lifted: false,
+            auto_scroll: true,
+            highlighted: None,
+        }
+    }
+}
+
+impl LogConsoleWindow {
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn open(&mut self) {
+        self.open = true;
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, logs: &LogBuffer) {
+        let mut open = self.open;
+
+        egui::Window::new(tr("log.title"))
+            .tool_window(ctx, ToolWindow::LogConsole)
+            .open(&mut open)
+            .default_size([600.0, 300.0])
+            .show(ctx, |ui| {
+                self.ui(ui, logs);
+            });
+
+        self.open = open;
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, logs: &LogBuffer) {
+        ui.horizontal(|ui| {
+            for level in [log::Level::Error, log::Level::Warn, log::Level::Info, log::Level::Debug, log::Level::Trace] {
+                ui.checkbox(&mut self.show_levels[level as usize - 1], level.as_str());
+            }
+        });
+
+        let mut jump = false;
+        ui.horizontal(|ui| {
+            let response = ui.add(egui::TextEdit::singleline(&mut self.filter).hint_text(tr("log.filter")))
+                .on_hover_text(tr("log.filter_hint"));
+            if response.changed() {
+                self.filter_lifted = false;
+            }
+            jump = !self.filter.is_empty() && response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter));
+            ui.checkbox(&mut self.auto_scroll, tr("log.auto_scroll"));
+        });
+
+        let records = logs.records();
+        let filter = self.filter.to_lowercase();
+        let matches = |record: &crate::logging::LogRecord| filter.is_empty()
+            || record.message.to_lowercase().contains(&filter)
+            || record.target.to_lowercase().contains(&filter);
+        let filter_lifted = self.filter_lifted || jump;
+        let visible: Vec<usize> = records.iter().enumerate()
+            .filter(|(_, record)| self.show_levels[record.level as usize - 1])
+            .filter(|(_, record)| filter_lifted || matches(record))
+            .map(|(i, _)| i)
+            .collect();
+        let jump_to = jump.then(|| visible.iter().position(|&i| matches(&records[i]))).flatten();
+
+        let mut clear = false;
+        ui.horizontal(|ui| {
+            if ui.button(tr("log.copy")).on_hover_text(tr("log.copy_hint")).clicked() {
+                let text = visible.iter()
+                    .map(|&i| {
+                        let record = &records[i];
+                        format!("{} {:<5} {}: {}", record.time_of_day(), record.level, record.target, record.message)
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                ui.ctx().copy_text(text);
+            }
+            clear = ui.button(tr("log.clear")).clicked();
+            ui.label(tr_format("log.count", &[("visible", &visible.len()), ("total", &records.len())]));
+        });
+        ui.separator();
+
+        let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+        let mut scroll_area = egui::ScrollArea::both().auto_shrink(false);
+        if let Some(row) = jump_to {
+            // Following new records would scroll away from it right away.
+            self.filter_lifted = true;
+            self.auto_scroll = false;
+            let record = &records[visible[row]];
+            self.highlighted = Some((record.timestamp_ms, record.message.clone(), ui.input(|input| input.time)));
+            let spacing = row_height + ui.spacing().item_spacing.y;
+            scroll_area = scroll_area.vertical_scroll_offset((row as f32 * spacing - (ui.available_height() - spacing) / 2.0).max(0.0));
+        }
+        let mut highlight_over = false;
+        scroll_area
+            .stick_to_bottom(self.auto_scroll)
+            .show_rows(ui, row_height, visible.len(), |ui, rows| {
+                for &i in &visible[rows] {
+                    let record = &records[i];
+                    let background = ui.painter().add(egui::Shape::Noop);
+                    let row = ui.horizontal(|ui| {
+                        ui.monospace(record.time_of_day());
+                        ui.label(egui::RichText::new(format!("{:<5}", record.level)).monospace().color(level_color(record.level, ui.visuals())));
+                        ui.label(egui::RichText::new(&record.target).monospace().weak());
+                        ui.monospace(&record.message);
+                    });
+                    let Some((_, _, since)) = self.highlighted.as_ref().filter(|(time, message, _)| *time == record.timestamp_ms && *message == record.message) else {
+                        continue;
+                    };
+                    // Takes the focus from the filter, so the arrow and page keys scroll from here.
+                    let focus = ui.interact(row.response.rect, ui.id().with(("log_record", i)), egui::Sense::focusable_noninteractive());
+                    if jump_to.is_some() {
+                        focus.request_focus();
+                    }
+                    highlight_over = !fade_highlight(ui, background, row.response.rect, *since);
+                }
+            });
+        if highlight_over {
+            self.highlighted = None;
+        }
+
+        drop(records);
+        if clear {
+            logs.clear();
+        }
+    }
+}
+
+fn level_color(level: log::Level, visuals: &egui::Visuals) -> egui::Color32 {
+    match level {
+        log::Level::Error => visuals.error_fg_color,
+        log::Level::Warn => visuals.warn_fg_color,
+        log::Level::Info => visuals.text_color(),
+        log::Level::Debug | log::Level::Trace => visuals.weak_text_color(),
+    }
+}
+
+/// How long an item jumped to by a search is highlighted, in seconds.
+const SEARCH_HIGHLIGHT_TIME: f64 = 1.5;
+
+/// Fills the background slot `shape` behind `rect` with a highlight that fades out `SEARCH_HIGHLIGHT_TIME` after
+/// `since`, asking for frames until then. Returns false once it's over.
+fn fade_highlight(ui: &egui::Ui, shape: egui::layers::ShapeIdx, rect: egui::Rect, since: f64) -> bool {
+    let t = (ui.input(|input| input.time) - since) / SEARCH_HIGHLIGHT_TIME;
+    if t >= 1.0 {
+        return false;
+    }
+    // With reduced motion it stays solid and goes away at once.
+    let reduce_motion = MotionPrefs::get(ui.ctx()).reduce_motion;
+    let opacity = if reduce_motion { 1.0 } else { 1.0 - t as f32 };
+    let color = ui.visuals().selection.bg_fill.gamma_multiply(opacity);
+    ui.painter().set(shape, egui::epaint::RectShape::filled(rect.expand(2.0), 2.0, color));
+    if reduce_motion {
+        ui.ctx().request_repaint_after_secs(((1.0 - t) * SEARCH_HIGHLIGHT_TIME) as f32);
+    } else {
+        ui.ctx().request_repaint();
+    }
+    true
+}
+
+/// Puts the rows of a horizontal layout together into one search item: the rect is the row's, everything else the
+/// response of the widget `add` returns, which gets the focus when the search jumps there.
+fn search_row(ui: &mut egui::Ui, add: impl FnOnce(&mut egui::Ui) -> egui::Response) -> egui::Response {
+    let row = ui.horizontal(add);
+    row.inner.union(row.response)
+}
+
+/// The section being shown while a query filters the items, see [`SearchJump::section`].
+struct SearchSection {
+    id: egui::Id,
+    title: String,
+    title_matches: bool,
+    heading_shown: bool,
+}
+
+/// A search box for the items of a window, which are added with [`SearchJump::item`] in collapsible
+/// [`SearchJump::section`]s. While there's a query only the matching items are shown. Enter shows all of them again
+/// and jumps to the first match: its section opens, it's scrolled into view, gets the keyboard focus and flashes.
+#[derive(Default)]
+struct SearchJump {
+    query: String,
+    filtering: bool,
+    /// Enter was pressed in this frame, the first matching item becomes the jump target.
+    enter_pressed: bool,
+    /// The jump target was found, all items are shown again from the next frame on.
+    filtering_ends: bool,
+    /// The section and texts of the item to jump to once it's shown.
+    jump_target: Option<(egui::Id, String)>,
+    section: Option<SearchSection>,
+    /// The item jumped to and when.
+    highlighted: Option<(egui::Id, f64)>,
+}
+
+impl SearchJump {
+    fn search_box(&mut self, ui: &mut egui::Ui) {
+        if std::mem::take(&mut self.filtering_ends) {
+            self.filtering = false;
+        }
+        let response = ui.add(egui::TextEdit::singleline(&mut self.query).hint_text(tr("search.hint")));
+        if response.changed() {
+            self.filtering = !self.query.is_empty();
+            self.jump_target = None;
+        }
+        self.enter_pressed = self.filtering && response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter));
+    }
+
+    fn matches(&self, text: &str) -> bool {
+        text.to_lowercase().contains(&self.query.to_lowercase())
+    }
+
+    /// A collapsible group of items, `key` identifies it whatever the language of `title` is.
+    fn section(&mut self, ui: &mut egui::Ui, key: &str, title: &str, add_contents: impl FnOnce(&mut Self, &mut egui::Ui)) {
+        let id = ui.make_persistent_id(key);
+        if self.filtering {
+            // The heading only comes before the first match, a section without one isn't shown at all.
+            self.section = Some(SearchSection { id, title: title.to_owned(), title_matches: self.matches(title), heading_shown: false });
+            add_contents(self, ui);
+            self.section = None;
+            return;
+        }
+        let jump_here = self.jump_target.as_ref().is_some_and(|(section, _)| *section == id);
+        egui::CollapsingHeader::new(egui::RichText::new(title).strong())
+            .id_salt(key)
+            .default_open(true)
+            .open(jump_here.then_some(true))
+            .show(ui, |ui| {
+                self.section = Some(SearchSection { id, title: title.to_owned(), title_matches: false, heading_shown: true });
+                add_contents(self, ui);
+                self.section = None;
+            });
+    }
+
+    /// An item with the texts it's found by, `add` adds its widgets.
+    fn item(&mut self, ui: &mut egui::Ui, texts: &[&str], add: impl FnOnce(&mut egui::Ui) -> egui::Response) {
+        let section_id = self.section.as_ref().map_or(egui::Id::NULL, |section| section.id);
+        let key = texts.join("\n");
+        if self.filtering {
+            let title_matches = self.section.as_ref().is_some_and(|section| section.title_matches);
+            if !title_matches && !texts.iter().any(|text| self.matches(text)) {
+                return;
+            }
+            if let Some(section) = self.section.as_mut().filter(|section| !section.heading_shown) {
+                section.heading_shown = true;
+                ui.strong(&section.title);
+            }
+        }
+        let background = ui.painter().add(egui::Shape::Noop);
+        let response = add(ui);
+        if std::mem::take(&mut self.enter_pressed) {
+            self.filtering_ends = true;
+            self.jump_target = Some((section_id, key));
+            ui.ctx().request_repaint();
+        } else if !self.filtering && self.jump_target.as_ref().is_some_and(|target| *target == (section_id, key)) {
+            self.jump_target = None;
+            response.scroll_to_me(Some(egui::Align::Center));
+            response.request_focus();
+            self.highlighted = Some((response.id, ui.input(|input| input.time)));
+        }
+        if let Some((_, since)) = self.highlighted.filter(|(id, _)| *id == response.id) {
+            if !fade_highlight(ui, background, response.rect, since) {
+                self.highlighted = None;
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct SettingsWindow {
+    open: bool,
+    search: SearchJump,
+}
+
+impl SettingsWindow {
+    pub fn open(&mut self) {
+        self.open = true;
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, settings: &mut Settings) {
+        let mut open = self.open;
+        egui::Window::new(tr("settings.title"))
+            .tool_window(ctx, ToolWindow::Settings)
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                self.search.search_box(ui);
+                ui.separator();
+                egui::ScrollArea::vertical().show(ui, |ui| Self::ui(&mut self.search, ui, settings));
+            });
+        self.open = open;
+    }
+
+    fn ui(search: &mut SearchJump, ui: &mut egui::Ui, settings: &mut Settings) {
+        search.section(ui, "window", tr("settings.window"), |search, ui| {
+            search.item(ui, &[tr("settings.window_title")], |ui| search_row(ui, |ui| {
+                ui.label(tr("settings.window_title"));
+                ui.text_edit_singleline(&mut settings.title)
+            }));
+            search.item(ui, &[tr("settings.suffix")], |ui| search_row(ui, |ui| {
+                ui.label(tr("settings.suffix"));
+                let first = ui.radio_value(&mut settings.title_suffix, TitleSuffix::None, tr("settings.none"));
+                ui.radio_value(&mut settings.title_suffix, TitleSuffix::Fps, tr("settings.fps"));
+                ui.radio_value(&mut settings.title_suffix, TitleSuffix::UnsavedMarker, tr("settings.unsaved_changes"));
+                first
+            }));
+            search.item(ui, &[tr("settings.window_level"), tr("settings.window_level_top"), tr("settings.window_level_bottom")], |ui| search_row(ui, |ui| {
+                ui.label(tr("settings.window_level"));
+                let first = ui.radio_value(&mut settings.window_level, WindowLevel::Normal, tr("settings.window_level_normal"));
+                ui.radio_value(&mut settings.window_level, WindowLevel::AlwaysOnTop, tr("settings.window_level_top"));
+                ui.radio_value(&mut settings.window_level, WindowLevel::AlwaysOnBottom, tr("settings.window_level_bottom"));
+                first
+            }));
+            search.item(ui, &[tr("settings.skip_taskbar")], |ui| {
+                ui.add_enabled(cfg!(target_os = "windows"), egui::Checkbox::new(&mut settings.skip_taskbar, tr("settings.skip_taskbar")))
+                    .on_disabled_hover_text(tr("settings.skip_taskbar_unsupported"))
+            });
+            let tray = cfg!(all(target_os = "windows", feature = "tray"));
+            search.item(ui, &[tr("settings.close_to_tray")], |ui| {
+                ui.add_enabled(tray, egui::Checkbox::new(&mut settings.close_to_tray, tr("settings.close_to_tray")))
+                    .on_disabled_hover_text(tr("settings.tray_unsupported"))
+            });
+            search.item(ui, &[tr("settings.start_in_tray")], |ui| {
+                ui.add_enabled(tray, egui::Checkbox::new(&mut settings.start_in_tray, tr("settings.start_in_tray")))
+                    .on_disabled_hover_text(tr("settings.tray_unsupported"))
+            });
+            search.item(ui, &[tr("settings.resizable")], |ui| ui.checkbox(&mut settings.resizable, tr("settings.resizable")));
+        });
+
+        search.section(ui, "saving", tr("settings.saving"), |search, ui| {
+            search.item(ui, &[tr("settings.autosave")], |ui| {
+                ui.checkbox(&mut settings.autosave, tr("settings.autosave"))
+                    .on_hover_text(tr("settings.autosave_hint"))
+            });
+            search.item(ui, &[tr("settings.autosave_seconds")], |ui| {
+                ui.add_enabled(
+                    settings.autosave,
+                    egui::Slider::new(&mut settings.autosave_seconds, 5..=600).logarithmic(true).text(tr("settings.autosave_seconds")),
+                )
+            });
+        });
+
+        search.section(ui, "appearance", tr("settings.appearance"), |search, ui| {
+            search.item(ui, &[tr("settings.language")], |ui| {
+                egui::ComboBox::from_label(tr("settings.language"))
+                    .selected_text(settings.language.native_name())
+                    .show_ui(ui, |ui| {
+                        for language in Language::ALL {
+                            ui.selectable_value(&mut settings.language, language, language.native_name());
+                        }
+                    })
+                    .response
+            });
+            search.item(ui, &[tr("settings.theme")], |ui| search_row(ui, |ui| {
+                ui.label(tr("settings.theme"));
+                let first = ui.radio_value(&mut settings.theme, Theme::System, tr("settings.theme_system"));
+                ui.radio_value(&mut settings.theme, Theme::Light, tr("settings.theme_light"));
+                ui.radio_value(&mut settings.theme, Theme::Dark, tr("settings.theme_dark"));
+                first
+            }));
+            search.item(ui, &[tr("settings.zoom")], |ui| {
+                ui.add(egui::Slider::new(&mut settings.zoom_factor, 0.5..=3.0).step_by(0.05).text(tr("settings.zoom")))
+                    .on_hover_text(tr("settings.zoom_hint"))
+            });
+            search.item(ui, &[tr("settings.pixel_snap")], |ui| search_row(ui, |ui| {
+                ui.label(tr("settings.pixel_snap")).on_hover_text(tr("settings.pixel_snap_hint"));
+                let first = ui.radio_value(&mut settings.pixel_snap, PixelSnap::Off, tr("settings.pixel_snap_off"));
+                ui.radio_value(&mut settings.pixel_snap, PixelSnap::Quarter, tr("settings.pixel_snap_quarter"));
+                ui.radio_value(&mut settings.pixel_snap, PixelSnap::Whole, tr("settings.pixel_snap_whole"));
+                first
+            }));
+        });
+
+        search.section(ui, "accessibility", tr("settings.accessibility"), |search, ui| {
+            search.item(ui, &[tr("settings.motion"), tr("settings.motion_reduced")], |ui| search_row(ui, |ui| {
+                ui.label(tr("settings.motion")).on_hover_text(tr("settings.motion_hint"));
+                let first = ui.radio_value(&mut settings.motion, Motion::System, tr("settings.motion_system"));
+                ui.radio_value(&mut settings.motion, Motion::Reduced, tr("settings.motion_reduced"));
+                ui.radio_value(&mut settings.motion, Motion::Full, tr("settings.motion_full"));
+                first
+            }));
+            search.item(ui, &[tr("settings.feathering")], |ui| {
+                ui.checkbox(&mut settings.feathering, tr("settings.feathering"))
+                    .on_hover_text(tr("settings.feathering_hint"))
+            });
+            search.item(ui, &[tr("settings.min_ui_scale")], |ui| {
+                ui.add(egui::Slider::new(&mut settings.min_ui_scale, 1.0..=2.0).step_by(0.05).text(tr("settings.min_ui_scale")))
+                    .on_hover_text(tr("settings.min_ui_scale_hint"))
+            });
+        });
+
+        search.section(ui, "input", tr("settings.input"), |search, ui| {
+            search.item(ui, &[tr("settings.line_scroll_speed")], |ui| {
+                ui.add(egui::Slider::new(&mut settings.line_scroll_speed, 0.1..=5.0).logarithmic(true).text(tr("settings.line_scroll_speed")))
+                    .on_hover_text(tr("settings.line_scroll_speed_hint"))
+            });
+            search.item(ui, &[tr("settings.pixel_scroll_speed")], |ui| {
+                ui.add(egui::Slider::new(&mut settings.pixel_scroll_speed, 0.1..=5.0).logarithmic(true).text(tr("settings.pixel_scroll_speed")))
+                    .on_hover_text(tr("settings.pixel_scroll_speed_hint"))
+            });
+            search.item(ui, &[tr("settings.smooth_scrolling")], |ui| ui.checkbox(&mut settings.smooth_scrolling, tr("settings.smooth_scrolling")));
+            search.item(ui, &[tr("settings.shift_scroll_horizontal")], |ui| {
+                ui.checkbox(&mut settings.shift_scroll_horizontal, tr("settings.shift_scroll_horizontal"))
+            });
+            search.item(ui, &[tr("settings.key_repeat")], |ui| {
+                ui.checkbox(&mut settings.key_repeat, tr("settings.key_repeat"))
+                    .on_hover_text(tr("settings.key_repeat_hint"))
+            });
+            search.item(ui, &[tr("settings.on_screen_keyboard")], |ui| {
+                ui.checkbox(&mut settings.on_screen_keyboard, tr("settings.on_screen_keyboard"))
+                    .on_hover_text(tr("settings.on_screen_keyboard_hint"))
+            });
+        });
+
+        search.section(ui, "background", tr("settings.background"), |search, ui| {
+            search.item(ui, &[tr("settings.throttle")], |ui| {
+                ui.checkbox(&mut settings.throttle_in_background, tr("settings.throttle"))
+                    .on_hover_text(tr("settings.throttle_hint"))
+            });
+            search.item(ui, &[tr("settings.unfocused_fps")], |ui| {
+                ui.add_enabled(
+                    settings.throttle_in_background,
+                    egui::Slider::new(&mut settings.unfocused_fps, 1.0..=60.0).text(tr("settings.unfocused_fps")),
+                )
+            });
+            search.item(ui, &[tr("settings.trim_when_idle")], |ui| {
+                ui.checkbox(&mut settings.trim_when_idle, tr("settings.trim_when_idle"))
+                    .on_hover_text(tr("settings.trim_when_idle_hint"))
+            });
+            search.item(ui, &[tr("settings.idle_trim_minutes")], |ui| {
+                ui.add_enabled(
+                    settings.trim_when_idle,
+                    egui::Slider::new(&mut settings.idle_trim_minutes, 1..=120).text(tr("settings.idle_trim_minutes")),
+                )
+            });
+        });
+
+        search.section(ui, "frame_limiter", tr("settings.frame_limiter"), |search, ui| {
+            search.item(ui, &[tr("settings.limit_fps")], |ui| {
+                ui.checkbox(&mut settings.limit_fps, tr("settings.limit_fps"))
+                    .on_hover_text(tr("settings.limit_fps_hint"))
+            });
+            search.item(ui, &[tr("settings.target_fps")], |ui| {
+                ui.add_enabled(
+                    settings.limit_fps,
+                    egui::Slider::new(&mut settings.target_fps, 10.0..=360.0).text(tr("settings.target_fps")),
+                )
+            });
+        });
+
+        search.section(ui, "latency", tr("settings.latency"), |search, ui| {
+            search.item(ui, &[tr("settings.frame_latency")], |ui| {
+                ui.add(egui::Slider::new(&mut settings.frame_latency, 1..=3).text(tr("settings.frame_latency")))
+                    .on_hover_text(tr("settings.frame_latency_hint"))
+            });
+            search.item(ui, &[tr("settings.low_latency")], |ui| {
+                ui.checkbox(&mut settings.low_latency, tr("settings.low_latency"))
+                    .on_hover_text(tr("settings.low_latency_hint"))
+            });
+            search.item(ui, &[tr("settings.wait_for_gpu")], |ui| {
+                ui.add_enabled(settings.low_latency, egui::Checkbox::new(&mut settings.wait_for_gpu, tr("settings.wait_for_gpu")))
+            });
+        });
+
+        search.section(ui, "resolution", tr("settings.resolution"), |search, ui| {
+            search.item(ui, &[tr("settings.render_scale")], |ui| {
+                ui.add(egui::Slider::new(&mut settings.render_scale, 0.5..=2.0).step_by(0.05).text(tr("settings.render_scale")))
+                    .on_hover_text(tr("settings.render_scale_hint"))
+            });
+            search.item(ui, &[tr("settings.linear"), tr("settings.nearest")], |ui| {
+                ui.add_enabled_ui(settings.render_scale != 1.0, |ui| search_row(ui, |ui| {
+                    let first = ui.radio_value(&mut settings.scale_filter, ScaleFilter::Linear, tr("settings.linear"));
+                    ui.radio_value(&mut settings.scale_filter, ScaleFilter::Nearest, tr("settings.nearest"));
+                    first
+                })).inner
+            });
+        });
+
+        search.section(ui, "color", tr("settings.color"), |search, ui| {
+            search.item(ui, &[tr("settings.dithering")], |ui| {
+                ui.checkbox(&mut settings.dithering, tr("settings.dithering"))
+                    .on_hover_text(tr("settings.dithering_hint"))
+            });
+            search.item(ui, &[tr("settings.srgb_view")], |ui| {
+                ui.checkbox(&mut settings.srgb_view, tr("settings.srgb_view"))
+                    .on_hover_text(tr("settings.srgb_view_hint"))
+            });
+            search.item(ui, &[tr("settings.msaa")], |ui| search_row(ui, |ui| {
+                ui.label(tr("settings.msaa"));
+                let mut first = None;
+                for samples in [1, 2, 4, 8] {
+                    let text = if samples == 1 { tr("settings.msaa_off").to_owned() } else { format!("{samples}×") };
+                    let response = ui.radio_value(&mut settings.msaa_samples, samples, text);
+                    first.get_or_insert(response);
+                }
+                first.unwrap()
+            }));
+        });
+
+        search.section(ui, "post_processing", tr("settings.post_processing"), |search, ui| {
+            search.item(ui, &[tr("settings.fxaa"), tr("settings.color_adjust")], |ui| search_row(ui, |ui| {
+                let first = ui.radio_value(&mut settings.post_effect, PostEffect::None, tr("settings.none"));
+                ui.radio_value(&mut settings.post_effect, PostEffect::Fxaa, tr("settings.fxaa"));
+                ui.radio_value(&mut settings.post_effect, PostEffect::ColorAdjust, tr("settings.color_adjust"));
+                first
+            }));
+            match settings.post_effect {
+                PostEffect::None => {},
+                PostEffect::Fxaa => {
+                    search.item(ui, &[tr("settings.search_span")], |ui| {
+                        ui.add(egui::Slider::new(&mut settings.fxaa_span_max, 1.0..=16.0).text(tr("settings.search_span")))
+                    });
+                },
+                PostEffect::ColorAdjust => {
+                    search.item(ui, &[tr("settings.gamma")], |ui| ui.add(egui::Slider::new(&mut settings.gamma, 0.2..=3.0).text(tr("settings.gamma"))));
+                    search.item(ui, &[tr("settings.brightness")], |ui| {
+                        ui.add(egui::Slider::new(&mut settings.brightness, -0.5..=0.5).text(tr("settings.brightness")))
+                    });
+                },
+            }
+        });
+    }
+}
+
+#[derive(Default)]
+pub struct DiagnosticsWindow {
+    open: bool,
+    /// Shown in a palette window of its own instead, until that's closed.
+    detached: Option<Palette>,
+}
+
+impl DiagnosticsWindow {
+    pub fn open(&mut self) {
+        self.open = true;
+    }
+
+    pub fn is_detached(&self) -> bool {
+        self.detached.is_some()
+    }
+
+    /// Moves the diagnostics into a palette window or back into the main window, where they're shown either way.
+    pub fn set_detached(&mut self, detached: bool) {
+        self.open = true;
+        if detached != self.is_detached() {
+            self.detached = detached.then(|| Palette::new("diagnostics", tr("diagnostics.title").to_owned()));
+        }
+    }
+
+    /// `render_size` is the resolution the UI is actually rendered at, which differs from the window with a render scale.
+    pub fn show(&mut self, ctx: &egui::Context, stats: &FrameStats, ui_stats: &UiStats, settings: &Settings, render_size: [u32; 2], watchdog: &Watchdog) {
+        let contents = |ui: &mut egui::Ui| diagnostics_grid(ui, stats, ui_stats, settings, render_size, watchdog);
+        if let Some(palette) = &mut self.detached {
+            // Closing the palette puts the diagnostics back into the main window.
+            if !palette.show(ctx, contents) {
+                self.detached = None;
+            }
+            return;
+        }
+        egui::Window::new(tr("diagnostics.title"))
+            .tool_window(ctx, ToolWindow::Diagnostics)
+            .open(&mut self.open)
+            .resizable(false)
+            .show(ctx, contents);
+    }
+}
+
+fn diagnostics_grid(ui: &mut egui::Ui, stats: &FrameStats, ui_stats: &UiStats, settings: &Settings, render_size: [u32; 2], watchdog: &Watchdog) {
+    egui::Grid::new("diagnostics").num_columns(2).show(ui, |ui| {
+        ui.label(tr("diagnostics.fps"));
+        let achieved = stats.achieved_fps().map_or("-".to_owned(), |fps| format!("{fps:.1}"));
+        let target = settings.frame_limit().map_or(tr("diagnostics.uncapped").to_owned(), |fps| format!("{fps:.0}"));
+        ui.label(tr_format("diagnostics.fps_value", &[("achieved", &achieved), ("target", &target)]));
+        ui.end_row();
+
+        ui.label(tr("diagnostics.acquire_wait"));
+        ui.label(format_duration(stats.average_acquire_wait()));
+        ui.end_row();
+
+        ui.label(tr("diagnostics.acquire_to_present"));
+        ui.label(format_duration(stats.average_acquire_to_present()));
+        ui.end_row();
+
+        ui.label(tr("diagnostics.render_resolution"));
+        ui.label(format!("{} × {} ({:.2}×)", render_size[0], render_size[1], settings.render_scale));
+        ui.end_row();
+
+        ui.label(tr("diagnostics.display"));
+        let frame_time = FrameTime::get(ui.ctx());
+        ui.label(frame_time.and_then(|time| time.refresh_rate_hz).map_or(tr("diagnostics.unknown").to_owned(), |hz| format!("{hz:.2} Hz")));
+        ui.end_row();
+
+        ui.label(tr("diagnostics.long_frames"));
+        ui.label(tr_format("diagnostics.long_frames_value", &[
+            ("count", &watchdog.long_frames()),
+            ("budget", &format_duration(Some(watchdog.budget()))),
+            ("longest", &format_duration(Some(watchdog.longest_frame()))),
+        ]));
+        ui.end_row();
+
+        ui.label(tr("diagnostics.frame_time"));
+        ui.label(frame_time.map_or("-".to_owned(), |time| {
+            format!("{:.1} s, {}", time.time.as_secs_f64(), format_duration(Some(time.dt)))
+        }));
+        ui.end_row();
+
+        ui.label(tr("diagnostics.clipped_primitives"));
+        ui.label(tr_format("diagnostics.clipped_primitives_value", &[("primitives", &ui_stats.clipped_primitives), ("triangles", &ui_stats.triangles())]));
+        ui.end_row();
+
+        // Their union, so never over 100%. How often what they cover is drawn over is what the overdraw view shows.
+        ui.label(tr("diagnostics.clip_rects"));
+        ui.label(tr_format("diagnostics.clip_rects_value", &[("percent", &format!("{:.0}", ui_stats.clip_coverage * 100.0))]));
+        ui.end_row();
+    });
+}
+
+fn format_duration(duration: Option<Duration>) -> String {
+    duration.map_or("-".to_owned(), |duration| format!("{:.2} ms", duration.as_secs_f64() * 1000.0))
+}
+
+/// A surface setting picked in the [`SurfaceInfoWindow`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SurfaceChange {
+    Format(wgpu::TextureFormat),
+    PresentMode(wgpu::PresentMode),
+    AlphaMode(wgpu::CompositeAlphaMode),
+}
+
+#[derive(Default)]
+pub struct SurfaceInfoWindow {
+    open: bool,
+}
+
+impl SurfaceInfoWindow {
+    pub fn open(&mut self) {
+        self.open = true;
+    }
+
+    /// Lists what the surface and adapter support, the active configuration is highlighted and clicking another
+    /// entry returns it so the surface can be reconfigured. `surface` is `None` while the app is suspended.
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        adapter: &wgpu::Adapter,
+        capabilities: &CapabilityReport,
+        surface: Option<&wgpu::Surface>,
+        config: &wgpu::SurfaceConfiguration,
+    ) -> Option<SurfaceChange> {
+        let mut change = None;
+
+        egui::Window::new(tr("surface_info.title"))
+            .tool_window(ctx, ToolWindow::SurfaceInfo)
+            .open(&mut self.open)
+            .default_size([360.0, 480.0])
+            .show(ctx, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    let info = adapter.get_info();
+                    egui::CollapsingHeader::new(tr("surface_info.adapter")).id_salt("adapter").default_open(true).show(ui, |ui| {
+                        egui::Grid::new("adapter_info").num_columns(2).show(ui, |ui| {
+                            for (name, value) in [
+                                (tr("surface_info.name"), info.name.clone()),
+                                (tr("surface_info.backend"), format!("{:?}", info.backend)),
+                                (tr("surface_info.device_type"), format!("{:?}", info.device_type)),
+                                (tr("surface_info.vendor_device"), format!("{:#06x} / {:#06x}", info.vendor, info.device)),
+                                (tr("surface_info.driver"), format!("{} {}", info.driver, info.driver_info)),
+                            ] {
+                                ui.label(name);
+                                ui.label(value);
+                                ui.end_row();
+                            }
+                        });
+                    });
+
+                    egui::CollapsingHeader::new(tr("surface_info.capabilities")).id_salt("capabilities").default_open(true).show(ui, |ui| {
+                        ui.weak(tr_format("surface_info.base_limits", &[("limits", &capabilities.base_limits)]));
+                        egui::Grid::new("device_capabilities").num_columns(3).show(ui, |ui| {
+                            for request in &capabilities.features {
+                                ui.label(format!("{:?}", request.feature));
+                                if request.granted {
+                                    ui.label(tr("surface_info.granted"));
+                                } else {
+                                    ui.colored_label(ui.visuals().warn_fg_color, tr("surface_info.denied"));
+                                }
+                                ui.weak(request.used_for);
+                                ui.end_row();
+                            }
+                            for request in &capabilities.limits {
+                                ui.label(request.name);
+                                if request.granted < request.requested {
+                                    ui.colored_label(ui.visuals().warn_fg_color, tr_format("surface_info.limit_of", &[("granted", &request.granted), ("requested", &request.requested)]));
+                                } else {
+                                    ui.label(request.granted.to_string());
+                                }
+                                ui.weak(request.used_for);
+                                ui.end_row();
+                            }
+                        });
+                    });
+
+                    egui::CollapsingHeader::new(tr("surface_info.features")).id_salt("features").show(ui, |ui| {
+                        for (name, _) in adapter.features().iter_names() {
+                            ui.label(name);
+                        }
+                    });
+
+                    egui::CollapsingHeader::new(tr("surface_info.limits")).id_salt("limits").show(ui, |ui| {
+                        ui.monospace(format!("{:#?}", adapter.limits()));
+                    });
+
+                    let Some(surface) = surface else {
+                        ui.label(tr("surface_info.suspended"));
+                        return;
+                    };
+                    let capabilities = surface.get_capabilities(adapter);
+
+                    egui::CollapsingHeader::new(tr("surface_info.formats")).id_salt("formats").default_open(true).show(ui, |ui| {
+                        for &format in &capabilities.formats {
+                            if ui.selectable_label(format == config.format, format!("{format:?}")).clicked() && format != config.format {
+                                change = Some(SurfaceChange::Format(format));
+                            }
+                        }
+                    });
+
+                    egui::CollapsingHeader::new(tr("surface_info.present_modes")).id_salt("present_modes").default_open(true).show(ui, |ui| {
+                        for &mode in &capabilities.present_modes {
+                            if ui.selectable_label(mode == config.present_mode, format!("{mode:?}")).clicked() && mode != config.present_mode {
+                                change = Some(SurfaceChange::PresentMode(mode));
+                            }
+                        }
+                    });
+
+                    egui::CollapsingHeader::new(tr("surface_info.alpha_modes")).id_salt("alpha_modes").default_open(true).show(ui, |ui| {
+                        for &mode in &capabilities.alpha_modes {
+                            if ui.selectable_label(mode == config.alpha_mode, format!("{mode:?}")).clicked() && mode != config.alpha_mode {
+                                change = Some(SurfaceChange::AlphaMode(mode));
+                            }
+                        }
+                    });
+
+                    egui::CollapsingHeader::new(tr("surface_info.usages")).id_salt("usages").show(ui, |ui| {
+                        for (name, usage) in capabilities.usages.iter_names() {
+                            // Only informational, the surface is used as nothing but a render target.
+                            ui.add_enabled(false, egui::SelectableLabel::new(config.usage.contains(usage), name));
+                        }
+                    });
+                });
+            });
+
+        change
+    }
+}
+
+#[derive(Default)]
+pub struct AdaptersWindow {
+    open: bool,
+    /// Enumerating adapters is slow, so it only happens when the window opens or on request.
+    adapters: Option<Vec<wgpu::AdapterInfo>>,
+}
+
+impl AdaptersWindow {
+    pub fn open(&mut self) {
+        self.open = true;
+        self.adapters = None;
+    }
+
+    /// Returns the adapter the user wants to switch to.
+    pub fn show(&mut self, ctx: &egui::Context, instance: &wgpu::Instance, current: &wgpu::AdapterInfo) -> Option<wgpu::AdapterInfo> {
+        let mut switch_to = None;
+        let mut refresh = false;
+        let adapters = &mut self.adapters;
+
+        egui::Window::new(tr("adapters.title"))
+            .tool_window(ctx, ToolWindow::Adapters)
+            .open(&mut self.open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let adapters = adapters.get_or_insert_with(|| {
+                    instance.enumerate_adapters(wgpu::Backends::all()).iter().map(wgpu::Adapter::get_info).collect()
+                });
+
+                egui::Grid::new("adapters").num_columns(3).striped(true).show(ui, |ui| {
+                    for adapter in adapters.iter() {
+                        let active = adapter == current;
+                        ui.add(egui::SelectableLabel::new(active, &adapter.name));
+                        ui.label(format!("{:?}, {:?}", adapter.backend, adapter.device_type));
+                        if ui.add_enabled(!active, egui::Button::new(tr("adapters.switch"))).clicked() {
+                            switch_to = Some(adapter.clone());
+                        }
+                        ui.end_row();
+                    }
+                });
+
+                refresh = ui.button(tr("adapters.refresh")).clicked();
+            });
+
+        if refresh {
+            self.adapters = None;
+        }
+        switch_to
+    }
+}
+
+/// Windows the main menu opens.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ToolWindow {
+    Gallery,
+    Toasts,
+    LogConsole,
+    Settings,
+    Diagnostics,
+    GpuMemory,
+    StressTest,
+    SurfaceInfo,
+    Adapters,
+    #[cfg(feature = "profiling")]
+    Profiler,
+    Images,
+    Painting,
+    Reorder,
+    Modal,
+    Camera,
+    ZoomCanvas,
+    BouncingBall,
+    Gradients,
+    PixelTest,
+    ColorTest,
+}
+
+impl ToolWindow {
+    /// The windows layout profiles keep, all but the modal demo, which is always centered.
+    pub const WITH_LAYOUT: &[ToolWindow] = &[
+        ToolWindow::Gallery,
+        ToolWindow::Toasts,
+        ToolWindow::LogConsole,
+        ToolWindow::Settings,
+        ToolWindow::Diagnostics,
+        ToolWindow::GpuMemory,
+        ToolWindow::StressTest,
+        ToolWindow::SurfaceInfo,
+        ToolWindow::Adapters,
+        #[cfg(feature = "profiling")]
+        ToolWindow::Profiler,
+        ToolWindow::Images,
+        ToolWindow::Painting,
+        ToolWindow::Reorder,
+        ToolWindow::Camera,
+        ToolWindow::ZoomCanvas,
+        ToolWindow::BouncingBall,
+        ToolWindow::Gradients,
+        ToolWindow::PixelTest,
+        ToolWindow::ColorTest,
+    ];
+
+    /// Stays the same across versions and languages, it names the window in the state file.
+    pub fn key(self) -> &'static str {
+        match self {
+            ToolWindow::Gallery => "widget_gallery",
+            ToolWindow::Toasts => "toasts_demo",
+            ToolWindow::LogConsole => "log_console",
+            ToolWindow::Settings => "settings_window",
+            ToolWindow::Diagnostics => "diagnostics",
+            ToolWindow::GpuMemory => "gpu_memory",
+            ToolWindow::StressTest => "stress_test",
+            ToolWindow::SurfaceInfo => "surface_info",
+            ToolWindow::Adapters => "adapters",
+            #[cfg(feature = "profiling")]
+            ToolWindow::Profiler => "profiler",
+            ToolWindow::Images => "image_demo",
+            ToolWindow::Painting => "painting",
+            ToolWindow::Reorder => "reorder",
+            ToolWindow::Modal => "modal_demo",
+            ToolWindow::Camera => "camera_demo",
+            ToolWindow::ZoomCanvas => "zoom_canvas",
+            ToolWindow::BouncingBall => "bouncing_ball",
+            ToolWindow::Gradients => "gradients",
+            ToolWindow::PixelTest => "pixel_test_pattern",
+            ToolWindow::ColorTest => "color_test",
+        }
+    }
+
+    /// Of the `egui::Window`, so its rect can be found in egui's memory.
+    pub fn id(self) -> egui::Id {
+        egui::Id::new(self.key())
+    }
+}
+
+/// The open flag of a tool window, read and set by layout profiles.
+pub trait ToolWindowOpen {
+    fn is_open(&self) -> bool;
+    fn set_open(&mut self, open: bool);
+}
+
+macro_rules! tool_window_open {
+    ($($window:ty),* $(,)?) => {
+        $(impl ToolWindowOpen for $window {
+            fn is_open(&self) -> bool {
+                self.open
+            }
+
+            fn set_open(&mut self, open: bool) {
+                self.open = open;
+            }
+        })*
+    };
+}
+
+tool_window_open!(
+    WidgetGallery, ToastsDemo, LogConsoleWindow, SettingsWindow, DiagnosticsWindow, GpuMemoryWindow, StressTest,
+    SurfaceInfoWindow, ImageDemo, PaintingDemo, ReorderDemo, CameraDemo, ZoomCanvas, BouncingBall, GradientWindow,
+    PixelTestPattern, ColorTestWindow,
+);
+#[cfg(feature = "profiling")]
+tool_window_open!(ProfilerWindow);
+
+impl ToolWindowOpen for AdaptersWindow {
+    fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Opening it lists the adapters again, like the menu does.
+    fn set_open(&mut self, open: bool) {
+        if open && !self.open {
+            self.open();
+        }
+        self.open = open;
+    }
+}
+
+/// Sets up an `egui::Window` as one of the tool windows, see [`ToolWindow`].
+trait ToolWindowExt {
+    fn tool_window(self, ctx: &egui::Context, window: ToolWindow) -> Self;
+}
+
+impl ToolWindowExt for egui::Window<'_> {
+    /// Gives it the id of `window`, and in the frame after switching layout profiles the rect the profile has for it.
+    /// Moves it up while its focused field is under the on-screen keyboard.
+    fn tool_window(self, ctx: &egui::Context, window: ToolWindow) -> Self {
+        let this = self.id(window.id());
+        if let Some(rect) = layouts::take_pending_rect(ctx, window) {
+            return this.current_pos(rect.min).fixed_size(layouts::content_size(ctx, rect.size()));
+        }
+        let lifted = on_screen_keyboard::take_lift(ctx, window.id())
+            .zip(ctx.memory(|memory| memory.area_rect(window.id())))
+            .map(|(by, rect)| rect.min - egui::vec2(0.0, by));
+        match lifted {
+            Some(pos) => this.current_pos(pos.max(egui::Pos2::ZERO)),
+            None => this,
+        }
+    }
+}
+
+/// Buttons of the input replay controls.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplayAction {
+    Record,
+    Play,
+    Stop,
+}
+
+/// What was picked in the main menu, see [`main_menu`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum MenuAction {
+    Open,
+    Save,
+    SaveAs,
+    OpenConfigFolder,
+    ClickMe,
+    Show(ToolWindow),
+    /// Starts a recording in the format, or stops the one that runs.
+    ToggleRecording(RecordingFormat),
+    Replay(ReplayAction),
+    NextMonitor,
+    SetAlwaysOnTop(bool),
+    /// Snapshots the tool windows as the layout profile with the name, replacing one that has it already.
+    SaveLayout(String),
+    SwitchLayout(String),
+    DeleteLayout(String),
+    /// Switches to the layout profile after the current one.
+    NextLayout,
+    /// Moves the diagnostics into a palette window of their own, or back into a window inside the main one.
+    DetachDiagnostics(bool),
+}
+
+/// What the main menu shows besides its buttons.
+#[derive(Debug, Clone, Default)]
+pub struct MenuState {
+    pub has_file: bool,
+    pub has_config_folder: bool,
+    /// The format of the recording that runs.
+    pub recording: Option<RecordingFormat>,
+    pub replay_idle: bool,
+    pub replay_recording: bool,
+    pub always_on_top: bool,
+    pub diagnostics_detached: bool,
+    /// Names of the layout profiles, and the one switched to last.
+    pub layouts: Vec<String>,
+    pub current_layout: Option<String>,
+}
+
+/// The menu of the main window. It only reports what was picked, the app applies that after the pass.
+pub fn main_menu(ui: &mut egui::Ui, state: &MenuState, debug: &mut DebugTools) -> Option<MenuAction> {
+    let mut action = None;
+    ui.menu_button(tr("menu.file"), |ui| {
+        if ui.button(tr("menu.open")).clicked() {
+            action = Some(MenuAction::Open);
+            ui.close_menu();
+        }
+        if ui.add_enabled(state.has_file, egui::Button::new(tr("menu.save"))).clicked() {
+            action = Some(MenuAction::Save);
+            ui.close_menu();
+        }
+        if ui.button(tr("menu.save_as")).clicked() {
+            action = Some(MenuAction::SaveAs);
+            ui.close_menu();
+        }
+        ui.separator();
+        if ui.add_enabled(state.has_config_folder, egui::Button::new(tr("menu.open_config_folder"))).clicked() {
+            action = Some(MenuAction::OpenConfigFolder);
+            ui.close_menu();
+        }
+    });
+    ui.menu_button(tr("menu.debug"), |ui| debug.menu(ui));
+    ui.menu_button(tr("menu.layouts"), |ui| layouts_menu(ui, state, &mut action));
+    ui.label(tr("menu.hello"));
+    ui.weak(tr("menu.scene_hint"));
+    ui.label(tr("menu.font_sample")).on_hover_text(tr("menu.font_sample_hint"));
+    if ui.button(tr("menu.click_me")).clicked() {
+        action = Some(MenuAction::ClickMe);
+    }
+    let windows = [
+        (ToolWindow::Gallery, "menu.gallery"),
+        (ToolWindow::Toasts, "menu.toasts"),
+        (ToolWindow::LogConsole, "menu.log_console"),
+        (ToolWindow::Settings, "menu.settings"),
+        (ToolWindow::Diagnostics, "menu.diagnostics"),
+        (ToolWindow::GpuMemory, "menu.gpu_memory"),
+        (ToolWindow::StressTest, "menu.stress_test"),
+        (ToolWindow::SurfaceInfo, "menu.surface_info"),
+        (ToolWindow::Adapters, "menu.adapters"),
+    ];
+    for (window, key) in windows {
+        if ui.button(tr(key)).clicked() {
+            action = Some(MenuAction::Show(window));
+        }
+    }
+    ui.horizontal(|ui| {
+        if let Some(format) = state.recording {
+            if ui.button(tr("menu.stop_recording")).clicked() {
+                action = Some(MenuAction::ToggleRecording(format));
+            }
+        } else {
+            if ui.button(tr("menu.record_pngs")).clicked() {
+                action = Some(MenuAction::ToggleRecording(RecordingFormat::PngSequence));
+            }
+            if ui.button(tr("menu.record_gif")).clicked() {
+                action = Some(MenuAction::ToggleRecording(RecordingFormat::Gif));
+            }
+        }
+    });
+    ui.horizontal(|ui| {
+        if state.replay_idle {
+            if ui.button(tr("menu.record_input")).clicked() {
+                action = Some(MenuAction::Replay(ReplayAction::Record));
+            }
+            if ui.button(tr("menu.play_back_input")).clicked() {
+                action = Some(MenuAction::Replay(ReplayAction::Play));
+            }
+        } else if ui.button(if state.replay_recording { tr("menu.stop_input_recording") } else { tr("menu.stop_playback") }).clicked() {
+            action = Some(MenuAction::Replay(ReplayAction::Stop));
+        }
+    });
+    #[cfg(feature = "profiling")]
+    if ui.button(tr("menu.profiler")).clicked() {
+        action = Some(MenuAction::Show(ToolWindow::Profiler));
+    }
+    let windows = [
+        (ToolWindow::Images, "menu.images"),
+        (ToolWindow::Painting, "menu.painting"),
+        (ToolWindow::Reorder, "menu.reorder"),
+        (ToolWindow::Modal, "menu.modal"),
+        (ToolWindow::Camera, "menu.camera"),
+        (ToolWindow::ZoomCanvas, "menu.zoom_canvas"),
+        (ToolWindow::BouncingBall, "menu.bouncing_ball"),
+        (ToolWindow::Gradients, "menu.gradients"),
+        (ToolWindow::PixelTest, "menu.pixel_test"),
+        (ToolWindow::ColorTest, "menu.color_test"),
+    ];
+    for (window, key) in windows {
+        if ui.button(tr(key)).clicked() {
+            action = Some(MenuAction::Show(window));
+        }
+    }
+    let mut diagnostics_detached = state.diagnostics_detached;
+    if ui.checkbox(&mut diagnostics_detached, tr("menu.detach_diagnostics")).changed() {
+        action = Some(MenuAction::DetachDiagnostics(diagnostics_detached));
+    }
+    if ui.button(tr("menu.next_monitor")).clicked() {
+        action = Some(MenuAction::NextMonitor);
+    }
+    let mut always_on_top = state.always_on_top;
+    if ui.checkbox(&mut always_on_top, tr("menu.always_on_top")).changed() {
+        action = Some(MenuAction::SetAlwaysOnTop(always_on_top));
+    }
+    action
+}
+
+/// Switches to and deletes layout profiles, and saves the current layout under a name typed in.
+fn layouts_menu(ui: &mut egui::Ui, state: &MenuState, action: &mut Option<MenuAction>) {
+    for name in &state.layouts {
+        ui.horizontal(|ui| {
+            if ui.selectable_label(state.current_layout.as_ref() == Some(name), name).clicked() {
+                *action = Some(MenuAction::SwitchLayout(name.clone()));
+                ui.close_menu();
+            }
+            if ui.small_button("🗑").on_hover_text(tr("menu.delete_layout")).clicked() {
+                *action = Some(MenuAction::DeleteLayout(name.clone()));
+            }
+        });
+    }
+    if state.layouts.is_empty() {
+        ui.weak(tr("menu.no_layouts"));
+    }
+    let next = egui::Button::new(tr("menu.next_layout")).shortcut_text("F8");
+    if ui.add_enabled(!state.layouts.is_empty(), next).clicked() {
+        *action = Some(MenuAction::NextLayout);
+        ui.close_menu();
+    }
+    ui.separator();
+    // Only needed while the menu is open, egui's memory is good enough for it.
+    let name_id = ui.id().with("layout_name");
+    let mut name = ui.data(|data| data.get_temp::<String>(name_id)).unwrap_or_default();
+    let response = ui.add(egui::TextEdit::singleline(&mut name).hint_text(tr("menu.layout_name")));
+    let entered = response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter));
+    let valid = !name.trim().is_empty();
+    if (ui.add_enabled(valid, egui::Button::new(tr("menu.save_layout"))).clicked() || entered) && valid {
+        *action = Some(MenuAction::SaveLayout(name.trim().to_owned()));
+        name.clear();
+        ui.close_menu();
+    }
+    ui.data_mut(|data| data.insert_temp(name_id, name));
+}
+
+/// Red "REC" badge in the top right corner while a recording runs.
+pub fn recording_indicator(ctx: &egui::Context, elapsed: Duration, dropped: u32) {
+    egui::Area::new(egui::Id::new("recording_indicator"))
+        .order(egui::Order::Foreground)
+        .anchor(egui::Align2::RIGHT_TOP, [-8.0, 8.0])
+        .interactable(false)
+        .show(ctx, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                let seconds = elapsed.as_secs();
+                let mut text = format!("⏺ REC {:02}:{:02}", seconds / 60, seconds % 60);
+                if dropped > 0 {
+                    text += &format!(" ({dropped} dropped)");
+                }
+                ui.label(egui::RichText::new(text).color(egui::Color32::RED).strong());
+            });
+        });
+    // Keeps the timer ticking even when nothing else changes.
+    ctx.request_repaint_after(Duration::from_millis(1000 - elapsed.subsec_millis() as u64));
+}
+
+/// Names and battery levels of the connected game controllers, in the bottom left corner.
+#[cfg(feature = "gamepad")]
+pub fn gamepad_indicator<'a>(ctx: &egui::Context, controllers: impl Iterator<Item = &'a crate::gamepad::Controller>) {
+    egui::Area::new(egui::Id::new("gamepad_indicator"))
+        .order(egui::Order::Foreground)
+        .anchor(egui::Align2::LEFT_BOTTOM, [8.0, -8.0])
+        .interactable(false)
+        .show(ctx, |ui| {
+            for controller in controllers {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    let battery = controller.battery.map(|percent| format!(" 🔋{percent}%")).unwrap_or_default();
+                    ui.label(format!("🎮 {}{battery}", controller.name));
+                });
+            }
+        });
+}
+
+/// Frame times and a flame graph of the scopes recorded by the profiler.
+#[cfg(feature = "profiling")]
+#[derive(Default)]
+pub struct ProfilerWindow {
+    open: bool,
+    /// Frames shown while paused, so they stay put to be inspected.
+    paused: Option<Vec<std::sync::Arc<crate::profiler::FrameRecord>>>,
+    /// Index into the shown frames, the latest one if `None`.
+    selected: Option<usize>,
+}
+
+#[cfg(feature = "profiling")]
+impl ProfilerWindow {
+    pub fn open(&mut self) {
+        self.open = true;
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        let mut open = self.open;
+        egui::Window::new(tr("profiler.title"))
+            .tool_window(ctx, ToolWindow::Profiler)
+            .open(&mut open)
+            .default_size([480.0, 320.0])
+            .show(ctx, |ui| {
+                let frames = self.paused.clone().unwrap_or_else(crate::profiler::frames);
+                ui.horizontal(|ui| {
+                    let mut paused = self.paused.is_some();
+                    if ui.checkbox(&mut paused, tr("profiler.pause")).changed() {
+                        self.paused = paused.then(|| frames.clone());
+                        self.selected = None;
+                    }
+                    if let Some(frame) = frames.last() {
+                        ui.label(tr_format("profiler.last_frame", &[("ms", &format!("{:.2}", frame.duration.as_secs_f64() * 1000.0))]));
+                    }
+                });
+
+                // Frame times as bars, clicking one shows its scopes below.
+                let (rect, response) = ui.allocate_exact_size(egui::vec2(ui.available_width(), 60.0), egui::Sense::click());
+                let painter = ui.painter_at(rect);
+                let longest = frames.iter().map(|frame| frame.duration.as_secs_f32()).fold(1.0 / 60.0, f32::max);
+                let bar_width = rect.width() / frames.len().max(1) as f32;
+                let selected = self.selected.filter(|&i| i < frames.len()).unwrap_or(frames.len().saturating_sub(1));
+                for (i, frame) in frames.iter().enumerate() {
+                    let height = frame.duration.as_secs_f32() / longest * rect.height();
+                    let x = rect.left() + i as f32 * bar_width;
+                    let bar = egui::Rect::from_min_max(egui::pos2(x, rect.bottom() - height), egui::pos2(x + bar_width, rect.bottom()));
+                    let color = if i == selected { ui.visuals().selection.bg_fill } else { ui.visuals().widgets.inactive.bg_fill };
+                    painter.rect_filled(bar.shrink2(egui::vec2(bar_width * 0.1, 0.0)), 0.0, color);
+                }
+                if let Some(pos) = response.interact_pointer_pos().filter(|_| response.clicked()) {
+                    self.selected = Some(((pos.x - rect.left()) / bar_width) as usize);
+                    if self.paused.is_none() {
+                        self.paused = Some(frames.clone());
+                    }
+                }
+
+                let Some(frame) = frames.get(selected) else {
+                    ui.label(tr("profiler.no_frames"));
+                    return;
+                };
+                flame_graph(ui, frame);
+            });
+        self.open = open;
+        if self.open && self.paused.is_none() {
+            ctx.request_repaint();
+        }
+    }
+}
+
+#[cfg(feature = "profiling")]
+fn flame_graph(ui: &mut egui::Ui, frame: &crate::profiler::FrameRecord) {
+    const ROW_HEIGHT: f32 = 18.0;
+    let depth = frame.scopes.iter().map(|scope| scope.depth + 1).max().unwrap_or(1);
+    let (rect, response) = ui.allocate_exact_size(egui::vec2(ui.available_width(), depth as f32 * ROW_HEIGHT), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    let total = frame.duration.as_secs_f32().max(f32::EPSILON);
+    let hover_pos = response.hover_pos();
+
+    for scope in &frame.scopes {
+        let left = rect.left() + scope.start.as_secs_f32() / total * rect.width();
+        let width = (scope.duration.as_secs_f32() / total * rect.width()).max(1.0);
+        let top = rect.top() + scope.depth as f32 * ROW_HEIGHT;
+        let scope_rect = egui::Rect::from_min_size(egui::pos2(left, top), egui::vec2(width, ROW_HEIGHT - 1.0));
+        // The same scope always gets the same color across frames.
+        let hue = scope.name.bytes().fold(0u32, |hash, byte| hash.wrapping_mul(31).wrapping_add(byte as u32)) % 360;
+        painter.rect_filled(scope_rect, 2.0, egui::ecolor::Hsva::new(hue as f32 / 360.0, 0.5, 0.6, 1.0));
+
+        let label = format!("{} {:.2} ms", scope.name, scope.duration.as_secs_f64() * 1000.0);
+        if width > 40.0 {
+            painter.text(scope_rect.left_center() + egui::vec2(3.0, 0.0), egui::Align2::LEFT_CENTER, &label, egui::FontId::proportional(11.0), egui::Color32::WHITE);
+        }
+        if hover_pos.is_some_and(|pos| scope_rect.contains(pos)) {
+            response.clone().on_hover_text_at_pointer(label);
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct GpuMemoryWindow {
+    open: bool,
+}
+
+impl GpuMemoryWindow {
+    pub fn open(&mut self) {
+        self.open = true;
+    }
+
+    /// `trim` is what the last `AppResources::trim_memory` freed.
+    pub fn show(&mut self, ctx: &egui::Context, device: &wgpu::Device, ui_stats: &UiStats, trim: Option<&TrimReport>) {
+        egui::Window::new(tr("gpu_memory.title"))
+            .tool_window(ctx, ToolWindow::GpuMemory)
+            .open(&mut self.open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.heading("wgpu");
+                if cfg!(feature = "gpu-counters") {
+                    let counters = device.get_internal_counters().hal;
+                    egui::Grid::new("wgpu_counters").num_columns(2).show(ui, |ui| {
+                        for (name, value) in [
+                            (tr("gpu_memory.buffers"), counters.buffers.read()),
+                            (tr("gpu_memory.textures"), counters.textures.read()),
+                            (tr("gpu_memory.texture_views"), counters.texture_views.read()),
+                            (tr("gpu_memory.bind_groups"), counters.bind_groups.read()),
+                            (tr("gpu_memory.samplers"), counters.samplers.read()),
+                            (tr("gpu_memory.render_pipelines"), counters.render_pipelines.read()),
+                            (tr("gpu_memory.shader_modules"), counters.shader_modules.read()),
+                            (tr("gpu_memory.memory_allocations"), counters.memory_allocations.read()),
+                        ] {
+                            ui.label(name);
+                            ui.label(value.to_string());
+                            ui.end_row();
+                        }
+                        ui.label(tr("gpu_memory.buffer_memory"));
+                        ui.label(format_bytes(counters.buffer_memory.read().max(0) as u64));
+                        ui.end_row();
+                        ui.label(tr("gpu_memory.texture_memory"));
+                        ui.label(format_bytes(counters.texture_memory.read().max(0) as u64));
+                        ui.end_row();
+                    });
+                } else {
+                    ui.label(tr("gpu_memory.no_counters"));
+                }
+                // Only backends with their own allocator can report this.
+                if let Some(report) = device.generate_allocator_report() {
+                    ui.label(tr_format("gpu_memory.allocated", &[
+                        ("allocated", &format_bytes(report.total_allocated_bytes)),
+                        ("reserved", &format_bytes(report.total_reserved_bytes)),
+                    ]));
+                }
+
+                ui.heading("egui");
+                let (vertex_bytes, index_bytes) = ui_stats.buffer_bytes();
+                egui::Grid::new("egui_stats").num_columns(2).show(ui, |ui| {
+                    ui.label(tr("gpu_memory.textures"));
+                    ui.label(ui_stats.texture_count().to_string());
+                    ui.end_row();
+                    ui.label(tr("gpu_memory.clipped_primitives"));
+                    ui.label(ui_stats.clipped_primitives.to_string());
+                    ui.end_row();
+                    ui.label(tr("gpu_memory.vertices"));
+                    ui.label(format!("{} ({})", ui_stats.vertices, format_bytes(vertex_bytes as u64)));
+                    ui.end_row();
+                    ui.label(tr("gpu_memory.indices"));
+                    ui.label(format!("{} ({})", ui_stats.indices, format_bytes(index_bytes as u64)));
+                    ui.end_row();
+                    ui.label(tr("gpu_memory.tessellation_cache"));
+                    ui.label(tr_format("gpu_memory.cache_value", &[("hits", &ui_stats.tessellation_cache_hits), ("misses", &ui_stats.tessellation_cache_misses)]));
+                    ui.end_row();
+                    ui.label(tr("gpu_memory.painting"));
+                    ui.label(tr_format("gpu_memory.painting_value", &[("points", &ui_stats.painting_points), ("shapes", &ui_stats.painting_shapes)]));
+                    ui.end_row();
+                });
+
+                if let Some(trim) = trim {
+                    ui.heading(tr("gpu_memory.last_trim"));
+                    trim_report_grid(ui, trim);
+                }
+            });
+    }
+}
+
+fn trim_report_grid(ui: &mut egui::Ui, trim: &TrimReport) {
+    ui.label(match trim.level {
+        TrimLevel::Idle => tr("gpu_memory.trim_idle"),
+        TrimLevel::Full => tr("gpu_memory.trim_full"),
+    });
+    let cells = |usage: &CacheUsage| [
+        format!("{} × {}", usage.font_atlas[0], usage.font_atlas[1]),
+        usage.textures.to_string(),
+        usage.native_textures.to_string(),
+        format_bytes(usage.image_bytes as u64),
+        usage.log_records.to_string(),
+    ];
+    let before = cells(&trim.before);
+    // Taken once the next frame was rendered.
+    let after = trim.after.as_ref().map(cells);
+    egui::Grid::new("trim_report").num_columns(3).show(ui, |ui| {
+        ui.label("");
+        ui.strong(tr("gpu_memory.before"));
+        ui.strong(tr("gpu_memory.after"));
+        ui.end_row();
+        for (index, name) in ["gpu_memory.font_atlas", "gpu_memory.textures", "gpu_memory.native_textures", "gpu_memory.images", "gpu_memory.log_records"].map(tr).into_iter().enumerate() {
+            ui.label(name);
+            ui.label(&before[index]);
+            ui.label(after.as_ref().map_or("…", |after| after[index].as_str()));
+            ui.end_row();
+        }
+    });
+}
+
+/// Widgets per row of the [`StressTest`] grid.
+const STRESS_COLUMNS: usize = 12;
+
+/// Thousands of widgets at once, with what it took to draw them, to see at how many egui or the GPU can't keep up.
+pub struct StressTest {
+    open: bool,
+    /// Every third one is a label, a slider and a colored rect.
+    count: usize,
+    /// Everything is laid out either way, but in a scroll area most of it is clipped away before it's tessellated.
+    scroll_area: bool,
+    /// Translucent panels stacked over the widgets, to see what they cost in fill rate.
+    layers: usize,
+    values: Vec<f32>,
+}
+
+impl Default for StressTest {
+    fn default() -> Self {
+        Self {
+            open: false,
+            count: 1000,
+            scroll_area: true,
+            layers: 0,
+            values: Vec::new(),
+        }
+    }
+}
+
+impl StressTest {
+    pub fn open(&mut self) {
+        self.open = true;
+    }
+
+    /// The GPU time of the UI is only measured while it's open.
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn set_count(&mut self, count: usize) {
+        self.count = count.clamp(1000, 50_000);
+    }
+
+    /// The numbers in `ui_stats` are of the whole UI of the previous frame, the stress test being most of it.
+    /// `gpu_timing` is whether the device can measure GPU times at all. `overdraw` is the Debug menu's overdraw view,
+    /// it can be turned on here too.
+    pub fn show(&mut self, ctx: &egui::Context, ui_stats: &UiStats, gpu_timing: bool, overdraw: &mut bool) {
+        let mut open = self.open;
+        egui::Window::new(tr("stress.title"))
+            .tool_window(ctx, ToolWindow::StressTest)
+            .open(&mut open)
+            .default_size([640.0, 480.0])
+            // Without the scroll area the window grows to fit everything, as far as the screen goes that's all drawn.
+            .resizable(self.scroll_area)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.add(egui::Slider::new(&mut self.count, 1000..=50_000).logarithmic(true).text(tr("stress.widgets")));
+                    ui.checkbox(&mut self.scroll_area, tr("stress.scroll_area")).on_hover_text(tr("stress.scroll_area_hint"));
+                });
+                ui.horizontal(|ui| {
+                    ui.add(egui::Slider::new(&mut self.layers, 0..=32).text(tr("stress.layers"))).on_hover_text(tr("stress.layers_hint"));
+                    ui.checkbox(overdraw, tr("debug.overdraw")).on_hover_text(tr("debug.overdraw_hint"));
+                });
+                let milliseconds = |time: Duration| format!("{:.2} ms", time.as_secs_f64() * 1000.0);
+                egui::Grid::new("stress_report").num_columns(2).show(ui, |ui| {
+                    for (name, value) in [
+                        (tr("stress.shapes"), ui_stats.shapes.to_string()),
+                        (tr("stress.clipped_primitives"), ui_stats.clipped_primitives.to_string()),
+                        (tr("stress.vertices"), ui_stats.vertices.to_string()),
+                        (tr("stress.indices"), ui_stats.indices.to_string()),
+                        (tr("stress.clip_coverage"), format!("{:.0}%", ui_stats.clip_coverage * 100.0)),
+                        (tr("stress.tessellation"), milliseconds(ui_stats.tessellation_time)),
+                        (tr("stress.cache"), tr_format("stress.cache_counts", &[
+                            ("hits", &ui_stats.tessellation_cache_hits),
+                            ("misses", &ui_stats.tessellation_cache_misses),
+                        ])),
+                        (tr("stress.gpu"), match ui_stats.gpu_time {
+                            Some(time) => milliseconds(time),
+                            None if gpu_timing => tr("stress.measuring").to_owned(),
+                            None => tr("stress.unsupported").to_owned(),
+                        }),
+                    ] {
+                        ui.label(name);
+                        ui.label(value);
+                        ui.end_row();
+                    }
+                });
+                ui.separator();
+
+                self.values.resize(self.count, 0.5);
+                let widgets = if self.scroll_area {
+                    egui::ScrollArea::both().auto_shrink(false).show(ui, |ui| stress_widgets(ui, &mut self.values)).inner_rect
+                } else {
+                    ui.scope(|ui| stress_widgets(ui, &mut self.values)).response.rect
+                };
+                // Each a bit smaller than the one below, so the stack shows.
+                for layer in 0..self.layers {
+                    let rect = widgets.shrink(layer as f32 * 4.0);
+                    ui.painter().rect_filled(rect, 4.0, egui::Color32::from_black_alpha(12));
+                }
+            });
+        self.open = open;
+        // Rendering continuously so the times are of frames that actually draw all of it.
+        if self.open {
+            ctx.request_repaint();
+        }
+    }
+}
+
+fn stress_widgets(ui: &mut egui::Ui, values: &mut [f32]) {
+    egui::Grid::new("stress_widgets").show(ui, |ui| {
+        for (i, value) in values.iter_mut().enumerate() {
+            match i % 3 {
+                0 => {
+                    ui.label(format!("#{i}"));
+                },
+                1 => {
+                    ui.add(egui::Slider::new(value, 0.0..=1.0).show_value(false));
+                },
+                _ => {
+                    let (rect, _) = ui.allocate_exact_size(egui::vec2(16.0, 16.0), egui::Sense::hover());
+                    let color = egui::ecolor::Hsva::new((i as f32 * 0.618).fract(), 0.6, 0.8, 1.0);
+                    ui.painter().rect_filled(rect, 2.0, color);
+                },
+            }
+            if i % STRESS_COLUMNS == STRESS_COLUMNS - 1 {
+                ui.end_row();
+            }
+        }
+    });
+}
+
+fn format_bytes(bytes: u64) -> String {
+    match bytes {
+        0..1024 => format!("{bytes} B"),
+        1024..1048576 => format!("{:.1} KiB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MiB", bytes as f64 / 1048576.0),
+    }
+}
+
+/// Wide, dark gradients where banding is easy to see, to compare the dithering and sRGB options.
+#[derive(Default)]
+pub struct GradientWindow {
+    open: bool,
+}
+
+impl GradientWindow {
+    pub fn open(&mut self) {
+        self.open = true;
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        egui::Window::new(tr("gradients.title"))
+            .tool_window(ctx, ToolWindow::Gradients)
+            .open(&mut self.open)
+            .default_width(600.0)
+            .show(ctx, |ui| {
+                ui.label(tr("gradients.hint"));
+                for (from, to) in [
+                    (egui::Color32::BLACK, egui::Color32::from_gray(40)),
+                    (egui::Color32::from_rgb(0, 0, 20), egui::Color32::from_rgb(20, 30, 60)),
+                    (egui::Color32::BLACK, egui::Color32::WHITE),
+                ] {
+                    let (rect, _) = ui.allocate_exact_size(egui::vec2(ui.available_width(), 48.0), egui::Sense::hover());
+                    // Colors are only given at the ends, the GPU interpolates everything in between.
+                    let mut mesh = egui::Mesh::default();
+                    mesh.colored_vertex(rect.left_top(), from);
+                    mesh.colored_vertex(rect.left_bottom(), from);
+                    mesh.colored_vertex(rect.right_top(), to);
+                    mesh.colored_vertex(rect.right_bottom(), to);
+                    mesh.add_triangle(0, 1, 2);
+                    mesh.add_triangle(1, 2, 3);
+                    ui.painter().add(mesh);
+                }
+            });
+    }
+}
+
+/// Size of each pattern in the [`PixelTestPattern`], in points.
+const PIXEL_PATTERN_SIZE: egui::Vec2 = egui::vec2(160.0, 32.0);
+
+/// Patterns of single pixels that only come out crisp with whole pixels per point, to compare the pixel snapping options.
+#[derive(Default)]
+pub struct PixelTestPattern {
+    open: bool,
+}
+
+impl PixelTestPattern {
+    pub fn open(&mut self) {
+        self.open = true;
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, native_pixels_per_point: f32) {
+        egui::Window::new(tr("pixels.title"))
+            .tool_window(ctx, ToolWindow::PixelTest)
+            .open(&mut self.open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let pixels_per_point = ctx.pixels_per_point();
+                ui.label(tr_format("pixels.scale", &[
+                    ("native", &format!("{native_pixels_per_point:.2}")),
+                    ("pixels_per_point", &format!("{pixels_per_point:.3}")),
+                ]));
+                ui.weak(tr("pixels.hint"));
+                ui.separator();
+                pixel_pattern(ui, tr("pixels.columns"), |x, _| x % 2 == 0);
+                pixel_pattern(ui, tr("pixels.rows"), |_, y| y % 2 == 0);
+                pixel_pattern(ui, tr("pixels.checkerboard"), |x, y| (x + y) % 2 == 0);
+
+                // These go through egui's own rounding of lines to pixels, which can't help 1pt not being a whole number of them.
+                ui.label(tr("pixels.point_lines"));
+                let (rect, _) = ui.allocate_exact_size(PIXEL_PATTERN_SIZE, egui::Sense::hover());
+                ui.painter().rect_filled(rect, 0.0, egui::Color32::WHITE);
+                let stroke = egui::Stroke::new(1.0, egui::Color32::BLACK);
+                for i in 0..(rect.width() / 2.0) as u32 {
+                    ui.painter().vline(rect.left() + 0.5 + 2.0 * i as f32, rect.y_range(), stroke);
+                }
+            });
+    }
+}
+
+/// Fills a rect with white and the pixels `dark` returns true for, by their column and row, with black.
+fn pixel_pattern(ui: &mut egui::Ui, label: &str, dark: impl Fn(u32, u32) -> bool) {
+    use egui::emath::GuiRounding;
+
+    ui.label(label);
+    let (rect, _) = ui.allocate_exact_size(PIXEL_PATTERN_SIZE, egui::Sense::hover());
+    let pixels_per_point = ui.ctx().pixels_per_point();
+    let pixel = 1.0 / pixels_per_point;
+    // Starting on a pixel edge, so the pattern can match the pixels if the pixels per point let it.
+    let origin = rect.min.round_to_pixels(pixels_per_point);
+    let [columns, rows] = [rect.width(), rect.height()].map(|points| (points * pixels_per_point) as u32);
+    let mut mesh = egui::Mesh::default();
+    mesh.add_colored_rect(egui::Rect::from_min_size(origin, egui::vec2(columns as f32, rows as f32) * pixel), egui::Color32::WHITE);
+    for y in 0..rows {
+        for x in (0..columns).filter(|&x| dark(x, y)) {
+            let min = origin + egui::vec2(x as f32, y as f32) * pixel;
+            mesh.add_colored_rect(egui::Rect::from_min_size(min, egui::Vec2::splat(pixel)), egui::Color32::BLACK);
+        }
+    }
+    ui.painter().add(mesh);
+}
+
+/// Size of the patches of the [`ColorTestWindow`]'s ramp and theme colors, in points.
+const COLOR_SWATCH_SIZE: egui::Vec2 = egui::vec2(20.0, 20.0);
+
+/// A test card of known colors, read back from the surface to check it's set up for the colors it gets.
+#[derive(Default)]
+pub struct ColorTestWindow {
+    open: bool,
+    /// The patches shown in the last frame, where to find them in the surface.
+    probes: Vec<ColorProbe>,
+    check: Option<ColorCheck>,
+}
+
+impl ColorTestWindow {
+    pub fn open(&mut self) {
+        self.open = true;
+    }
+
+    /// Shows the surface's `format` and the `view_format` the UI is drawn with. Returns true when the surface should
+    /// be read back with this frame, for [`Self::check`].
+    pub fn show(&mut self, ctx: &egui::Context, format: wgpu::TextureFormat, view_format: wgpu::TextureFormat) -> bool {
+        self.probes.clear();
+        let mut read_back = false;
+        let mut open = self.open;
+        egui::Window::new(tr("color_test.title"))
+            .tool_window(ctx, ToolWindow::ColorTest)
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(tr_format("color_test.formats", &[("format", &format!("{format:?}")), ("view", &format!("{view_format:?}"))]));
+                ui.separator();
+                ui.weak(tr("color_test.gray_hint"));
+                pixel_pattern(ui, tr("color_test.checkerboard"), |x, y| (x + y) % 2 == 0);
+                ui.label(tr("color_test.same_light"));
+                color_patch(ui, &mut self.probes, "sRGB 188".to_owned(), egui::Color32::from_gray(188), PIXEL_PATTERN_SIZE);
+                ui.label(tr("color_test.half_value"));
+                color_patch(ui, &mut self.probes, "sRGB 128".to_owned(), egui::Color32::from_gray(128), PIXEL_PATTERN_SIZE);
+
+                ui.label(tr("color_test.ramp"));
+                ui.horizontal(|ui| {
+                    ui.spacing_mut().item_spacing.x = 0.0;
+                    for step in 0..=8 {
+                        let value = (step * 32).min(255) as u8;
+                        color_patch(ui, &mut self.probes, format!("sRGB {value}"), egui::Color32::from_gray(value), COLOR_SWATCH_SIZE);
+                    }
+                });
+                ui.label(tr("color_test.theme"));
+                let visuals = ui.visuals().clone();
+                ui.horizontal_wrapped(|ui| {
+                    let colors = [
+                        ("panel_fill", visuals.panel_fill),
+                        ("window_fill", visuals.window_fill),
+                        ("extreme_bg_color", visuals.extreme_bg_color),
+                        ("faint_bg_color", visuals.faint_bg_color),
+                        ("text_color", visuals.text_color()),
+                        ("selection", visuals.selection.bg_fill),
+                        ("hyperlink_color", visuals.hyperlink_color),
+                        ("warn_fg_color", visuals.warn_fg_color),
+                        ("error_fg_color", visuals.error_fg_color),
+                    ];
+                    for (name, color) in colors {
+                        // Anything see-through would be mixed with what's behind it.
+                        color_patch(ui, &mut self.probes, name.to_owned(), color.to_opaque(), COLOR_SWATCH_SIZE);
+                    }
+                });
+                ui.separator();
+
+                read_back = ui.button(tr("color_test.read_back")).on_hover_text(tr("color_test.read_back_hint")).clicked();
+                let Some(check) = &self.check else {
+                    return;
+                };
+                let view = format!("{:?}", check.view_format);
+                ui.weak(tr_format("color_test.checked", &[("format", &format!("{:?}", check.surface_format)), ("view", &view)]));
+                match check.verdict {
+                    ColorVerdict::Matches => {
+                        ui.colored_label(egui::Color32::from_rgb(80, 200, 120), tr("color_test.matches"));
+                    },
+                    ColorVerdict::EncodedTwice => {
+                        ui.colored_label(ui.visuals().warn_fg_color, tr_format("color_test.encoded_twice", &[("view", &view)]));
+                    },
+                    ColorVerdict::NotEncoded => {
+                        ui.colored_label(ui.visuals().warn_fg_color, tr_format("color_test.not_encoded", &[("view", &view)]));
+                    },
+                    ColorVerdict::Adjusted => {
+                        ui.label(tr("color_test.adjusted"));
+                    },
+                    ColorVerdict::Different => {
+                        ui.colored_label(ui.visuals().warn_fg_color, tr("color_test.different"));
+                    },
+                }
+                egui::CollapsingHeader::new(tr("color_test.samples")).show(ui, |ui| {
+                    egui::Grid::new("color_test_samples").striped(true).show(ui, |ui| {
+                        ui.strong(tr("color_test.patch"));
+                        ui.strong(tr("color_test.painted"));
+                        ui.strong(tr("color_test.read"));
+                        ui.end_row();
+                        let rgb = |color: egui::Color32| format!("{} {} {}", color.r(), color.g(), color.b());
+                        for (probe, measured) in &check.samples {
+                            ui.label(&probe.name);
+                            ui.monospace(rgb(probe.color));
+                            ui.monospace(measured.map_or("–".to_owned(), rgb));
+                            ui.end_row();
+                        }
+                    });
+                });
+            });
+        self.open = open;
+        read_back && self.open
+    }
+
+    /// Checks `frame`, read back because [`Self::show`] asked for it, against the patches. `adjusted` is whether a
+    /// post effect changes the colors.
+    pub fn check(&mut self, frame: &CapturedFrame, format: wgpu::TextureFormat, view_format: wgpu::TextureFormat, adjusted: bool) {
+        let check = ColorCheck::new(frame, &self.probes, format, view_format, adjusted);
+        log::info!("Color test of a {format:?} surface drawn as {view_format:?}: {:?}", check.verdict);
+        self.check = Some(check);
+    }
+}
+
+/// A patch of `color` that's read back by the [`ColorTestWindow`] if it isn't scrolled or clipped away.
+fn color_patch(ui: &mut egui::Ui, probes: &mut Vec<ColorProbe>, name: String, color: egui::Color32, size: egui::Vec2) {
+    let (rect, response) = ui.allocate_exact_size(size, egui::Sense::hover());
+    ui.painter().rect_filled(rect, 0.0, color);
+    if ui.clip_rect().contains(rect.center()) {
+        let pixel = (rect.center() * ui.ctx().pixels_per_point()).floor();
+        probes.push(ColorProbe { name: name.clone(), color, pixel: [pixel.x as u32, pixel.y as u32] });
+    }
+    response.on_hover_text(name);
+}
+
+/// What the user did in the image demo.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImageDemoAction {
+    SamplerChanged,
+    Regenerate,
+    /// Ctrl+V was pressed while the window was in front.
+    Paste,
+}
+
+/// Shows native textures with different sampler options.
+pub struct ImageDemo {
+    open: bool,
+    pixel_art: Option<egui::TextureId>,
+    checkerboard: Option<egui::TextureId>,
+    pub nearest: bool,
+    pub mipmaps: bool,
+    zoom: f32,
+    /// Images from the clipboard, shown below the generated ones.
+    pasted: Vec<egui::TextureHandle>,
+}
+
+impl Default for ImageDemo {
+    fn default() -> Self {
+        Self {
+            open: false,
+            pixel_art: None,
+            checkerboard: None,
+            nearest: true,
+            mipmaps: true,
+            zoom: 8.0,
+            pasted: Vec::new(),
+        }
+    }
+}
+
+impl ImageDemo {
+    pub fn open(&mut self) {
+        self.open = true;
+    }
+
+    /// The textures are only created once the window is opened.
+    pub fn needs_textures(&self) -> bool {
+        self.open && self.pixel_art.is_none()
+    }
+
+    pub fn set_textures(&mut self, pixel_art: egui::TextureId, checkerboard: egui::TextureId) {
+        self.pixel_art = Some(pixel_art);
+        self.checkerboard = Some(checkerboard);
+    }
+
+    pub fn pixel_art(&self) -> Option<egui::TextureId> {
+        self.pixel_art
+    }
+
+    pub fn checkerboard(&self) -> Option<egui::TextureId> {
+        self.checkerboard
+    }
+
+    /// Hands over the textures to be freed while the window is closed, they're created again once it's opened.
+    pub fn take_unused_textures(&mut self) -> Vec<egui::TextureId> {
+        if self.open {
+            return Vec::new();
+        }
+        [self.pixel_art.take(), self.checkerboard.take()].into_iter().flatten().collect()
+    }
+
+    /// The textures are gone, e.g. because the device they were created on was replaced.
+    pub fn forget_textures(&mut self) {
+        self.pixel_art = None;
+        self.checkerboard = None;
+    }
+
+    pub fn add_pasted(&mut self, texture: egui::TextureHandle) {
+        self.pasted.push(texture);
+    }
+
+    /// `paste` is whether Ctrl+V was pressed this frame, it's only for this window while it's in front.
+    pub fn show(&mut self, ctx: &egui::Context, paste: bool) -> Option<ImageDemoAction> {
+        let mut action = None;
+        let (Some(pixel_art), Some(checkerboard)) = (self.pixel_art, self.checkerboard) else {
+            return None;
+        };
+
+        let response = egui::Window::new(tr("images.title"))
+            .tool_window(ctx, ToolWindow::Images)
+            .open(&mut self.open)
+            .default_size([420.0, 480.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.checkbox(&mut self.nearest, tr("images.nearest")).changed() || ui.checkbox(&mut self.mipmaps, tr("images.mipmaps")).changed() {
+                        action = Some(ImageDemoAction::SamplerChanged);
+                    }
+                    if ui.button(tr("images.regenerate")).on_hover_text(tr("images.regenerate_hint")).clicked() {
+                        action = Some(ImageDemoAction::Regenerate);
+                    }
+                });
+                ui.add(egui::Slider::new(&mut self.zoom, 1.0..=16.0).text(tr("images.zoom")));
+                ui.horizontal(|ui| {
+                    ui.weak(tr("images.paste_hint"));
+                    if ui.add_enabled(!self.pasted.is_empty(), egui::Button::new(tr("images.clear_pasted"))).clicked() {
+                        self.pasted.clear();
+                    }
+                });
+
+                egui::ScrollArea::both().show(ui, |ui| {
+                    ui.image((pixel_art, egui::Vec2::splat(16.0 * self.zoom)));
+                    // Shrunk a lot, this is where mipmaps make the difference.
+                    ui.image((checkerboard, egui::Vec2::splat(512.0 / self.zoom)));
+                    // Pixel for pixel.
+                    for texture in &self.pasted {
+                        ui.image((texture.id(), texture.size_vec2() / ui.ctx().pixels_per_point()));
+                    }
+                });
+            });
+
+        if paste && response.is_some_and(|response| is_in_front(ctx, &response.response)) {
+            action = Some(ImageDemoAction::Paste);
+        }
+        action
+    }
+}
+
+/// Whether the window `response` belongs to is the one in front, where shortcuts go that no widget has taken.
+fn is_in_front(ctx: &egui::Context, response: &egui::Response) -> bool {
+    ctx.top_layer_id() == Some(response.layer_id)
+}
+
+/// Asks for the path to open or save to in a text field, for `dialogs::Dialogs` where there's no file dialog.
+pub struct FileDialog {
+    title: String,
+    /// The label of the button that takes the path.
+    confirm: &'static str,
+    path: String,
+}
+
+impl FileDialog {
+    pub fn new(title: &str, confirm: &'static str, path: &std::path::Path) -> Self {
+        Self { title: title.to_owned(), confirm, path: path.display().to_string() }
+    }
+
+    /// The path once it's confirmed, `Some(None)` once the dialog was closed without one.
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<Option<std::path::PathBuf>> {
+        let mut open = true;
+        let mut confirmed = false;
+        let mut cancelled = false;
+
+        egui::Window::new(&self.title)
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let response = ui.add(egui::TextEdit::singleline(&mut self.path).desired_width(320.0));
+                confirmed = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                ui.horizontal(|ui| {
+                    confirmed |= ui.add_enabled(!self.path.trim().is_empty(), egui::Button::new(self.confirm)).clicked();
+                    cancelled = ui.button(tr("dialogs.cancel")).clicked();
+                });
+            });
+
+        if confirmed && !self.path.trim().is_empty() {
+            return Some(Some(self.path.trim().into()));
+        }
+        (!open || cancelled).then_some(None)
+    }
+}
+
+pub enum PaintingAction {
+    /// Ctrl+V was pressed while the window was in front.
+    Paste,
+    /// The canvas should be put on the clipboard as an image: `shapes` drawn onto a canvas of `size` points with
+    /// its top left corner at zero.
+    CopyImage { shapes: Vec<egui::epaint::ClippedShape>, size: egui::Vec2 },
+}
+
+/// Freehand drawing with the mouse or a finger, to see what large drawings cost to tessellate.
+pub struct PaintingDemo {
+    open: bool,
+    /// Pasted from the clipboard, pixel for pixel with their top left corner at the given position, under the lines.
+    images: Vec<(egui::TextureHandle, egui::Pos2)>,
+    /// Relative to the top left corner of the canvas, in points, so the drawing stays put when the window is resized.
+    lines: Vec<Vec<egui::Pos2>>,
+    /// What each of `lines` is drawn with.
+    strokes: Vec<egui::Stroke>,
+    stroke: egui::Stroke,
+    eraser: bool,
+    /// Whether the last line is still being drawn.
+    drawing: bool,
+}
+
+impl Default for PaintingDemo {
+    fn default() -> Self {
+        Self {
+            open: false,
+            images: Vec::new(),
+            lines: Vec::new(),
+            strokes: Vec::new(),
+            stroke: egui::Stroke::new(2.0, egui::Color32::from_rgb(25, 200, 100)),
+            eraser: false,
+            drawing: false,
+        }
+    }
+}
+
+/// Lines passing closer than this to the eraser are removed, in points.
+const ERASER_RADIUS: f32 = 8.0;
+
+impl PaintingDemo {
+    pub fn open(&mut self) {
+        self.open = true;
+    }
+
+    /// Pasted images are placed a bit below and right of the previous one, so they don't hide each other completely.
+    pub fn add_image(&mut self, texture: egui::TextureHandle) {
+        let position = egui::Pos2::ZERO + egui::Vec2::splat(16.0) * self.images.len() as f32;
+        self.images.push((texture, position));
+    }
+
+    /// Returns how many points and shapes the canvas submitted this frame. `paste` is whether Ctrl+V was pressed,
+    /// it's only for this window while it's in front.
+    pub fn show(&mut self, ctx: &egui::Context, paste: bool) -> (usize, usize, Option<PaintingAction>) {
+        let mut submitted = (0, 0);
+        let mut action = None;
+        let mut copy = false;
+        let mut open = self.open;
+        let response = egui::Window::new(tr("painting.title"))
+            .tool_window(ctx, ToolWindow::Painting)
+            .open(&mut open)
+            .default_size([480.0, 360.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.add_enabled(!self.eraser, &mut self.stroke);
+                    ui.toggle_value(&mut self.eraser, tr("painting.eraser"));
+                    if ui.add_enabled(!self.lines.is_empty(), egui::Button::new(tr("painting.undo"))).clicked() {
+                        self.lines.pop();
+                        self.strokes.pop();
+                        self.drawing = false;
+                    }
+                    if ui.button(tr("painting.clear")).clicked() {
+                        self.lines.clear();
+                        self.strokes.clear();
+                        self.images.clear();
+                    }
+                    copy = ui.button(tr("painting.copy")).on_hover_text(tr("painting.copy_hint")).clicked();
+                });
+
+                egui::Frame::canvas(ui.style()).show(ui, |ui| {
+                    let (points, shapes, copied) = self.canvas(ui, copy);
+                    submitted = (points, shapes);
+                    action = copied;
+                });
+            });
+        self.open = open;
+        if paste && response.is_some_and(|response| is_in_front(ctx, &response.response)) {
+            action = Some(PaintingAction::Paste);
+        }
+        (submitted.0, submitted.1, action)
+    }
+
+    /// With `copy` the shapes are returned as well, for [`PaintingAction::CopyImage`].
+    fn canvas(&mut self, ui: &mut egui::Ui, copy: bool) -> (usize, usize, Option<PaintingAction>) {
+        let (response, painter) = ui.allocate_painter(ui.available_size_before_wrap(), egui::Sense::drag());
+        let origin = response.rect.min;
+        let mut drawn = Vec::new();
+        let pixels_per_point = ui.ctx().pixels_per_point();
+        for (texture, position) in &self.images {
+            let rect = egui::Rect::from_min_size(origin + position.to_vec2(), texture.size_vec2() / pixels_per_point);
+            let uv = egui::Rect::from_min_max(egui::Pos2::ZERO, egui::pos2(1.0, 1.0));
+            drawn.push(egui::Shape::image(texture.id(), rect, uv, egui::Color32::WHITE));
+        }
+
+        // egui turns the first touch into the pointer, so this covers fingers as well.
+        if let Some(pointer) = response.interact_pointer_pos() {
+            let pos = egui::Pos2::ZERO + (pointer - origin);
+            if self.eraser {
+                let mut index = 0;
+                while index < self.lines.len() {
+                    if self.lines[index].iter().any(|point| point.distance(pos) <= ERASER_RADIUS) {
+                        self.lines.remove(index);
+                        self.strokes.remove(index);
+                    } else {
+                        index += 1;
+                    }
+                }
+            } else {
+                if !self.drawing {
+                    self.lines.push(Vec::new());
+                    self.strokes.push(self.stroke);
+                    self.drawing = true;
+                }
+                let line = self.lines.last_mut().unwrap();
+                if line.last() != Some(&pos) {
+                    line.push(pos);
+                }
+            }
+        } else {
+            self.drawing = false;
+        }
+
+        let mut points = 0;
+        let mut shapes = 0;
+        for (line, stroke) in self.lines.iter().zip(&self.strokes) {
+            let line: Vec<_> = line.iter().map(|point| origin + point.to_vec2()).collect();
+            points += line.len();
+            match line.as_slice() {
+                [] => continue,
+                // A click without dragging leaves a dot.
+                [point] => drawn.push(egui::Shape::circle_filled(*point, stroke.width / 2.0, stroke.color)),
+                _ => drawn.push(egui::Shape::line(line, *stroke)),
+            }
+            shapes += 1;
+        }
+
+        let copied = copy.then(|| {
+            let size = response.rect.size();
+            let clip_rect = egui::Rect::from_min_size(egui::Pos2::ZERO, size);
+            let background = egui::Shape::rect_filled(clip_rect, 0.0, ui.visuals().extreme_bg_color);
+            let shapes = std::iter::once(background)
+                .chain(drawn.iter().cloned().map(|mut shape| {
+                    shape.translate(-origin.to_vec2());
+                    shape
+                }))
+                .map(|shape| egui::epaint::ClippedShape { clip_rect, shape })
+                .collect();
+            PaintingAction::CopyImage { shapes, size }
+        });
+        painter.extend(drawn);
+        (points, shapes, copied)
+    }
+}
+
+/// The item being dragged in the [`ReorderDemo`].
+#[derive(Debug, Clone, Copy)]
+struct DraggedItem {
+    list: usize,
+    row: usize,
+}
+
+/// egui's own debugging UIs, opened from the Debug menu. Everything starts off and stays as it was left.
+#[derive(Default)]
+pub struct DebugTools {
+    inspection: bool,
+    memory: bool,
+    textures: bool,
+    style: bool,
+    debug_on_hover: bool,
+    /// Tints the window in frames that were drawn because something requested a repaint, and lists what did.
+    highlight_repaints: bool,
+    /// Set by the menu until the app picks it up with [`Self::take_inflate_atlas`].
+    inflate_atlas: bool,
+    /// Set by the menu until the app picks it up with [`Self::take_trim_memory`].
+    trim_memory: bool,
+    /// Draws the UI with `overdraw::OverdrawView` instead of its renderer. Not saved, an app that comes up looking
+    /// like this would look broken.
+    pub overdraw: bool,
+}
+
+impl DebugTools {
+    pub fn load(document: &Document) -> Self {
+        let get = |key| document.get_bool("debug", key).unwrap_or(false);
+        Self {
+            inspection: get("inspection"),
+            memory: get("memory"),
+            textures: get("textures"),
+            style: get("style"),
+            debug_on_hover: get("debug_on_hover"),
+            highlight_repaints: get("highlight_repaints"),
+            inflate_atlas: false,
+            trim_memory: false,
+            overdraw: false,
+        }
+    }
+
+    pub fn save(&self, document: &mut Document) {
+        document.set("debug", "inspection", self.inspection);
+        document.set("debug", "memory", self.memory);
+        document.set("debug", "textures", self.textures);
+        document.set("debug", "style", self.style);
+        document.set("debug", "debug_on_hover", self.debug_on_hover);
+        document.set("debug", "highlight_repaints", self.highlight_repaints);
+    }
+
+    /// The contents of the Debug menu.
+    pub fn menu(&mut self, ui: &mut egui::Ui) {
+        ui.checkbox(&mut self.inspection, tr("debug.inspection"));
+        ui.checkbox(&mut self.memory, tr("debug.memory"));
+        ui.checkbox(&mut self.textures, tr("debug.textures"));
+        ui.checkbox(&mut self.style, tr("debug.style"));
+        ui.separator();
+        // egui only has it in debug builds.
+        ui.add_enabled(cfg!(debug_assertions), egui::Checkbox::new(&mut self.debug_on_hover, tr("debug.debug_on_hover")))
+            .on_hover_text(tr("debug.debug_on_hover_hint"))
+            .on_disabled_hover_text(tr("debug.debug_on_hover_release"));
+        ui.checkbox(&mut self.highlight_repaints, tr("debug.highlight_repaints"))
+            .on_hover_text(tr("debug.highlight_repaints_hint"));
+        ui.checkbox(&mut self.overdraw, tr("debug.overdraw")).on_hover_text(tr("debug.overdraw_hint"));
+        ui.separator();
+        if ui.button(tr("debug.inflate_atlas")).on_hover_text(tr("debug.inflate_atlas_hint")).clicked() {
+            self.inflate_atlas = true;
+        }
+        if ui.button(tr("debug.trim_memory")).on_hover_text(tr("debug.trim_memory_hint")).clicked() {
+            self.trim_memory = true;
+            // It's done before the next frame.
+            ui.ctx().request_repaint();
+        }
+        if ui.button(tr("debug.panic")).on_hover_text(tr("debug.panic_hint")).clicked() {
+            panic!("The panic button in the Debug menu was clicked");
+        }
+    }
+
+    /// Whether the font atlas should be made to overflow, see `fonts::inflate_atlas`.
+    pub fn take_inflate_atlas(&mut self) -> bool {
+        std::mem::take(&mut self.inflate_atlas)
+    }
+
+    /// Whether all caches should be trimmed, see `AppResources::trim_memory`.
+    pub fn take_trim_memory(&mut self) -> bool {
+        std::mem::take(&mut self.trim_memory)
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        #[cfg(debug_assertions)]
+        if ctx.debug_on_hover() != self.debug_on_hover {
+            ctx.set_debug_on_hover(self.debug_on_hover);
+        }
+        debug_window(ctx, "debug_inspection", tr("debug.inspection"), &mut self.inspection, |ui| ctx.inspection_ui(ui));
+        debug_window(ctx, "debug_memory", tr("debug.memory"), &mut self.memory, |ui| ctx.memory_ui(ui));
+        debug_window(ctx, "debug_textures", tr("debug.textures"), &mut self.textures, |ui| ctx.texture_ui(ui));
+        debug_window(ctx, "debug_style", tr("debug.style"), &mut self.style, |ui| {
+            ctx.style_ui(ui, ctx.theme());
+            ui.separator();
+            ctx.settings_ui(ui);
+        });
+
+        // These are the requests that led to this frame, drawing the highlight doesn't ask for another one.
+        let causes = ctx.repaint_causes();
+        if self.highlight_repaints && !causes.is_empty() {
+            let painter = ctx.layer_painter(egui::LayerId::new(egui::Order::Debug, egui::Id::new("repaint_highlight")));
+            let rect = ctx.screen_rect();
+            let color = egui::Color32::from_rgb(255, 120, 0);
+            painter.rect(rect, 0.0, color.gamma_multiply(0.08), egui::Stroke::new(3.0, color), egui::StrokeKind::Inside);
+            let text = causes.iter().map(ToString::to_string).collect::<Vec<_>>().join("\n");
+            painter.text(rect.left_bottom() + egui::vec2(8.0, -8.0), egui::Align2::LEFT_BOTTOM, text, egui::FontId::monospace(10.0), color);
+        }
+    }
+}
+
+fn debug_window(ctx: &egui::Context, id: &str, title: &str, open: &mut bool, add_contents: impl FnOnce(&mut egui::Ui)) {
+    egui::Window::new(title)
+        .id(egui::Id::new(id))
+        .open(open)
+        .vscroll(true)
+        .default_width(360.0)
+        .show(ctx, add_contents);
+}
+
+/// Dragging closer than this to the top or bottom of a list scrolls it, in points.
+const AUTO_SCROLL_MARGIN: f32 = 24.0;
+const AUTO_SCROLL_SPEED: f32 = 6.0;
+
+/// Two lists whose items can be reordered and moved between them by dragging.
+pub struct ReorderDemo {
+    open: bool,
+    lists: [Vec<String>; 2],
+}
+
+impl ReorderDemo {
+    /// The lists are restored from `document`, or start with some fruit and vegetables.
+    pub fn load(document: &Document) -> Self {
+        let list = |key, default: &[&str]| match document.get_str("reorder_demo", key) {
+            Some(items) => items.lines().map(str::to_owned).collect(),
+            None => default.iter().map(|&item| item.to_owned()).collect(),
+        };
+        Self {
+            open: false,
+            lists: [
+                list("left", &["Apple", "Banana", "Cherry", "Date", "Elderberry", "Fig", "Grape", "Honeydew", "Kiwi", "Lemon", "Mango", "Nectarine"]),
+                list("right", &["Artichoke", "Broccoli", "Carrot"]),
+            ],
+        }
+    }
+
+    pub fn save(&self, document: &mut Document) {
+        document.set("reorder_demo", "left", self.lists[0].join("\n"));
+        document.set("reorder_demo", "right", self.lists[1].join("\n"));
+    }
+
+    pub fn open(&mut self) {
+        self.open = true;
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        let mut open = self.open;
+        egui::Window::new(tr("reorder.title"))
+            .tool_window(ctx, ToolWindow::Reorder)
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(tr("reorder.hint"));
+                // Where the dragged item was dropped, as the list and row to insert it at.
+                let mut moved = None;
+                ui.horizontal_top(|ui| {
+                    for list in 0..self.lists.len() {
+                        if let Some((from, to)) = self.list_ui(ui, list) {
+                            moved = Some((from, to));
+                        }
+                    }
+                });
+                if let Some((from, to)) = moved {
+                    self.move_item(from, to);
+                }
+            });
+        self.open = open;
+    }
+
+    /// Returns the dragged item and where it should go if it was dropped onto this list.
+    fn list_ui(&self, ui: &mut egui::Ui, list: usize) -> Option<(DraggedItem, (usize, usize))> {
+        let mut target = None;
+        let frame = egui::Frame::default().inner_margin(4.0);
+        let (_, dropped) = ui.dnd_drop_zone::<DraggedItem, ()>(frame, |ui| {
+            ui.set_width(140.0);
+            egui::ScrollArea::vertical().id_salt(list).max_height(200.0).show(ui, |ui| {
+                ui.set_min_size(egui::vec2(140.0, 200.0));
+                let pointer = ui.ctx().pointer_interact_pos();
+                for (row, item) in self.lists[list].iter().enumerate() {
+                    let response = ui.dnd_drag_source(egui::Id::new(("reorder_item", list, row)), DraggedItem { list, row }, |ui| {
+                        ui.label(item);
+                    }).response;
+
+                    // Over the top half of a row the item goes before it, over the bottom half after it.
+                    if let (Some(pointer), Some(_)) = (pointer, response.dnd_hover_payload::<DraggedItem>()) {
+                        let rect = response.rect;
+                        let (y, insert_at) = if pointer.y < rect.center().y { (rect.top(), row) } else { (rect.bottom(), row + 1) };
+                        let stroke = egui::Stroke::new(2.0, ui.visuals().selection.stroke.color);
+                        ui.painter().hline(rect.x_range(), y, stroke);
+                        target = Some(insert_at);
+                    }
+                }
+
+                // Scrolling doesn't happen on its own while dragging, the pointer near an edge scrolls that way.
+                if let Some(pointer) = pointer.filter(|_| egui::DragAndDrop::has_payload_of_type::<DraggedItem>(ui.ctx())) {
+                    let visible = ui.clip_rect();
+                    if visible.x_range().contains(pointer.x) {
+                        let delta = if (visible.top()..visible.top() + AUTO_SCROLL_MARGIN).contains(&pointer.y) {
+                            AUTO_SCROLL_SPEED
+                        } else if (visible.bottom() - AUTO_SCROLL_MARGIN..visible.bottom()).contains(&pointer.y) {
+                            -AUTO_SCROLL_SPEED
+                        } else {
+                            0.0
+                        };
+                        if delta != 0.0 {
+                            ui.scroll_with_delta(egui::vec2(0.0, delta));
+                            ui.ctx().request_repaint();
+                        }
+                    }
+                }
+            });
+        });
+
+        // Dropped below the last row, or onto an empty list.
+        let dropped = dropped?;
+        Some((*dropped, (list, target.unwrap_or(self.lists[list].len()))))
+    }
+
+    fn move_item(&mut self, from: DraggedItem, (list, mut row): (usize, usize)) {
+        if from.row >= self.lists[from.list].len() {
+            return;
+        }
+        let item = self.lists[from.list].remove(from.row);
+        // Removing the item shifts everything after it up by one.
+        if from.list == list && from.row < row {
+            row -= 1;
+        }
+        let row = row.min(self.lists[list].len());
+        self.lists[list].insert(row, item);
+    }
+}
+
+/// A button picked in a [`Modal`]. Escape and clicking outside, where allowed, count as `Cancel`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModalChoice {
+    Ok,
+    Cancel,
+    Custom(&'static str),
+}
+
+/// Kept by a [`Modal`] between frames, the choice stays until it's opened again.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum ModalState {
+    #[default]
+    Closed,
+    Open,
+    Chosen(ModalChoice),
+}
+
+/// A dialog in front of a dimmed backdrop that blocks all input to what's below it, until one of its buttons is picked.
+/// egui keeps keyboard focus, and with it Tab, inside the topmost modal. Modals shown later stack on top.
+pub struct Modal {
+    id: egui::Id,
+    title: String,
+    buttons: Vec<(ModalChoice, String)>,
+    close_on_escape: bool,
+    close_on_backdrop_click: bool,
+    state: ModalState,
+}
+
+impl Modal {
+    /// With OK and Cancel buttons, closed by Escape but not by clicking outside.
+    pub fn new(id_salt: impl std::hash::Hash, title: impl Into<String>) -> Self {
+        Self {
+            id: egui::Id::new(id_salt),
+            title: title.into(),
+            buttons: vec![(ModalChoice::Ok, tr("modal.ok").to_owned()), (ModalChoice::Cancel, tr("modal.cancel").to_owned())],
+            close_on_escape: true,
+            close_on_backdrop_click: false,
+            state: ModalState::Closed,
+        }
+    }
+
+    /// Buttons from left to right with their labels.
+    pub fn with_buttons(mut self, buttons: &[(ModalChoice, &str)]) -> Self {
+        self.buttons = buttons.iter().map(|(choice, label)| (choice.clone(), (*label).to_owned())).collect();
+        self
+    }
+
+    pub fn close_on_escape(mut self, close_on_escape: bool) -> Self {
+        self.close_on_escape = close_on_escape;
+        self
+    }
+
+    pub fn close_on_backdrop_click(mut self, close_on_backdrop_click: bool) -> Self {
+        self.close_on_backdrop_click = close_on_backdrop_click;
+        self
+    }
+
+    pub fn open(&mut self) {
+        self.state = ModalState::Open;
+    }
+
+    pub fn state(&self) -> &ModalState {
+        &self.state
+    }
+
+    /// Shows the modal while it's open, returns the choice on the frame it was made.
+    pub fn show(&mut self, ctx: &egui::Context, add_contents: impl FnOnce(&mut egui::Ui)) -> Option<ModalChoice> {
+        if self.state != ModalState::Open {
+            return None;
+        }
+
+        let response = egui::Modal::new(self.id).show(ctx, |ui| {
+            ui.set_min_width(240.0);
+            ui.heading(&self.title);
+            add_contents(ui);
+            ui.separator();
+
+            let mut choice = None;
+            ui.horizontal(|ui| {
+                for (button, label) in &self.buttons {
+                    if ui.button(label).clicked() {
+                        choice = Some(button.clone());
+                    }
+                }
+            });
+            choice
+        });
+
+        let mut choice = response.inner;
+        // A modal stacked on top gets these first.
+        if choice.is_none() && response.is_top_modal {
+            let escape = self.close_on_escape && !response.any_popup_open
+                && ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Escape));
+            if escape || (self.close_on_backdrop_click && response.backdrop_response.clicked()) {
+                choice = Some(ModalChoice::Cancel);
+            }
+        }
+        if let Some(choice) = &choice {
+            self.state = ModalState::Chosen(choice.clone());
+        }
+        choice
+    }
+}
+
+/// Opens a modal that can open a second one on top of it.
+pub struct ModalDemo {
+    outer: Modal,
+    inner: Modal,
+    name: String,
+}
+
+impl Default for ModalDemo {
+    fn default() -> Self {
+        Self { outer: Self::outer_modal(), inner: Self::inner_modal(), name: String::new() }
+    }
+}
+
+impl ModalDemo {
+    /// The modals are made anew each time, so they're in the current language.
+    pub fn open(&mut self) {
+        self.outer = Self::outer_modal();
+        self.inner = Self::inner_modal();
+        self.outer.open();
+    }
+
+    fn outer_modal() -> Modal {
+        Modal::new("modal_demo_outer", tr("modal.demo_title"))
+            .with_buttons(&[(ModalChoice::Ok, tr("modal.ok")), (ModalChoice::Custom("nested"), tr("modal.open_another")), (ModalChoice::Cancel, tr("modal.cancel"))])
+            .close_on_escape(false)
+    }
+
+    fn inner_modal() -> Modal {
+        Modal::new("modal_demo_inner", tr("modal.nested_title")).close_on_backdrop_click(true)
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        let name = &mut self.name;
+        let outer_choice = self.outer.show(ctx, |ui| {
+            ui.label(tr("modal.demo_text"));
+            ui.horizontal(|ui| {
+                ui.label(tr("modal.name"));
+                ui.text_edit_singleline(name);
+            });
+        });
+        if outer_choice == Some(ModalChoice::Custom("nested")) {
+            // Picking a button closes the modal, this one stays open below the nested one.
+            self.outer.open();
+            self.inner.open();
+        }
+
+        let outer_state = match self.outer.state() {
+            ModalState::Closed => tr("modal.state_closed"),
+            ModalState::Open => tr("modal.state_open"),
+            ModalState::Chosen(_) => tr("modal.state_chosen"),
+        };
+        self.inner.show(ctx, |ui| {
+            ui.label(tr("modal.nested_text"));
+            ui.label(tr_format("modal.outer_state", &[("state", &outer_state)]));
+        });
+    }
+}
+
+/// Radians the camera turns per pixel of mouse motion.
+const CAMERA_SENSITIVITY: f32 = 0.004;
+
+/// A wireframe cube looked at by a camera that turns with relative mouse motion while the cursor is grabbed.
+pub struct CameraDemo {
+    open: bool,
+    yaw: f32,
+    pitch: f32,
+}
+
+impl Default for CameraDemo {
+    fn default() -> Self {
+        Self {
+            open: false,
+            yaw: 0.6,
+            pitch: 0.4,
+        }
+    }
+}
+
+impl CameraDemo {
+    pub fn open(&mut self) {
+        self.open = true;
+    }
+
+    /// Turns the camera by a raw mouse delta, as reported by `DeviceEvent::MouseMotion`.
+    pub fn rotate(&mut self, (dx, dy): (f64, f64)) {
+        self.yaw += dx as f32 * CAMERA_SENSITIVITY;
+        self.yaw %= std::f32::consts::TAU;
+        // Looking straight up or down would flip the view over.
+        self.pitch = (self.pitch + dy as f32 * CAMERA_SENSITIVITY).clamp(-1.5, 1.5);
+    }
+
+    /// Returns true if the cursor should be grabbed.
+    pub fn show(&mut self, ctx: &egui::Context, grabbed: bool) -> bool {
+        let mut grab = false;
+        let mut open = self.open;
+        egui::Window::new(tr("camera.title"))
+            .tool_window(ctx, ToolWindow::Camera)
+            .open(&mut open)
+            .default_size([320.0, 280.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if grabbed {
+                        ui.label(tr("camera.grabbed"));
+                    } else if ui.button(tr("camera.grab")).clicked() {
+                        grab = true;
+                    }
+                });
+                ui.label(tr_format("camera.angles", &[("yaw", &self.yaw.to_degrees().round()), ("pitch", &self.pitch.to_degrees().round())]));
+                egui::Frame::canvas(ui.style()).show(ui, |ui| {
+                    self.paint_cube(ui);
+                });
+            });
+        self.open = open;
+        grab
+    }
+
+    fn paint_cube(&self, ui: &mut egui::Ui) {
+        let (response, painter) = ui.allocate_painter(ui.available_size_before_wrap(), egui::Sense::hover());
+        let rect = response.rect;
+        let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+        let (sin_pitch, cos_pitch) = self.pitch.sin_cos();
+        let scale = rect.width().min(rect.height()) * 0.9;
+        let project = |[x, y, z]: [f32; 3]| {
+            let (x, z) = (x * cos_yaw - z * sin_yaw, x * sin_yaw + z * cos_yaw);
+            let (y, z) = (y * cos_pitch - z * sin_pitch, y * sin_pitch + z * cos_pitch);
+            // The camera sits 3 units in front of the cube.
+            let depth = z + 3.0;
+            rect.center() + egui::vec2(x, -y) * (scale / depth)
+        };
+
+        let corner = |i: usize| [i & 1, i >> 1 & 1, i >> 2 & 1].map(|bit| if bit == 1 { 0.5 } else { -0.5 });
+        let stroke = egui::Stroke::new(1.5, ui.visuals().strong_text_color());
+        for from in 0..8 {
+            // Corners one bit apart share an edge.
+            for bit in [1, 2, 4] {
+                if from & bit == 0 {
+                    painter.line_segment([project(corner(from)), project(corner(from | bit))], stroke);
+                }
+            }
+        }
+    }
+}
+
+/// How far the [`ZoomCanvas`] can be zoomed out and in.
+const CANVAS_ZOOM_RANGE: std::ops::RangeInclusive<f32> = 0.1..=20.0;
+
+/// A plot that can be panned and zoomed with a finger or two, or with the mouse wheel.
+pub struct ZoomCanvas {
+    open: bool,
+    /// Where the plot's origin is, relative to the center of the canvas, in points.
+    pan: egui::Vec2,
+    /// Points per plot unit.
+    zoom: f32,
+}
+
+impl Default for ZoomCanvas {
+    fn default() -> Self {
+        Self {
+            open: false,
+            pan: egui::Vec2::ZERO,
+            zoom: 40.0,
+        }
+    }
+}
+
+impl ZoomCanvas {
+    pub fn open(&mut self) {
+        self.open = true;
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        let mut open = self.open;
+        egui::Window::new(tr("canvas.title"))
+            .tool_window(ctx, ToolWindow::ZoomCanvas)
+            .open(&mut open)
+            .default_size([420.0, 320.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(tr_format("canvas.zoom", &[("percent", &(self.zoom / Self::default().zoom * 100.0).round())]));
+                    if ui.button(tr("canvas.reset")).clicked() {
+                        self.reset();
+                    }
+                });
+                ui.weak(tr("canvas.hint"));
+                egui::Frame::canvas(ui.style()).show(ui, |ui| {
+                    self.canvas(ui);
+                });
+            });
+        self.open = open;
+    }
+
+    fn reset(&mut self) {
+        self.pan = egui::Vec2::ZERO;
+        self.zoom = Self::default().zoom;
+    }
+
+    fn canvas(&mut self, ui: &mut egui::Ui) {
+        let (response, painter) = ui.allocate_painter(ui.available_size_before_wrap(), egui::Sense::click_and_drag());
+        let center = response.rect.center();
+
+        if response.double_clicked() {
+            self.reset();
+        } else if response.contains_pointer() {
+            let (zoom_delta, scroll_delta, multi_touch) = ui.input(|i| (i.zoom_delta(), i.smooth_scroll_delta, i.multi_touch()));
+            // With two fingers down the first one also drags as the pointer, the gesture's translation already covers it.
+            self.pan += match multi_touch {
+                Some(touch) => touch.translation_delta,
+                None => response.drag_delta(),
+            };
+            self.pan += scroll_delta;
+
+            // Zooming keeps the plot point under the fingers or the mouse where it is.
+            let anchor = multi_touch.map(|touch| touch.center_pos).or(response.hover_pos()).unwrap_or(center);
+            let zoom = (self.zoom * zoom_delta).clamp(*CANVAS_ZOOM_RANGE.start(), *CANVAS_ZOOM_RANGE.end());
+            if zoom != self.zoom {
+                let plot_anchor = (anchor - center - self.pan) / self.zoom;
+                self.pan = anchor - center - plot_anchor * zoom;
+                self.zoom = zoom;
+            }
+        }
+
+        let to_screen = |x: f32, y: f32| center + self.pan + egui::vec2(x, -y) * self.zoom;
+        let rect = response.rect;
+        let plot_min = (rect.left_bottom() - center - self.pan) / self.zoom;
+        let plot_max = (rect.right_top() - center - self.pan) / self.zoom;
+        let (x_range, y_range) = (plot_min.x..=plot_max.x, -plot_min.y..=-plot_max.y);
+
+        // Grid lines stay between 20 and 200 points apart by switching between powers of ten.
+        let step = 10f32.powf((20.0 / self.zoom).log10().ceil());
+        let grid = egui::Stroke::new(1.0, ui.visuals().faint_bg_color.gamma_multiply(4.0));
+        let axis = egui::Stroke::new(1.0, ui.visuals().weak_text_color());
+        let mut x = (x_range.start() / step).floor() * step;
+        while x <= *x_range.end() {
+            let stroke = if x.abs() < step / 2.0 { axis } else { grid };
+            painter.vline(to_screen(x, 0.0).x, rect.y_range(), stroke);
+            x += step;
+        }
+        let mut y = (y_range.start() / step).floor() * step;
+        while y <= *y_range.end() {
+            let stroke = if y.abs() < step / 2.0 { axis } else { grid };
+            painter.hline(rect.x_range(), to_screen(0.0, y).y, stroke);
+            y += step;
+        }
+
+        // One point per screen pixel or so, whatever the zoom.
+        let samples = rect.width().ceil() as usize;
+        let curve = (0..=samples)
+            .map(|i| {
+                let x = x_range.start() + (x_range.end() - x_range.start()) * i as f32 / samples as f32;
+                to_screen(x, x.sin() * (x * 0.3).cos() * 2.0)
+            })
+            .collect();
+        painter.add(egui::Shape::line(curve, egui::Stroke::new(2.0, egui::Color32::from_rgb(60, 160, 255))));
+    }
+}
+
+/// Where the [`BouncingBall`] can go, in units of the canvas' shorter side, and how big it is.
+const BALL_AREA: egui::Vec2 = egui::vec2(1.6, 1.0);
+const BALL_RADIUS: f32 = 0.05;
+/// Units per second squared.
+const BALL_GRAVITY: f32 = 3.0;
+
+/// A ball bouncing around, simulated in fixed steps and drawn interpolated between the last two of them.
+/// Its path doesn't depend on the frame rate, with vsync on or off it bounces the same way.
+pub struct BouncingBall {
+    open: bool,
+    position: egui::Vec2,
+    /// Where the ball was one step earlier.
+    previous: egui::Vec2,
+    velocity: egui::Vec2,
+    steps: u64,
+}
+
+impl Default for BouncingBall {
+    fn default() -> Self {
+        let position = egui::vec2(0.2, 0.2);
+        Self {
+            open: false,
+            position,
+            previous: position,
+            velocity: egui::vec2(0.7, 0.0),
+            steps: 0,
+        }
+    }
+}
+
+impl BouncingBall {
+    pub fn open(&mut self) {
+        self.open = true;
+    }
+
+    /// Only simulated while it's shown.
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// `alpha` blends from the previous step to the last one, see `FixedTimestep::alpha`.
+    /// With reduced motion it isn't simulated and stays where it is.
+    pub fn show(&mut self, ctx: &egui::Context, alpha: f32, steps_this_frame: u32) {
+        let reduce_motion = MotionPrefs::get(ctx).reduce_motion;
+        let mut open = self.open;
+        egui::Window::new(tr("ball.title"))
+            .tool_window(ctx, ToolWindow::BouncingBall)
+            .open(&mut open)
+            .default_size([400.0, 300.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(tr_format("ball.steps", &[("steps", &self.steps), ("rate", &UPDATE_RATE)]));
+                    ui.weak(tr_format("ball.this_frame", &[("steps", &steps_this_frame), ("alpha", &format!("{alpha:.2}"))]));
+                    if ui.button(tr("ball.reset")).clicked() {
+                        *self = Self { open: true, ..Self::default() };
+                    }
+                });
+                if reduce_motion {
+                    ui.weak(tr("ball.reduced_motion"));
+                }
+                egui::Frame::canvas(ui.style()).show(ui, |ui| {
+                    let (response, painter) = ui.allocate_painter(ui.available_size_before_wrap(), egui::Sense::hover());
+                    let scale = (response.rect.width() / BALL_AREA.x).min(response.rect.height() / BALL_AREA.y);
+                    let area = egui::Rect::from_center_size(response.rect.center(), BALL_AREA * scale);
+                    painter.rect_stroke(area, 0.0, ui.visuals().widgets.noninteractive.bg_stroke, egui::StrokeKind::Inside);
+                    let position = egui::lerp(self.previous..=self.position, alpha.clamp(0.0, 1.0));
+                    painter.circle_filled(area.left_top() + position * scale, BALL_RADIUS * scale, ui.visuals().selection.bg_fill);
+                });
+            });
+        self.open = open;
+        if self.open && !reduce_motion {
+            ctx.request_repaint();
+        }
+    }
+}
+
+impl FixedUpdate for BouncingBall {
+    fn update(&mut self, dt: f32) {
+        self.previous = self.position;
+        self.velocity.y += BALL_GRAVITY * dt;
+        self.position += self.velocity * dt;
+        // Reflect off the walls, the floor bounces back a little less each time.
+        let min = egui::Vec2::splat(BALL_RADIUS);
+        let max = BALL_AREA - min;
+        for axis in 0..2 {
+            if self.position[axis] < min[axis] {
+                self.position[axis] = 2.0 * min[axis] - self.position[axis];
+                self.velocity[axis] = self.velocity[axis].abs();
+            } else if self.position[axis] > max[axis] {
+                self.position[axis] = 2.0 * max[axis] - self.position[axis];
+                self.velocity[axis] = -self.velocity[axis].abs() * if axis == 1 { 0.9 } else { 1.0 };
+            }
+        }
+        // Kick it back up once it has come to rest, so there's always something to watch.
+        if self.position.y > max.y - 0.01 && self.velocity.y.abs() < 0.3 {
+            self.velocity.y = -2.2;
+        }
+        self.steps += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui_driver::{self, UiDriver};
+
+    fn raw_input(events: Vec<egui::Event>) -> egui::RawInput {
+        egui::RawInput {
+            // Tall enough for the whole main menu.
+            screen_rect: Some(egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(800.0, 800.0))),
+            events,
+            ..Default::default()
+        }
+    }
+
+    fn run_gallery(ctx: &egui::Context, gallery: &mut WidgetGallery, events: Vec<egui::Event>) -> egui::FullOutput {
+        ctx.run(raw_input(events), |ctx| gallery.show(ctx))
+    }
+
+    fn run_menu(ctx: &egui::Context, state: &MenuState, events: Vec<egui::Event>) -> (Option<MenuAction>, egui::FullOutput) {
+        let mut action = None;
+        let output = ctx.run(raw_input(events), |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| action = main_menu(ui, state, &mut DebugTools::default()));
+        });
+        (action, output)
+    }
+
+    /// The middle of where `text` was drawn.
+    fn text_position(output: &egui::FullOutput, text: &str) -> egui::Pos2 {
+        ui_driver::text_rect(output, text).unwrap_or_else(|| panic!("{text:?} isn't shown")).center()
+    }
+
+    /// The gallery alone, in a screen tall enough for all of it.
+    fn gallery_driver() -> UiDriver<WidgetGallery> {
+        UiDriver::new(WidgetGallery::default(), WidgetGallery::show, egui::vec2(800.0, 1000.0))
+    }
+
+    /// Clicks on `text` in the menu, returns what the menu reported in the frames of the press and the release.
+    fn click_menu(ctx: &egui::Context, state: &MenuState, text: &str) -> Vec<MenuAction> {
+        let (_, output) = run_menu(ctx, state, Vec::new());
+        let pos = text_position(&output, text);
+        let button = |pressed| egui::Event::PointerButton { pos, button: egui::PointerButton::Primary, pressed, modifiers: egui::Modifiers::NONE };
+        [vec![egui::Event::PointerMoved(pos), button(true)], vec![button(false)]].into_iter()
+            .filter_map(|events| run_menu(ctx, state, events).0)
+            .collect()
+    }
+
+    #[test]
+    fn settings_search_only_shows_matches_under_their_heading() {
+        let ctx = egui::Context::default();
+        let mut window = SettingsWindow { open: true, ..Default::default() };
+        window.search.query = tr("settings.zoom").to_owned();
+        window.search.filtering = true;
+        let mut settings = Settings::default();
+        // The window is invisible while it sizes itself in the first frame.
+        let _ = ctx.run(raw_input(Vec::new()), |ctx| window.show(ctx, &mut settings));
+        let output = ctx.run(raw_input(Vec::new()), |ctx| window.show(ctx, &mut settings));
+        let shown = |text| output.shapes.iter().any(|clipped| matches!(&clipped.shape, egui::Shape::Text(shape) if shape.galley.text() == text));
+        assert!(shown(tr("settings.appearance")));
+        assert!(shown(tr("settings.zoom")));
+        assert!(!shown(tr("settings.theme")));
+        assert!(!shown(tr("settings.window")));
+    }
+
+    #[test]
+    fn menu_buttons_report_the_window_to_show() {
+        let ctx = egui::Context::default();
+        let state = MenuState::default();
+        assert_eq!(click_menu(&ctx, &state, tr("menu.settings")), [MenuAction::Show(ToolWindow::Settings)]);
+        assert_eq!(click_menu(&ctx, &state, tr("menu.pixel_test")), [MenuAction::Show(ToolWindow::PixelTest)]);
+    }
+
+    #[test]
+    fn always_on_top_checkbox_flips() {
+        let ctx = egui::Context::default();
+        let mut state = MenuState::default();
+        assert_eq!(click_menu(&ctx, &state, tr("menu.always_on_top")), [MenuAction::SetAlwaysOnTop(true)]);
+        state.always_on_top = true;
+        assert_eq!(click_menu(&ctx, &state, tr("menu.always_on_top")), [MenuAction::SetAlwaysOnTop(false)]);
+    }
+
+    #[test]
+    fn recording_button_stops_the_running_format() {
+        let ctx = egui::Context::default();
+        let state = MenuState { recording: Some(RecordingFormat::Gif), ..Default::default() };
+        assert_eq!(click_menu(&ctx, &state, tr("menu.stop_recording")), [MenuAction::ToggleRecording(RecordingFormat::Gif)]);
+    }
+
+    #[test]
+    fn ime_composition_goes_into_the_multiline_editor() {
+        let ctx = egui::Context::default();
+        let mut gallery = WidgetGallery { notes: "一行目\n".to_owned(), ..Default::default() };
+        // The window sizes itself during the first frames.
+        for _ in 0..2 {
+            run_gallery(&ctx, &mut gallery, Vec::new());
+        }
+        let id = egui::Id::new(GALLERY_NOTES_ID);
+        ctx.memory_mut(|memory| memory.request_focus(id));
+        run_gallery(&ctx, &mut gallery, Vec::new());
+        // Typing continues at the end of the text, on the second line.
+        let mut state = egui::TextEdit::load_state(&ctx, id).unwrap_or_default();
+        let end = egui::text::CCursor::new(gallery.notes.chars().count());
+        state.cursor.set_char_range(Some(egui::text::CCursorRange::one(end)));
+        state.store(&ctx, id);
+
+        let output = run_gallery(&ctx, &mut gallery, vec![
+            egui::Event::Ime(egui::ImeEvent::Enabled),
+            egui::Event::Ime(egui::ImeEvent::Preedit("にほん".to_owned())),
+        ]);
+        // The composition is previewed inline, and the candidate window is told where the cursor is.
+        assert_eq!(gallery.notes, "一行目\nにほん");
+        let ime = output.platform_output.ime.expect("the focused editor should ask for IME");
+        assert!(ime.cursor_rect.is_positive());
+
+        run_gallery(&ctx, &mut gallery, vec![
+            egui::Event::Ime(egui::ImeEvent::Preedit("にほんご".to_owned())),
+            egui::Event::Ime(egui::ImeEvent::Commit("日本語".to_owned())),
+            egui::Event::Ime(egui::ImeEvent::Disabled),
+        ]);
+        assert_eq!(gallery.notes, "一行目\n日本語");
+    }
+
+    #[test]
+    fn gallery_button_toggles_the_checkbox() {
+        let mut driver = gallery_driver();
+        driver.click_label(tr("gallery.click_me"));
+        assert!(driver.app().boolean);
+        // The first is the name of the widget in front of it.
+        driver.click_nth_label(tr("gallery.checkbox"), 1);
+        assert!(!driver.app().boolean);
+    }
+
+    #[test]
+    fn gallery_text_field_takes_typing_and_undoes_it() {
+        let mut driver = gallery_driver();
+        driver.click_label(tr("gallery.string_hint"));
+        assert!(driver.output().platform_output.ime.is_some(), "the field should have the focus");
+        driver.type_text("hello");
+        driver.press_key(egui::Key::Enter);
+        assert!(driver.ctx().memory(|memory| memory.focused().is_none()));
+        assert_eq!(driver.app().string, "hello");
+        assert!(driver.is_shown("hello"));
+
+        driver.click_nth_label(tr("gallery.undo"), 1);
+        assert_eq!(driver.app().string, "");
+        assert!(driver.is_shown(tr("gallery.string_hint")));
+    }
+
+    #[test]
+    fn gallery_slider_drags_across_its_range() {
+        let mut driver = gallery_driver();
+        driver.drag_slider_to("42°", 0.5);
+        assert!((driver.app().scalar - 180.0).abs() < 2.0, "{}", driver.app().scalar);
+        let progress = format!("{}%", (driver.app().scalar / 3.6).round());
+        assert!(driver.is_shown(&progress));
+    }
+
+    #[test]
+    fn gallery_radio_and_collapsing_header() {
+        let mut driver = gallery_driver();
+        driver.click_label(tr("gallery.second"));
+        assert_eq!(driver.app().radio, Enum::Second);
+
+        assert!(!driver.is_shown(tr("gallery.its_a")));
+        driver.click_label(tr("gallery.collapsing"));
+        assert!(driver.is_shown(tr("gallery.its_a")));
+    }
+}
@@ -0,0 +1,151 @@
+use std::time::Duration;
+
+/// Frames stepped at most after an input for the UI to settle, with an animation that never ends it gives up after
+/// these.
+const MAX_SETTLE_FRAMES: usize = 30;
+const FRAME_TIME: f32 = 1.0 / 60.0;
+
+/// Where `text` was drawn in `output` and wasn't clipped away, in the order it was painted in.
+pub fn text_rects<'a>(output: &'a egui::FullOutput, text: &'a str) -> impl Iterator<Item = egui::Rect> + 'a {
+    output.shapes.iter().filter_map(move |clipped| match &clipped.shape {
+        egui::Shape::Text(shape) if shape.galley.text() == text => {
+            let rect = shape.visual_bounding_rect();
+            clipped.clip_rect.contains(rect.center()).then_some(rect)
+        },
+        _ => None,
+    })
+}
+
+/// The first place `text` was drawn, see [`text_rects`].
+pub fn text_rect(output: &egui::FullOutput, text: &str) -> Option<egui::Rect> {
+    text_rects(output, text).next()
+}
+
+/// Drives the UI of `app` the way a user would, for end-to-end tests: widgets are found by the text the last frame
+/// drew for them, the input is made of the events egui-winit would send, and after each input the frames are stepped
+/// until the UI settles. Time goes on by a 60 Hz frame per pass, so animations play out the same on every run.
+/// Nothing is painted, the assertions are on `app` and on what egui output.
+pub struct UiDriver<T> {
+    ctx: egui::Context,
+    app: T,
+    /// Shows the UI of `app` for a pass.
+    show: fn(&mut T, &egui::Context),
+    screen_size: egui::Vec2,
+    time: f64,
+    output: egui::FullOutput,
+}
+
+impl<T> UiDriver<T> {
+    /// Runs the first frames right away, windows size themselves in those.
+    pub fn new(app: T, show: fn(&mut T, &egui::Context), screen_size: egui::Vec2) -> Self {
+        let mut driver = Self { ctx: egui::Context::default(), app, show, screen_size, time: 0.0, output: Default::default() };
+        driver.input(Vec::new());
+        driver
+    }
+
+    pub fn app(&self) -> &T {
+        &self.app
+    }
+
+    pub fn ctx(&self) -> &egui::Context {
+        &self.ctx
+    }
+
+    /// What the last frame output.
+    pub fn output(&self) -> &egui::FullOutput {
+        &self.output
+    }
+
+    pub fn is_shown(&self, text: &str) -> bool {
+        text_rect(&self.output, text).is_some()
+    }
+
+    /// Runs one frame with `events`.
+    pub fn step(&mut self, events: Vec<egui::Event>) -> &egui::FullOutput {
+        let raw_input = egui::RawInput {
+            screen_rect: Some(egui::Rect::from_min_size(egui::Pos2::ZERO, self.screen_size)),
+            time: Some(self.time),
+            predicted_dt: FRAME_TIME,
+            events,
+            ..Default::default()
+        };
+        self.time += f64::from(FRAME_TIME);
+        let show = self.show;
+        let app = &mut self.app;
+        self.output = self.ctx.run(raw_input, |ctx| show(app, ctx));
+        &self.output
+    }
+
+    /// Runs a frame with `events`, then more without any until egui doesn't ask for the next one right away.
+    pub fn input(&mut self, events: Vec<egui::Event>) {
+        self.step(events);
+        for _ in 0..MAX_SETTLE_FRAMES {
+            let repaint_delay = self.output.viewport_output.get(&egui::ViewportId::ROOT).map_or(Duration::MAX, |viewport| viewport.repaint_delay);
+            if !repaint_delay.is_zero() {
+                break;
+            }
+            self.step(Vec::new());
+        }
+    }
+
+    /// The middle of the `index`th place `text` was drawn in the last frame, widgets are painted in the order they
+    /// were added.
+    pub fn find_label(&self, text: &str, index: usize) -> egui::Pos2 {
+        text_rects(&self.output, text).nth(index).unwrap_or_else(|| panic!("{text:?} isn't shown {} times", index + 1)).center()
+    }
+
+    /// Clicks on `text`, e.g. a button's, a checkbox's or the hint of an empty text field. Where it's shown more
+    /// than once that's the first widget with it, [`Self::click_nth_label`] picks another.
+    pub fn click_label(&mut self, text: &str) {
+        self.click_nth_label(text, 0);
+    }
+
+    pub fn click_nth_label(&mut self, text: &str, index: usize) {
+        let pos = self.find_label(text, index);
+        self.input(vec![egui::Event::PointerMoved(pos), button(pos, true)]);
+        self.input(vec![button(pos, false)]);
+    }
+
+    /// Types into the focused widget.
+    pub fn type_text(&mut self, text: &str) {
+        self.input(vec![egui::Event::Text(text.to_owned())]);
+    }
+
+    pub fn press_key(&mut self, key: egui::Key) {
+        self.input([true, false].map(|pressed| {
+            egui::Event::Key { key, physical_key: None, pressed, repeat: false, modifiers: egui::Modifiers::NONE }
+        }).into());
+    }
+
+    /// Drags the handle of the slider whose value reads `value_text` to `fraction` of its range, 0.0 the start and
+    /// 1.0 the end. The slider is the handle on the row of the value that's closest to the left of it.
+    pub fn drag_slider_to(&mut self, value_text: &str, fraction: f32) {
+        let value = text_rect(&self.output, value_text).unwrap_or_else(|| panic!("{value_text:?} isn't shown"));
+        let handle = self.output.shapes.iter()
+            .filter_map(|clipped| match &clipped.shape {
+                egui::Shape::Circle(circle) if value.y_range().contains(circle.center.y) && circle.center.x < value.left() => Some(*circle),
+                _ => None,
+            })
+            .max_by(|a, b| a.center.x.total_cmp(&b.center.x))
+            .unwrap_or_else(|| panic!("no slider next to {value_text:?}"));
+        // The rail is as wide as the slider and thinner than the handle, whose middle stays a radius away from its
+        // ends.
+        let rail = self.output.shapes.iter()
+            .filter_map(|clipped| match &clipped.shape {
+                egui::Shape::Rect(rect) if rect.rect.contains(handle.center) && rect.rect.height() < handle.radius * 2.0 => Some(rect.rect),
+                _ => None,
+            })
+            .max_by(|a, b| a.width().total_cmp(&b.width()))
+            .unwrap_or_else(|| panic!("no rail under the slider next to {value_text:?}"));
+        let range = rail.x_range().shrink(handle.radius);
+        let to = egui::pos2(egui::lerp(range, fraction.clamp(0.0, 1.0)), handle.center.y);
+
+        self.input(vec![egui::Event::PointerMoved(handle.center), button(handle.center, true)]);
+        self.input(vec![egui::Event::PointerMoved(to)]);
+        self.input(vec![button(to, false)]);
+    }
+}
+
+fn button(pos: egui::Pos2, pressed: bool) -> egui::Event {
+    egui::Event::PointerButton { pos, button: egui::PointerButton::Primary, pressed, modifiers: egui::Modifiers::NONE }
+}
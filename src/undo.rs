@@ -0,0 +1,89 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Edits closer together than this are undone together, as long as they stay below `COALESCE_CHARS`.
+const COALESCE_TIME: Duration = Duration::from_secs(1);
+const COALESCE_CHARS: usize = 20;
+
+/// Edits since the last snapshot that get undone in one go.
+struct EditGroup {
+    last_edit: Instant,
+    changed_chars: usize,
+}
+
+/// Undo and redo for a text buffer that's kept across focus changes, unlike the one built into `TextEdit`.
+///
+/// Typing is coalesced into one step per burst of edits, so undo doesn't go back one keystroke at a time.
+pub struct TextHistory {
+    /// Texts from before each undoable step, oldest first.
+    undo: VecDeque<String>,
+    redo: Vec<String>,
+    group: Option<EditGroup>,
+    max_len: usize,
+}
+
+impl TextHistory {
+    /// Keeps at most `max_len` undo steps, the oldest ones are dropped first.
+    pub fn new(max_len: usize) -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            group: None,
+            max_len,
+        }
+    }
+
+    /// Records that the user changed the text from `before` to `after`, e.g. when a `TextEdit` response `changed()`.
+    pub fn user_edit(&mut self, before: &str, after: &str, now: Instant) {
+        let changed_chars = before.chars().count().abs_diff(after.chars().count()).max(1);
+        match &mut self.group {
+            Some(group) if now - group.last_edit < COALESCE_TIME && group.changed_chars + changed_chars <= COALESCE_CHARS => {
+                group.last_edit = now;
+                group.changed_chars += changed_chars;
+                self.redo.clear();
+            },
+            _ => {
+                self.checkpoint(before);
+                self.group = Some(EditGroup { last_edit: now, changed_chars });
+            },
+        }
+    }
+
+    /// Records a change made by code rather than typing, which is always its own undo step.
+    pub fn checkpoint(&mut self, before: &str) {
+        if self.undo.len() == self.max_len {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(before.to_owned());
+        self.redo.clear();
+        self.group = None;
+    }
+
+    /// Returns false if there was nothing to undo.
+    pub fn undo(&mut self, text: &mut String) -> bool {
+        let Some(previous) = self.undo.pop_back() else {
+            return false;
+        };
+        self.redo.push(std::mem::replace(text, previous));
+        self.group = None;
+        true
+    }
+
+    /// Returns false if there was nothing to redo.
+    pub fn redo(&mut self, text: &mut String) -> bool {
+        let Some(next) = self.redo.pop() else {
+            return false;
+        };
+        self.undo.push_back(std::mem::replace(text, next));
+        self.group = None;
+        true
+    }
+
+    pub fn undo_len(&self) -> usize {
+        self.undo.len()
+    }
+
+    pub fn redo_len(&self) -> usize {
+        self.redo.len()
+    }
+}
@@ -0,0 +1,341 @@
+use std::cell::RefCell;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use winit::event::WindowEvent;
+use winit::event_loop::ActiveEventLoop;
+use winit::window::{Window, WindowId};
+
+use crate::fonts;
+use crate::offscreen::MsaaTarget;
+
+/// What a viewport window draws with, all of it shared with the main window.
+pub struct ViewportGpu<'a> {
+    pub instance: &'a wgpu::Instance,
+    pub adapter: &'a wgpu::Adapter,
+    pub device: &'a wgpu::Device,
+    pub queue: &'a wgpu::Queue,
+    /// The format the surfaces are configured with.
+    pub surface_format: wgpu::TextureFormat,
+    /// The format the UI renderer draws in, a view format of the surfaces if it differs.
+    pub view_format: wgpu::TextureFormat,
+    /// What the UI renderer was created with, see [`MsaaTarget`].
+    pub msaa_samples: u32,
+    /// The texture size egui is told it can use, the same as for the main window.
+    pub max_texture_side: usize,
+}
+
+/// A viewport that egui UI code opened as a window of its own.
+struct Viewport {
+    window: Arc<Window>,
+    surface: wgpu::Surface<'static>,
+    config: wgpu::SurfaceConfiguration,
+    msaa: Option<MsaaTarget>,
+    state: egui_winit::State,
+    info: egui::ViewportInfo,
+    builder: egui::ViewportBuilder,
+    parent: egui::ViewportId,
+    /// `None` for immediate viewports, those run as part of their parent's pass.
+    ui_cb: Option<Arc<egui::DeferredViewportUiCallback>>,
+    repaint_at: Option<Instant>,
+}
+
+impl Viewport {
+    fn new(event_loop: &ActiveEventLoop, gpu: &ViewportGpu, ctx: &egui::Context, id: egui::ViewportId, output: &egui::ViewportOutput) -> Result<Self, String> {
+        let window = Arc::new(egui_winit::create_window(ctx, event_loop, &output.builder).map_err(|error| error.to_string())?);
+        let surface = gpu.instance.create_surface(window.clone()).map_err(|error| error.to_string())?;
+        let capabilities = surface.get_capabilities(gpu.adapter);
+        // The UI renderer is built for one format, a window that can't show it can't be drawn to.
+        if !capabilities.formats.contains(&gpu.surface_format) {
+            return Err(format!("the surface doesn't support {:?}", gpu.surface_format));
+        }
+        let size = window.inner_size();
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: gpu.surface_format,
+            width: size.width.max(1),
+            height: size.height.max(1),
+            present_mode: wgpu::PresentMode::AutoVsync,
+            desired_maximum_frame_latency: 2,
+            alpha_mode: capabilities.alpha_modes[0],
+            view_formats: if gpu.view_format != gpu.surface_format { vec![gpu.view_format] } else { Vec::new() },
+        };
+        surface.configure(gpu.device, &config);
+
+        let state = egui_winit::State::new(ctx.clone(), id, &window, Some(window.scale_factor() as f32), None, Some(gpu.max_texture_side));
+        let mut info = egui::ViewportInfo::default();
+        egui_winit::update_viewport_info(&mut info, ctx, &window, true);
+        Ok(Self {
+            window,
+            surface,
+            config,
+            msaa: None,
+            state,
+            info,
+            builder: output.builder.clone(),
+            parent: output.parent,
+            ui_cb: output.viewport_ui_cb.clone(),
+            repaint_at: Some(Instant::now()),
+        })
+    }
+
+    fn take_input(&mut self, ctx: &egui::Context) -> egui::RawInput {
+        egui_winit::update_viewport_info(&mut self.info, ctx, &self.window, false);
+        let mut raw_input = self.state.take_egui_input(&self.window);
+        raw_input.viewports.insert(raw_input.viewport_id, self.info.clone());
+        // Events like a close request are for one pass only.
+        self.info.events.clear();
+        raw_input
+    }
+
+    fn schedule_repaint(&mut self, delay: Duration) {
+        if let Some(at) = Instant::now().checked_add(delay) {
+            self.repaint_at = Some(self.repaint_at.map_or(at, |repaint_at| repaint_at.min(at)));
+        }
+    }
+
+    fn paint(&mut self, gpu: &ViewportGpu, renderer: &mut egui_wgpu::Renderer, ctx: &egui::Context, output: egui::FullOutput) {
+        self.state.handle_platform_output(&self.window, output.platform_output);
+        let clipped_primitives = ctx.tessellate(output.shapes, output.pixels_per_point);
+        let max_side = gpu.device.limits().max_texture_dimension_2d as usize;
+        for (id, delta) in &output.textures_delta.set {
+            // The main window recovers from the font atlas growing too big, here it's only kept from panicking.
+            if !fonts::delta_fits(delta, max_side) || (delta.pos.is_some() && renderer.texture(id).is_none()) {
+                log::warn!("Not uploading {id:?} for a viewport, it's too big or was never uploaded");
+                continue;
+            }
+            renderer.update_texture(gpu.device, gpu.queue, *id, delta);
+        }
+
+        match self.surface.get_current_texture() {
+            Ok(frame) => {
+                let screen_descriptor = egui_wgpu::ScreenDescriptor {
+                    size_in_pixels: [self.config.width, self.config.height],
+                    pixels_per_point: output.pixels_per_point,
+                };
+                let view = frame.texture.create_view(&wgpu::TextureViewDescriptor {
+                    format: Some(gpu.view_format),
+                    ..Default::default()
+                });
+                let size = [self.config.width, self.config.height];
+                MsaaTarget::prepare(&mut self.msaa, gpu.device, gpu.view_format, size, gpu.msaa_samples);
+                let mut ce = gpu.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("Viewport") });
+                let user_buffers = renderer.update_buffers(gpu.device, gpu.queue, &mut ce, &clipped_primitives, &screen_descriptor);
+                {
+                    let render_pass = ce.begin_render_pass(&wgpu::RenderPassDescriptor {
+                        label: Some("Viewport"),
+                        color_attachments: &[Some(MsaaTarget::color_attachment(self.msaa.as_ref(), &view, wgpu::Color::BLACK))],
+                        depth_stencil_attachment: None,
+                        timestamp_writes: None,
+                        occlusion_query_set: None,
+                    });
+                    renderer.render(&mut render_pass.forget_lifetime(), &clipped_primitives, &screen_descriptor);
+                }
+                gpu.queue.submit(user_buffers.into_iter().chain(std::iter::once(ce.finish())));
+                frame.present();
+            },
+            Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                self.resize(gpu.device, self.window.inner_size());
+                self.schedule_repaint(Duration::ZERO);
+            },
+            Err(error) => log::warn!("Failed to acquire a frame for viewport {:?}: {error}", self.builder.title),
+        }
+
+        for id in &output.textures_delta.free {
+            renderer.free_texture(id);
+        }
+    }
+
+    fn resize(&mut self, device: &wgpu::Device, size: winit::dpi::PhysicalSize<u32>) {
+        if size.width > 0 && size.height > 0 {
+            self.config.width = size.width;
+            self.config.height = size.height;
+            self.surface.configure(device, &self.config);
+        }
+    }
+}
+
+/// The windows of egui viewports besides the root one, see `Context::show_viewport_deferred` and
+/// `Context::show_viewport_immediate`.
+///
+/// Deferred viewports run their own passes whenever their window needs a frame. Immediate ones run from inside their
+/// parent's pass, the output is kept until that pass is done and painted right after it.
+/// Windows are created and closed based on the viewport output of the last pass, once there is an event loop for it.
+#[derive(Default)]
+pub struct Viewports {
+    viewports: HashMap<egui::ViewportId, Viewport>,
+    /// Viewports whose window couldn't be created, so that isn't retried every frame.
+    failed: HashSet<egui::ViewportId>,
+    /// Output of the passes since the last `sync`.
+    pending: Option<egui::ViewportIdMap<egui::ViewportOutput>>,
+    immediate_frames: Vec<(egui::ViewportId, egui::FullOutput)>,
+}
+
+impl Viewports {
+    /// Makes `ctx` open viewports as real windows where the platform has more than one.
+    pub fn install(ctx: &egui::Context) -> Rc<RefCell<Self>> {
+        let viewports = Rc::new(RefCell::new(Self::default()));
+        let embed = cfg!(target_os = "android");
+        ctx.set_embed_viewports(embed);
+        if !embed {
+            let weak = Rc::downgrade(&viewports);
+            egui::Context::set_immediate_viewport_renderer(move |ctx, mut viewport| match weak.upgrade() {
+                Some(viewports) => Self::run_immediate(&viewports, ctx, viewport),
+                None => (viewport.viewport_ui_cb)(ctx),
+            });
+        }
+        viewports
+    }
+
+    fn run_immediate(viewports: &RefCell<Self>, ctx: &egui::Context, mut viewport: egui::ImmediateViewport) {
+        let id = viewport.ids.this;
+        // The borrow has to end before the pass, the UI in it may open immediate viewports of its own.
+        let raw_input = viewports.borrow_mut().viewports.get_mut(&id).map(|window| window.take_input(ctx));
+        let has_window = raw_input.is_some();
+        // Without a window yet the UI still has to run, the window is created from the output of this pass.
+        let raw_input = raw_input.unwrap_or_else(|| egui::RawInput { viewport_id: id, ..Default::default() });
+        let output = ctx.run(raw_input, |ctx| (viewport.viewport_ui_cb)(ctx));
+        if has_window {
+            viewports.borrow_mut().immediate_frames.push((id, output));
+        }
+    }
+
+    /// Records the viewport output of a pass, which viewports are still open and what commands they got.
+    pub fn add_output(&mut self, output: egui::ViewportIdMap<egui::ViewportOutput>) {
+        match &mut self.pending {
+            Some(pending) => {
+                pending.retain(|id, _| output.contains_key(id));
+                for (id, output) in output {
+                    match pending.get_mut(&id) {
+                        Some(pending) => pending.append(output),
+                        None => {
+                            pending.insert(id, output);
+                        },
+                    }
+                }
+            },
+            None => self.pending = Some(output),
+        }
+    }
+
+    /// Opens and closes windows to match the recorded output and applies the viewport commands.
+    pub fn sync(&mut self, event_loop: &ActiveEventLoop, gpu: &ViewportGpu, ctx: &egui::Context) {
+        let Some(outputs) = self.pending.take() else {
+            return;
+        };
+        // Closed viewports aren't part of the output anymore, dropping the window closes it.
+        self.viewports.retain(|id, _| outputs.contains_key(id));
+        self.failed.retain(|id| outputs.contains_key(id));
+
+        for (id, output) in outputs {
+            if id == egui::ViewportId::ROOT || self.failed.contains(&id) {
+                continue;
+            }
+            let mut commands = output.commands.clone();
+            if let Some(viewport) = self.viewports.get_mut(&id) {
+                let (patch, recreate) = viewport.builder.patch(output.builder.clone());
+                if recreate {
+                    self.viewports.remove(&id);
+                } else {
+                    commands.splice(0..0, patch);
+                    viewport.ui_cb = output.viewport_ui_cb.clone();
+                }
+            }
+            let viewport = match self.viewports.entry(id) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => match Viewport::new(event_loop, gpu, ctx, id, &output) {
+                    Ok(viewport) => entry.insert(viewport),
+                    Err(error) => {
+                        log::warn!("Failed to open a window for viewport {:?}: {error}", output.builder.title);
+                        self.failed.insert(id);
+                        continue;
+                    },
+                },
+            };
+            // Screenshots and clipboard actions aren't supported in viewport windows.
+            let mut actions = Default::default();
+            egui_winit::process_viewport_commands(ctx, &mut viewport.info, commands, &viewport.window, &mut actions);
+            viewport.schedule_repaint(output.repaint_delay);
+        }
+    }
+
+    /// The viewport a window belongs to, `None` for windows that aren't viewports like the root one.
+    pub fn find(&self, window_id: WindowId) -> Option<egui::ViewportId> {
+        self.viewports.iter().find(|(_, viewport)| viewport.window.id() == window_id).map(|(&id, _)| id)
+    }
+
+    /// Passes an event on to the viewport's egui input. Returns the viewport whose pass has to run again to react to it,
+    /// that's the parent of an immediate viewport.
+    pub fn on_window_event(&mut self, id: egui::ViewportId, device: &wgpu::Device, event: &WindowEvent) -> Option<egui::ViewportId> {
+        let viewport = self.viewports.get_mut(&id)?;
+        let mut repaint = viewport.state.on_window_event(&viewport.window, event).repaint;
+        match event {
+            WindowEvent::Resized(size) => {
+                viewport.resize(device, *size);
+                repaint = true;
+            },
+            // The UI decides whether the window really closes, by not showing the viewport anymore.
+            WindowEvent::CloseRequested => {
+                viewport.info.events.push(egui::ViewportEvent::Close);
+                repaint = true;
+            },
+            _ => {},
+        }
+        repaint.then(|| if viewport.ui_cb.is_some() { id } else { viewport.parent })
+    }
+
+    pub fn schedule_repaint(&mut self, id: egui::ViewportId, delay: Duration) {
+        if let Some(viewport) = self.viewports.get_mut(&id) {
+            viewport.schedule_repaint(delay);
+        }
+    }
+
+    /// Asks the windows of viewports that are due for a frame, returns when the next one is.
+    pub fn request_redraws(&mut self, now: Instant) -> Option<Instant> {
+        let mut next = None;
+        for viewport in self.viewports.values_mut() {
+            match viewport.repaint_at {
+                Some(at) if at <= now => {
+                    viewport.repaint_at = None;
+                    viewport.window.request_redraw();
+                },
+                Some(at) => next = Some(next.map_or(at, |next: Instant| next.min(at))),
+                None => {},
+            }
+        }
+        next
+    }
+
+    /// Input for a pass of a deferred viewport, along with its UI. `None` for immediate viewports.
+    pub fn begin_deferred(&mut self, id: egui::ViewportId, ctx: &egui::Context) -> Option<(egui::RawInput, Arc<egui::DeferredViewportUiCallback>)> {
+        let viewport = self.viewports.get_mut(&id)?;
+        let ui_cb = viewport.ui_cb.clone()?;
+        Some((viewport.take_input(ctx), ui_cb))
+    }
+
+    pub fn paint(&mut self, id: egui::ViewportId, gpu: &ViewportGpu, renderer: &mut egui_wgpu::Renderer, ctx: &egui::Context, output: egui::FullOutput) {
+        if let Some(viewport) = self.viewports.get_mut(&id) {
+            viewport.paint(gpu, renderer, ctx, output);
+        }
+    }
+
+    /// The frames of immediate viewports that ran since the last call.
+    pub fn take_immediate_frames(&mut self) -> Vec<(egui::ViewportId, egui::FullOutput)> {
+        std::mem::take(&mut self.immediate_frames)
+    }
+
+    pub fn set_max_texture_side(&mut self, side: usize) {
+        for viewport in self.viewports.values_mut() {
+            viewport.state.set_max_texture_side(side);
+        }
+    }
+
+    /// Closes all windows, e.g. because their surfaces belong to a GPU that's gone. They're reopened on the next `sync`.
+    pub fn close_all(&mut self) {
+        self.viewports.clear();
+        self.failed.clear();
+    }
+}
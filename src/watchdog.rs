@@ -0,0 +1,166 @@
+use std::backtrace::Backtrace;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering};
+use std::sync::{Arc, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// What the frame is busy with, reported by the watchdog when it takes too long.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(u8)]
+pub enum Phase {
+    Input,
+    Run,
+    Tessellate,
+    Upload,
+    Acquire,
+    Render,
+    Present,
+    /// The OS runs a loop of its own, e.g. while the window is dragged on Windows, the frame isn't held up by the app.
+    ModalLoop,
+}
+
+impl Phase {
+    const ALL: [Phase; 8] = [
+        Phase::Input,
+        Phase::Run,
+        Phase::Tessellate,
+        Phase::Upload,
+        Phase::Acquire,
+        Phase::Render,
+        Phase::Present,
+        Phase::ModalLoop,
+    ];
+
+    fn from_u8(value: u8) -> Self {
+        Self::ALL[value as usize]
+    }
+}
+
+/// Marks `frame_start` between frames.
+const NO_FRAME: u64 = u64::MAX;
+
+struct Shared {
+    epoch: Instant,
+    phase: AtomicU8,
+    /// Nanoseconds since `epoch` when the current frame started, or [`NO_FRAME`].
+    frame_start: AtomicU64,
+    /// Set by the watchdog thread once a frame ran past `backtrace_after`.
+    backtrace_requested: AtomicBool,
+}
+
+impl Shared {
+    fn now(&self) -> u64 {
+        self.epoch.elapsed().as_nanos() as u64
+    }
+}
+
+/// Warns about frames that take longer than a budget, while they're still running, so a UI thread that got stuck in
+/// blocking I/O or a slow loop says where instead of freezing silently. A thread of its own looks at the frame's start
+/// and [`Phase`] a few times per budget.
+///
+/// std can't capture the stack of another thread. After `backtrace_after` the frame logs a backtrace of itself at its
+/// next phase change instead, which points at the code right after whatever blocked.
+pub struct Watchdog {
+    shared: Arc<Shared>,
+    budget: Duration,
+    long_frames: u64,
+    longest: Duration,
+}
+
+impl Watchdog {
+    pub fn new(budget: Duration, backtrace_after: Option<Duration>) -> Self {
+        let shared = Arc::new(Shared {
+            epoch: Instant::now(),
+            phase: AtomicU8::new(Phase::Input as u8),
+            frame_start: AtomicU64::new(NO_FRAME),
+            backtrace_requested: AtomicBool::new(false),
+        });
+        let watched = Arc::downgrade(&shared);
+        let spawned = thread::Builder::new()
+            .name("frame watchdog".into())
+            .spawn(move || watch(&watched, budget, backtrace_after));
+        if let Err(error) = spawned {
+            log::error!("Failed to start the frame watchdog: {error}");
+        }
+        Self { shared, budget, long_frames: 0, longest: Duration::ZERO }
+    }
+
+    pub fn budget(&self) -> Duration {
+        self.budget
+    }
+
+    /// Frames that took longer than the budget so far.
+    pub fn long_frames(&self) -> u64 {
+        self.long_frames
+    }
+
+    pub fn longest_frame(&self) -> Duration {
+        self.longest
+    }
+
+    pub fn frame_started(&self) {
+        self.shared.phase.store(Phase::Input as u8, Ordering::Relaxed);
+        self.shared.frame_start.store(self.shared.now(), Ordering::Relaxed);
+    }
+
+    pub fn set_phase(&self, phase: Phase) {
+        self.shared.phase.store(phase as u8, Ordering::Relaxed);
+        if self.shared.backtrace_requested.swap(false, Ordering::Relaxed) {
+            log::warn!("Backtrace of the long frame, starting {phase:?}:\n{}", Backtrace::force_capture());
+        }
+    }
+
+    pub fn frame_finished(&mut self) {
+        let start = self.shared.frame_start.swap(NO_FRAME, Ordering::Relaxed);
+        self.shared.backtrace_requested.store(false, Ordering::Relaxed);
+        if start == NO_FRAME {
+            return;
+        }
+        let duration = Duration::from_nanos(self.shared.now().saturating_sub(start));
+        if duration > self.budget {
+            self.long_frames += 1;
+            log::warn!("A frame took {:.0} ms", duration.as_secs_f64() * 1000.0);
+        }
+        self.longest = self.longest.max(duration);
+    }
+
+    /// Runs `f`, which enters a loop of the OS, without it counting towards the frame: the time is taken out of the
+    /// frame by moving its start.
+    pub fn modal_loop<R>(&self, f: impl FnOnce() -> R) -> R {
+        let phase = self.shared.phase.swap(Phase::ModalLoop as u8, Ordering::Relaxed);
+        let entered = self.shared.now();
+        let result = f();
+        let spent = self.shared.now() - entered;
+        let _ = self.shared.frame_start.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |start| {
+            (start != NO_FRAME).then(|| start + spent)
+        });
+        self.shared.phase.store(phase, Ordering::Relaxed);
+        result
+    }
+}
+
+fn watch(shared: &Weak<Shared>, budget: Duration, backtrace_after: Option<Duration>) {
+    let interval = (budget / 4).clamp(Duration::from_millis(10), Duration::from_millis(100));
+    // The frame last warned about and whether its backtrace was asked for, so every frame is only reported once.
+    let mut reported = (NO_FRAME, false);
+    loop {
+        thread::sleep(interval);
+        let Some(shared) = shared.upgrade() else {
+            return;
+        };
+        let start = shared.frame_start.load(Ordering::Relaxed);
+        let phase = Phase::from_u8(shared.phase.load(Ordering::Relaxed));
+        if start == NO_FRAME || phase == Phase::ModalLoop {
+            continue;
+        }
+        let running = Duration::from_nanos(shared.now().saturating_sub(start));
+        if running > budget && reported.0 != start {
+            log::warn!("The frame has been running for {:.0} ms, it's stuck in {phase:?}", running.as_secs_f64() * 1000.0);
+            reported = (start, false);
+        }
+        if backtrace_after.is_some_and(|after| running > after) && reported == (start, false) {
+            shared.backtrace_requested.store(true, Ordering::Relaxed);
+            reported.1 = true;
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use std::sync::{Arc, Mutex};
+
+use winit::event_loop::EventLoopProxy;
+use winit::window::WindowLevel;
+
+use crate::app::AppEvent;
+
+/// A change to the window or the event loop that UI code asks for. The UI only gets a shared `&Window` and no
+/// `ActiveEventLoop`, and changing the window while a frame is built could interfere with it, so these are queued.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WindowCommand {
+    SetTitle(String),
+    /// Borderless fullscreen on the current monitor, or back to a normal window.
+    SetFullscreen(bool),
+    /// Leaving fullscreen puts the level back, some platforms drop it.
+    SetWindowLevel(WindowLevel),
+    /// Only supported on Windows, elsewhere it's logged and nothing happens.
+    SetSkipTaskbar(bool),
+    SetResizable(bool),
+    /// Hides the cursor and sends relative mouse motion to the camera demo instead of egui, see `AppResources::set_cursor_grab`.
+    SetCursorGrab(bool),
+    /// The smallest size the window can be resized to, in logical pixels. `None` lifts the limit.
+    SetMinInnerSize(Option<[f64; 2]>),
+    /// Progress of the background tasks together, for the taskbar button. `None` once none are running.
+    TaskProgress(Option<f32>),
+    /// A background task finished, which asks for the user's attention if the window isn't focused.
+    TaskCompleted,
+    /// Like [`Self::TaskCompleted`], but asks more urgently where the platform can.
+    TaskFailed,
+    /// What closing the window does: quits, unless there are unsaved changes, which asks first.
+    RequestExit,
+    /// Quits without asking.
+    Exit,
+}
+
+/// Queue of [`WindowCommand`]s. Clones share the queue and can be handed to other threads.
+///
+/// Commands are applied in the order they were pushed, by the event loop once it's done with what it's handling.
+/// Those pushed while a frame is built are applied after that frame was presented, never in the middle of it.
+/// Pushing into an empty queue wakes the event loop up, so commands from other threads don't wait for the next frame.
+#[derive(Clone)]
+pub struct WindowCommands {
+    queue: Arc<Mutex<Vec<WindowCommand>>>,
+    proxy: EventLoopProxy<AppEvent>,
+}
+
+impl WindowCommands {
+    pub fn new(proxy: EventLoopProxy<AppEvent>) -> Self {
+        Self { queue: Arc::default(), proxy }
+    }
+
+    pub fn push(&self, command: WindowCommand) {
+        let mut queue = self.queue.lock().unwrap();
+        if queue.is_empty() {
+            let _ = self.proxy.send_event(AppEvent::WindowCommands);
+        }
+        queue.push(command);
+    }
+
+    /// Everything pushed so far, the queue is empty afterwards.
+    pub fn take(&self) -> Vec<WindowCommand> {
+        std::mem::take(&mut *self.queue.lock().unwrap())
+    }
+}